@@ -0,0 +1,157 @@
+// Compare the boxed future from `Address::call` on a RemoteAddr with the named future from
+// `RemoteAddr::call_concrete`. Besides timing, this counts the average number of heap allocations
+// per call for both with a global allocator, and fails if the concrete future does not allocate
+// less than the boxed one.
+//
+use
+{
+	async_executors :: { AsyncStd, SpawnHandleExt                 } ,
+	criterion       :: { Criterion, criterion_group, criterion_main } ,
+	futures         :: { executor::block_on                         } ,
+	futures_ringbuf :: { Endpoint                                   } ,
+	serde           :: { Serialize, Deserialize                     } ,
+	thespis         :: { *                                          } ,
+	thespis_impl    :: { *                                          } ,
+	thespis_remote  :: { *, service_map                             } ,
+
+	std ::
+	{
+		alloc        :: { GlobalAlloc, Layout, System } ,
+		sync         :: { Arc                         } ,
+		sync::atomic :: { AtomicUsize, Ordering       } ,
+	},
+};
+
+
+struct Counting;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new( 0 );
+
+unsafe impl GlobalAlloc for Counting
+{
+	unsafe fn alloc( &self, layout: Layout ) -> *mut u8
+	{
+		ALLOCS.fetch_add( 1, Ordering::Relaxed );
+		System.alloc( layout )
+	}
+
+	unsafe fn dealloc( &self, ptr: *mut u8, layout: Layout )
+	{
+		System.dealloc( ptr, layout )
+	}
+}
+
+#[ global_allocator ]
+//
+static GLOBAL: Counting = Counting;
+
+
+
+#[ derive( Actor ) ] struct Sum( i64 );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Add( i64 );
+
+impl Message for Add { type Return = i64; }
+
+impl Handler<Add> for Sum
+{
+	#[async_fn] fn handle( &mut self, msg: Add ) -> i64
+	{
+		self.0 += msg.0;
+		self.0
+	}
+}
+
+
+service_map!
+(
+	namespace       : bench ;
+	wire_format     : ThesWF ;
+	concrete_futures: true   ;
+	services        : Add    ;
+);
+
+
+
+fn connect() -> bench::RemoteAddr
+{
+	let (server, client) = Endpoint::pair( 1024, 1024 );
+
+	let handler = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = bench::Services::new();
+	sm.register_handler::<Add>( handler.clone_box() );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+	let mut server_peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "create peer" );
+	server_peer.register_services( Arc::new( sm ) );
+
+	AsyncStd.spawn_handle( server_mb.start( server_peer ) ).expect( "start server" ).detach();
+
+	let (client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+	let client_peer = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "create peer" );
+
+	AsyncStd.spawn_handle( client_mb.start( client_peer ) ).expect( "start client" ).detach();
+
+	bench::RemoteAddr::new( client_addr )
+}
+
+
+
+fn allocs_per_call( addr: &mut bench::RemoteAddr, concrete: bool ) -> f64
+{
+	const CALLS: usize = 1000;
+
+	let before = ALLOCS.load( Ordering::Relaxed );
+
+	for _ in 0..CALLS
+	{
+		let res = match concrete
+		{
+			true  => block_on( addr.call_concrete( Add(1) ) ),
+			false => block_on( addr.call         ( Add(1) ) ),
+		};
+
+		res.expect( "call" );
+	}
+
+	( ALLOCS.load( Ordering::Relaxed ) - before ) as f64 / CALLS as f64
+}
+
+
+
+fn call_futures( c: &mut Criterion )
+{
+	let mut addr = connect();
+
+	// Warm up both paths so one time initialization doesn't end up in either count.
+	//
+	allocs_per_call( &mut addr, false );
+	allocs_per_call( &mut addr, true  );
+
+	let boxed    = allocs_per_call( &mut addr, false );
+	let concrete = allocs_per_call( &mut addr, true  );
+
+	println!( "allocations per call, boxed   : {}", boxed    );
+	println!( "allocations per call, concrete: {}", concrete );
+
+	assert!
+	(
+		concrete < boxed,
+		"call_concrete should allocate less than Address::call, got {} vs {} per call", concrete, boxed
+	);
+
+	c.bench_function( "Address::call boxed", |b| b.iter( ||
+	{
+		block_on( addr.call( Add(1) ) ).expect( "call" )
+	}));
+
+	c.bench_function( "RemoteAddr::call_concrete", |b| b.iter( ||
+	{
+		block_on( addr.call_concrete( Add(1) ) ).expect( "call" )
+	}));
+}
+
+
+criterion_group!( benches, call_futures );
+criterion_main! ( benches );
@@ -159,7 +159,7 @@ async fn handle_conn( socket: WebSocketStream<TcpStream>, peer_addr: SocketAddr,
 	let peer_out = client_map::Services::recipient::< ServerMsg >( cc_addr.clone() );
 	let user     = User::new( server.clone(), usr_addr.clone(), Box::new( peer_out ) );
 
-	let filter = Filter::Pointer( |e| *e == PeerEvent::Closed || *e == PeerEvent::ClosedByRemote );
+	let filter = Filter::Pointer( |e| matches!( e, PeerEvent::Closed(_) | PeerEvent::ClosedByRemote(_) ) );
 
 	let close_evt = peer
 
@@ -0,0 +1,105 @@
+use crate :: { import::*, *, peer::Response };
+
+
+/// A [ServiceMap] that makes several sids reach the same service of another map, eg. during a rollout
+/// where the old and the new version of a service exist under different sids. Requests for an alias
+/// get the sid of their primary before they are handed to the inner map, so the handler for the primary
+/// handles both.
+///
+/// An alias the inner map provides itself is ignored, the inner map keeps handling it.
+//
+pub struct AliasMap<Wf: 'static = ThesWF>
+{
+	inner   : Arc< dyn ServiceMap<Wf> >     ,
+	aliases : HashMap<ServiceID, ServiceID> ,
+	services: Vec<ServiceID>                ,
+}
+
+
+impl<Wf: WireFormat> AliasMap<Wf>
+{
+	/// Wrap `inner`. Without aliases, this behaves exactly like `inner`.
+	//
+	pub fn new( inner: Arc< dyn ServiceMap<Wf> > ) -> Self
+	{
+		let services = inner.services().copied().collect();
+
+		Self { inner, aliases: HashMap::new(), services }
+	}
+
+
+	/// Deliver requests for `alias` to the service `primary` of the inner map.
+	//
+	pub fn alias( mut self, alias: ServiceID, primary: ServiceID ) -> Self
+	{
+		if self.inner.services().any( |sid| *sid == alias )
+		{
+			warn!( "AliasMap: ignoring alias {} for {}, the inner map provides it.", alias, primary );
+			return self;
+		}
+
+		if self.aliases.insert( alias, primary ).is_none()
+		{
+			self.services.push( alias );
+		}
+
+		self
+	}
+
+
+	// Give the request the sid of its primary if it's addressed to an alias.
+	//
+	fn resolve( &self, mut msg: Wf ) -> Wf
+	{
+		if let Some( primary ) = self.aliases.get( &msg.sid() )
+		{
+			msg.set_sid( *primary );
+		}
+
+		msg
+	}
+}
+
+
+
+impl<Wf: WireFormat> ServiceMap<Wf> for AliasMap<Wf>
+{
+	fn send_service( &self, msg: Wf, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.inner.send_service( self.resolve( msg ), ctx )
+	}
+
+
+	fn call_service( &self, msg: Wf, ctx: PeerErrCtx, cancel: CancelToken )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.inner.call_service( self.resolve( msg ), ctx, cancel )
+	}
+
+
+	fn open_stream( &self, msg: Wf, channel: StreamChannel<Wf>, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.inner.open_stream( self.resolve( msg ), channel, ctx )
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		Box::new( self.services.iter() )
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for AliasMap<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "AliasMap, inner: {:?}, aliases: {}", self.inner, self.aliases.len() )
+	}
+}
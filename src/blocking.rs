@@ -0,0 +1,99 @@
+use
+{
+	crate           :: { import::*, *                           } ,
+	async_executors :: { ThreadPool                             } ,
+	futures         :: { executor::block_on, task::SpawnExt     } ,
+};
+
+
+// All peers started by BlockingRemoteAddr::connect run here, so connecting several times doesn't
+// create more threads.
+//
+static BACKGROUND: SyncLazy<ThreadPool> = SyncLazy::new( ||
+{
+	ThreadPool::new().expect( "create the background executor for BlockingRemoteAddr" )
+});
+
+
+
+/// A blocking facade over a remote address for synchronous code, like command line tools and scripts.
+/// Each method blocks the current thread until the remote answers, so never use this from async code.
+///
+/// `A` is the `RemoteAddr` generated by [service_map!](crate::service_map). The [Peer] it talks to must
+/// run on an executor. [`BlockingRemoteAddr::connect`] takes care of that by starting it on a background
+/// thread pool which is shared by all connections.
+///
+/// Requires the `blocking` feature.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct BlockingRemoteAddr<A>
+{
+	addr: A,
+}
+
+
+impl<A> BlockingRemoteAddr<A>
+{
+	/// Wrap a remote address for a peer that already runs on an executor.
+	//
+	pub fn new( addr: A ) -> Self
+	{
+		Self { addr }
+	}
+
+
+	/// Start a [Peer] for `socket` on the background executor and wrap the remote address that `remote`
+	/// creates for it, eg. `BlockingRemoteAddr::connect( socket, 1024, remotes::RemoteAddr::new )`.
+	//
+	pub fn connect
+	(
+		socket  : impl FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static ,
+		max_size: usize                                                      ,
+		remote  : impl FnOnce( Addr<Peer> ) -> A                             ,
+	)
+
+		-> Result< Self, PeerErr >
+
+	{
+		let (addr, mb) = Addr::builder().name( "blocking_client".into() ).build();
+		let peer       = Peer::from_async_read( addr.clone(), socket, max_size, BACKGROUND.clone(), None, None )?;
+
+		BACKGROUND.spawn( async move { mb.start( peer ).await; } ).map_err( |_|
+		{
+			PeerErr::Spawn{ ctx: Peer::err_ctx( &addr, None, None, "Start the mailbox of the blocking peer".to_string() ) }
+		})?;
+
+		Ok( Self::new( remote( addr ) ) )
+	}
+
+
+	/// Call a remote service and block until the response comes in.
+	//
+	pub fn call<S>( &mut self, msg: S ) -> Result< S::Return, PeerErr >
+
+		where A: Address<S, Error=PeerErr>,
+		      S: Message                  ,
+	{
+		block_on( self.addr.call( msg ) )
+	}
+
+
+	/// Send to a remote service, blocking until the message has been handed to the peer.
+	//
+	pub fn send<S>( &mut self, msg: S ) -> Result< (), PeerErr >
+
+		where A: Address<S, Error=PeerErr>,
+		      S: Message                  ,
+	{
+		block_on( self.addr.send( msg ) )
+	}
+
+
+	/// The wrapped address, to use it from async code.
+	//
+	pub fn inner( &self ) -> &A
+	{
+		&self.addr
+	}
+}
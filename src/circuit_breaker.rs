@@ -0,0 +1,368 @@
+use
+{
+	crate :: { import::*, *  } ,
+	std   :: { time::Instant } ,
+};
+
+
+/// An [Address] that stops calling a failing provider for a while. After `threshold` failures in a
+/// row the circuit opens and calls fail right away with [`PeerErr::CircuitOpen`], without reaching the
+/// wrapped address. Once the cooldown is over, the circuit is half open: one call at a time is let
+/// through as a probe, the others still fail fast. A failed probe opens the circuit for another cooldown,
+/// after [`CircuitBreakerAddr::probes`] successful probes in a row it closes again.
+///
+/// By default every error counts as a failure, see [`CircuitBreakerAddr::fail_if`] to change that. Errors
+/// that don't count reset the failures like a success, since the provider did respond.
+///
+/// Clones share the state of the circuit. `Sink::send` is forwarded as is while the circuit is closed
+/// and fails with [`PeerErr::CircuitOpen`] otherwise. Sends don't count as failures nor as probes.
+//
+pub struct CircuitBreakerAddr<S: Message>
+{
+	addr     : BoxAddress<S, PeerErr>                          ,
+	circuit  : Arc< Mutex<Circuit> >                           ,
+	threshold: usize                                           ,
+	cooldown : Duration                                        ,
+	probes   : usize                                           ,
+	fails    : Arc< dyn Fn( &PeerErr ) -> bool + Send + Sync > ,
+}
+
+
+/// The state of a [`CircuitBreakerAddr`].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum CircuitState
+{
+	/// Calls go through.
+	//
+	Closed,
+
+	/// Calls fail fast until the cooldown is over.
+	//
+	Open,
+
+	/// The cooldown is over, calls go through one at a time to find out whether the provider recovered.
+	//
+	HalfOpen,
+}
+
+
+#[ derive( Debug ) ]
+//
+enum Circuit
+{
+	Closed  { failures: usize                  },
+	Open    { until   : Instant                },
+	HalfOpen{ probing : bool, successes: usize },
+}
+
+
+// Whether a call was let through as a probe.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+enum Admit
+{
+	Call  ,
+	Probe ,
+}
+
+
+// Frees the probe slot if the probe gets dropped before its outcome is known, so the circuit doesn't
+// stay half open forever.
+//
+struct ProbeGuard
+{
+	circuit: Arc< Mutex<Circuit> > ,
+	armed  : bool                  ,
+}
+
+
+impl Drop for ProbeGuard
+{
+	fn drop( &mut self )
+	{
+		if !self.armed { return }
+
+		if let Circuit::HalfOpen{ probing, .. } = &mut *self.circuit.lock()
+		{
+			*probing = false;
+		}
+	}
+}
+
+
+
+impl<S: Message> CircuitBreakerAddr<S>
+{
+	/// Open the circuit after `threshold` failures in a row and keep it open for `cooldown`.
+	//
+	pub fn new( addr: BoxAddress<S, PeerErr>, threshold: NonZeroUsize, cooldown: Duration ) -> Self
+	{
+		Self
+		{
+			addr                                                                ,
+			cooldown                                                            ,
+			circuit  : Arc::new( Mutex::new( Circuit::Closed{ failures: 0 } ) ) ,
+			threshold: threshold.get()                                          ,
+			probes   : 1                                                        ,
+			fails    : Arc::new( |_| true )                                     ,
+		}
+	}
+
+
+	/// The number of successful probes in a row needed to close the circuit again. Defaults to 1.
+	//
+	pub fn probes( mut self, probes: NonZeroUsize ) -> Self
+	{
+		self.probes = probes.get();
+		self
+	}
+
+
+	/// Decide which errors count as failures.
+	//
+	pub fn fail_if( mut self, fails: impl Fn( &PeerErr ) -> bool + Send + Sync + 'static ) -> Self
+	{
+		self.fails = Arc::new( fails );
+		self
+	}
+
+
+	/// The current state of the circuit. An open circuit of which the cooldown is over is reported
+	/// as half open.
+	//
+	pub fn state( &self ) -> CircuitState
+	{
+		match &*self.circuit.lock()
+		{
+			Circuit::Closed{..}                               => CircuitState::Closed   ,
+			Circuit::Open{ until } if Instant::now() < *until => CircuitState::Open     ,
+			Circuit::Open{..} | Circuit::HalfOpen{..}         => CircuitState::HalfOpen ,
+		}
+	}
+
+
+	// Decide whether a call can go through.
+	//
+	fn admit( &self ) -> Option<Admit>
+	{
+		let mut circuit = self.circuit.lock();
+
+		match &mut *circuit
+		{
+			Circuit::Closed{..} => Some( Admit::Call ),
+
+			Circuit::Open{ until } =>
+			{
+				if Instant::now() < *until { return None }
+
+				*circuit = Circuit::HalfOpen{ probing: true, successes: 0 };
+
+				Some( Admit::Probe )
+			}
+
+			Circuit::HalfOpen{ probing, .. } =>
+			{
+				if *probing { return None }
+
+				*probing = true;
+
+				Some( Admit::Probe )
+			}
+		}
+	}
+
+
+	// Update the circuit with the outcome of a call.
+	//
+	fn record( &self, admit: Admit, failed: bool )
+	{
+		let mut circuit = self.circuit.lock();
+
+		match ( &mut *circuit, admit )
+		{
+			( Circuit::Closed{ failures }, Admit::Call ) =>
+			{
+				*failures = if failed { *failures + 1 } else { 0 };
+
+				if *failures >= self.threshold
+				{
+					warn!( "CircuitBreakerAddr: {} failures in a row, opening the circuit for {:?}", failures, self.cooldown );
+
+					*circuit = Circuit::Open{ until: Instant::now() + self.cooldown };
+				}
+			}
+
+			( Circuit::HalfOpen{ probing, successes }, Admit::Probe ) =>
+			{
+				*probing = false;
+
+				if failed
+				{
+					debug!( "CircuitBreakerAddr: probe failed, opening the circuit for {:?}", self.cooldown );
+
+					*circuit = Circuit::Open{ until: Instant::now() + self.cooldown };
+				}
+
+				else
+				{
+					*successes += 1;
+
+					if *successes >= self.probes
+					{
+						debug!( "CircuitBreakerAddr: the provider recovered, closing the circuit" );
+
+						*circuit = Circuit::Closed{ failures: 0 };
+					}
+				}
+			}
+
+			// Calls that went through before the circuit opened.
+			//
+			_ => {}
+		}
+	}
+
+
+	fn open_err( &self ) -> PeerErr
+	{
+		let ctx = PeerErrCtx::default()
+
+			.context  ( "CircuitBreakerAddr".to_string() )
+			.peer_id  ( self.addr.id()                   )
+			.peer_name( self.addr.name()                 )
+		;
+
+		PeerErr::CircuitOpen{ ctx }
+	}
+}
+
+
+
+impl<S> Address<S> for CircuitBreakerAddr<S>
+
+	where  S                    : Message + Send,
+	      <S as Message>::Return: Send,
+
+{
+	fn call( &mut self, msg: S ) -> Return<'_, Result< <S as Message>::Return, PeerErr >>
+	{
+		async move
+		{
+			let admit = match self.admit()
+			{
+				Some( admit ) => admit,
+				None          => return Err( self.open_err() ),
+			};
+
+			let mut guard = match admit
+			{
+				Admit::Probe => Some( ProbeGuard{ circuit: self.circuit.clone(), armed: true } ),
+				Admit::Call  => None,
+			};
+
+			let res = self.addr.call( msg ).await;
+
+			// The outcome is known, record takes care of the probe slot.
+			//
+			if let Some( guard ) = &mut guard { guard.armed = false; }
+
+			self.record( admit, matches!( &res, Err(e) if (self.fails)( e ) ) );
+
+			res
+
+		}.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<S, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+
+impl<S> Sink<S> for CircuitBreakerAddr<S>
+
+	where S: Message,
+
+{
+	type Error = PeerErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		if !matches!( *self.circuit.lock(), Circuit::Closed{..} )
+		{
+			return Poll::Ready( Err( self.open_err() ) );
+		}
+
+		self.addr.poll_ready_unpin( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: S ) -> Result<(), Self::Error>
+	{
+		self.addr.start_send_unpin( msg )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.addr.poll_flush_unpin( cx )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.addr.poll_close_unpin( cx )
+	}
+}
+
+
+
+impl<S: Message> Identify for CircuitBreakerAddr<S>
+{
+	fn id( &self ) -> usize
+	{
+		self.addr.id()
+	}
+
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		self.addr.name()
+	}
+}
+
+
+
+/// The clone shares the state of the circuit.
+//
+impl<S: Message> Clone for CircuitBreakerAddr<S>
+{
+	fn clone( &self ) -> Self
+	{
+		Self
+		{
+			addr     : self.addr.clone_box() ,
+			circuit  : self.circuit.clone()  ,
+			threshold: self.threshold        ,
+			cooldown : self.cooldown         ,
+			probes   : self.probes           ,
+			fails    : self.fails.clone()    ,
+		}
+	}
+}
+
+
+
+impl<S: Message> fmt::Debug for CircuitBreakerAddr<S>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "CircuitBreakerAddr: {}, {:?}", self.addr.id(), self.state() )
+	}
+}
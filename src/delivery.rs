@@ -0,0 +1,64 @@
+use crate :: { import::*, * };
+
+
+/// The guarantee with which a message is delivered, see [`DeliverExt::deliver`].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum Delivery
+{
+	/// The message is handled once or not at all. There is a single attempt. When the acknowledgement
+	/// doesn't come back, the error is returned and the message is not sent again, even though the
+	/// remote might have handled it.
+	//
+	AtMostOnce,
+
+	/// The message is sent again until it's acknowledged, like with [RetryAddr]. When an acknowledgement
+	/// gets lost, the remote handles the message more than once, so handlers must tolerate duplicates.
+	//
+	AtLeastOnce
+	{
+		/// Give up after this many attempts and return the last error.
+		//
+		max_attempts: NonZeroUsize,
+
+		/// The wait before the first retry. It doubles after each attempt.
+		//
+		backoff: Duration,
+	},
+}
+
+
+
+/// Send a message with a [Delivery] guarantee. The acknowledgement is the response to a call, which
+/// comes back once the remote handler has processed the message. That's why this is only available
+/// for messages that return `()`. `Sink::send` stays fire and forget.
+//
+pub trait DeliverExt<S: Message<Return = ()>>: Address<S, Error = PeerErr>
+{
+	/// Deliver `msg` with the given guarantee.
+	//
+	fn deliver( &mut self, msg: S, delivery: Delivery ) -> Return<'_, Result<(), PeerErr>>;
+}
+
+
+impl<A, S> DeliverExt<S> for A
+
+	where A: Address<S, Error = PeerErr> + ?Sized ,
+	      S: Message<Return = ()> + Clone + Send  ,
+{
+	fn deliver( &mut self, msg: S, delivery: Delivery ) -> Return<'_, Result<(), PeerErr>>
+	{
+		match delivery
+		{
+			Delivery::AtMostOnce => self.call( msg ),
+
+			Delivery::AtLeastOnce{ max_attempts, backoff } =>
+			{
+				let mut retry = RetryAddr::new( self.clone_box(), max_attempts, backoff );
+
+				async move { retry.call( msg ).await }.boxed()
+			}
+		}
+	}
+}
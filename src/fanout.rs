@@ -0,0 +1,400 @@
+use crate :: { import::*, * };
+
+
+/// An [Address] that sends every message to several addresses. Useful for redundancy, eg. when
+/// several provider peers can handle the same service.
+///
+/// - `Address::call` is issued to all addresses concurrently. The first successful response is
+///   returned and the other calls are dropped. If all of them fail, you get [`PeerErr::Fanout`]
+///   with the errors of every address.
+/// - `Sink::send` delivers the message to all addresses.
+///
+/// Typically this wraps the `RemoteAddr` of a `service_map!` for each provider.
+///
+/// The [Identify] implementation reports the id and name of the first address.
+///
+/// See [`QuorumAddr`] if you need several addresses to agree on the response.
+//
+pub struct FanoutAddr<S: Message>
+{
+	addrs: Vec< BoxAddress<S, PeerErr> >,
+}
+
+
+
+impl<S: Message> FanoutAddr<S>
+{
+	/// Create a fanout over the given addresses.
+	///
+	/// # Panics
+	///
+	/// When `addrs` is empty.
+	//
+	pub fn new( addrs: Vec< BoxAddress<S, PeerErr> > ) -> Self
+	{
+		assert!( !addrs.is_empty(), "FanoutAddr needs at least one address" );
+
+		Self { addrs }
+	}
+
+
+	/// The number of addresses messages get sent to.
+	//
+	pub fn len( &self ) -> usize
+	{
+		self.addrs.len()
+	}
+
+
+	/// Always false, a fanout has at least one address.
+	//
+	pub fn is_empty( &self ) -> bool
+	{
+		self.addrs.is_empty()
+	}
+}
+
+
+
+impl<S> Address<S> for FanoutAddr<S>
+
+	where  S                    : Message + Clone + Send,
+	      <S as Message>::Return: Send,
+
+{
+	fn call( &mut self, msg: S ) -> Return<'_, Result< <S as Message>::Return, PeerErr >>
+	{
+		let mut calls: FuturesUnordered<_> = self.addrs.iter_mut().map( |a| a.call( msg.clone() ) ).collect();
+
+		async move
+		{
+			let mut errors = Vec::new();
+
+			while let Some( res ) = calls.next().await
+			{
+				match res
+				{
+					Ok ( resp ) => return Ok( resp ),
+					Err( e    ) => errors.push( e ),
+				}
+			}
+
+			let ctx = PeerErrCtx::default().context( "FanoutAddr: all addresses failed".to_string() );
+
+			Err( PeerErr::Fanout{ ctx, errors } )
+
+		}.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<S, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+
+impl<S> Sink<S> for FanoutAddr<S>
+
+	where S: Message + Clone + Send,
+
+{
+	type Error = PeerErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		let mut ready = true;
+
+		for addr in self.addrs.iter_mut()
+		{
+			match addr.poll_ready_unpin( cx )
+			{
+				Poll::Ready( Ok(()) ) => {}
+				Poll::Ready( Err(e) ) => return Poll::Ready( Err(e) ),
+				Poll::Pending         => ready = false,
+			}
+		}
+
+		match ready
+		{
+			true  => Poll::Ready( Ok(()) ),
+			false => Poll::Pending,
+		}
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: S ) -> Result<(), Self::Error>
+	{
+		for addr in self.addrs.iter_mut()
+		{
+			addr.start_send_unpin( msg.clone() )?;
+		}
+
+		Ok(())
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		let mut flushed = true;
+
+		for addr in self.addrs.iter_mut()
+		{
+			match addr.poll_flush_unpin( cx )
+			{
+				Poll::Ready( Ok(()) ) => {}
+				Poll::Ready( Err(e) ) => return Poll::Ready( Err(e) ),
+				Poll::Pending         => flushed = false,
+			}
+		}
+
+		match flushed
+		{
+			true  => Poll::Ready( Ok(()) ),
+			false => Poll::Pending,
+		}
+	}
+
+
+	/// Like RemoteAddr, this will only close when dropped.
+	//
+	fn poll_close( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Poll::Ready( Ok(()) )
+	}
+}
+
+
+
+impl<S: Message> Identify for FanoutAddr<S>
+{
+	/// The id of the first address.
+	//
+	fn id( &self ) -> usize
+	{
+		self.addrs[0].id()
+	}
+
+	/// The name of the first address.
+	//
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		self.addrs[0].name()
+	}
+}
+
+
+
+impl<S: Message> Clone for FanoutAddr<S>
+{
+	fn clone( &self ) -> Self
+	{
+		Self { addrs: self.addrs.iter().map( |a| a.clone_box() ).collect() }
+	}
+}
+
+
+
+impl<S: Message> fmt::Debug for FanoutAddr<S>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "FanoutAddr: [" )?;
+
+		for (i, a) in self.addrs.iter().enumerate()
+		{
+			if i > 0 { write!( f, ", " )?; }
+
+			write!( f, "{}", a.id() )?;
+		}
+
+		write!( f, "]" )
+	}
+}
+
+
+
+/// An [Address] over replicas of a service that only accepts the response of a call once `quorum`
+/// of them returned the same value. Useful for correctness-sensitive reads of replicated state.
+///
+/// The call is issued to all replicas concurrently. As soon as `quorum` identical responses came
+/// in, that response is returned and the other calls are dropped. When it becomes impossible to reach
+/// the quorum, you get [`PeerErr::NoQuorum`]. Errors from replicas count as disagreeing.
+///
+/// `Sink::send` delivers the message to all replicas, like [`FanoutAddr`].
+//
+pub struct QuorumAddr<S: Message>
+{
+	inner : FanoutAddr<S> ,
+	quorum: usize         ,
+}
+
+
+
+impl<S: Message> QuorumAddr<S>
+{
+	/// Create a quorum over the given replicas.
+	///
+	/// # Panics
+	///
+	/// When `addrs` is empty or `quorum` is bigger than the number of replicas.
+	//
+	pub fn new( addrs: Vec< BoxAddress<S, PeerErr> >, quorum: NonZeroUsize ) -> Self
+	{
+		assert!( quorum.get() <= addrs.len(), "QuorumAddr: quorum is bigger than the number of replicas" );
+
+		Self { inner: FanoutAddr::new( addrs ), quorum: quorum.get() }
+	}
+
+
+	/// The number of identical responses required.
+	//
+	pub fn quorum( &self ) -> usize
+	{
+		self.quorum
+	}
+}
+
+
+
+impl<S> Address<S> for QuorumAddr<S>
+
+	where  S                    : Message + Clone + Send,
+	      <S as Message>::Return: Eq + Send,
+
+{
+	fn call( &mut self, msg: S ) -> Return<'_, Result< <S as Message>::Return, PeerErr >>
+	{
+		let quorum      = self.quorum;
+		let mut pending = self.inner.addrs.len();
+
+		let mut calls: FuturesUnordered<_> = self.inner.addrs.iter_mut().map( |a| a.call( msg.clone() ) ).collect();
+
+		async move
+		{
+			// Return doesn't have to be Hash, so just keep a list of distinct responses.
+			//
+			let mut votes : Vec<( <S as Message>::Return, usize )> = Vec::new();
+			let mut errors                                          = Vec::new();
+			let mut agreed                                          = 0;
+
+			while let Some( res ) = calls.next().await
+			{
+				pending -= 1;
+
+				match res
+				{
+					Ok( resp ) =>
+					{
+						let idx = match votes.iter().position( |(r, _)| *r == resp )
+						{
+							Some( idx ) => { votes[idx].1 += 1; idx }
+							None        => { votes.push( (resp, 1) ); votes.len() - 1 }
+						};
+
+						let count = votes[idx].1;
+						agreed    = agreed.max( count );
+
+						if count >= quorum
+						{
+							return Ok( votes.swap_remove( idx ).0 );
+						}
+					}
+
+					Err( e ) => errors.push( e ),
+				}
+
+				if agreed + pending < quorum
+				{
+					break;
+				}
+			}
+
+			let ctx = PeerErrCtx::default().context( "QuorumAddr: replicas did not agree".to_string() );
+
+			Err( PeerErr::NoQuorum{ ctx, quorum, agreed, errors } )
+
+		}.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<S, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+
+impl<S> Sink<S> for QuorumAddr<S>
+
+	where S: Message + Clone + Send,
+
+{
+	type Error = PeerErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.inner.poll_ready_unpin( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: S ) -> Result<(), Self::Error>
+	{
+		self.inner.start_send_unpin( msg )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.inner.poll_flush_unpin( cx )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.inner.poll_close_unpin( cx )
+	}
+}
+
+
+
+impl<S: Message> Identify for QuorumAddr<S>
+{
+	/// The id of the first replica.
+	//
+	fn id( &self ) -> usize
+	{
+		self.inner.id()
+	}
+
+	/// The name of the first replica.
+	//
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		self.inner.name()
+	}
+}
+
+
+
+impl<S: Message> Clone for QuorumAddr<S>
+{
+	fn clone( &self ) -> Self
+	{
+		Self { inner: self.inner.clone(), quorum: self.quorum }
+	}
+}
+
+
+
+impl<S: Message> fmt::Debug for QuorumAddr<S>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "QuorumAddr: quorum: {}, {:?}", self.quorum, self.inner )
+	}
+}
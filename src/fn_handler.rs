@@ -0,0 +1,138 @@
+use
+{
+	crate   :: { import::*, *           } ,
+	futures :: { future::BoxFuture      } ,
+};
+
+
+/// An [Address] that handles messages with an async closure instead of an actor, so simple stateless
+/// services don't need an actor type. See `Services::register_fn`.
+///
+/// There is no mailbox, the closure runs in the task the [Peer] spawns for the request, so requests
+/// are processed concurrently. Clones share the closure.
+//
+pub struct FnHandler<S, F>
+{
+	f      : Arc<F>                           ,
+	pending: Option< BoxFuture<'static, ()> > ,
+	_msg   : PhantomData< fn(S) >             ,
+}
+
+
+impl<S, F, Fut> FnHandler<S, F>
+
+	where S  : Message                                                   ,
+	      F  : Fn(S) -> Fut + Send + Sync + 'static                      ,
+	      Fut: Future< Output = <S as Message>::Return > + Send + 'static ,
+{
+	/// Handle messages of type `S` with `f`.
+	//
+	pub fn new( f: F ) -> Self
+	{
+		Self { f: Arc::new( f ), pending: None, _msg: PhantomData }
+	}
+
+
+	// Drive the send in progress to completion.
+	//
+	fn poll_pending( &mut self, cx: &mut Context<'_> ) -> Poll< Result<(), ThesErr> >
+	{
+		if let Some( pending ) = &mut self.pending
+		{
+			if pending.as_mut().poll( cx ).is_pending()
+			{
+				return Poll::Pending;
+			}
+
+			self.pending = None;
+		}
+
+		Poll::Ready( Ok(()) )
+	}
+}
+
+
+impl<S, F, Fut> Address<S> for FnHandler<S, F>
+
+	where S  : Message                                                   ,
+	      F  : Fn(S) -> Fut + Send + Sync + 'static                      ,
+	      Fut: Future< Output = <S as Message>::Return > + Send + 'static ,
+{
+	fn call( &mut self, msg: S ) -> Return<'_, Result< <S as Message>::Return, ThesErr >>
+	{
+		let fut = (self.f)( msg );
+
+		async move { Ok( fut.await ) }.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<S, ThesErr>
+	{
+		Box::new( Self { f: self.f.clone(), pending: None, _msg: PhantomData } )
+	}
+}
+
+
+impl<S, F, Fut> Sink<S> for FnHandler<S, F>
+
+	where S  : Message                                                   ,
+	      F  : Fn(S) -> Fut + Send + Sync + 'static                      ,
+	      Fut: Future< Output = <S as Message>::Return > + Send + 'static ,
+{
+	type Error = ThesErr;
+
+
+	fn poll_ready( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), ThesErr>>
+	{
+		self.get_mut().poll_pending( cx )
+	}
+
+
+	fn start_send( self: Pin<&mut Self>, msg: S ) -> Result<(), ThesErr>
+	{
+		let this = self.get_mut();
+		let fut  = (this.f)( msg );
+
+		this.pending = Some( async move { fut.await; }.boxed() );
+
+		Ok(())
+	}
+
+
+	fn poll_flush( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), ThesErr>>
+	{
+		self.get_mut().poll_pending( cx )
+	}
+
+
+	fn poll_close( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), ThesErr>>
+	{
+		self.get_mut().poll_pending( cx )
+	}
+}
+
+
+impl<S, F> Identify for FnHandler<S, F>
+{
+	/// There is no actor, so this is always 0.
+	//
+	fn id( &self ) -> usize
+	{
+		0
+	}
+
+
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		None
+	}
+}
+
+
+impl<S, F> fmt::Debug for FnHandler<S, F>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "FnHandler<{}>", std::any::type_name::<S>() )
+	}
+}
@@ -0,0 +1,327 @@
+use
+{
+	crate   :: { import::*, *, peer::Response } ,
+	futures :: { io::AsyncWriteExt            } ,
+	std     :: { io::Write as IoWrite         } ,
+};
+
+
+// The most we read before the end of the request head.
+//
+const MAX_HEAD: usize = 8 * 1024;
+
+// The most headers we look at.
+//
+const MAX_HEADERS: usize = 32;
+
+
+/// Serves the calls of a [ServiceMap] over HTTP/1.1, for clients like browsers that can't speak the framed
+/// protocol. A `POST` to `/{sid}`, with the sid in hex as shown by `{:x}` on a [ServiceID], runs
+/// `call_service` with the body as the serialized message. The response body is the serialized response.
+/// No [Peer] is involved, the request goes straight to the service map.
+///
+/// Errors map to status codes: 404 for an unknown service, 400 when the body doesn't deserialize, 403
+/// when unauthorized, 504 on timeout, 503 when the handler is gone and 500 for anything else. Errors
+/// that a relay gets back from the relayed provider give 502, unless there is a more specific code.
+///
+/// Requires the `http` feature.
+//
+pub struct HttpAdapter<Wf: 'static = ThesWF>
+{
+	services: Arc< dyn ServiceMap<Wf> >,
+	max_size: usize,
+}
+
+
+// What came in on the connection.
+//
+enum Next
+{
+	Request{ method: String, path: String, body: Vec<u8>, close: bool },
+	Reject ( u16 ),
+	Eof,
+}
+
+
+
+impl<Wf: WireFormat> HttpAdapter<Wf>
+{
+	/// Serve `services`. Request bodies bigger than `max_size` bytes are rejected with 413.
+	//
+	pub fn new( services: Arc< dyn ServiceMap<Wf> >, max_size: usize ) -> Self
+	{
+		Self { services, max_size }
+	}
+
+
+	/// Serve the requests on one connection until the client closes it or asks to close it.
+	/// Malformed requests get an error response, after which the connection is closed.
+	//
+	pub async fn serve( &self, mut socket: impl FutAsyncRead + FutAsyncWrite + Unpin ) -> Result<(), WireErr>
+	{
+		let mut buf = Vec::new();
+
+		loop
+		{
+			match self.next( &mut socket, &mut buf ).await?
+			{
+				Next::Eof => return Ok(()),
+
+				Next::Reject( status ) =>
+				{
+					return respond( &mut socket, status, reason( status ).as_bytes(), true ).await;
+				}
+
+				Next::Request{ method, path, body, close } =>
+				{
+					let (status, body) = self.dispatch( &method, &path, body ).await;
+
+					respond( &mut socket, status, &body, close ).await?;
+
+					if close { return Ok(()) }
+				}
+			}
+		}
+	}
+
+
+	// Read the next request. Bytes that come after it stay in `buf`.
+	//
+	async fn next( &self, socket: &mut (impl FutAsyncRead + Unpin), buf: &mut Vec<u8> ) -> Result<Next, WireErr>
+	{
+		let mut chunk = [0u8; 1024];
+
+		// Read the head.
+		//
+		let (head_len, method, path, body_len, close) = loop
+		{
+			let mut headers = [ httparse::EMPTY_HEADER; MAX_HEADERS ];
+			let mut request = httparse::Request::new( &mut headers );
+
+			match request.parse( buf )
+			{
+				Ok( httparse::Status::Complete( head_len ) ) =>
+				{
+					let header = |name: &str| request.headers.iter()
+
+						.find( |h| h.name.eq_ignore_ascii_case( name ) )
+						.map( |h| String::from_utf8_lossy( h.value ).trim().to_string() )
+					;
+
+					let body_len = match header( "content-length" ).map( |l| l.parse::<usize>() )
+					{
+						None           => 0,
+						Some( Ok(l) )  => l,
+						Some( Err(_) ) => return Ok( Next::Reject( 400 ) ),
+					};
+
+					let close = header( "connection" ).map_or( false, |c| c.eq_ignore_ascii_case( "close" ) );
+
+					// unwrap: a complete request has a method and a path.
+					//
+					let method = request.method.unwrap().to_string();
+					let path   = request.path  .unwrap().to_string();
+
+					break (head_len, method, path, body_len, close);
+				}
+
+				Ok( httparse::Status::Partial ) if buf.len() > MAX_HEAD => return Ok( Next::Reject( 431 ) ),
+				Ok( httparse::Status::Partial )                         => {}
+				Err( _ )                                                => return Ok( Next::Reject( 400 ) ),
+			}
+
+			let read = socket.read( &mut chunk ).await?;
+
+			if read == 0
+			{
+				return match buf.is_empty()
+				{
+					true  => Ok( Next::Eof ),
+					false => Err( io::Error::from( io::ErrorKind::UnexpectedEof ).into() ),
+				}
+			}
+
+			buf.extend_from_slice( &chunk[ ..read ] );
+		};
+
+
+		if body_len > self.max_size
+		{
+			return Ok( Next::Reject( 413 ) );
+		}
+
+
+		// Read the body.
+		//
+		while buf.len() < head_len + body_len
+		{
+			let read = socket.read( &mut chunk ).await?;
+
+			if read == 0
+			{
+				return Err( io::Error::from( io::ErrorKind::UnexpectedEof ).into() );
+			}
+
+			buf.extend_from_slice( &chunk[ ..read ] );
+		}
+
+		let body = buf[ head_len..head_len + body_len ].to_vec();
+
+		buf.drain( ..head_len + body_len );
+
+		Ok( Next::Request{ method, path, body, close } )
+	}
+
+
+	// Run the call and give the status and body of the response.
+	//
+	async fn dispatch( &self, method: &str, path: &str, body: Vec<u8> ) -> (u16, Vec<u8>)
+	{
+		if method != "POST"
+		{
+			return (405, reason( 405 ).into());
+		}
+
+		let sid = match parse_sid( path )
+		{
+			Some( sid ) if self.services.services().any( |s| *s == sid ) => sid,
+			_                                                             => return (404, reason( 404 ).into()),
+		};
+
+		let mut frame = Wf::with_capacity( body.len() );
+
+		frame.set_sid( sid              );
+		frame.set_cid( ConnID::random() );
+
+		// unwrap: writing to an in memory buffer.
+		//
+		frame.write_all( &body ).unwrap();
+
+		let ctx = PeerErrCtx::default().context( "HttpAdapter".to_string() ).sid( sid );
+
+		let response = match self.services.call_service( frame, ctx, CancelToken::never() )
+		{
+			Ok ( fut ) => fut.await,
+			Err( err ) => Err( err ),
+		};
+
+		let status = match response
+		{
+			Ok( Response::CallResponse( resp ) ) => return (200, resp.frame().msg().to_vec()),
+
+			// A relay passes on the errors it gets from the provider as frames.
+			//
+			Ok( Response::WireFormat( wf ) ) if wf.sid().is_null() =>
+			{
+				CborErrorCodec.decode( wf.msg() ).map_or( 502, |err| remote_status( &err ) )
+			}
+
+			Ok( Response::WireFormat( wf ) ) => return (200, wf.msg().to_vec()),
+			Ok( Response::Nothing          ) => 500,
+			Err( err                       ) => peer_status( &err ),
+		};
+
+		(status, reason( status ).into())
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for HttpAdapter<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "HttpAdapter, max_size: {}, services: {:?}", self.max_size, self.services )
+	}
+}
+
+
+
+// The sid from a path like `/0123456789abcdef`, with or without 0x.
+//
+fn parse_sid( path: &str ) -> Option<ServiceID>
+{
+	ServiceID::from_hex( path.strip_prefix( '/' )? )
+}
+
+
+fn peer_status( err: &PeerErr ) -> u16
+{
+	match err
+	{
+		PeerErr::Deserialize     {..} => 400,
+		PeerErr::Unauthorized    {..} => 403,
+		PeerErr::Validation      {..} => 422,
+		PeerErr::UnknownService  {..} |
+		PeerErr::NoHandler       {..} => 404,
+		PeerErr::HandlerDead     {..} |
+		PeerErr::RelayGone       {..} |
+		PeerErr::ShuttingDown    {..} |
+		PeerErr::Cancelled       {..} |
+		PeerErr::CircuitOpen     {..} => 503,
+		PeerErr::Timeout         {..} => 504,
+		PeerErr::Remote{ err, .. }    => remote_status( err ),
+		_                             => 500,
+	}
+}
+
+
+fn remote_status( err: &ConnectionError ) -> u16
+{
+	match err
+	{
+		ConnectionError::Deserialize          {..} |
+		ConnectionError::DeserializeWireFormat{..} => 400,
+		ConnectionError::Unauthorized         {..} => 403,
+		ConnectionError::Validation           {..} => 422,
+		ConnectionError::UnknownService       {..} => 404,
+		ConnectionError::ShuttingDown         {..} |
+		ConnectionError::Cancelled            {..} => 503,
+		ConnectionError::Timeout              {..} => 504,
+		_                                          => 502,
+	}
+}
+
+
+fn reason( status: u16 ) -> &'static str
+{
+	match status
+	{
+		200 => "OK",
+		400 => "Bad Request",
+		403 => "Forbidden",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		413 => "Payload Too Large",
+		422 => "Unprocessable Entity",
+		431 => "Request Header Fields Too Large",
+		500 => "Internal Server Error",
+		502 => "Bad Gateway",
+		503 => "Service Unavailable",
+		504 => "Gateway Timeout",
+		_   => "",
+	}
+}
+
+
+async fn respond( socket: &mut (impl FutAsyncWrite + Unpin), status: u16, body: &[u8], close: bool ) -> Result<(), WireErr>
+{
+	let mut head = format!
+	(
+		"HTTP/1.1 {} {}\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n",
+		status, reason( status ), body.len()
+	);
+
+	if close
+	{
+		head.push_str( "Connection: close\r\n" );
+	}
+
+	head.push_str( "\r\n" );
+
+	socket.write_all( head.as_bytes() ).await?;
+	socket.write_all( body            ).await?;
+	socket.flush().await?;
+
+	Ok(())
+}
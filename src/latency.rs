@@ -0,0 +1,206 @@
+use
+{
+	crate :: { import::*, *, peer::Response } ,
+	std   :: { time::Instant                } ,
+};
+
+
+/// A [ServiceMap] in front of another one that measures how long incoming calls take per service, from
+/// the moment the call is handed to the service map, before deserializing, until the response is ready.
+/// Get the statistics with [`LatencyMap::snapshot`]. Clones share the measurements, so keep one to read
+/// them after registering the other with a [Peer].
+///
+/// Percentiles are computed over the most recent calls of each service, see [`LatencyMap::window`].
+/// Calls that fail or get cancelled are counted as well. Sends and streams are passed on to the inner
+/// service map as is, since their handlers don't report when they are done.
+//
+pub struct LatencyMap<Wf: 'static = ThesWF>
+{
+	inner  : Arc< dyn ServiceMap<Wf> >                   ,
+	samples: Arc< Mutex< HashMap<ServiceID, Samples> > > ,
+	window : usize                                       ,
+}
+
+
+#[ derive( Default ) ]
+//
+struct Samples
+{
+	count : u64                ,
+	recent: VecDeque<Duration> ,
+}
+
+
+
+/// Latency statistics for the calls to one service, see [LatencyMap].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub struct LatencyStats
+{
+	/// The total number of calls measured.
+	//
+	pub count: u64,
+
+	/// The median over the most recent calls.
+	//
+	pub p50: Duration,
+
+	/// The 99th percentile over the most recent calls.
+	//
+	pub p99: Duration,
+
+	/// The slowest of the most recent calls.
+	//
+	pub max: Duration,
+}
+
+
+
+impl<Wf: WireFormat> LatencyMap<Wf>
+{
+	/// Measure the calls handled by `inner`. By default percentiles cover the last 1024 calls per service.
+	//
+	pub fn new( inner: Arc< dyn ServiceMap<Wf> > ) -> Self
+	{
+		Self
+		{
+			inner                                             ,
+			samples: Arc::new( Mutex::new( HashMap::new() ) ) ,
+			window : 1024                                     ,
+		}
+	}
+
+
+	/// How many of the most recent calls per service the percentiles are computed over.
+	//
+	pub fn window( mut self, window: NonZeroUsize ) -> Self
+	{
+		self.window = window.get();
+		self
+	}
+
+
+	/// The statistics of every service that has been called so far.
+	//
+	pub fn snapshot( &self ) -> HashMap<ServiceID, LatencyStats>
+	{
+		self.samples.lock().iter().map( |(sid, samples)|
+		{
+			let mut sorted: Vec<Duration> = samples.recent.iter().copied().collect();
+
+			sorted.sort_unstable();
+
+			let stats = LatencyStats
+			{
+				count: samples.count                              ,
+				p50  : percentile( &sorted, 50 )                  ,
+				p99  : percentile( &sorted, 99 )                  ,
+				max  : sorted.last().copied().unwrap_or_default() ,
+			};
+
+			(*sid, stats)
+
+		}).collect()
+	}
+
+
+	fn record( samples: &Mutex< HashMap<ServiceID, Samples> >, window: usize, sid: ServiceID, took: Duration )
+	{
+		let mut samples = samples.lock();
+		let     entry   = samples.entry( sid ).or_default();
+
+		if entry.recent.len() >= window
+		{
+			entry.recent.pop_front();
+		}
+
+		entry.recent.push_back( took );
+		entry.count += 1;
+	}
+}
+
+
+// Nearest rank percentile of sorted samples.
+//
+fn percentile( sorted: &[Duration], pct: usize ) -> Duration
+{
+	if sorted.is_empty() { return Duration::default() }
+
+	let rank = ( sorted.len() * pct + 99 ) / 100;
+
+	sorted[ rank.max( 1 ) - 1 ]
+}
+
+
+
+impl<Wf: WireFormat> ServiceMap<Wf> for LatencyMap<Wf>
+{
+	fn send_service( &self, msg: Wf, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.inner.send_service( msg, ctx )
+	}
+
+
+	fn call_service( &self, msg: Wf, ctx: PeerErrCtx, cancel: CancelToken )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		let sid     = msg.sid();
+		let start   = Instant::now();
+		let fut     = self.inner.call_service( msg, ctx, cancel )?;
+		let samples = self.samples.clone();
+		let window  = self.window;
+
+		Ok( async move
+		{
+			let response = fut.await;
+
+			Self::record( &samples, window, sid, start.elapsed() );
+
+			response
+
+		}.boxed() )
+	}
+
+
+	fn open_stream( &self, msg: Wf, channel: StreamChannel<Wf>, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.inner.open_stream( msg, channel, ctx )
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		self.inner.services()
+	}
+}
+
+
+
+impl<Wf> Clone for LatencyMap<Wf>
+{
+	fn clone( &self ) -> Self
+	{
+		Self
+		{
+			inner  : self.inner  .clone() ,
+			samples: self.samples.clone() ,
+			window : self.window          ,
+		}
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for LatencyMap<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "LatencyMap, services: {}, inner: {:?}", self.samples.lock().len(), self.inner )
+	}
+}
@@ -21,20 +21,54 @@
 
 
 pub mod peer              ;
+    mod alias_map         ;
+    mod circuit_breaker   ;
+    mod delivery          ;
+    mod fanout            ;
+    mod fn_handler        ;
+    mod latency           ;
+    mod local_peer        ;
+    mod pass_through      ;
     mod relay_map         ;
+    mod relay_pool        ;
     mod pub_sub           ;
+    mod rate_limit        ;
+    mod raw_peer_sink     ;
+    mod raw_response      ;
+    mod response_cache    ;
+    mod retry             ;
     mod service_handler   ;
     mod service_map       ;
     mod service_map_macro ;
 pub mod thes_wf           ;
 pub mod wire_format       ;
 
+#[ cfg( feature = "blocking" ) ] mod blocking;
+#[ cfg( feature = "blocking" ) ] pub use blocking::*;
+
+#[ cfg( feature = "http" ) ] mod http;
+#[ cfg( feature = "http" ) ] pub use http::*;
+
 pub use
 {
 	thes_wf           :: * ,
+	alias_map         :: * ,
+	circuit_breaker   :: * ,
+	delivery          :: * ,
+	fanout            :: * ,
+	fn_handler        :: * ,
+	latency           :: * ,
+	local_peer        :: * ,
+	pass_through      :: * ,
 	peer              :: * ,
 	pub_sub           :: * ,
+	rate_limit        :: * ,
+	raw_peer_sink     :: * ,
+	raw_response      :: * ,
+	response_cache    :: * ,
+	retry             :: * ,
 	relay_map         :: * ,
+	relay_pool        :: * ,
 	service_handler   :: * ,
 	service_map       :: * ,
 	service_map_macro :: * ,
@@ -79,25 +113,25 @@ mod import
 		rand            :: { Rng                                                 } ,
 		serde           :: { Serialize, Deserialize                              } ,
 		thespis         :: { *                                                   } ,
-		thespis_impl    :: { Addr, ThesErr                                       } ,
+		thespis_impl    :: { Addr, ThesErr, WeakAddr                             } ,
 		twox_hash       :: { XxHash64                                            } ,
 
 		std ::
 		{
-			collections  :: { HashMap, VecDeque                 } ,
-			convert      :: { TryFrom, TryInto                  } ,
-			fmt                                                   ,
-			io                                                    ,
-			future       :: { Future                            } ,
-			hash         :: { Hasher                            } ,
-			marker       :: { PhantomData                       } ,
-			num          :: { NonZeroUsize                      } ,
-			ops          :: { DerefMut                          } ,
-			pin          :: { Pin                               } ,
-			sync         :: { Arc                               } ,
-			sync::atomic :: { AtomicI64, AtomicU64, Ordering::* } ,
-			task         :: { Poll, Context, Waker              } ,
-			time         :: { Duration                          } ,
+			collections  :: { HashMap, HashSet, VecDeque                                 } ,
+			convert      :: { TryFrom, TryInto                                           } ,
+			fmt                                                                            ,
+			io                                                                             ,
+			future       :: { Future                                                     } ,
+			hash         :: { Hasher                                                     } ,
+			marker       :: { PhantomData                                                } ,
+			num          :: { NonZeroUsize                                               } ,
+			ops          :: { DerefMut                                                   } ,
+			pin          :: { Pin                                                        } ,
+			sync         :: { Arc, Weak                                                  } ,
+			sync::atomic :: { AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering::* } ,
+			task         :: { Poll, Context, Waker                                       } ,
+			time         :: { Duration, SystemTime                                       } ,
 		},
 
 
@@ -0,0 +1,413 @@
+use
+{
+	crate           :: { import::*, *                                        } ,
+	async_executors :: { LocalSpawnHandle, LocalSpawnHandleExt               } ,
+	async_nursery   :: { LocalNurseExt                                       } ,
+	futures         :: { future::{ self, LocalBoxFuture }                    } ,
+	std             :: { cell::{ Cell, RefCell }, rc::{ Rc, Weak as RcWeak } } ,
+};
+
+
+/// Like [ServiceMap], but for a [LocalPeer]. The futures don't have to be `Send`, so handlers can keep
+/// their state in `Rc` and `RefCell`. `service_map!` generates `LocalServices`, which implements this.
+//
+pub trait LocalServiceMap<Wf>
+{
+	/// Deliver an incoming send to its handler.
+	//
+	fn send_service( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< LocalBoxFuture<'static, Result<(), PeerErr>>, PeerErr >;
+
+	/// Deliver an incoming call to its handler. The future resolves to the response frame.
+	//
+	fn call_service( &self, msg: Wf, ctx: PeerErrCtx ) -> Result< LocalBoxFuture<'static, Result<Wf, PeerErr>>, PeerErr >;
+}
+
+
+
+/// A single threaded peer for shared nothing designs, eg. a connection per core, each on its own `LocalPool`.
+///
+/// It processes calls and sends in both directions like [Peer], but nothing has to be `Send`. Incoming
+/// requests go to a [LocalServiceMap] and are processed in a nursery on a [LocalSpawnHandle], so handlers
+/// can keep their state in `Rc`. Use `LocalRemoteAddr` of `service_map!` to call the remote.
+///
+/// It isn't an actor. Clones share the connection, which is closed when the last clone is dropped or with
+/// [`LocalPeer::close`]. Only calls and sends are supported, the other features of [Peer], like streams,
+/// chunking, backpressure and events, are not.
+//
+pub struct LocalPeer<Wf>
+{
+	inner: Rc< Inner<Wf> >,
+}
+
+
+struct Inner<Wf>
+{
+	// Outgoing frames, written to the connection by a task.
+	//
+	out: mpsc::UnboundedSender<Wf>,
+
+	// The outgoing calls waiting for a response.
+	//
+	responses: RefCell< HashMap< ConnID, oneshot::Sender<Result<Wf, ConnectionError>> > >,
+
+	// Generates the cid of outgoing calls.
+	//
+	counter: Cell<u64>,
+
+	// Set by LocalPeer::close. The tasks only stop the next time the executor polls them.
+	//
+	closed: Cell<bool>,
+
+	// The tasks writing and reading the connection and the one tending the nursery. Dropping them
+	// stops the peer.
+	//
+	tasks: RefCell< Vec<JoinHandle<()>> >,
+}
+
+
+
+impl<Wf: WireFormat + 'static> LocalPeer<Wf>
+{
+	/// Create a LocalPeer and spawn the tasks that read and write the connection on `exec`.
+	/// Without `services`, incoming requests are refused with [`ConnectionError::UnknownService`].
+	//
+	pub fn new<S>
+	(
+		incoming: impl Stream< Item = Result<Wf, WireErr> > + Unpin + 'static ,
+		outgoing: impl Sink  < Wf, Error = WireErr        > + Unpin + 'static ,
+		services: Option< Rc<dyn LocalServiceMap<Wf>> >                       ,
+		exec    : S                                                           ,
+	)
+		-> Result<Self, PeerErr>
+
+		where S: LocalSpawnHandle<()> + Clone + 'static
+
+	{
+		let (tx, rx) = mpsc::unbounded();
+
+		let inner = Rc::new( Inner
+		{
+			out      : tx                    ,
+			responses: RefCell::default()    ,
+			counter  : Cell::new( 1 )        ,
+			closed   : Cell::new( false )    ,
+			tasks    : RefCell::default()    ,
+		});
+
+		let (nursery, nursery_stream) = Nursery::new( exec.clone() );
+
+		let write = async move
+		{
+			if let Err( e ) = rx.map( Ok::<_, WireErr> ).forward( outgoing ).await
+			{
+				error!( "LocalPeer: failed to write outgoing frame: {}", e );
+			}
+		};
+
+		let listen = Self::listen( incoming, services, Rc::downgrade( &inner ), nursery );
+		let tend   = nursery_stream.for_each( |_| future::ready(()) );
+
+		for task in vec![ write.boxed_local(), listen.boxed_local(), tend.boxed_local() ]
+		{
+			let handle = exec.spawn_handle_local( task ).map_err( |_|
+			{
+				PeerErr::Spawn{ ctx: Self::ctx( None, None, "LocalPeer: spawn task" ) }
+			})?;
+
+			inner.tasks.borrow_mut().push( handle );
+		}
+
+		Ok( Self{ inner } )
+	}
+
+
+	/// Call the remote with `frame`, which must have the sid of the service. The cid is set by the peer.
+	/// Resolves to the response frame or the error the remote sent back.
+	//
+	pub async fn call( &self, mut frame: Wf ) -> Result< Result<Wf, ConnectionError>, PeerErr >
+	{
+		let sid = frame.sid();
+		let cid = self.inner.new_cid();
+
+		frame.set_cid( cid );
+
+		let (tx, rx) = oneshot::channel();
+
+		self.inner.responses.borrow_mut().insert( cid, tx );
+
+		if self.inner.closed.get() || self.inner.out.unbounded_send( frame ).is_err()
+		{
+			self.inner.responses.borrow_mut().remove( &cid );
+
+			return Err( PeerErr::ConnectionClosed{ ctx: Self::ctx( sid, cid, "LocalPeer: call remote service" ) } );
+		}
+
+		// The sender is dropped when the connection closes.
+		//
+		rx.await.map_err( |_| PeerErr::ConnectionClosed
+		{
+			ctx: Self::ctx( sid, cid, "LocalPeer: connection closed before the response came in" )
+		})
+	}
+
+
+	/// Send `frame` to the remote, which must have the sid of the service.
+	//
+	pub fn send( &self, mut frame: Wf ) -> Result<(), PeerErr>
+	{
+		let sid = frame.sid();
+
+		frame.set_cid( ConnID::null() );
+
+		let closed = PeerErr::ConnectionClosed{ ctx: Self::ctx( sid, None, "LocalPeer: send to remote service" ) };
+
+		if self.inner.closed.get()
+		{
+			return Err( closed );
+		}
+
+		self.inner.out.unbounded_send( frame ).map_err( |_| closed )
+	}
+
+
+	/// Close the connection for all clones. Requests in progress are dropped and calls waiting for a
+	/// response fail with [`PeerErr::ConnectionClosed`].
+	//
+	pub fn close( &self )
+	{
+		self.inner.closed.set( true );
+		self.inner.tasks.borrow_mut().clear();
+		self.inner.responses.borrow_mut().clear();
+	}
+
+
+	// Read incoming frames until the connection closes.
+	//
+	async fn listen<S>
+	(
+		mut incoming: impl Stream< Item = Result<Wf, WireErr> > + Unpin ,
+		    services: Option< Rc<dyn LocalServiceMap<Wf>> >             ,
+		    peer    : RcWeak< Inner<Wf> >                               ,
+		    nursery : Nursery<S, ()>                                    ,
+	)
+		where S: LocalSpawnHandle<()> + Clone + 'static
+	{
+		while let Some( frame ) = incoming.next().await
+		{
+			let inner = match peer.upgrade()
+			{
+				Some( inner ) => inner,
+				None          => return,
+			};
+
+			let frame = match frame
+			{
+				Ok ( frame ) => frame,
+				Err( error ) =>
+				{
+					error!( "LocalPeer: failed to read incoming frame: {}", error );
+					continue;
+				}
+			};
+
+			let sid = frame.sid();
+			let cid = frame.cid();
+
+			match frame.kind()
+			{
+				FrameKind::Response => inner.respond( cid, Ok( frame ) ),
+
+				FrameKind::Error => match CborErrorCodec.decode( frame.msg() )
+				{
+					Ok ( err ) => inner.respond( cid, Err( err ) ),
+					Err( e   ) => error!( "LocalPeer: failed to deserialize error from remote: {}", e ),
+				}
+
+				FrameKind::Send =>
+				{
+					let ctx = Self::ctx( sid, None, "LocalPeer: handle incoming send" );
+
+					let fut = match &services
+					{
+						Some( sm ) => sm.send_service( frame, ctx ),
+						None       => Err( PeerErr::UnknownService{ ctx } ),
+					};
+
+					let fut = match fut
+					{
+						Ok ( fut ) => fut,
+						Err( e   ) => { error!( "{}", e ); continue; }
+					};
+
+					let nursed = nursery.nurse_local( async move
+					{
+						if let Err( e ) = fut.await
+						{
+							error!( "{}", e );
+						}
+					});
+
+					if nursed.is_err()
+					{
+						error!( "LocalPeer: failed to spawn incoming send, sid: {}", sid );
+					}
+				}
+
+				FrameKind::Call =>
+				{
+					let ctx = Self::ctx( sid, cid, "LocalPeer: handle incoming call" );
+					let out = inner.out.clone();
+
+					let fut = match &services
+					{
+						Some( sm ) => sm.call_service( frame, ctx ),
+						None       => Err( PeerErr::UnknownService{ ctx } ),
+					};
+
+					let fut = match fut
+					{
+						Ok ( fut ) => fut,
+						Err( e   ) =>
+						{
+							let _ = out.unbounded_send( Self::error_frame( cid, &e ) );
+							continue;
+						}
+					};
+
+					let nursed = nursery.nurse_local( async move
+					{
+						let resp = match fut.await
+						{
+							Ok ( wf ) => wf,
+							Err( e  ) => Self::error_frame( cid, &e ),
+						};
+
+						// If the connection is closed, nobody is waiting for the response.
+						//
+						let _ = out.unbounded_send( resp );
+					});
+
+					if nursed.is_err()
+					{
+						let ctx = Self::ctx( sid, cid, "LocalPeer: spawn incoming call" );
+
+						let _ = inner.out.unbounded_send( Self::error_frame( cid, &PeerErr::Spawn{ ctx } ) );
+					}
+				}
+
+				_ => warn!( "LocalPeer: dropping frame that is not a call, a send or a response, sid: {}", sid ),
+			}
+		}
+
+		// The connection is closed, calls waiting for a response fail.
+		//
+		if let Some( inner ) = peer.upgrade()
+		{
+			inner.responses.borrow_mut().clear();
+		}
+	}
+
+
+	fn error_frame( cid: ConnID, err: &PeerErr ) -> Wf
+	{
+		error!( "{}", err );
+
+		Peer::<Wf>::error_frame( &CborErrorCodec, cid, &ConnectionError::from( err ) )
+	}
+
+
+	fn ctx( sid: impl Into<Option<ServiceID>>, cid: impl Into<Option<ConnID>>, context: &str ) -> PeerErrCtx
+	{
+		PeerErrCtx::default().context( context.to_string() ).sid( sid ).cid( cid )
+	}
+}
+
+
+
+impl LocalPeer<ThesWF>
+{
+	/// Like [`LocalPeer::new`], for a connection that implements `AsyncRead` and `AsyncWrite`, with the
+	/// codec of [`Peer::from_async_read`].
+	//
+	pub fn from_async_read<S>
+	(
+		socket  : impl FutAsyncRead + FutAsyncWrite + Unpin + 'static ,
+		max_size: usize                                                ,
+		services: Option< Rc<dyn LocalServiceMap<ThesWF>> >            ,
+		exec    : S                                                    ,
+	)
+		-> Result< Self, PeerErr >
+
+		where S: LocalSpawnHandle<()> + Clone + 'static
+
+	{
+		let (reader, writer) = socket.split();
+
+		let stream = thes_wf::Decoder::new( reader, max_size );
+		let sink   = thes_wf::Encoder::new( writer, max_size );
+
+		LocalPeer::new( stream, sink, services, exec )
+	}
+}
+
+
+
+impl<Wf> Inner<Wf>
+{
+	// Generate a cid for an outgoing call that is not null and not in use.
+	//
+	fn new_cid( &self ) -> ConnID
+	{
+		loop
+		{
+			let n = self.counter.get();
+			self.counter.set( n.wrapping_add( 1 ) );
+
+			let cid = ConnID::from( n );
+
+			if !cid.is_null() && !self.responses.borrow().contains_key( &cid )
+			{
+				return cid;
+			}
+		}
+	}
+
+
+	// Deliver what came back for an outgoing call.
+	//
+	fn respond( &self, cid: ConnID, resp: Result<Wf, ConnectionError> )
+	{
+		match self.responses.borrow_mut().remove( &cid )
+		{
+			// The caller might no longer be waiting.
+			//
+			Some( tx ) => { let _ = tx.send( resp ); }
+
+			None => match resp
+			{
+				Ok (_  ) => debug!( "LocalPeer: dropping response for unknown cid: {}", cid ),
+				Err(err) => warn! ( "LocalPeer: error from remote: {:?}", err               ),
+			}
+		}
+	}
+}
+
+
+
+impl<Wf> Clone for LocalPeer<Wf>
+{
+	fn clone( &self ) -> Self
+	{
+		Self { inner: self.inner.clone() }
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for LocalPeer<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "LocalPeer, open calls: {}", self.inner.responses.borrow().len() )
+	}
+}
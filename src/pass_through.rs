@@ -0,0 +1,104 @@
+use crate :: { import::*, *, peer::Response };
+
+
+/// A [ServiceMap] for proxies that handles the services of a local service map and passes all others
+/// through to a downstream [ServiceHandler] as opaque frames, without deserializing them. This composes
+/// a local map with a [RelayMap], so it can be registered with a [Peer] as a single map.
+///
+/// The peer only delivers the services a map lists, so the services to pass through still have to be
+/// given by sid. Those the local map provides are always handled locally. Streams are only supported
+/// by the local map.
+//
+pub struct PassThroughMap<Wf: 'static = ThesWF>
+{
+	local   : Arc< dyn ServiceMap<Wf> > ,
+	known   : HashSet<ServiceID>        ,
+	relay   : RelayMap<Wf>              ,
+	services: Vec<ServiceID>            ,
+}
+
+
+impl<Wf: WireFormat> PassThroughMap<Wf>
+{
+	/// Handle the services of `local` and pass the requests for `forward` through to `downstream`.
+	//
+	pub fn new( local: Arc< dyn ServiceMap<Wf> >, downstream: ServiceHandler<Wf>, forward: Vec<ServiceID> ) -> Self
+	{
+		let known: HashSet<ServiceID> = local.services().copied().collect();
+
+		let forward : Vec<ServiceID> = forward.into_iter().filter( |sid| !known.contains( sid ) ).collect();
+		let services: Vec<ServiceID> = local.services().chain( &forward ).copied().collect();
+
+		Self
+		{
+			relay: RelayMap::new( downstream, forward ) ,
+			local                                       ,
+			known                                       ,
+			services                                    ,
+		}
+	}
+
+
+	/// The [RelayMap] that passes requests through, eg. to drain it before shutting down.
+	//
+	pub fn relay( &self ) -> &RelayMap<Wf>
+	{
+		&self.relay
+	}
+
+
+	// Where requests for this service go.
+	//
+	fn route( &self, sid: &ServiceID ) -> &dyn ServiceMap<Wf>
+	{
+		match self.known.contains( sid )
+		{
+			true  => &*self.local,
+			false => &self.relay ,
+		}
+	}
+}
+
+
+
+impl<Wf: WireFormat> ServiceMap<Wf> for PassThroughMap<Wf>
+{
+	fn send_service( &self, msg: Wf, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.route( &msg.sid() ).send_service( msg, ctx )
+	}
+
+
+	fn call_service( &self, msg: Wf, ctx: PeerErrCtx, cancel: CancelToken )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.route( &msg.sid() ).call_service( msg, ctx, cancel )
+	}
+
+
+	fn open_stream( &self, msg: Wf, channel: StreamChannel<Wf>, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.route( &msg.sid() ).open_stream( msg, channel, ctx )
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		Box::new( self.services.iter() )
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for PassThroughMap<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "PassThroughMap, local: {:?}, passed through: {}", self.local, self.services.len() - self.known.len() )
+	}
+}
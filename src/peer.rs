@@ -1,32 +1,96 @@
 //! The peer module holds everything that deals with managing a remote connection over which
 //! actor messages can be sent and received.
 //
-use crate :: { import::*, * };
+use
+{
+	crate   :: { import::*, *                            } ,
+	futures :: { future::{ select, Either, AbortHandle } } ,
+};
 
 
     mod backpressure      ;
     mod call              ;
     mod call_response     ;
+    mod capacity          ;
+    mod cancel_all        ;
+    mod cancel_token      ;
+    mod chunked           ;
     mod close_connection  ;
+#[ cfg( feature = "compress" ) ]
+    mod compression       ;
+    mod connect           ;
+    mod control           ;
     mod connection_error  ;
+    mod duplicate_response;
+    mod error_codec       ;
+    mod fair_queue        ;
+    mod frame_size        ;
+    mod idle_timeout      ;
+    mod in_process        ;
     mod incoming          ;
+    mod inflight_bytes    ;
+    mod lifecycle         ;
+    mod manual_dispatch   ;
+    mod memory_limit      ;
+    mod open_calls        ;
     mod peer_err          ;
     mod peer_event        ;
+    mod ping              ;
 pub mod request_error     ;
+    mod reload_services   ;
     mod response          ;
+    mod send_queue        ;
+    mod shutdown          ;
+    mod stall             ;
+    mod status            ;
+    mod stream            ;
+    mod swap_transport    ;
     mod timeout           ;
 
-pub use backpressure      :: { BackPressure        } ;
-pub use call              :: { Call                } ;
-pub use call_response     :: { CallResponse        } ;
-pub use close_connection  :: { CloseConnection     } ;
-pub use connection_error  :: { ConnectionError     } ;
-    use incoming          :: { Incoming            } ;
-pub use peer_err          :: { PeerErr, PeerErrCtx } ;
-pub use peer_event        :: { PeerEvent           } ;
-    use request_error     :: { RequestError        } ;
-pub use response          :: { Response            } ;
-    use timeout           :: { Timeout             } ;
+pub use backpressure      :: { BackPressure             } ;
+pub use call              :: { Call, DetachedCall       } ;
+pub use call              :: { ChannelCall              } ;
+pub use call_response     :: { CallResponse             } ;
+pub use capacity          :: { AdvertiseCapacity        } ;
+pub use cancel_all        :: { CancelAll                } ;
+pub use cancel_token      :: { CancelToken, Cancellable } ;
+    use cancel_token      :: { CancelSource             } ;
+pub use chunked           :: { Chunked                  } ;
+    use chunked           :: { Reassembly               } ;
+pub use close_connection  :: { CloseConnection          } ;
+pub use close_connection  :: { CloseReason              } ;
+    use close_connection  :: { IncomingEnded            } ;
+    use duplicate_response:: { Answered                 } ;
+#[ cfg( feature = "compress" ) ]
+pub use compression       :: { SetCompressionThreshold  } ;
+pub use connection_error  :: { ConnectionError          } ;
+pub use control           :: { Control                  } ;
+pub use error_codec       :: { ErrorCodec               } ;
+pub use error_codec       :: { CborErrorCodec           } ;
+pub use frame_size        :: { QueryMaxFrameSize        } ;
+pub use in_process        :: { PairHandles              } ;
+    use incoming          :: { Incoming                 } ;
+pub use open_calls        :: { GetOpenCalls             } ;
+pub use peer_err          :: { PeerErr, PeerErrCtx      } ;
+pub use peer_event        :: { PeerEvent, PeerEventsExt } ;
+    use ping              :: { Ping                     } ;
+    use request_error     :: { RequestError             } ;
+pub use reload_services   :: { ReloadServices           } ;
+pub use response          :: { Response                 } ;
+pub use send_queue        :: { OverflowPolicy           } ;
+    use send_queue        :: { SendQueue                } ;
+pub use shutdown          :: { Shutdown                 } ;
+pub use status            :: { GetStatus, PeerStatus    } ;
+pub use stream            :: { OpenStream, Streaming    } ;
+pub use stream            :: { StreamChannel, StreamRx  } ;
+pub use stream            :: { StreamSink, STREAM_WINDOW } ;
+    use stream            :: { SendWindow               } ;
+pub use swap_transport    :: { SwapTransport            } ;
+    use inflight_bytes    :: { ByteBudget, InflightBytes } ;
+    use memory_limit      :: { MemoryAccount, MemoryLimitHit } ;
+    use lifecycle         :: { Connected                } ;
+    use fair_queue        :: { FairQueue, QueueRoom     } ;
+    use timeout           :: { Timeout                  } ;
 
 
 // Reduce trait bound boilerplate, since we have to repeat them all over
@@ -72,6 +136,8 @@ impl<T, Wf> BoundsOut<Wf> for T
 /// actor/connection, and you can tell the peer to start/stop exposing a certain service. Once
 /// the mailbox for the peer has been started, you can only communicate to it by means of messages,
 /// so the messages `AddServices` and `RemoveServices` can be used to convey runtime instructions.
+/// To replace all service maps at once, eg. when reloading configuration, send [`ReloadServices`].
+/// The connection and calls that are in flight will not be affected.
 ///
 /// In principle you setup the peer with at least one ServiceMap before starting it, that way it
 /// is fully operational before it receives the first incoming message. `service_map!` let's you
@@ -102,9 +168,18 @@ impl<T, Wf> BoundsOut<Wf> for T
 /// Once you have serialized a message as `ThesWF`, sending that directly to `Peer` will be considered
 /// a Send to a remote actor and it will just be sent out. For a Call, there is the Call message type,
 /// which will resolve to a channel you can await in order to get your response from the remote process.
+/// Messages that are too big for a single frame can be sent with [`Chunked`] or [`Call::chunked`].
 /// The `service_map!` macro provides a `RemoteAddress` type which acts much the same as a local actor address
 /// and will accept messages of all services that are defined in the service map.
 ///
+/// ### Order of outgoing frames
+///
+/// All outgoing frames go through the mailbox of the peer, which writes a frame completely to the
+/// connection before it processes the next message. So frames never interleave on the wire, however
+/// many tasks send over clones of the peer address or of a `RemoteAddress` at the same time. The frames
+/// of a single sender go out in the order it sent them, those of different senders in the order they
+/// reach the mailbox.
+///
 /// ### Closing the connection
 ///
 /// The reasoning behind a peer is that it is tied to a stream/sink, often a framed connection.
@@ -114,6 +189,10 @@ impl<T, Wf> BoundsOut<Wf> for T
 /// If the remote closes the connection, and you are no longer holding any addresses to this
 /// peer (or recipients for remote actors), then the peer will get dropped.
 ///
+/// Since the peer holds its own address, it stays around until the connection is closed, even
+/// when you drop all addresses. For short lived connections, [`Peer::set_auto_close_on_idle`] lets
+/// the peer drop its own address while it has no work in progress.
+///
 /// If you do hold recipients and try to send on them, 2 things can happen. Since Send is like
 /// throwing a message in a bottle, without feedback, it's infallible, so your message will
 /// just get dropped silently. If you use call, which returns a result, you will get an error
@@ -147,6 +226,12 @@ pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 	//
 	outgoing: Option< Box<dyn BoundsOut<Wf>> >,
 
+	// Hands a new incoming stream to the task reading the connection, see SwapTransport. The sinks we
+	// swapped out are kept until the connection closes.
+	//
+	swap_incoming: mpsc::UnboundedSender< Box<dyn BoundsIn<Wf>> >,
+	retired      : Vec< Box<dyn BoundsOut<Wf>> >,
+
 	/// This is needed so that the loop listening to the incoming stream can send messages to this actor.
 	/// The loop runs in parallel of the rest of the actor, yet processing incoming messages need mutable
 	/// access to our state, so we have to pass through a message, or we need to put everything in Rc<RefCell>>.
@@ -156,6 +241,15 @@ pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 	//
 	addr: Option< Addr<Self> >,
 
+	// Our own address without keeping our mailbox alive. Tasks that run for the lifetime of the peer use
+	// this, so our mailbox can stop when `addr` is dropped.
+	//
+	weak_addr: WeakAddr<Self>,
+
+	// Whether to drop `addr` while we have no work in progress.
+	//
+	auto_close_on_idle: bool,
+
 	// The ID of this actor. Since the addr isn't always there (eg. after we start closing the connection),
 	// it's a royal PITA not to have these directly available. Keeping them after the addr is gone also means
 	// better error messages.
@@ -168,9 +262,32 @@ pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 	//
 	services: HashMap< ServiceID, Arc<dyn ServiceMap<Wf>> >,
 
-	/// We use oneshot channels to give clients a future that will resolve to their response.
+	/// The service maps for incoming requests on other channels than the default one, by channel id,
+	/// see register_channel.
+	//
+	channels: HashMap< u64, HashMap< ServiceID, Arc<dyn ServiceMap<Wf>> > >,
+
+	/// We use oneshot channels to give clients a future that will resolve to their response. The sid
+	/// is kept for GetOpenCalls.
+	//
+	responses: HashMap< ConnID, (ServiceID, oneshot::Sender<Result<Wf, ConnectionError>>) >,
+
+	/// The outgoing calls that got their response most recently, to recognize duplicate responses.
 	//
-	responses: HashMap< ConnID, oneshot::Sender<Result<Wf, ConnectionError>> >,
+	answered: Answered,
+
+	/// Chunked messages that are being reassembled, by transfer id.
+	//
+	chunks: HashMap< ConnID, Reassembly<Wf> >,
+
+	// The maximum size of a message reassembled from chunks.
+	//
+	max_chunked_size: usize,
+
+	// The maximum number of chunked messages reassembled at the same time and their maximum total size.
+	//
+	max_reassemblies    : usize,
+	max_reassembly_bytes: usize,
 
 	/// The pharos allows us to have observers.
 	//
@@ -196,6 +313,10 @@ pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 	//
 	closed: bool,
 
+	// Cancelled by close connection, so handlers of incoming calls can stop working.
+	//
+	cancel: CancelSource,
+
 	// The counter for conn_id. This will wrap. If there are still old connections
 	// open by the time this wraps, we have a problem. It's quite unlikely to happen though.
 	// It would mean this peer has an outstanding call that is still open by the time
@@ -203,6 +324,131 @@ pub struct Peer<Wf: 'static + WireFormat = ThesWF>
 	//
 	conn_id_counter: AtomicU64,
 
+	// Replaces conn_id_counter for generating the cid of outgoing calls when set.
+	//
+	cid_source: Option< Box< dyn FnMut() -> ConnID + Send > >,
+
+	// The cids of incoming calls we haven't responded to yet.
+	//
+	inbound: HashSet<ConnID>,
+
+	// Whether outgoing calls get a deadline derived from our timeout.
+	//
+	propagate_deadline: bool,
+
+	// The local ends of open streams. The key holds whether we opened the stream, since
+	// both sides choose cids for the streams they open. Next to the sender, the number of bytes
+	// of items we received that the reader hasn't handed back to the remote yet.
+	//
+	streams: HashMap< (bool, ConnID), (mpsc::UnboundedSender<Wf>, Arc<AtomicUsize>) >,
+
+	// The send windows of open streams, with the same keys as `streams`.
+	//
+	stream_windows: HashMap< (bool, ConnID), Weak<SendWindow> >,
+
+	// The maximum frame size we announced to the remote and the one the remote announced to us.
+	//
+	max_size       : Option<usize>,
+	remote_max_size: Option<usize>,
+
+	// The last capacity the remote advertised, see AdvertiseCapacity.
+	//
+	remote_capacity: Option<usize>,
+
+	// Encodes the payload of the error frames we send and decodes the ones we receive.
+	//
+	error_codec: Arc<dyn ErrorCodec>,
+
+	// Our pings waiting for an answer, by nonce, with the time they were sent. See Peer::ping.
+	//
+	pings       : HashMap< u64, (std::time::Instant, oneshot::Sender<Duration>) >,
+	ping_counter: u64,
+
+	// Where to send incoming sends whose handling actor is dead.
+	//
+	dead_letters: Option< mpsc::Sender<(ServiceID, Wf)> >,
+
+	// Where incoming sends and calls go instead of the service maps, see manual_dispatch.
+	//
+	manual: Option< mpsc::UnboundedSender< Result<Wf, WireErr> > >,
+
+	// Whether to tell the remote about incoming sends that fail, see set_report_send_errors.
+	//
+	report_send_errors: bool,
+
+	// Limits the incoming sends waiting for their handling actor, see set_send_queue.
+	//
+	send_queue: Option<Arc<SendQueue>>,
+
+	// Whether to tell the remote about sends the send queue drops, see set_report_dropped_sends.
+	//
+	report_dropped_sends: bool,
+
+	// Decides whether incoming sends and calls may be delivered, before the service map sees them.
+	//
+	guard: Option< Box< dyn Fn( &Wf, &PeerErrCtx ) -> bool + Send > >,
+
+	// See the payload of each frame we send out and each one that comes in.
+	//
+	outgoing_hook: Option< Box< dyn Fn( ServiceID, ConnID, &[u8] ) + Send > >,
+	incoming_hook: Option< Box< dyn Fn( ServiceID, ConnID, &[u8] ) + Send > >,
+
+	// Run once when the mailbox starts and when the connection closes.
+	//
+	on_connect   : Option< Box< dyn FnOnce() + Send > >,
+	on_disconnect: Option< Box< dyn FnOnce( &CloseConnection, CloseReason ) + Send > >,
+
+	// Limits the bytes of incoming frames we hold, shared with the task reading the connection.
+	//
+	byte_budget: ByteBudget,
+
+	// Limits the memory this connection ties up, shared with the task reading the connection.
+	//
+	memory: MemoryAccount,
+
+	// Incoming calls waiting for a slot of the backpressure, see set_fair_queuing. The room left in the
+	// queue is shared with the task reading the connection.
+	//
+	fair_queue: Option< FairQueue<Wf> >,
+	queue_room: QueueRoom,
+
+	// Publish SinkStalled when writing a frame takes longer than this.
+	//
+	stall_threshold: Option<Duration>,
+
+	// Responses to incoming calls that are ready, but not sent out yet.
+	//
+	queued_responses: Arc<AtomicUsize>,
+
+	// Switches elide_null_header on the Encoder of the peers from from_async_read and friends.
+	//
+	elide_null_header: Option< Arc<AtomicBool> >,
+
+	// The threshold of the Compress sink, see SetCompressionThreshold.
+	//
+	#[ cfg( feature = "compress" ) ]
+	//
+	compress_threshold: Option<thes_wf::CompressThreshold>,
+
+	// Statistics for GetStatus.
+	//
+	bytes_in     : u64,
+	bytes_out    : u64,
+	last_activity: Option<SystemTime>,
+
+	// Close the connection when no frames go in or out for this long, see set_idle_timeout.
+	//
+	idle_timeout: Option<Duration>,
+	active_at   : std::time::Instant,
+
+	// Stops the task watching for an idle connection, so setting the timeout again replaces it.
+	//
+	idle_watch: Option<AbortHandle>,
+
+	// The address of the remote end of the transport, see set_remote_addr.
+	//
+	remote_addr: Option<std::net::SocketAddr>,
+
 
 	// When the remote closes the connection, we could immediately drop all outstanding tasks related to
 	// this peer. This makes sense for a request-response type connection, as it doesn't make sense to
@@ -234,7 +480,7 @@ impl Peer<ThesWF>
 	///
 	/// *max_size*: The maximum accepted message size in bytes. The codec will reject parsing a message from the
 	/// stream if it exceeds this size. Also used for encoding outgoing messages.
-	/// **Set the same max_size in the remote!**.
+	/// **Set the same max_size in the remote!**, or let the peers agree on it with [`Peer::announce_max_frame_size`].
 	///
 	/// *grace_period*: When the remote closes the connection, we could immediately drop all outstanding tasks related to
 	/// this peer. This makes sense for a request-response type connection, as it doesn't make sense to
@@ -258,11 +504,156 @@ impl Peer<ThesWF>
 	{
 		let (reader, writer) = socket.split();
 
-		let stream = thes_wf::Decoder::new( reader, max_size );
-		let sink   = thes_wf::Encoder::new( writer, max_size );
+		let memory     = MemoryAccount::default();
+		let mut stream = thes_wf::Decoder::new( reader, max_size );
+		let sink       = thes_wf::Encoder::new( writer, max_size );
+		let elide      = sink.elide_switch();
+
+		stream.on_progress( Self::partial_frames( memory.clone() ) );
+
+		let mut peer = Peer::with_memory( addr, stream, sink, Arc::new(exec), bp, grace_period, memory )?;
+
+		peer.elide_null_header = Some( elide );
+
+		Ok( peer )
+	}
+
+
+	/// Like [`Peer::from_async_read`], but frames go over the wire with a compact header of varints
+	/// instead of a fixed one, which saves most of the overhead for tiny messages. See
+	/// [`VarintEncoder`](crate::thes_wf::VarintEncoder) for the details. The remote must also be created
+	/// with this method.
+	///
+	/// `max_size` applies to the compact frames on the wire.
+	//
+	pub fn from_async_read_varint
+	(
+		addr        : Addr<Self>                                                 ,
+		socket      : impl FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static ,
+		max_size    : usize                                                      ,
+		exec        : impl PeerExec<ThesWF>                                      ,
+		bp          : Option<Arc<BackPressure>>                                  ,
+		grace_period: Option<Duration>                                           ,
+	)
+
+		-> Result< Self, PeerErr >
+
+	{
+		let (reader, writer) = socket.split();
+
+		let stream = thes_wf::VarintDecoder::new( reader, max_size );
+		let sink   = thes_wf::VarintEncoder::new( writer           );
 
 		Peer::new( addr, stream, sink, Arc::new(exec), bp, grace_period )
 	}
+
+
+	/// Like [`Peer::from_async_read`], but all frames are encrypted with ChaCha20-Poly1305 using a pre-shared
+	/// key. Both ends of the connection must use the same key. See [`FrameCipher`](crate::thes_wf::FrameCipher)
+	/// for the details.
+	///
+	/// `max_size` applies to the encrypted frames, which are [`ENCRYPT_OVERHEAD`](crate::thes_wf::ENCRYPT_OVERHEAD)
+	/// bytes bigger than the plain ones.
+	///
+	/// When an incoming frame fails to decrypt, a [`WireErr::Decrypt`] is reported as an event and the
+	/// connection is closed.
+	//
+	#[ cfg( feature = "encrypt" ) ]
+	//
+	pub fn from_async_read_encrypted
+	(
+		addr        : Addr<Self>                                                 ,
+		socket      : impl FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static ,
+		max_size    : usize                                                      ,
+		key         : &[u8; 32]                                                  ,
+		exec        : impl PeerExec<ThesWF>                                      ,
+		bp          : Option<Arc<BackPressure>>                                  ,
+		grace_period: Option<Duration>                                           ,
+	)
+
+		-> Result< Self, PeerErr >
+
+	{
+		let (reader, writer) = socket.split();
+		let cipher           = thes_wf::FrameCipher::new( key );
+		let memory           = MemoryAccount::default();
+		let mut decoder      = thes_wf::Decoder::new( reader, max_size );
+		let encoder          = thes_wf::Encoder::new( writer, max_size );
+		let elide            = encoder.elide_switch();
+
+		decoder.on_progress( Self::partial_frames( memory.clone() ) );
+
+		let stream = thes_wf::Decrypt::new( decoder, cipher.clone() );
+		let sink   = thes_wf::Encrypt::new( encoder, cipher         );
+
+		let mut peer = Peer::with_memory( addr, stream, sink, Arc::new(exec), bp, grace_period, memory )?;
+
+		peer.elide_null_header = Some( elide );
+
+		Ok( peer )
+	}
+
+
+	/// Like [`Peer::from_async_read`], but the payload of outgoing frames is compressed with LZ4 as
+	/// chosen by `compression`, eg. only responses, see [`Compress`](crate::thes_wf::Compress). The
+	/// remote must also be created with this method, but it can choose a different [`Compression`](crate::thes_wf::Compression).
+	///
+	/// `max_size` applies to the frames on the wire, which are [`COMPRESS_OVERHEAD`](crate::thes_wf::COMPRESS_OVERHEAD)
+	/// bytes bigger than the plain ones when not compressed. Incoming frames that would decompress to more
+	/// than `max_size` bytes are refused.
+	//
+	#[ cfg( feature = "compress" ) ]
+	//
+	pub fn from_async_read_compressed
+	(
+		addr        : Addr<Self>                                                 ,
+		socket      : impl FutAsyncRead + FutAsyncWrite + Unpin + Send + 'static ,
+		max_size    : usize                                                      ,
+		compression : thes_wf::Compression                                       ,
+		exec        : impl PeerExec<ThesWF>                                      ,
+		bp          : Option<Arc<BackPressure>>                                  ,
+		grace_period: Option<Duration>                                           ,
+	)
+
+		-> Result< Self, PeerErr >
+
+	{
+		let (reader, writer) = socket.split();
+		let memory           = MemoryAccount::default();
+		let mut decoder      = thes_wf::Decoder::new( reader, max_size );
+		let encoder          = thes_wf::Encoder::new( writer, max_size );
+		let elide            = encoder.elide_switch();
+
+		decoder.on_progress( Self::partial_frames( memory.clone() ) );
+
+		let stream    = thes_wf::Decompress::new( decoder, max_size    );
+		let sink      = thes_wf::Compress  ::new( encoder, compression );
+		let threshold = sink.threshold();
+
+		let mut peer = Peer::with_memory( addr, stream, sink, Arc::new(exec), bp, grace_period, memory )?;
+
+		peer.set_compress_threshold( threshold );
+		peer.elide_null_header = Some( elide );
+
+		Ok( peer )
+	}
+
+
+	/// Leave out the header of outgoing frames with a null sid and connID and of control frames, eg. pings,
+	/// see [`Encoder::elide_null_header`](thes_wf::Encoder::elide_null_header). The remote must run a version
+	/// that puts them back. Off by default.
+	///
+	/// This works for the peers created with [`Peer::from_async_read`], [`Peer::from_async_read_encrypted`]
+	/// and [`Peer::from_async_read_compressed`]. With [`Peer::new`], configure the encoder instead.
+	//
+	pub fn set_elide_null_header( &mut self, elide: bool )
+	{
+		match &self.elide_null_header
+		{
+			Some( switch ) => switch.store( elide, Relaxed ),
+			None           => warn!( "{}: set_elide_null_header, but the encoder of this peer is unknown.", self.identify() ),
+		}
+	}
 }
 
 
@@ -355,6 +746,253 @@ impl<Wf: WireFormat> Peer<Wf>
 
 
 
+	/// Give outgoing calls a deadline of now + the timeout set with [`Peer::set_timeout`], so that the
+	/// remote, and any relays between us and the provider, can drop the call once we are no longer
+	/// waiting for the response. The remote answers such calls with a timeout. This defaults to false.
+	///
+	/// Calls that already carry a deadline, like calls we relay, always keep it, whether this
+	/// is set or not. See [`WireFormat::deadline`].
+	//
+	pub fn set_propagate_deadline( &mut self, propagate: bool )
+	{
+		self.propagate_deadline = propagate;
+	}
+
+
+
+	/// Replace the generator for the cid of outgoing calls. By default the peer uses a counter.
+	///
+	/// Whatever the source, a cid that is null or already in use by an outgoing call that is
+	/// waiting for its response is never used. A new one is requested instead, and after
+	/// 16 attempts the call fails with [`PeerErr::DuplicateCid`]. This also allows testing
+	/// collisions with a deterministic source.
+	//
+	pub fn set_cid_source( &mut self, source: impl FnMut() -> ConnID + Send + 'static )
+	{
+		self.cid_source = Some( Box::new( source ) );
+	}
+
+
+
+	/// Set the maximum size in bytes of a message reassembled from chunks sent with [`Chunked`] or
+	/// [`Call::chunked`]. This defaults to 64MiB. The transfer is aborted if the remote sends more.
+	/// See [`Peer::set_max_reassemblies`] for the limit on all messages being reassembled.
+	//
+	pub fn set_max_chunked_size( &mut self, max_size: usize )
+	{
+		self.max_chunked_size = max_size;
+	}
+
+
+
+	/// Limit the chunked messages that are being reassembled at the same time, so a remote can't tie up
+	/// memory by starting transfers it never finishes. `count` defaults to 8 and `total_size` to 128MiB.
+	/// A transfer that would exceed either is aborted with [`WireErr::OutOfMemory`], the ones in progress
+	/// are not affected.
+	//
+	pub fn set_max_reassemblies( &mut self, count: usize, total_size: usize )
+	{
+		self.max_reassemblies     = count;
+		self.max_reassembly_bytes = total_size;
+	}
+
+
+
+	/// Route incoming sends that can't be delivered because the handling actor is no longer running to
+	/// `dead_letters`, together with their sid, so the application can retry or persist them. Without this
+	/// they are dropped. [`PeerErr::HandlerDead`] is reported as an event either way.
+	///
+	/// When the channel is full, the task delivering the send waits for room. Calls are not affected,
+	/// the remote gets an error for those.
+	//
+	pub fn set_dead_letters( &mut self, dead_letters: mpsc::Sender<(ServiceID, Wf)> )
+	{
+		self.dead_letters = Some( dead_letters );
+	}
+
+
+
+	/// Send an error frame back to the remote when one of its sends can't be processed, eg. because it
+	/// doesn't deserialize or we don't provide the service. The remote publishes it as
+	/// [`PeerEvent::RemoteError`], with the sid of the send and no cid. Sends normally get no feedback,
+	/// so this defaults to false.
+	///
+	/// This is best effort, not an acknowledgement. No frame is sent when the send succeeds, and an error
+	/// frame can get lost like any other.
+	//
+	pub fn set_report_send_errors( &mut self, report: bool )
+	{
+		self.report_send_errors = report;
+	}
+
+
+
+	/// Install a guard that decides whether an incoming send or call may be delivered, eg. for authorization.
+	/// It gets the frame and the context of the request, which identifies this peer and the sid and cid,
+	/// and runs before the frame is handed to the service map, so nothing gets deserialized and no handler
+	/// runs when it returns false. A rejected call fails on the remote with [`ConnectionError::Unauthorized`],
+	/// rejected sends are dropped. Both are reported as [`PeerErr::Unauthorized`] in the events.
+	///
+	/// The guard also applies to streams the remote opens, with the frame carrying the sid of the service
+	/// and the payload of the open request, and to requests that go to [`Peer::manual_dispatch`]. A refused
+	/// stream is closed right away.
+	///
+	/// Only the latest guard is kept.
+	//
+	pub fn set_guard( &mut self, guard: impl Fn( &Wf, &PeerErrCtx ) -> bool + Send + 'static )
+	{
+		self.guard = Some( Box::new( guard ) );
+	}
+
+
+	/// Inspect the payload of every frame we send out, after serialization. The hook gets the sid, the cid
+	/// and the bytes, eg. to log payloads while debugging. It runs on the peer, so keep it quick. This
+	/// covers requests as well as responses and errors, including the errors the peer sends itself when it
+	/// can't process an incoming request, and with [`Chunked`] each chunk separately.
+	///
+	/// Only the latest hook is kept.
+	//
+	pub fn set_outgoing_hook( &mut self, hook: impl Fn( ServiceID, ConnID, &[u8] ) + Send + 'static )
+	{
+		self.outgoing_hook = Some( Box::new( hook ) );
+	}
+
+
+	/// Inspect the payload of every frame that comes in, before it gets deserialized. See [`Peer::set_outgoing_hook`].
+	//
+	pub fn set_incoming_hook( &mut self, hook: impl Fn( ServiceID, ConnID, &[u8] ) + Send + 'static )
+	{
+		self.incoming_hook = Some( Box::new( hook ) );
+	}
+
+
+
+	// Whether the guard lets this request through.
+	//
+	fn authorized( &self, frame: &Wf, ctx: &PeerErrCtx ) -> bool
+	{
+		self.guard.as_ref().map_or( true, |guard| guard( frame, ctx ) )
+	}
+
+
+
+	/// Let the mailbox of the peer stop once it has no work in progress and you have dropped all addresses
+	/// to it, without sending [`CloseConnection`]. Normally the peer holds its own address, so it only stops
+	/// when the connection closes. This is meant for short lived request/response connections. This defaults
+	/// to false.
+	///
+	/// Work in progress are outgoing calls waiting for a response, incoming calls we haven't answered yet
+	/// and open streams. Frames that come in while the peer shuts down are not processed. Dropping the peer
+	/// drops the connection, so the remote will see it closed.
+	//
+	pub fn set_auto_close_on_idle( &mut self, auto_close: bool )
+	{
+		self.auto_close_on_idle = auto_close;
+		self.update_idle();
+	}
+
+
+
+	// With auto close on idle, only hold our own address while there is work in progress. Call this
+	// whenever outgoing calls, incoming calls or streams get added or removed.
+	//
+	fn update_idle( &mut self )
+	{
+		if self.closed { return }
+
+		let busy = !self.auto_close_on_idle
+			|| !self.responses.is_empty()
+			|| !self.inbound  .is_empty()
+			|| !self.streams  .is_empty()
+		;
+
+		if !busy
+		{
+			self.addr = None;
+		}
+
+		// Only fails when our mailbox is stopping, in which case there is nothing to keep alive.
+		//
+		else if self.addr.is_none()
+		{
+			self.addr = self.weak_addr.strong().ok();
+		}
+	}
+
+
+
+	// Our own address, `None` once we are closed or our mailbox is stopping.
+	//
+	fn own_addr( &self ) -> Option< Addr<Self> >
+	{
+		if self.closed { return None }
+
+		self.addr.clone().or_else( || self.weak_addr.strong().ok() )
+	}
+
+
+
+	/// A [RawPeerSink] to push ready made frames to the remote, bypassing service types. It can be
+	/// cloned and holds the address of the peer, just like `RemoteAddr`, so get it before handing the
+	/// peer to its mailbox. Later on, build one from the address of the peer with [`RawPeerSink::new`].
+	///
+	/// Fails with [`PeerErr::ConnectionClosed`] once the connection is closed.
+	//
+	pub fn wire_sink( &self ) -> Result< RawPeerSink<Wf>, PeerErr >
+	{
+		let peer = self.own_addr().ok_or_else( ||
+		{
+			let ctx = self.ctx( None, None, "Peer::wire_sink" );
+
+			PeerErr::ConnectionClosed{ ctx }
+
+		})?;
+
+		Ok( RawPeerSink::new( peer ) )
+	}
+
+
+
+	/// Tell the remote the maximum size in bytes of the frames we accept, normally the `max_size` of our
+	/// decoder. The remote does the same and both peers use the smallest of both values as the limit for
+	/// outgoing frames, see [`Peer::max_frame_size`]. Sending a bigger frame fails right away with
+	/// [`WireErr::MessageSizeExceeded`] rather than having the remote drop the connection.
+	///
+	/// Call this before starting the mailbox of the peer, so the announcement is the first frame to go out.
+	/// Both ends need to announce, a peer that doesn't support the handshake will report the frame as an
+	/// unknown service. Note that when frames are encrypted, the announced size is that of the plain frames.
+	//
+	pub fn announce_max_frame_size( &mut self, max_size: usize ) -> Result<(), PeerErr>
+	{
+		self.max_size = Some( max_size );
+
+		let frame = Self::handshake_frame( max_size );
+
+		self.nursery.nurse( async move { Ok( Response::WireFormat( frame ) ) } ).map_err( |_|
+		{
+			let ctx = self.ctx( ServiceID::handshake(), None, "Announce maximum frame size" );
+
+			PeerErr::Spawn{ ctx }
+		})
+	}
+
+
+
+	/// The maximum size in bytes of outgoing frames. This is the smallest of the sizes announced with
+	/// [`Peer::announce_max_frame_size`] on both ends. Until the remote has announced its size, it is our own
+	/// and `None` if neither side announced one. Ask a peer whose mailbox is running with [`QueryMaxFrameSize`].
+	//
+	pub fn max_frame_size( &self ) -> Option<usize>
+	{
+		match ( self.max_size, self.remote_max_size )
+		{
+			( Some( ours ), Some( theirs ) ) => Some( ours.min( theirs ) ),
+			( ours        , theirs         ) => ours.or( theirs )        ,
+		}
+	}
+
+
+
 	/// Create a new peer to represent a connection to some remote.
 	/// `addr` is the actor address for this actor.
 	///
@@ -377,6 +1015,26 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		-> Result< Self, PeerErr >
 
+	{
+		Self::with_memory( addr, incoming, outgoing, exec, bp, grace_period, MemoryAccount::default() )
+	}
+
+
+	// Like new, but the memory limit is shared with the decoder, see Peer::partial_frames.
+	//
+	fn with_memory
+	(
+		addr        : Addr<Self>                                                                ,
+		incoming    : impl BoundsIn<Wf>                                                         ,
+		outgoing    : impl BoundsOut<Wf>                                                        ,
+		exec        : impl SpawnHandle<Result<Response<Wf>, PeerErr>> + Send + Sync + 'static   ,
+		bp          : Option<Arc<BackPressure>>                                                 ,
+		grace_period: Option<Duration>                                                          ,
+		memory      : MemoryAccount                                                             ,
+	)
+
+		-> Result< Self, PeerErr >
+
 	{
 		trace!( "{}: Create peer", &addr );
 
@@ -385,7 +1043,9 @@ impl<Wf: WireFormat> Peer<Wf>
 		let (nursery, nursery_stream) = Nursery::new( exec.clone() );
 
 
-		let nursery_handle = exec.spawn_handle( Self::listen_request_results( nursery_stream, addr.clone() ) )
+		let queued_responses = Arc::new( AtomicUsize::new( 0 ) );
+
+		let nursery_handle = exec.spawn_handle( Self::listen_request_results( nursery_stream, addr.weak(), queued_responses.clone() ) )
 
 			.map_err( |_| -> PeerErr
 			{
@@ -401,7 +1061,14 @@ impl<Wf: WireFormat> Peer<Wf>
 		;
 
 
-		nursery.nurse( Self::listen_incoming( incoming, addr.clone(), bp.clone() ) )
+		let byte_budget = ByteBudget::default();
+		let queue_room  = QueueRoom ::default();
+
+		let (swap_incoming, swaps) = mpsc::unbounded();
+
+		let listen = Self::listen_incoming( incoming, swaps, addr.weak(), bp.clone(), byte_budget.clone(), memory.clone(), queue_room.clone() );
+
+		nursery.nurse( listen )
 
 			.map_err( |_| -> PeerErr
 			{
@@ -417,13 +1084,21 @@ impl<Wf: WireFormat> Peer<Wf>
 			id             : addr.id()                  ,
 			name           : addr.name()                ,
 			outgoing       : Some( Box::new(outgoing) ) ,
+			swap_incoming                               ,
+			retired        : Vec::new()                 ,
+			weak_addr      : addr.weak()                ,
 			addr           : Some( addr )               ,
 			responses      : HashMap::new()             ,
+			answered       : Answered::default()        ,
+			chunks         : HashMap::new()             ,
+			max_chunked_size: 64 * 1024 * 1024          ,
 			services       : HashMap::new()             ,
+			channels       : HashMap::new()             ,
 			pharos         : Pharos::default()          ,
 			timeout        : Duration::from_secs(60)    ,
 			backpressure   : bp                         ,
 			closed         : false                      ,
+			cancel         : CancelSource::new()        ,
 			nursery_stream : Some( nursery_handle )     ,
 			nursery                                     ,
 			grace_period                                ,
@@ -431,6 +1106,49 @@ impl<Wf: WireFormat> Peer<Wf>
 			// must not start at 0. Zero has a special meaning.
 			//
 			conn_id_counter: AtomicU64::new(1),
+			cid_source     : None              ,
+			inbound        : HashSet::new()    ,
+
+			propagate_deadline: false,
+			streams           : HashMap::new(),
+			stream_windows    : HashMap::new(),
+			max_size          : None,
+			remote_max_size   : None,
+			remote_capacity   : None,
+			auto_close_on_idle: false,
+			bytes_in          : 0,
+			bytes_out         : 0,
+			last_activity     : None,
+			idle_timeout      : None,
+			active_at         : std::time::Instant::now(),
+			idle_watch        : None,
+			remote_addr       : None,
+			pings             : HashMap::new(),
+			ping_counter      : 0,
+			dead_letters      : None,
+			manual            : None,
+			report_send_errors: false,
+			send_queue        : None,
+			report_dropped_sends: false,
+			max_reassemblies    : 8,
+			max_reassembly_bytes: 128 * 1024 * 1024,
+			guard             : None,
+			outgoing_hook     : None,
+			incoming_hook     : None,
+			on_connect        : None,
+			on_disconnect     : None,
+			byte_budget       ,
+			memory            ,
+			fair_queue        : None,
+			queue_room        ,
+			stall_threshold   : None,
+			queued_responses  ,
+			error_codec       : Arc::new( CborErrorCodec ),
+			elide_null_header : None,
+
+			#[ cfg( feature = "compress" ) ]
+			//
+			compress_threshold: None,
 		})
 	}
 
@@ -452,13 +1170,27 @@ impl<Wf: WireFormat> Peer<Wf>
 	async fn listen_request_results
 	(
 		mut stream: NurseryStream<Result<Response<Wf>, PeerErr>> ,
-		mut addr  : Addr<Peer<Wf>>                               ,
+		    addr  : WeakAddr<Peer<Wf>>                           ,
+		    queued: Arc<AtomicUsize>                             ,
 	)
 		-> Result<Response<Wf>, PeerErr>
 
 	{
 		while let Some(result) = stream.next().await
 		{
+			// We don't keep our mailbox alive, see Peer::set_auto_close_on_idle. Normally the peer holds
+			// it's own address until the connection is closed, and then this task gets dropped.
+			//
+			let mut addr = match addr.strong()
+			{
+				Ok ( addr ) => addr,
+				Err( _    ) =>
+				{
+					warn!( "Mailbox of peer stopped, dropping the result of a request." );
+					continue;
+				}
+			};
+
 			// The result of addr.send
 			//
 			let res = match result
@@ -467,7 +1199,11 @@ impl<Wf: WireFormat> Peer<Wf>
 				{
 					Response::Nothing         => Ok(())               ,
 					Response::WireFormat  (x) => addr.send( x ).await ,
-					Response::CallResponse(x) => addr.send( x ).await ,
+					Response::CallResponse(x) =>
+					{
+						queued.fetch_add( 1, Relaxed );
+						addr.send( x ).await
+					}
 				}
 
 				Err(err) => addr.send( RequestError::from( err ) ).await
@@ -491,42 +1227,156 @@ impl<Wf: WireFormat> Peer<Wf>
 	//
 	async fn listen_incoming
 	(
-		mut incoming: impl BoundsIn<Wf>         ,
-		mut addr    : Addr<Peer<Wf>>            ,
-		    bp      : Option<Arc<BackPressure>> ,
+		incoming: impl BoundsIn<Wf>                                 ,
+		swaps   : mpsc::UnboundedReceiver< Box<dyn BoundsIn<Wf>> > ,
+		addr    : WeakAddr<Peer<Wf>>                                ,
+		bp      : Option<Arc<BackPressure>>                         ,
+		budget  : ByteBudget                                        ,
+		memory  : MemoryAccount                                     ,
+		room    : QueueRoom                                         ,
 	)
 		-> Result<Response<Wf>, PeerErr>
 
 	{
+		// See Peer::on_connect.
+		//
+		if let Ok( mut addr ) = addr.strong()
+		{
+			if addr.send( Connected ).await.is_err()
+			{
+				error!( "{} has panicked or it's inbox has been dropped.", Peer::identify_addr( &addr ) );
+			}
+		}
+
+
+		let mut incoming: Box<dyn BoundsIn<Wf>> = Box::new( incoming );
+		let mut swaps                           = Some( swaps );
+
+		// The streams swapped out, see SwapTransport. We don't read them anymore, but dropping them might
+		// close the connection before the remote has swapped.
+		//
+		let mut retired = Vec::new();
+
 		// This can fail if:
 		//
 		// the receiver is dropped. The receiver is our mailbox, so it should never be dropped
 		// as long as we have an address to it and this task would be dropped with it.
 		//
-		while let Some(msg) = incoming.next().await
+		loop
 		{
-			if let Some( ref bp ) = bp
+			let next = match &mut swaps
+			{
+				Some( rx ) => match select( incoming.next(), rx.next() ).await
+				{
+					Either::Left ( (msg , _) ) => Either::Left ( msg  ),
+					Either::Right( (swap, _) ) => Either::Right( swap ),
+				}
+
+				None => Either::Left( incoming.next().await ),
+			};
+
+			let msg = match next
+			{
+				Either::Left( Some( msg ) ) => msg,
+				Either::Left( None        ) => break,
+
+				Either::Right( Some( new ) ) =>
+				{
+					trace!( "swapping the incoming stream" );
+
+					retired.push( std::mem::replace( &mut incoming, new ) );
+					continue;
+				}
+
+				// The peer is gone, only happens when our mailbox stops.
+				//
+				Either::Right( None ) =>
+				{
+					swaps = None;
+					continue;
+				}
+			};
+
+			// With fair queuing, keep reading while calls can be queued, see Peer::set_fair_queuing.
+			//
+			let gate = room.lock().clone().or_else( || bp.clone() );
+
+			if let Some( ref gate ) = gate
 			{
 				trace!( "check for backpressure" );
 
-				bp.wait().await;
+				gate.wait().await;
 
 				trace!( "backpressure allows progress now." );
 			}
 
+			// Only fails when our mailbox is stopping, see Peer::set_auto_close_on_idle.
+			//
+			let mut addr = match addr.strong()
+			{
+				Ok ( addr ) => addr,
+				Err( _    ) => return Ok(Response::Nothing),
+			};
+
 			trace!( "{}: incoming message.", &addr );
 
-			if addr.send( Incoming{ msg } ).await.is_err()
+			let bytes  = budget.lock().clone();
+			let memory = memory.lock().clone();
+
+			let inflight = match &msg
+			{
+				Ok( frame ) =>
+				{
+					let budgets = bytes.iter().cloned().chain( memory.as_ref().map( |m| m.budget() ) ).collect();
+
+					InflightBytes::take( budgets, usize::try_from( frame.len() ).unwrap_or( usize::MAX ) )
+				}
+
+				Err( _ ) => None,
+			};
+
+			// Once the frame is queued, wait for room in the byte budget before reading the next one.
+			//
+			let waiter = bytes.filter( |_| inflight.is_some() );
+
+			if addr.send( Incoming{ msg, inflight } ).await.is_err()
 			{
 				error!( "{} has panicked or it's inbox has been dropped.", Peer::identify_addr( &addr ) );
 			}
+
+			if let Some( budget ) = waiter
+			{
+				budget.wait().await;
+			}
+
+			// Also the frame the decoder is reading counts for the memory limit, so check it even if this
+			// frame wasn't charged. The responses we wait for don't stop us from reading, see Peer::admit_call.
+			//
+			if let Some( memory ) = memory
+			{
+				if let Some( used ) = memory.exceeded()
+				{
+					if addr.send( MemoryLimitHit{ used } ).await.is_err()
+					{
+						error!( "{} has panicked or it's inbox has been dropped.", Peer::identify_addr( &addr ) );
+					}
+
+					memory.budget().wait().await;
+				}
+			}
 		}
 
+		let mut addr = match addr.strong()
+		{
+			Ok ( addr ) => addr,
+			Err( _    ) => return Ok(Response::Nothing),
+		};
+
 		trace!( "{}:  incoming stream end, closing out.", &addr );
 
 		// The connection was closed by remote, tell peer to clean up.
 		//
-		let res = addr.send( CloseConnection{ remote: true, reason: "Connection closed by remote.".to_string() } ).await;
+		let res = addr.send( IncomingEnded ).await;
 
 		// As we hold an address, the only way the mailbox can already be shut
 		// is if the peer panics, or the mailbox get's dropped. Since the mailbox
@@ -562,26 +1412,131 @@ impl<Wf: WireFormat> Peer<Wf>
 	}
 
 
+	/// Register a service map for the incoming requests on `channel`, see [`WireFormat::channel`]. Each channel
+	/// has its own set of services, so the same sid can be handled by a different service map on each channel,
+	/// eg. to serve several tenants over one connection. Requests on a channel for which nothing is registered
+	/// fail with [`ConnectionError::UnknownService`]. The service maps from [`Peer::register_services`] serve
+	/// the requests without a channel. Streams are always opened on the default channel.
+	///
+	/// Like with `register_services`, each service should be registered only once per channel. This will panic
+	/// in debug mode otherwise.
+	//
+	pub fn register_channel( &mut self, channel: u64, sm: Arc< dyn ServiceMap<Wf>> )
+	{
+		let identity = self.identify();
+		let services = self.channels.entry( channel ).or_default();
+
+		for sid in sm.services()
+		{
+			trace!( "{}: Register Service: {:?} on channel: {}", &identity, &sid, channel );
+
+			debug_assert!
+			(
+				!services.contains_key( &sid ),
+				"{}: Register Service: Can't register same service twice on a channel. sid: {}, channel: {}", &identity, &sid, channel,
+			);
+
+			services.insert( *sid, sm.clone() );
+		}
+	}
+
+
+	// The service map that handles `sid` on `channel`.
+	//
+	fn service_map( &self, channel: Option<u64>, sid: &ServiceID ) -> Option<&Arc<dyn ServiceMap<Wf>>>
+	{
+		match channel
+		{
+			None    => self.services.get( sid ),
+			Some(c) => self.channels.get( &c )?.get( sid ),
+		}
+	}
+
+
+	// Generate a cid for an outgoing call that is not null and doesn't collide with another
+	// outgoing call that is still waiting for a response.
+	//
+	fn new_cid( &mut self, sid: ServiceID ) -> Result<ConnID, PeerErr>
+	{
+		for _ in 0..16
+		{
+			let cid = match &mut self.cid_source
+			{
+				Some( source ) => source(),
+				None           => ConnID::from( self.conn_id_counter.fetch_add( 1, Relaxed ) ),
+			};
+
+			// Null is reserved for sends. The counter can also produce it when it wraps.
+			//
+			if cid.is_null() { continue; }
+
+			if self.responses.contains_key( &cid )
+			{
+				warn!( "{}: generated cid {} is already in use, generating a new one.", self.identify(), cid );
+				continue;
+			}
+
+			return Ok( cid );
+		}
+
+		let ctx = self.ctx( sid, None, "Generate cid for outgoing call" );
+
+		Err( PeerErr::DuplicateCid{ ctx } )
+	}
+
+
+
 	// actually send the message accross the wire
 	//
 	async fn send_msg( &mut self, msg: Wf ) -> Result<(), PeerErr>
 	{
 		trace!( "{}: sending OUT WireFormat", self.identify() );
 
+		if let Some( max_size ) = self.max_frame_size()
+		{
+			let size = usize::try_from( msg.len() ).unwrap_or( usize::MAX );
+
+			if size > max_size
+			{
+				let ctx    = self.ctx( msg.sid(), msg.cid(), "Sending out WireFormat" );
+				let source = WireErr::MessageSizeExceeded{ context: "frame exceeds the size agreed with the remote".to_string(), size, max_size };
+
+				return Err( PeerErr::WireFormat{ ctx, source } );
+			}
+		}
+
+		// Responses and errors free up the cid of the incoming call they answer.
+		//
+		if msg.sid().is_full() || msg.sid().is_null()
+		{
+			self.inbound.remove( &msg.cid() );
+			self.update_idle();
+		}
+
 		match &mut self.outgoing
 		{
 			Some( out ) =>
 			{
 				let sid = msg.sid();
 				let cid = msg.cid();
+				let len = msg.len();
+
+				if let Some( hook ) = &self.outgoing_hook
+				{
+					hook( sid, cid, msg.msg() );
+				}
 
-				out.send( msg ).await
+				Self::write_frame( out, &mut self.pharos, self.stall_threshold, &self.queued_responses, msg ).await
 
 					.map_err( |source|
 					{
 						let ctx = self.ctx( sid, cid, "Sending out WireFormat" );
 						PeerErr::WireFormat{ ctx, source }
-					})
+					})?;
+
+				self.record_frame( len, false );
+
+				Ok(())
 			}
 
 			None =>
@@ -606,13 +1561,18 @@ impl<Wf: WireFormat> Peer<Wf>
 	(
 		&mut self               ,
 
-		cid  : ConnID           ,
-		err  : &ConnectionError ,
-		close: bool             , // whether the connection should be closed (eg stream corrupted)
+		cid  : ConnID              ,
+		err  : &ConnectionError    ,
+		close: Option<CloseReason> , // whether the connection should be closed (eg stream corrupted)
 	)
 	{
 		trace!( "{}: sending OUT ConnectionError", self.identify() );
 
+		// Like a response, the error frees up the cid of the incoming call it answers.
+		//
+		self.inbound.remove( &cid );
+		self.update_idle();
+
 		// If self.outgoing is None, we have already closed.
 		//
 		let out = match self.outgoing
@@ -624,18 +1584,23 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		// sid null is the marker that this is an error message.
 		//
-		let msg = Self::prep_error( cid, &err );
+		let msg = Self::error_frame( &*self.error_codec, cid, err );
+
+		if let Some( hook ) = &self.outgoing_hook
+		{
+			hook( msg.sid(), cid, msg.msg() );
+		}
 
 
 		// We are already trying to report an error. If we can't send, just give up.
 		//
 		let _ = out.send( msg ).await;
 
-		if close
+		if let Some( reason ) = close
 		{
 			let close_conn = CloseConnection{ remote: false, reason: format!( "{:?}", err ) };
 
-			Handler::<CloseConnection>::handle( self, close_conn ).await
+			self.close_with( close_conn, reason ).await
 		}
 	}
 
@@ -648,14 +1613,7 @@ impl<Wf: WireFormat> Peer<Wf>
 	//
 	pub fn prep_error( cid: ConnID, err: &ConnectionError ) -> Wf
 	{
-		// It's bigger in CBOR because it has String data.
-		//
-		let mut msg = Wf::with_capacity( std::mem::size_of::<ConnectionError>() * 2 );
-		msg.set_sid( ServiceID::null() );
-		msg.set_cid( cid               );
-		serde_cbor::to_writer( &mut msg, err ).expect( "serialize ConnectionError" );
-
-		msg
+		Self::error_frame( &CborErrorCodec, cid, err )
 	}
 }
 
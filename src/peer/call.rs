@@ -11,8 +11,9 @@ use crate::{ import::*, * };
 //
 pub struct Call<Wf>
 {
-	 wf   : Wf              ,
-	_ghost: PhantomData<Wf> ,
+	 wf        : Wf              ,
+	 chunk_size: Option<usize>   ,
+	_ghost     : PhantomData<Wf> ,
 }
 
 impl<Wf: WireFormat> Message for Call<Wf>
@@ -29,7 +30,16 @@ impl<Wf: WireFormat> Call<Wf>
 	//
 	pub fn new( wf: Wf ) -> Self
 	{
-		Self{ wf, _ghost: PhantomData }
+		Self{ wf, chunk_size: None, _ghost: PhantomData }
+	}
+
+	/// Create a new Call that will be sent out in chunks of at most `chunk_size` bytes of payload.
+	/// See [`Chunked`] for the details. The remote still reassembles the whole message in memory
+	/// before it is processed. The response is not chunked.
+	//
+	pub fn chunked( wf: Wf, chunk_size: NonZeroUsize ) -> Self
+	{
+		Self{ wf, chunk_size: Some( chunk_size.get() ), _ghost: PhantomData }
 	}
 
 	/// Get the service id.
@@ -38,6 +48,13 @@ impl<Wf: WireFormat> Call<Wf>
 	{
 		self.wf.sid()
 	}
+
+	/// The frame that will be sent, eg. for middleware that wants to look at the sid, cid or payload.
+	//
+	pub fn frame( &self ) -> &Wf
+	{
+		&self.wf
+	}
 }
 
 
@@ -52,7 +69,77 @@ impl<Wf: WireFormat> Call<Wf>
 //
 impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 {
-	#[async_fn] fn handle( &mut self, mut call: Call<Wf> ) -> <Call<Wf> as Message>::Return
+	#[async_fn] fn handle( &mut self, call: Call<Wf> ) -> <Call<Wf> as Message>::Return
+	{
+		self.outgoing_call( call ).await
+	}
+}
+
+
+
+/// A [Call] of which the result comes back on a channel rather than as the return value of the handler.
+/// Because of that it can be given to the peer with the `Sink` impl of its address, which doesn't need
+/// the boxed future of `Address::call`.
+///
+/// Normally you use `RemoteAddr::call_concrete` rather than this directly.
+//
+pub struct ChannelCall<Wf: WireFormat>
+{
+	call : Call<Wf>                                         ,
+	reply: oneshot::Sender< <Call<Wf> as Message>::Return > ,
+}
+
+impl<Wf: WireFormat> Message for ChannelCall<Wf>
+{
+	/// The result is sent on the channel.
+	//
+	type Return = ();
+}
+
+impl<Wf: WireFormat> ChannelCall<Wf>
+{
+	/// Wrap a call. The receiver gets what [`Address::call`] with the call would return. It's canceled
+	/// when the peer stops before processing the call.
+	//
+	pub fn new( call: Call<Wf> ) -> ( Self, oneshot::Receiver< <Call<Wf> as Message>::Return > )
+	{
+		let (reply, rx) = oneshot::channel();
+
+		( Self{ call, reply }, rx )
+	}
+}
+
+
+
+impl<Wf: WireFormat> fmt::Debug for ChannelCall<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "ChannelCall, sid: {}", self.call.service() )
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<ChannelCall<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: ChannelCall<Wf> )
+	{
+		let result = self.outgoing_call( msg.call ).await;
+
+		// The caller might have given up already.
+		//
+		let _ = msg.reply.send( result );
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	// Send out a call and store the channel for the response, see Handler<Call>.
+	//
+	async fn outgoing_call( &mut self, mut call: Call<Wf> ) -> <Call<Wf> as Message>::Return
 	{
 		let identity = self.identify();
 
@@ -70,23 +157,56 @@ impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 		};
 
 
-		// If self.closed is false, there should always be an address.
+		// The timeout doesn't keep our mailbox alive, see Peer::set_auto_close_on_idle.
 		//
-		let mut self_addr = self.addr.as_ref().unwrap().clone();
-		let mut cid       = ConnID::from( self.conn_id_counter.fetch_add(1, Relaxed ) );
+		let     self_addr = self.weak_addr.clone();
 		let     sid       = call.wf.sid();
+		let mut delay     = self.timeout;
+
 
-		// We wrapped round.
-		// It must not be 0 otherwise the remote will consider it a send, and it's reserved.
+		// Relayed calls keep the deadline of the original caller.
 		//
-		if cid.is_null()
+		let deadline = match call.wf.deadline()
 		{
-			cid = ConnID::from( self.conn_id_counter.fetch_add( 1, Relaxed ) );
+			None if self.propagate_deadline => Some( SystemTime::now() + self.timeout ),
+			d                               => d,
+		};
+
+		if let Some( deadline ) = deadline
+		{
+			// Nobody is waiting for the response anymore, don't bother the remote with it.
+			//
+			let remaining = match deadline.duration_since( SystemTime::now() )
+			{
+				Ok( r ) if r > Duration::from_millis(0) => r,
+
+				_ =>
+				{
+					let ctx = self.ctx( sid, None, "Handler<Call> for Peer: deadline expired" );
+
+					return Err( PeerErr::Timeout{ ctx } );
+				}
+			};
+
+			// No point in waiting longer than the deadline.
+			//
+			delay = delay.min( remaining );
+
+			call.wf.set_deadline( Some( deadline ) );
 		}
 
+
+		self.admit_call( sid )?;
+
+		let cid = self.new_cid( sid )?;
+
 		call.wf.set_cid( cid );
 
-		self.send_msg( call.wf ).await?;
+		match call.chunk_size
+		{
+			Some( size ) => self.send_chunked( call.wf, size ).await?,
+			None         => self.send_msg    ( call.wf       ).await?,
+		}
 
 		// If the above succeeded, store the other end of the channel
 		//
@@ -95,16 +215,18 @@ impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 
 		// send a timeout message to ourselves.
 		//
-		let delay = self.timeout;
-
-
 		let task = async move
 		{
 			Delay::new( delay ).await;
 
-			if self_addr.send( super::Timeout{ cid, sid } ).await.is_err()
+			// If our mailbox stopped, there is nobody left to wait for the response.
+			//
+			if let Ok( mut self_addr ) = self_addr.strong()
 			{
-				error!( "{}: Failed to send timeout to self.", &identity );
+				if self_addr.send( super::Timeout{ cid, sid } ).await.is_err()
+				{
+					error!( "{}: Failed to send timeout to self.", &identity );
+				}
 			}
 
 			Ok(Response::Nothing)
@@ -120,8 +242,68 @@ impl<Wf: WireFormat + Send + 'static> Handler<Call<Wf>> for Peer<Wf>
 		})?;
 
 
-		self.responses.insert( cid, sender );
+		self.responses.insert( cid, (sid, sender) );
+		self.answered .remove( cid );
+		self.update_idle();
 
 		Ok( receiver )
 	}
 }
+
+
+
+/// An outgoing call of which nobody reads the response. Unlike a send, the remote processes it as a
+/// call, so it's subject to its backpressure and the remote can tell the caller waits for nothing.
+/// The frame gets a fresh cid, but the peer doesn't keep a slot for the response, nor a timeout. A
+/// response that comes back later is dropped. Errors the remote returns for it are published as
+/// [`PeerEvent::RemoteError`].
+///
+/// Normally you use `RemoteAddr::call_detached` rather than this directly.
+//
+#[ derive( Debug ) ]
+//
+pub struct DetachedCall<Wf>
+{
+	wf: Wf,
+}
+
+impl<Wf: WireFormat> Message for DetachedCall<Wf>
+{
+	/// Resolves once the frame is sent out.
+	//
+	type Return = Result<(), PeerErr>;
+}
+
+impl<Wf: WireFormat> DetachedCall<Wf>
+{
+	/// Create a new detached call to send an outgoing message over the peer.
+	//
+	pub fn new( wf: Wf ) -> Self
+	{
+		Self{ wf }
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<DetachedCall<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, call: DetachedCall<Wf> ) -> Result<(), PeerErr>
+	{
+		trace!( "{}: polled Handler<DetachedCall>", self.identify() );
+
+		if self.closed
+		{
+			let ctx = self.ctx( None, None, "Handler<DetachedCall> for Peer" );
+
+			return Err( PeerErr::ConnectionClosed{ ctx } );
+		};
+
+		let mut wf  = call.wf;
+		let     cid = self.new_cid( wf.sid() )?;
+
+		wf.set_cid( cid );
+
+		self.send_msg( wf ).await
+	}
+}
@@ -29,6 +29,14 @@ impl<Wf> CallResponse<Wf>
 	{
 		Self{ msg }
 	}
+
+
+	/// The frame that will be sent, eg. for middleware that wants to look at the sid, cid or payload.
+	//
+	pub fn frame( &self ) -> &Wf
+	{
+		&self.msg
+	}
 }
 
 
@@ -42,6 +50,10 @@ impl<Wf: WireFormat + Send + 'static> Handler<CallResponse<Wf>> for Peer<Wf>
 	{
 		trace!( "{}: sending OUT CallResponse", self.identify() );
 
+		// Only responses from our own request handlers were counted.
+		//
+		let _ = self.queued_responses.fetch_update( Relaxed, Relaxed, |n| n.checked_sub( 1 ) );
+
 		let res = self.send_msg( wrap.msg ).await;
 
 		if let Some( ref bp ) = self.backpressure
@@ -51,6 +63,8 @@ impl<Wf: WireFormat + Send + 'static> Handler<CallResponse<Wf>> for Peer<Wf>
 			bp.add_slots( NonZeroUsize::new( 1 ).expect( "1 > 0" ) );
 		}
 
+		self.dispatch_queued().await;
+
 		res
 	}
 }
@@ -0,0 +1,41 @@
+use crate :: { import::*, * };
+
+
+/// Control message for [Peer] to give up on all outgoing calls that are waiting for a response, eg.
+/// during shutdown when you don't want to wait for them to time out. Their callers get
+/// [`PeerErr::Cancelled`] right away. Responses that still come in for them are ignored.
+///
+/// The remote is not told, so it keeps processing the calls. The connection stays open and calls
+/// made after this one are not affected. Returns the number of calls that were cancelled.
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+pub struct CancelAll;
+
+impl Message for CancelAll
+{
+	type Return = usize;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<CancelAll> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: CancelAll ) -> usize
+	{
+		let count = self.responses.len();
+
+		trace!( "{}: cancelling {} outgoing calls", self.identify(), count );
+
+		for (cid, (_, channel)) in self.responses.drain()
+		{
+			// The caller might have given up on the response already.
+			//
+			let _ = channel.send( Err( ConnectionError::Cancelled{ cid } ) );
+		}
+
+		self.update_idle();
+
+		count
+	}
+}
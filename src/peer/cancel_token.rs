@@ -0,0 +1,136 @@
+use
+{
+	crate   :: { import::*, *                    } ,
+	futures :: { future::{ Shared, select_all }  } ,
+};
+
+
+/// Lets a handler know that the result of its work is no longer needed, because the connection
+/// over which the request came in was closed or the call itself was dropped. Long running
+/// handlers can check [`CancelToken::is_cancelled`] or race their work against
+/// [`CancelToken::cancelled`].
+///
+/// To receive a token, register the handler with `Services::register_cancellable`. It will then
+/// receive a [`Cancellable`] wrapping the actual message.
+//
+#[ derive( Clone ) ]
+//
+pub struct CancelToken
+{
+	// The token is cancelled as soon as any of these resolves. They resolve when the
+	// corresponding CancelSource cancels or is dropped.
+	//
+	sources: Vec< Shared< oneshot::Receiver<()> > >,
+}
+
+
+impl CancelToken
+{
+	/// A token that never gets cancelled. Sends get this, as they are processed even
+	/// after the connection has been closed.
+	//
+	pub fn never() -> Self
+	{
+		Self { sources: Vec::new() }
+	}
+
+
+	/// Whether the token has been cancelled.
+	//
+	pub fn is_cancelled( &self ) -> bool
+	{
+		self.sources.iter().any( |s| s.clone().now_or_never().is_some() )
+	}
+
+
+	/// Resolves when the token is cancelled. Never resolves for [`CancelToken::never`].
+	//
+	pub async fn cancelled( &self )
+	{
+		if self.sources.is_empty()
+		{
+			return futures::future::pending::<()>().await;
+		}
+
+		select_all( self.sources.iter().cloned() ).await;
+	}
+}
+
+
+impl fmt::Debug for CancelToken
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "CancelToken: cancelled: {}", self.is_cancelled() )
+	}
+}
+
+
+
+/// The side that cancels a [`CancelToken`]. Dropping it also cancels the token.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct CancelSource
+{
+	tx   : Option< oneshot::Sender<()> > ,
+	token: CancelToken                   ,
+}
+
+
+impl CancelSource
+{
+	pub(crate) fn new() -> Self
+	{
+		Self::child_of( &CancelToken::never() )
+	}
+
+
+	/// A source whose token is also cancelled when `parent` is.
+	//
+	pub(crate) fn child_of( parent: &CancelToken ) -> Self
+	{
+		let (tx, rx) = oneshot::channel();
+
+		let mut token = parent.clone();
+		token.sources.push( rx.shared() );
+
+		Self { tx: Some( tx ), token }
+	}
+
+
+	pub(crate) fn token( &self ) -> CancelToken
+	{
+		self.token.clone()
+	}
+
+
+	pub(crate) fn cancel( &mut self )
+	{
+		self.tx.take();
+	}
+}
+
+
+
+/// A message delivered together with a [`CancelToken`] to handlers registered with
+/// `Services::register_cancellable`. The response is the response of the wrapped message.
+//
+#[ derive( Debug ) ]
+//
+pub struct Cancellable<S>
+{
+	/// The message sent by the remote.
+	//
+	pub msg: S,
+
+	/// Cancelled when the connection closes or the call gets dropped.
+	//
+	pub token: CancelToken,
+}
+
+
+impl<S: Message> Message for Cancellable<S>
+{
+	type Return = <S as Message>::Return;
+}
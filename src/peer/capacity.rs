@@ -0,0 +1,92 @@
+use
+{
+	crate     :: { import::*, *                              } ,
+	super     :: { RequestError                              } ,
+	byteorder :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
+};
+
+
+// The capacity advertisement is a control frame with code `CAPACITY`. The payload is the number of
+// free backpressure slots of the sender, u64::MAX if it has no backpressure:
+//
+// free u64 LE
+//
+pub(crate) const CAPACITY: u8 = 1;
+
+const LEN_CAPACITY: usize = 8;
+
+
+/// Have a running [Peer] tell the remote how many more concurrent calls it accepts right now,
+/// see [`Peer::capacity`]. The remote publishes it as [`PeerEvent::RemoteCapacity`] and reports it
+/// in [`PeerStatus::remote_capacity`], so a client talking to several providers can route to the
+/// least busy one.
+///
+/// Send this periodically, eg. from a timer, or when the remote asks for it.
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+pub struct AdvertiseCapacity;
+
+impl Message for AdvertiseCapacity
+{
+	type Return = Result<(), PeerErr>;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<AdvertiseCapacity> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: AdvertiseCapacity ) -> Result<(), PeerErr>
+	{
+		let free = self.capacity().map_or( u64::MAX, |c| u64::try_from( c ).unwrap_or( u64::MAX ) );
+
+		let mut payload = Vec::with_capacity( LEN_CAPACITY );
+
+		// unwrap: writing to an in memory buffer.
+		//
+		payload.write_u64::<LittleEndian>( free ).unwrap();
+
+		self.send_msg( Self::control_frame( CAPACITY, &payload ) ).await
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// The number of free slots of the [BackPressure], that is how many more incoming calls can be
+	/// processed concurrently right now. `None` for a peer without backpressure, which has no limit.
+	//
+	pub fn capacity( &self ) -> Option<usize>
+	{
+		self.backpressure.as_ref().map( |bp| usize::try_from( bp.available() ).unwrap_or( 0 ) )
+	}
+
+
+	/// Process a capacity advertisement from the remote.
+	//
+	pub(crate) async fn incoming_capacity( &mut self, frame: Wf )
+	{
+		let mut msg = frame.msg();
+
+		if msg.len() != LEN_CAPACITY
+		{
+			let source = WireErr::Deserialize{ context: "capacity advertisement doesn't hold a slot count".to_string(), source: None };
+			let ctx    = self.ctx( frame.sid(), None, "Process incoming capacity advertisement" );
+
+			self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+			return;
+		}
+
+		// unwrap: we just checked the length.
+		//
+		let free = msg.read_u64::<LittleEndian>().unwrap();
+		let free = usize::try_from( free ).unwrap_or( usize::MAX );
+
+		trace!( "{}: remote has {} free slots.", self.identify(), free );
+
+		self.remote_capacity = Some( free );
+
+		self.pharos.send( PeerEvent::RemoteCapacity( free ) ).await.expect( "pharos not closed" );
+	}
+}
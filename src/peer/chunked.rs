@@ -0,0 +1,343 @@
+use
+{
+	crate     :: { import::*, *                              } ,
+	super     :: { RequestError                              } ,
+	byteorder :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
+	std       :: { io::Write as IoWrite                      } ,
+};
+
+
+// Every chunk is a frame with sid `ServiceID::chunk()` and as cid a random id for the transfer.
+// The payload starts with a header describing the original message, followed by the data:
+//
+// sid u64 LE | cid u64 LE | seq u32 LE | total u32 LE | data
+//
+// With the sid128 feature, the sid is a u128.
+//
+const LEN_CHUNK_HEADER: usize = ServiceID::SIZE + 16;
+
+
+/// Send a message to the remote split up in several frames, so that no single frame is bigger
+/// than `chunk_size` bytes of payload (plus a 24 byte chunk header, 32 with the `sid128` feature). This allows sending messages
+/// bigger than the `max_size` of the codec, while the buffers of the codec on both ends stay near
+/// the chunk size. The remote [Peer] reassembles the message and processes it as if it had
+/// arrived in one frame.
+///
+/// This does not stream the message. The sender needs the whole frame up front, and the receiver
+/// keeps every chunk of a transfer in memory until the last one arrives, so both ends still hold the
+/// entire message. Only the transport buffers are bounded by the chunk size. The memory used for
+/// reassembly is bounded by [`Peer::set_max_chunked_size`] and [`Peer::set_max_reassemblies`].
+///
+/// The chunks of a message are sent out back to back, so no other outgoing message is
+/// interleaved with them.
+///
+/// For calls, use [`Call::chunked`].
+//
+#[ derive( Debug ) ]
+//
+pub struct Chunked<Wf>
+{
+	wf        : Wf    ,
+	chunk_size: usize ,
+}
+
+
+impl<Wf: WireFormat> Chunked<Wf>
+{
+	/// Create a new chunked send.
+	//
+	pub fn new( wf: Wf, chunk_size: NonZeroUsize ) -> Self
+	{
+		Self { wf, chunk_size: chunk_size.get() }
+	}
+}
+
+
+impl<Wf: WireFormat> Message for Chunked<Wf>
+{
+	type Return = Result<(), PeerErr>;
+}
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<Chunked<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: Chunked<Wf> ) -> <Chunked<Wf> as Message>::Return
+	{
+		trace!( "{}: polled Handler<Chunked>", self.identify() );
+
+		if self.closed
+		{
+			let ctx = self.ctx( None, None, "Handler<Chunked> for Peer" );
+
+			return Err( PeerErr::ConnectionClosed{ ctx } );
+		}
+
+		self.send_chunked( msg.wf, msg.chunk_size ).await
+	}
+}
+
+
+
+/// State of a chunked message that is being reassembled.
+//
+pub(crate) struct Reassembly<Wf>
+{
+	frame: Wf  ,
+	next : u32 ,
+	total: u32 ,
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Split a frame in chunks and send them out.
+	//
+	pub(crate) async fn send_chunked( &mut self, wf: Wf, chunk_size: usize ) -> Result<(), PeerErr>
+	{
+		let sid = wf.sid();
+		let cid = wf.cid();
+
+		let mut chunks: Vec<&[u8]> = wf.msg().chunks( chunk_size ).collect();
+
+		// An empty message still needs one chunk.
+		//
+		if chunks.is_empty()
+		{
+			chunks.push( &[] );
+		}
+
+		let total = u32::try_from( chunks.len() ).map_err( |_|
+		{
+			let ctx = self.ctx( sid, cid, "Split message in chunks" );
+
+			let source = WireErr::MessageSizeExceeded
+			{
+				context : "too many chunks, use a bigger chunk size".to_string() ,
+				size    : wf.msg().len()                                         ,
+				max_size: chunk_size.saturating_mul( u32::MAX as usize )         ,
+			};
+
+			PeerErr::WireFormat{ ctx, source }
+
+		})?;
+
+		let transfer = ConnID::random();
+
+		for (seq, data) in chunks.into_iter().enumerate()
+		{
+			let mut chunk = Wf::with_capacity( LEN_CHUNK_HEADER + data.len() );
+
+			chunk.set_sid     ( ServiceID::chunk()    );
+			chunk.set_cid     ( transfer              );
+			chunk.set_deadline( wf.deadline()         );
+			chunk.set_meta    ( wf.meta().as_ref()    );
+			chunk.set_channel ( wf.channel()          );
+			chunk.set_trace_id( wf.trace_id()         );
+			chunk.set_route   ( wf.route().as_deref() );
+
+			Self::write_chunk( &mut chunk, sid, cid, seq as u32, total, data ).map_err( |e|
+			{
+				let ctx = self.ctx( sid, cid, "Split message in chunks" );
+
+				PeerErr::Serialize{ ctx, source: Some( e.into() ) }
+
+			})?;
+
+			self.send_msg( chunk ).await?;
+		}
+
+		Ok(())
+	}
+
+
+	fn write_chunk( chunk: &mut Wf, sid: ServiceID, cid: ConnID, seq: u32, total: u32, data: &[u8] ) -> io::Result<()>
+	{
+		sid.write_le( chunk )?;
+		chunk.write_u64::<LittleEndian>( cid.into() )?;
+		chunk.write_u32::<LittleEndian>( seq        )?;
+		chunk.write_u32::<LittleEndian>( total      )?;
+		chunk.write_all( data )
+	}
+
+
+	/// Process an incoming chunk. Returns the original frame when this was the last chunk.
+	//
+	pub(crate) async fn incoming_chunk( &mut self, chunk: Wf ) -> Option<Wf>
+	{
+		let transfer = chunk.cid();
+		let msg      = chunk.msg();
+
+		if msg.len() < LEN_CHUNK_HEADER
+		{
+			let source = WireErr::Deserialize{ context: "chunk is too short for the chunk header".to_string(), source: None };
+
+			self.chunk_err( transfer, None, None, source ).await;
+			return None;
+		}
+
+		// unwrap: we just checked the length.
+		//
+		let mut header = &msg[ ..LEN_CHUNK_HEADER ];
+		let sid        = ServiceID::read_le( &mut header ).unwrap();
+		let cid        = ConnID   ::from( header.read_u64::<LittleEndian>().unwrap() );
+		let seq        =                  header.read_u32::<LittleEndian>().unwrap()  ;
+		let total      =                  header.read_u32::<LittleEndian>().unwrap()  ;
+		let data       = &msg[ LEN_CHUNK_HEADER.. ];
+
+		// Only calls get a response with the error.
+		//
+		let err_cid = Some( cid ).filter( |c| !c.is_null() );
+
+		trace!( "{}: Incoming chunk {} of {}, sid: {}, cid: {}", self.identify(), seq + 1, total, sid, cid );
+
+
+		// Bound the memory a remote can tie up with transfers it never finishes.
+		//
+		if seq == 0 && self.chunks.len() >= self.max_reassemblies && !self.chunks.contains_key( &transfer )
+		{
+			let source = WireErr::OutOfMemory
+			{
+				context: format!( "too many chunked messages in progress, the maximum is {}", self.max_reassemblies ),
+				size   : data.len(),
+			};
+
+			self.chunk_err( transfer, sid, err_cid, source ).await;
+			return None;
+		}
+
+
+		let problem = if sid.is_chunk() || seq >= total
+		{
+			Some( "invalid chunk header" )
+		}
+
+		else if seq == 0
+		{
+			match self.chunks.contains_key( &transfer )
+			{
+				true  => Some( "transfer already in progress" ),
+
+				false =>
+				{
+					let mut frame = Wf::with_capacity( data.len() );
+					frame.set_sid     ( sid                      );
+					frame.set_cid     ( cid                      );
+					frame.set_deadline( chunk.deadline()         );
+					frame.set_meta    ( chunk.meta().as_ref()    );
+					frame.set_channel ( chunk.channel()          );
+					frame.set_trace_id( chunk.trace_id()         );
+					frame.set_route   ( chunk.route().as_deref() );
+
+					self.chunks.insert( transfer, Reassembly{ frame, next: 0, total } );
+
+					None
+				}
+			}
+		}
+
+		else
+		{
+			match self.chunks.get( &transfer )
+			{
+				Some( r ) if r.next == seq && r.total == total && r.frame.sid() == sid && r.frame.cid() == cid => None,
+				_                                                                                              => Some( "chunk out of sequence" ),
+			}
+		};
+
+
+		if let Some( context ) = problem
+		{
+			let source = WireErr::Deserialize{ context: context.to_string(), source: None };
+
+			self.chunk_err( transfer, sid, err_cid, source ).await;
+			return None;
+		}
+
+
+		let max_size    = self.max_chunked_size;
+		let in_progress = self.reassembly_bytes();
+
+		if in_progress + data.len() > self.max_reassembly_bytes
+		{
+			let source = WireErr::OutOfMemory
+			{
+				context: format!( "chunked messages in progress exceed the maximum total size of {} bytes", self.max_reassembly_bytes ),
+				size   : in_progress + data.len(),
+			};
+
+			self.chunk_err( transfer, sid, err_cid, source ).await;
+			return None;
+		}
+
+		// unwrap: was checked or inserted above.
+		//
+		let r = self.chunks.get_mut( &transfer ).unwrap();
+
+		if r.frame.msg().len() + data.len() > max_size
+		{
+			let source = WireErr::MessageSizeExceeded
+			{
+				context : "reassembling chunked message".to_string() ,
+				size    : r.frame.msg().len() + data.len()            ,
+				max_size                                              ,
+			};
+
+			self.chunk_err( transfer, sid, err_cid, source ).await;
+			return None;
+		}
+
+		if let Err( e ) = r.frame.write_all( data )
+		{
+			let source = WireErr::Deserialize{ context: "reassembling chunked message".to_string(), source: Some( e.into() ) };
+
+			self.chunk_err( transfer, sid, err_cid, source ).await;
+			return None;
+		}
+
+		r.next += 1;
+
+		match r.next == r.total
+		{
+			true  => self.chunks.remove( &transfer ).map( |r| r.frame ),
+			false => None,
+		}
+	}
+
+
+	// The bytes held by the chunked messages that are being reassembled.
+	//
+	pub(crate) fn reassembly_bytes( &self ) -> usize
+	{
+		self.chunks.values().map( |r| r.frame.msg().len() ).sum()
+	}
+
+
+	// Drop the transfer and report the error.
+	//
+	async fn chunk_err
+	(
+		&mut self                              ,
+		transfer: ConnID                       ,
+		sid     : impl Into<Option<ServiceID>> ,
+		cid     : impl Into<Option<ConnID>>    ,
+		source  : WireErr                      ,
+	)
+	{
+		self.chunks.remove( &transfer );
+
+		let ctx = self.ctx( sid, cid, "Reassemble chunked message" );
+
+		self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for Reassembly<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Reassembly: chunk {} of {}", self.next, self.total )
+	}
+}
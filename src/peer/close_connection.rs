@@ -33,18 +33,143 @@ impl Message for CloseConnection { type Return = (); }
 
 
 
+/// Why a connection closed, see [`PeerEvent::Closed`] and [`Peer::on_disconnect`].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+#[ non_exhaustive ]
+//
+pub enum CloseReason
+{
+	/// The application closed the connection with [CloseConnection].
+	//
+	Local,
+
+	/// The application told the peer that the remote closed the connection, with `CloseConnection{ remote: true, .. }`.
+	//
+	Remote,
+
+	/// The incoming stream ended, the remote closed the connection or the transport went down.
+	//
+	Eof,
+
+	/// No frames went in or out for longer than allowed with [`Peer::set_idle_timeout`].
+	//
+	Idle,
+
+	/// The incoming stream is corrupt, eg. a frame that is too big or can't be decoded. The remote
+	/// got the error before the connection closed.
+	//
+	Protocol,
+
+	/// The connection exceeded the limit set with [`Peer::set_memory_limit`].
+	//
+	MemoryLimit,
+
+	/// An incoming frame failed to authenticate, eg. it couldn't be decrypted or it was replayed.
+	//
+	AuthFailed,
+
+	/// The peer was shut down with [`Peer::shutdown`].
+	//
+	Shutdown,
+
+	/// The peer could no longer spawn the tasks that process incoming messages.
+	//
+	Spawn,
+}
+
+
+impl fmt::Display for CloseReason
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		match self
+		{
+			CloseReason::Local       => write!( f, "closed locally"                           ),
+			CloseReason::Remote      => write!( f, "closed by remote"                         ),
+			CloseReason::Eof         => write!( f, "the incoming stream ended"                ),
+			CloseReason::Idle        => write!( f, "idle timeout"                             ),
+			CloseReason::Protocol    => write!( f, "protocol error"                           ),
+			CloseReason::MemoryLimit => write!( f, "memory limit exceeded"                    ),
+			CloseReason::AuthFailed  => write!( f, "an incoming frame failed to authenticate" ),
+			CloseReason::Shutdown    => write!( f, "peer shut down"                           ),
+			CloseReason::Spawn       => write!( f, "failed to spawn"                          ),
+		}
+	}
+}
+
+
+impl CloseReason
+{
+	/// Whether the connection was closed from the remote side, which is reported as
+	/// [`PeerEvent::ClosedByRemote`].
+	//
+	pub fn is_remote( &self ) -> bool
+	{
+		matches!( self, CloseReason::Remote | CloseReason::Eof )
+	}
+}
+
+
+
+// Sent by the task reading the connection when the incoming stream ends.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct IncomingEnded;
+
+impl Message for IncomingEnded { type Return = (); }
+
+
+
+impl<Wf: WireFormat> Handler<IncomingEnded> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: IncomingEnded )
+	{
+		let close = CloseConnection{ remote: true, reason: "Connection closed by remote.".to_string() };
+
+		self.close_with( close, CloseReason::Eof ).await
+	}
+}
+
+
+
 impl<Wf: WireFormat> Handler<CloseConnection> for Peer<Wf>
 {
 	#[async_fn] fn handle( &mut self, msg: CloseConnection )
 	{
-		trace!( "{}: CloseConnection, by remote: {}, reason: {}", self.identify(), msg.remote, &msg.reason );
+		let reason = if msg.remote { CloseReason::Remote } else { CloseReason::Local };
+
+		self.close_with( msg, reason ).await
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	// Close the connection for `reason`. All the ways the connection closes come through here.
+	//
+	pub(crate) async fn close_with( &mut self, msg: CloseConnection, reason: CloseReason )
+	{
+		trace!( "{}: CloseConnection, by remote: {}, reason: {} ({})", self.identify(), msg.remote, &msg.reason, reason );
 
 		self.closed = true;
 
+		if let Some( callback ) = self.on_disconnect.take()
+		{
+			callback( &msg, reason );
+		}
+
+		// Let handlers that are still processing calls know their work is no longer needed.
+		//
+		self.cancel.cancel();
+
 		// Since we don't close it, it shouldn't be closed.
 		//
-		if msg.remote { self.pharos.send( PeerEvent::ClosedByRemote ).await.expect( "pharos not closed" ) }
-		else          { self.pharos.send( PeerEvent::Closed         ).await.expect( "pharos not closed" ) }
+		if reason.is_remote() { self.pharos.send( PeerEvent::ClosedByRemote( reason ) ).await.expect( "pharos not closed" ) }
+		else                  { self.pharos.send( PeerEvent::Closed        ( reason ) ).await.expect( "pharos not closed" ) }
 
 
 		// Try to close the connection properly
@@ -69,6 +194,8 @@ impl<Wf: WireFormat> Handler<CloseConnection> for Peer<Wf>
 			self.outgoing = None;
 		};
 
+		self.retired.clear();
+
 
 		self.nursery.close_nursery();
 
@@ -96,6 +223,17 @@ impl<Wf: WireFormat> Handler<CloseConnection> for Peer<Wf>
 		// alive. This breaks that cycle.
 		//
 		self.services .clear();
+		self.channels .clear();
 		self.responses.clear();
+		self.inbound  .clear();
+		self.streams  .clear();
+		self.stream_windows.clear();
+		self.pings.clear();
+		self.manual = None;
+
+		if let Some( queue ) = &mut self.fair_queue
+		{
+			queue.clear();
+		}
 	}
 }
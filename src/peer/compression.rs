@@ -0,0 +1,46 @@
+use crate :: { import::*, *, thes_wf::CompressThreshold };
+
+
+/// Change the size in bytes under which the [Peer] leaves the payload of outgoing frames uncompressed,
+/// without reconnecting, eg. to trade CPU for bandwidth when conditions change. It applies from the
+/// next frame on.
+///
+/// This only has an effect when the peer knows the threshold of its [`Compress`](crate::thes_wf::Compress)
+/// sink, see [`Peer::set_compress_threshold`].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub struct SetCompressionThreshold( pub usize );
+
+impl Message for SetCompressionThreshold
+{
+	type Return = ();
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<SetCompressionThreshold> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: SetCompressionThreshold )
+	{
+		match &self.compress_threshold
+		{
+			Some( threshold ) => threshold.set( msg.0 ),
+			None              => warn!( "{}: SetCompressionThreshold, but this peer doesn't compress.", self.identify() ),
+		}
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Let [SetCompressionThreshold] change the threshold of the [`Compress`](crate::thes_wf::Compress) sink
+	/// of this peer, see [`Compress::threshold`](crate::thes_wf::Compress::threshold). Peers created with
+	/// [`Peer::from_async_read_compressed`] already have it.
+	//
+	pub fn set_compress_threshold( &mut self, threshold: CompressThreshold )
+	{
+		self.compress_threshold = Some( threshold );
+	}
+}
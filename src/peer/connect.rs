@@ -0,0 +1,74 @@
+use
+{
+	crate   :: { import::*, *               } ,
+	futures :: { future::{ select, Either } } ,
+	std     :: { time::Instant              } ,
+};
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Connect to a remote under a single deadline, so an unresponsive provider can't hang the startup
+	/// of a program that connects to many of them.
+	///
+	/// `connect` establishes the transport and starts the peer, eg. with [`Peer::from_async_read`].
+	/// `ready` then gets the address of the peer and does whatever has to happen before the connection is
+	/// usable: a handshake like [`Peer::announce_max_frame_size`], a first call or service discovery. A
+	/// [`Peer::ping`] is the simplest way to find out the remote answers at all.
+	///
+	/// Fails with [`PeerErr::Timeout`] when both together take longer than `timeout`, the context says which
+	/// of the two was still running. When `ready` fails or times out, the connection gets closed with
+	/// [CloseConnection], so nothing is left behind. When `connect` times out, it's dropped, so it should only
+	/// start the peer as its last step.
+	///
+	/// Closing doesn't wait past the deadline either. If the mailbox of the peer can't take the message
+	/// before then, the address is dropped, which stops the peer once nobody else holds one.
+	//
+	pub async fn connect_with_timeout<C, R, RFut>( timeout: Duration, connect: C, ready: R ) -> Result<Addr<Self>, PeerErr>
+
+		where C   : Future< Output = Result<Addr<Self>, PeerErr> > ,
+		      R   : FnOnce( Addr<Self> ) -> RFut                   ,
+		      RFut: Future< Output = Result<(), PeerErr> >         ,
+	{
+		let deadline = Instant::now() + timeout;
+
+		let mut addr = match select( Box::pin( connect ), Delay::new( timeout ) ).await
+		{
+			Either::Left ( (result, _) ) => result?,
+
+			Either::Right( _ ) =>
+			{
+				let ctx = PeerErrCtx::default().context( format!( "connect_with_timeout: establishing the connection took longer than {:?}", timeout ) );
+
+				return Err( PeerErr::Timeout{ ctx } );
+			}
+		};
+
+
+		let left = deadline.saturating_duration_since( Instant::now() );
+
+		let err = match select( Box::pin( ready( addr.clone() ) ), Delay::new( left ) ).await
+		{
+			Either::Left ( (Ok(()), _) ) => return Ok( addr ),
+			Either::Left ( (Err(e), _) ) => e,
+
+			Either::Right( _ ) =>
+			{
+				let ctx = Self::err_ctx( &addr, None, None, format!( "connect_with_timeout: the connection wasn't ready within {:?}", timeout ) );
+
+				PeerErr::Timeout{ ctx }
+			}
+		};
+
+
+		// If the peer is already gone, there is nothing left to close. select polls the send first, so
+		// it still goes through when the deadline has passed, as long as the mailbox has room.
+		//
+		let left  = deadline.saturating_duration_since( Instant::now() );
+		let close = addr.send( CloseConnection{ remote: false, reason: format!( "connect_with_timeout: {}", err ) } );
+
+		let _ = select( Box::pin( close ), Delay::new( left ) ).await;
+
+		Err( err )
+	}
+}
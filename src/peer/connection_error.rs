@@ -19,28 +19,111 @@ pub enum ConnectionError
 	//
 	DeserializeWireFormat{ context: String },
 
-	/// Your request could not be processed. This might mean spawning a future failed,
+	/// Your request could not be processed. This might mean the handling actor is gone,
 	/// downcasting a Receiver failed or other errors that are clearly not the fault
 	/// of the remote peer.
 	//
 	InternalServerError{ sid: Option<ServiceID>, cid: Option<ConnID> },
 
+	/// The remote could not spawn the task to process your request, eg. because its executor is
+	/// shutting down. The remote closes the connection after sending this.
+	//
+	SpawnFailed{ sid: Option<ServiceID>, cid: Option<ConnID> },
+
 	// The connection timed out while waiting for a response to a Call.
 	// This will actually be used internally when an outgoing call times out, since we need to
 	// send that over the channel which takes this error type. RemoteAddress will translate this in
-	// a PeerErr. Client code should never observe this variant.
+	// a PeerErr. Client code should never observe this variant. A remote also sends it when
+	// the deadline of a call had passed before it got processed.
 	//
 	#[ doc( hidden ) ]
 	//
 	Timeout{ sid: ServiceID },
 
+	// Our peer was shut down with `Peer::shutdown` while the call was waiting for a response. Like
+	// Timeout, this is only sent over the response channel and RemoteAddress translates it into
+	// PeerErr::ShuttingDown.
+	//
+	#[ doc( hidden ) ]
+	//
+	ShuttingDown{ cid: ConnID },
+
+	// The call was cancelled locally with `CancelAll` while waiting for a response. Like ShuttingDown,
+	// this is only sent over the response channel and RemoteAddress translates it into PeerErr::Cancelled.
+	//
+	#[ doc( hidden ) ]
+	//
+	Cancelled{ cid: ConnID },
+
+	/// You sent a call with the cid of another call of yours we haven't answered yet.
+	//
+	DuplicateCid{ sid: Option<ServiceID>, cid: Option<ConnID> },
+
 	/// We don't provide this service.
 	//
 	UnknownService{ sid: Option<ServiceID>, cid: Option<ConnID> },
 
+	/// We refused to process your request, see [`Peer::set_guard`](crate::Peer::set_guard).
+	//
+	Unauthorized{ sid: Option<ServiceID>, cid: Option<ConnID> },
+
 	/// We don't provide this service.
 	//
 	PubSubNoCall{ sid: Option<ServiceID>, cid: Option<ConnID> },
+
+	/// Your message was rejected by the validator of the service, `reason` says why.
+	//
+	Validation{ sid: Option<ServiceID>, cid: Option<ConnID>, reason: String },
+}
+
+
+
+impl ConnectionError
+{
+	/// The service id the error is about, if known.
+	//
+	pub fn sid( &self ) -> Option<ServiceID>
+	{
+		match self
+		{
+			  ConnectionError::Deserialize        { sid, .. }
+			| ConnectionError::InternalServerError{ sid, .. }
+			| ConnectionError::SpawnFailed        { sid, .. }
+			| ConnectionError::DuplicateCid       { sid, .. }
+			| ConnectionError::UnknownService     { sid, .. }
+			| ConnectionError::Unauthorized       { sid, .. }
+			| ConnectionError::PubSubNoCall       { sid, .. }
+			| ConnectionError::Validation         { sid, .. } => *sid,
+
+			ConnectionError::Timeout{ sid } => Some( *sid ),
+
+			  ConnectionError::DeserializeWireFormat{..}
+			| ConnectionError::ShuttingDown         {..}
+			| ConnectionError::Cancelled            {..} => None,
+		}
+	}
+
+
+	/// The connection id of the call the error is about, if known.
+	//
+	pub fn cid( &self ) -> Option<ConnID>
+	{
+		match self
+		{
+			  ConnectionError::Deserialize        { cid, .. }
+			| ConnectionError::InternalServerError{ cid, .. }
+			| ConnectionError::SpawnFailed        { cid, .. }
+			| ConnectionError::DuplicateCid       { cid, .. }
+			| ConnectionError::UnknownService     { cid, .. }
+			| ConnectionError::Unauthorized       { cid, .. }
+			| ConnectionError::PubSubNoCall       { cid, .. }
+			| ConnectionError::Validation         { cid, .. } => *cid,
+
+			ConnectionError::ShuttingDown{ cid } | ConnectionError::Cancelled{ cid } => Some( *cid ),
+
+			ConnectionError::DeserializeWireFormat{..} | ConnectionError::Timeout{..} => None,
+		}
+	}
 }
 
 
@@ -66,17 +149,41 @@ impl fmt::Display for ConnectionError
 
 				write!( f, "Remote ran into an internal server error (this isn't your fault). More information should be in their logs (sid: {:?}, cid: {:?}).", sid, cid ),
 
+			ConnectionError::SpawnFailed{ sid, cid } =>
+
+				write!( f, "Remote failed to spawn a task to process your request, it will close the connection (sid: {:?}, cid: {:?}).", sid, cid ),
+
 			ConnectionError::Timeout{ sid } =>
 
 				write!( f, "Timed out waiting for a response to a call (sid: {}).", sid ),
 
+			ConnectionError::ShuttingDown{ cid } =>
+
+				write!( f, "The peer was shut down while waiting for a response to a call (cid: {}).", cid ),
+
+			ConnectionError::Cancelled{ cid } =>
+
+				write!( f, "The call was cancelled while waiting for a response (cid: {}).", cid ),
+
+			ConnectionError::DuplicateCid{ sid, cid } =>
+
+				write!( f, "Remote is still processing another call with the same cid (sid: {:?}, cid: {:?}).", sid, cid ),
+
 			ConnectionError::UnknownService{ sid, .. } =>
 
 				write!( f, "Remote does not expose the service you are trying to call (sid: {:?}).", sid ),
 
+			ConnectionError::Unauthorized{ sid, .. } =>
+
+				write!( f, "Remote refused to process your request (sid: {:?}).", sid ),
+
 			ConnectionError::PubSubNoCall{ sid, .. } =>
 
 				write!( f, "Remote broadcasts this message type using thespis_remote::PubSub which does not support the `call` operation. Only `send` is supported (sid: {:?}).", sid ),
+
+			ConnectionError::Validation{ sid, reason, .. } =>
+
+				write!( f, "Remote rejected your message: {} (sid: {:?}).", reason, sid ),
 		}
 	}
 }
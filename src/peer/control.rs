@@ -0,0 +1,96 @@
+use
+{
+	crate :: { import::*, *                                                        } ,
+	super :: { capacity::CAPACITY, ping::{ PING, PONG }, send_queue::SEND_REJECTED } ,
+	std   :: { io::Write as IoWrite                                                } ,
+};
+
+
+/// An out of band control frame. Control frames use the sids reserved by [`ServiceID::control`], so
+/// they can't clash with services and they never reach a [ServiceMap]. Send this to a [Peer] to send
+/// it to the remote. Incoming control frames that the peer doesn't handle itself are published as
+/// [`PeerEvent::Control`].
+///
+/// Codes below 128 are reserved for thespis_remote, the others are free for applications.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct Control
+{
+	/// Which control message this is.
+	//
+	pub code: u8,
+
+	/// Data that comes with the control message, sent as is.
+	//
+	pub payload: Vec<u8>,
+}
+
+impl Message for Control
+{
+	type Return = Result<(), PeerErr>;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<Control> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: Control ) -> Result<(), PeerErr>
+	{
+		trace!( "{}: sending control frame with code {}", self.identify(), msg.code );
+
+		self.send_msg( Self::control_frame( msg.code, &msg.payload ) ).await
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Build a control frame. The cid is null, the payload is passed as is.
+	//
+	pub(crate) fn control_frame( code: u8, payload: &[u8] ) -> Wf
+	{
+		let mut wf = Wf::with_capacity( payload.len() );
+
+		wf.set_sid( ServiceID::control( code ) );
+		wf.set_cid( ConnID::null()             );
+
+		// unwrap: writing to an in memory buffer.
+		//
+		wf.write_all( payload ).unwrap();
+
+		wf
+	}
+
+
+	/// Process an incoming control frame. The codes the peer doesn't handle itself go to the observers.
+	//
+	pub(crate) async fn incoming_control( &mut self, frame: Wf )
+	{
+		// unwrap: the frame wouldn't be routed here if it wasn't a control frame.
+		//
+		let code = frame.sid().control_code().unwrap();
+
+		trace!( "{}: incoming control frame with code {}", self.identify(), code );
+
+		if code == CAPACITY
+		{
+			return self.incoming_capacity( frame ).await;
+		}
+
+		if code == PING || code == PONG
+		{
+			return self.incoming_ping( frame, code ).await;
+		}
+
+		if code == SEND_REJECTED
+		{
+			return self.incoming_send_rejected( frame ).await;
+		}
+
+		let control = Control{ code, payload: frame.msg().to_vec() };
+
+		self.pharos.send( PeerEvent::Control( control ) ).await.expect( "pharos not closed" );
+	}
+}
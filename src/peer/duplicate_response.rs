@@ -0,0 +1,90 @@
+use
+{
+	crate :: { import::*, *         } ,
+	std   :: { collections::VecDeque } ,
+};
+
+
+// The number of answered calls to remember.
+//
+const WINDOW: usize = 1024;
+
+
+// The outgoing calls that got their response most recently, by cid. It tells a second response to one of
+// them apart from a late response to a call that timed out or was detached, which we never waited for.
+//
+#[ derive( Debug, Default ) ]
+//
+pub(crate) struct Answered
+{
+	order     : VecDeque<ConnID>             ,
+	sids      : HashMap<ConnID, ServiceID>   ,
+	duplicates: u64                          ,
+}
+
+
+impl Answered
+{
+	// Remember that the call `cid` to `sid` got its response. The oldest one is forgotten when the window is full.
+	//
+	pub(crate) fn insert( &mut self, cid: ConnID, sid: ServiceID )
+	{
+		if self.sids.insert( cid, sid ).is_some() { return }
+
+		self.order.push_back( cid );
+
+		if self.order.len() > WINDOW
+		{
+			if let Some( old ) = self.order.pop_front()
+			{
+				self.sids.remove( &old );
+			}
+		}
+	}
+
+
+	// A new outgoing call uses `cid`, so responses for it are no longer duplicates.
+	//
+	pub(crate) fn remove( &mut self, cid: ConnID )
+	{
+		if self.sids.remove( &cid ).is_some()
+		{
+			self.order.retain( |c| *c != cid );
+		}
+	}
+
+
+	// The number of duplicate responses seen on this connection.
+	//
+	pub(crate) fn duplicates( &self ) -> u64
+	{
+		self.duplicates
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	// Check whether a response for `cid` we aren't waiting for duplicates one that was already delivered.
+	// If so, it gets counted and published as PeerEvent::DuplicateResponse.
+	//
+	pub(super) async fn duplicate_response( &mut self, cid: ConnID ) -> bool
+	{
+		let sid = match self.answered.sids.get( &cid )
+		{
+			Some( sid ) => *sid,
+			None        => return false,
+		};
+
+		self.answered.duplicates += 1;
+
+		warn!( "{}: Received a duplicate response, sid: {}, cid: {}. Dropping it.", self.identify(), sid, cid );
+
+		// If pharos is closed, we already panicked... so except is fine.
+		//
+		self.pharos.send( PeerEvent::DuplicateResponse{ sid, cid } ).await.expect( "pharos not closed" );
+
+		true
+	}
+}
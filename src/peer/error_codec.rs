@@ -0,0 +1,78 @@
+use
+{
+	crate :: { import::*, *         } ,
+	std   :: { io::Write as IoWrite } ,
+};
+
+
+/// Decides how a [Peer] encodes the [ConnectionError] in the payload of the error frames it sends and
+/// how it decodes the ones it receives. The default is [CborErrorCodec]. Install another one with
+/// [`Peer::set_error_codec`], eg. to agree on a JSON shape with a remote that isn't written in Rust.
+/// Both ends of a connection must use compatible codecs.
+///
+/// Errors that a [RelayMap](crate::RelayMap) forwards from the relayed connection are always
+/// encoded with the default codec.
+//
+pub trait ErrorCodec: Send + Sync
+{
+	/// Serialize `err` to the payload of an error frame.
+	//
+	fn encode( &self, err: &ConnectionError ) -> Vec<u8>;
+
+	/// Deserialize the payload of an incoming error frame.
+	//
+	fn decode( &self, payload: &[u8] ) -> Result<ConnectionError, ErrorSource>;
+}
+
+
+
+/// The default [ErrorCodec], which serializes errors with CBOR.
+//
+#[ derive( Debug, Clone, Copy, Default ) ]
+//
+pub struct CborErrorCodec;
+
+
+impl ErrorCodec for CborErrorCodec
+{
+	fn encode( &self, err: &ConnectionError ) -> Vec<u8>
+	{
+		serde_cbor::to_vec( err ).expect( "serialize ConnectionError" )
+	}
+
+
+	fn decode( &self, payload: &[u8] ) -> Result<ConnectionError, ErrorSource>
+	{
+		serde_cbor::from_slice( payload ).map_err( ErrorSource::new )
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Replace the codec used for the payload of error frames. See [ErrorCodec].
+	//
+	pub fn set_error_codec( &mut self, codec: impl ErrorCodec + 'static )
+	{
+		self.error_codec = Arc::new( codec );
+	}
+
+
+	// Build an error frame with the given codec. sid null is the marker that this is an error message.
+	//
+	pub(crate) fn error_frame( codec: &dyn ErrorCodec, cid: ConnID, err: &ConnectionError ) -> Wf
+	{
+		let payload = codec.encode( err );
+
+		let mut msg = Wf::with_capacity( payload.len() );
+		msg.set_sid( ServiceID::null() );
+		msg.set_cid( cid               );
+
+		// unwrap: writing to an in memory buffer.
+		//
+		msg.write_all( &payload ).unwrap();
+
+		msg
+	}
+}
@@ -0,0 +1,179 @@
+use crate :: { import::*, * };
+
+
+// The room left in the fair queue, see `Peer::set_fair_queuing`. It's shared with the task that reads
+// the connection, which waits for it instead of the backpressure when fair queuing is on.
+//
+pub(crate) type QueueRoom = Arc<Mutex< Option<Arc<BackPressure>> >>;
+
+
+type CallFut<Wf> = Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>;
+
+
+// Incoming calls waiting for a slot of the backpressure, one queue per service. Services take turns,
+// each dispatching as many calls as its weight before the next one gets to go.
+//
+pub(crate) struct FairQueue<Wf>
+{
+	weights: HashMap<ServiceID, usize>                               ,
+	queues : HashMap<ServiceID, VecDeque<(PeerErrCtx, CallFut<Wf>)>> ,
+	active : VecDeque<ServiceID>                                     ,
+	served : usize                                                   ,
+	room   : Arc<BackPressure>                                       ,
+}
+
+
+impl<Wf> FairQueue<Wf>
+{
+	pub(crate) fn new( weights: HashMap<ServiceID, usize>, room: Arc<BackPressure> ) -> Self
+	{
+		Self
+		{
+			weights                  ,
+			room                     ,
+			queues : HashMap::new()  ,
+			active : VecDeque::new() ,
+			served : 0               ,
+		}
+	}
+
+
+	pub(crate) fn push( &mut self, sid: ServiceID, ctx: PeerErrCtx, call: CallFut<Wf> )
+	{
+		let queue = self.queues.entry( sid ).or_default();
+
+		if queue.is_empty()
+		{
+			self.active.push_back( sid );
+		}
+
+		queue.push_back( (ctx, call) );
+		self.room.remove_slots( NonZeroUsize::new(1).expect( "1 > 0" ) );
+	}
+
+
+	// The next call to dispatch, from the service whose turn it is.
+	//
+	pub(crate) fn pop( &mut self ) -> Option<(PeerErrCtx, CallFut<Wf>)>
+	{
+		let sid    = *self.active.front()?;
+		let queue  = self.queues.get_mut( &sid )?;
+		let call   = queue.pop_front()?;
+		let weight = self.weights.get( &sid ).copied().unwrap_or( 1 );
+
+		self.served += 1;
+
+		if queue.is_empty()
+		{
+			self.queues.remove( &sid );
+			self.active.pop_front();
+			self.served = 0;
+		}
+
+		else if self.served >= weight
+		{
+			self.active.rotate_left( 1 );
+			self.served = 0;
+		}
+
+		self.room.add_slots( NonZeroUsize::new(1).expect( "1 > 0" ) );
+
+		Some( call )
+	}
+
+
+	// The number of calls waiting.
+	//
+	pub(crate) fn len( &self ) -> usize
+	{
+		self.queues.values().map( VecDeque::len ).sum()
+	}
+
+
+	// Drop all waiting calls, eg. when the connection closes.
+	//
+	pub(crate) fn clear( &mut self )
+	{
+		if let Some( n ) = NonZeroUsize::new( self.len() )
+		{
+			self.room.add_slots( n );
+		}
+
+		self.queues.clear();
+		self.active.clear();
+		self.served = 0;
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Let services take turns for the slots of the [BackPressure] instead of serving incoming calls in the
+	/// order they come in, so a burst of calls to one service doesn't starve the others. Each service gets
+	/// as many consecutive slots as its weight in `weights` before the next service gets to go. Services
+	/// not listed have a weight of 1.
+	///
+	/// To see the calls of other services while all slots are taken, the peer keeps reading from the
+	/// connection and queues up to `max_queued` calls. Only then the remote gets back pressure. Without
+	/// [BackPressure] calls are never queued, so this has no effect.
+	//
+	pub fn set_fair_queuing
+	(
+		&mut self                                                       ,
+		weights   : impl IntoIterator< Item=(ServiceID, NonZeroUsize) > ,
+		max_queued: NonZeroUsize                                        ,
+	)
+	{
+		let weights = weights.into_iter().map( |(sid, w)| (sid, w.get()) ).collect();
+		let slots   = i64::try_from( max_queued.get() ).unwrap_or( i64::MAX );
+		let room    = Arc::new( BackPressure::new( slots ) );
+
+		*self.queue_room.lock() = Some( room.clone() );
+
+		self.fair_queue = Some( FairQueue::new( weights, room ) );
+	}
+
+
+	// Queue an incoming call that has been authorized and is ready to run, and dispatch what the
+	// backpressure allows.
+	//
+	pub(crate) async fn queue_call( &mut self, sid: ServiceID, ctx: PeerErrCtx, call: CallFut<Wf> )
+	{
+		if let Some( queue ) = &mut self.fair_queue
+		{
+			queue.push( sid, ctx, call );
+		}
+
+		self.dispatch_queued().await;
+	}
+
+
+	// Start queued calls for as long as the backpressure has free slots.
+	//
+	pub(crate) async fn dispatch_queued( &mut self )
+	{
+		loop
+		{
+			if self.backpressure.as_ref().map_or( false, |bp| bp.available() <= 0 ) { return }
+
+			let (ctx, call) = match self.fair_queue.as_mut().and_then( FairQueue::pop )
+			{
+				Some( next ) => next,
+				None         => return,
+			};
+
+			if let Some( ref bp ) = self.backpressure
+			{
+				bp.remove_slots( NonZeroUsize::new(1).expect( "1 > 0" ) );
+			}
+
+			if self.nursery.nurse( call ).is_err()
+			{
+				let err = PeerErr::Spawn{ ctx };
+
+				self.handle( RequestError::from( err ) ).await;
+			}
+		}
+	}
+}
@@ -0,0 +1,84 @@
+use
+{
+	crate     :: { import::*, *                              } ,
+	super     :: { RequestError                              } ,
+	byteorder :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
+};
+
+
+// The handshake is a frame with sid `ServiceID::handshake()` and a null cid. The payload is the
+// maximum frame size the sender accepts:
+//
+// max_size u64 LE
+//
+const LEN_HANDSHAKE: usize = 8;
+
+
+/// Ask a running [Peer] for the maximum frame size agreed on with the remote.
+/// See [`Peer::max_frame_size`].
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+pub struct QueryMaxFrameSize;
+
+impl Message for QueryMaxFrameSize
+{
+	type Return = Option<usize>;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<QueryMaxFrameSize> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: QueryMaxFrameSize ) -> Option<usize>
+	{
+		self.max_frame_size()
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Build the frame announcing our maximum frame size to the remote.
+	//
+	pub(crate) fn handshake_frame( max_size: usize ) -> Wf
+	{
+		let mut wf = Wf::with_capacity( LEN_HANDSHAKE );
+
+		wf.set_sid( ServiceID::handshake() );
+		wf.set_cid( ConnID::null()         );
+
+		// unwrap: writing to an in memory buffer.
+		//
+		wf.write_u64::<LittleEndian>( u64::try_from( max_size ).unwrap_or( u64::MAX ) ).unwrap();
+
+		wf
+	}
+
+
+	/// Process the handshake in which the remote announces the maximum frame size it accepts.
+	//
+	pub(crate) async fn incoming_handshake( &mut self, frame: Wf )
+	{
+		let mut msg = frame.msg();
+
+		if msg.len() != LEN_HANDSHAKE
+		{
+			let source = WireErr::Deserialize{ context: "handshake doesn't hold a frame size".to_string(), source: None };
+			let ctx    = self.ctx( frame.sid(), None, "Process incoming handshake" );
+
+			self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+			return;
+		}
+
+		// unwrap: we just checked the length.
+		//
+		let max_size = msg.read_u64::<LittleEndian>().unwrap();
+		let max_size = usize::try_from( max_size ).unwrap_or( usize::MAX );
+
+		trace!( "{}: remote accepts frames up to {} bytes.", self.identify(), max_size );
+
+		self.remote_max_size = Some( max_size );
+	}
+}
@@ -0,0 +1,111 @@
+use
+{
+	crate   :: { import::*, *      } ,
+	futures :: { future::abortable } ,
+	std     :: { time::Instant     } ,
+};
+
+
+// Sent by the task watching the connection, see Peer::set_idle_timeout. Returns how long to wait before
+// checking again, `None` once the connection is closed.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct IdleCheck;
+
+impl Message for IdleCheck
+{
+	type Return = Option<Duration>;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<IdleCheck> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: IdleCheck ) -> Option<Duration>
+	{
+		if self.closed { return None }
+
+		let timeout = self.idle_timeout?;
+		let idle    = self.active_at.elapsed();
+
+		if idle < timeout
+		{
+			return Some( timeout - idle );
+		}
+
+		debug!( "{}: no frames for {:?}, closing the connection.", self.identify(), idle );
+
+		let close = CloseConnection{ remote: false, reason: "Idle timeout.".to_string() };
+
+		self.close_with( close, CloseReason::Idle ).await;
+
+		None
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Close the connection when no frames went in or out for `timeout`, eg. to free the resources of
+	/// clients that went away without closing their connection. The connection closes with
+	/// [`CloseReason::Idle`]. Combine it with [`Peer::ping`] on the remote to keep a quiet connection open.
+	///
+	/// This is unrelated to [`Peer::set_auto_close_on_idle`], which stops a peer that has no work in progress.
+	/// There is no idle timeout by default. Calling this again replaces the timeout.
+	//
+	pub fn set_idle_timeout( &mut self, timeout: Duration ) -> Result<(), PeerErr>
+	{
+		self.idle_timeout = Some( timeout );
+		self.active_at    = Instant::now();
+
+		// The previous watcher would check at its own pace.
+		//
+		if let Some( watch ) = self.idle_watch.take()
+		{
+			watch.abort();
+		}
+
+		let addr = self.weak_addr.clone();
+
+		let watch = async move
+		{
+			let mut wait = timeout;
+
+			loop
+			{
+				Delay::new( wait ).await;
+
+				// Don't keep the peer alive in between checks.
+				//
+				let mut addr = match addr.strong()
+				{
+					Ok ( addr ) => addr,
+					Err( _    ) => break,
+				};
+
+				wait = match addr.call( IdleCheck ).await
+				{
+					Ok( Some( wait ) ) => wait,
+					_                  => break,
+				};
+			}
+
+			Ok( Response::Nothing )
+		};
+
+		let (watch, handle) = abortable( watch );
+
+		self.idle_watch = Some( handle );
+
+		let watch = async move { watch.await.unwrap_or( Ok( Response::Nothing ) ) };
+
+		self.nursery.nurse( watch ).map_err( |_|
+		{
+			let ctx = self.ctx( None, None, "Watch for an idle connection" );
+
+			PeerErr::Spawn{ ctx }
+		})
+	}
+}
@@ -0,0 +1,75 @@
+use
+{
+	crate           :: { import::*, * } ,
+	futures_ringbuf :: { Endpoint     } ,
+	thespis_impl    :: { MailboxEnd   } ,
+};
+
+
+// The size of the in memory buffer in each direction.
+//
+const IN_PROCESS_BUF: usize = 64 * 1024;
+
+
+/// The join handles of the mailboxes of the peers created by [`Peer::in_process_pair`].
+//
+pub type PairHandles = ( JoinHandle<MailboxEnd<Peer>>, JoinHandle<MailboxEnd<Peer>> );
+
+
+impl Peer<ThesWF>
+{
+	/// Create two peers connected by an in memory pipe and start their mailboxes on `exec`. This lets
+	/// you use remote actors within one process without a socket, eg. to isolate plugins. Each peer
+	/// exposes the given services to the other one.
+	///
+	/// `max_size` is the maximum frame size for both directions. Awaiting or dropping the join handles
+	/// works like for any mailbox, the peers stop when the connection gets closed.
+	//
+	pub fn in_process_pair
+	(
+		services_a: Vec< Arc<dyn ServiceMap> >                      ,
+		services_b: Vec< Arc<dyn ServiceMap> >                      ,
+		max_size  : usize                                           ,
+		exec      : impl PeerExec + SpawnHandle< MailboxEnd<Self> > ,
+	)
+
+		-> Result< (Addr<Self>, Addr<Self>, PairHandles), PeerErr >
+
+	{
+		let (socket_a, socket_b) = Endpoint::pair( IN_PROCESS_BUF, IN_PROCESS_BUF );
+
+		let (addr_a, handle_a) = Self::start_in_process( socket_a, services_a, max_size, exec.clone(), "in_process_a" )?;
+		let (addr_b, handle_b) = Self::start_in_process( socket_b, services_b, max_size, exec        , "in_process_b" )?;
+
+		Ok(( addr_a, addr_b, (handle_a, handle_b) ))
+	}
+
+
+	fn start_in_process
+	(
+		socket  : Endpoint                                        ,
+		services: Vec< Arc<dyn ServiceMap> >                      ,
+		max_size: usize                                           ,
+		exec    : impl PeerExec + SpawnHandle< MailboxEnd<Self> > ,
+		name    : &str                                            ,
+	)
+
+		-> Result< (Addr<Self>, JoinHandle<MailboxEnd<Self>>), PeerErr >
+
+	{
+		let (addr, mb) = Addr::builder().name( name.into() ).build();
+		let mut peer   = Peer::from_async_read( addr.clone(), socket, max_size, exec.clone(), None, None )?;
+
+		for sm in services
+		{
+			peer.register_services( sm );
+		}
+
+		let handle = exec.spawn_handle( mb.start( peer ) ).map_err( |_|
+		{
+			PeerErr::Spawn{ ctx: Peer::err_ctx( &addr, None, None, "Start the mailbox of an in process peer".to_string() ) }
+		})?;
+
+		Ok(( addr, handle ))
+	}
+}
@@ -1,7 +1,7 @@
 use
 {
-	crate::{ import::*, *, WireType },
-	super::RequestError              ,
+	crate::{ import::*, *, FrameKind                   },
+	super::{ RequestError, CancelSource, InflightBytes },
 };
 
 
@@ -9,7 +9,8 @@ use
 //
 pub(super) struct Incoming<Wf>
 {
-	pub(crate) msg: Result<Wf, WireErr>
+	pub(crate) msg     : Result<Wf, WireErr>   ,
+	pub(crate) inflight: Option<InflightBytes> ,
 }
 
 impl<Wf: WireFormat> Message for Incoming<Wf>
@@ -29,6 +30,19 @@ impl<Wf: WireFormat> Message for Incoming<Wf>
 impl<Wf: WireFormat + Send + 'static> Handler<Incoming<Wf>> for Peer<Wf>
 {
 	#[async_fn] fn handle( &mut self, incoming: Incoming<Wf> )
+	{
+		self.process_incoming( incoming ).await;
+
+		// Incoming frames answer our calls, bring in calls from the remote and open or close streams.
+		//
+		self.update_idle();
+	}
+}
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	async fn process_incoming( &mut self, incoming: Incoming<Wf> )
 	{
 		// We're shut down. we can't really do anything useful.
 		//
@@ -39,43 +53,96 @@ impl<Wf: WireFormat + Send + 'static> Handler<Incoming<Wf>> for Peer<Wf>
 			Ok ( mesg  ) => mesg,
 			Err( error ) =>
 			{
+				if let Some( tx ) = &self.manual
+				{
+					// If the application dropped the stream, there is nobody to tell.
+					//
+					let _ = tx.unbounded_send( Err( error.clone() ) );
+				}
+
 				// Can be:
 				// - WireErr::MessageSizeExceeded (Codec)
 				// - WireErr::Deserialize (BytesFormat)
 				// - WireErr::IO...
+				// - WireErr::Decrypt (encryption), we can no longer trust this connection.
+				// - WireErr::Replay (encryption), same.
 				//
+				let close = matches!( error, WireErr::Decrypt{..} | WireErr::Replay{..} );
+
 				let err = PeerErr::WireFormat{ source: error, ctx: self.ctx( None, None, "Deserialize Incoming message or IO error." ) };
 
 				self.handle( RequestError::from( err ) ).await;
 
+				if close
+				{
+					let close_conn = CloseConnection{ remote: false, reason: "Failed to decrypt incoming frame.".to_string() };
+
+					self.close_with( close_conn, CloseReason::AuthFailed ).await;
+				}
+
 				return
 			}
 		};
 
+		self.record_frame( frame.len(), true );
+
+		if let Some( hook ) = &self.incoming_hook
+		{
+			hook( frame.sid(), frame.cid(), frame.msg() );
+		}
+
+
+		// Chunks are collected until the message is complete, which is then processed like any other frame.
+		//
+		let frame = match frame.kind()
+		{
+			FrameKind::Chunk => match self.incoming_chunk( frame ).await
+			{
+				Some( whole ) => whole,
+				None          => return,
+			}
+
+			_ => frame,
+		};
+
 
 		let sid    = frame.sid();
 		let cid    = frame.cid();
 		let kind   = frame.kind();
 
+		if self.manual.is_some() && matches!( kind, FrameKind::Send | FrameKind::Call )
+		{
+			return self.manual_request( frame ).await;
+		}
+
 		// TODO: when we have benchmarks, verify if it's better to return boxed submethods here
 		// rather than awaiting. Implies the rest of this method can run sync.
 		//
 		match kind
 		{
-			WireType::ConnectionError => self.remote_conn_err( frame, cid        ).await,
-			WireType::IncomingSend    => self.incoming_send  ( sid, frame      ).await,
-			WireType::IncomingCall    => self.incoming_call  ( cid, sid, frame ).await,
+			FrameKind::Error     => self.remote_conn_err( frame, cid        ).await,
+			FrameKind::Send      => self.incoming_send  ( sid, frame, incoming.inflight      ).await,
+			FrameKind::Call      => self.incoming_call  ( cid, sid, frame, incoming.inflight ).await,
+			FrameKind::Stream    => self.incoming_stream( frame           ).await,
+			FrameKind::Handshake => self.incoming_handshake( frame        ).await,
+			FrameKind::Control   => self.incoming_control  ( frame        ).await,
+
+			// incoming_chunk doesn't accept chunks inside of chunks.
+			//
+			FrameKind::Chunk => unreachable!(),
 
-			WireType::CallResponse =>
+			FrameKind::Response =>
 			{
 				// it's a succesful response to a (relayed) call
 				//
-				if let Some( channel ) = self.responses.remove( &cid )
+				if let Some( (sid, channel) ) = self.responses.remove( &cid )
 				{
 					// It's a response
 					//
 					trace!( "{}: Incoming Return", self.identify() );
 
+					self.answered.insert( cid, sid );
+
 					// Normally if this fails it means the receiver of the channel was dropped...
 					//
 					if channel.send( Ok(frame) ).is_err()
@@ -84,12 +151,13 @@ impl<Wf: WireFormat + Send + 'static> Handler<Incoming<Wf>> for Peer<Wf>
 					}
 				}
 
-				// There is a CID, so it's a response, but it's not in our self.responses, so it has timed out.
-				// We are no longer waiting for this response, so we can only drop it.
+				// There is a CID, so it's a response, but it's not in our self.responses. Either we already delivered
+				// a response for this call, or it has timed out or it was a DetachedCall. We are no longer waiting
+				// for this response, so we can only drop it.
 				//
-				else
+				else if !self.duplicate_response( cid ).await
 				{
-					warn!( "{}: Received response for a timed out outgoing request, cid: {}. Dropping response.", self.identify(), cid );
+					debug!( "{}: Received response for a timed out or detached outgoing request, cid: {}. Dropping response.", self.identify(), cid );
 				}
 			}
 		}
@@ -109,43 +177,52 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		// We can correctly interprete the error
 		//
-		if let Ok( err ) = serde_cbor::from_slice::<ConnectionError>( serialized )
+		match self.error_codec.decode( serialized )
 		{
-			// We need to report the connection error to the caller
-			//
-			if let Some( channel ) = self.responses.remove( &cid )
+			Ok( err ) =>
 			{
-				// If this returns an error, it means the receiver was dropped, so if they no longer
-				// care for the result, neither do we, so ignoring the result.
+				// We need to report the connection error to the caller
 				//
-				let _ = channel.send( Err( err ) );
+				if let Some( (sid, channel) ) = self.responses.remove( &cid )
+				{
+					self.answered.insert( cid, sid );
 
-				// Since this was not our error, just relay the response.
-				//
-				return
-			}
+					// If this returns an error, it means the receiver was dropped, so if they no longer
+					// care for the result, neither do we, so ignoring the result.
+					//
+					let _ = channel.send( Err( err ) );
 
-			// Notify observers
-			//
-			let shine = PeerEvent::RemoteError( err );
+					// Since this was not our error, just relay the response.
+					//
+					return
+				}
 
-			// If pharos is closed, we already panicked... so except is fine.
-			//
-			self.pharos.send( shine ).await.expect( "pharos not closed" );
+				if self.duplicate_response( cid ).await
+				{
+					return
+				}
 
-		}
+				// Notify observers
+				//
+				let shine = PeerEvent::RemoteError( err );
 
-		// Since we can't deserialize it, we can't do much except log.
-		//
-		else
-		{
-			let ctx = self.ctx( None, cid, "We received an error message from a remote peer, but couldn't deserialize it" );
-			let err = PeerErr::Deserialize{ ctx };
-			let shine = PeerEvent::Error(err);
+				// If pharos is closed, we already panicked... so except is fine.
+				//
+				self.pharos.send( shine ).await.expect( "pharos not closed" );
+			}
 
-			// If pharos is closed, we already panicked... so except is fine.
+			// Since we can't deserialize it, we can't do much except log.
 			//
-			self.pharos.send( shine ).await.expect( "pharos not closed" );
+			Err( source ) =>
+			{
+				let ctx   = self.ctx( None, cid, "We received an error message from a remote peer, but couldn't deserialize it" );
+				let err   = PeerErr::Deserialize{ ctx, source: Some( source ) };
+				let shine = PeerEvent::Error(err);
+
+				// If pharos is closed, we already panicked... so except is fine.
+				//
+				self.pharos.send( shine ).await.expect( "pharos not closed" );
+			}
 		}
 	}
 
@@ -156,8 +233,9 @@ impl<Wf: WireFormat> Peer<Wf>
 	async fn incoming_send
 	(
 		&mut self           ,
-		sid     : ServiceID ,
-		frame   : Wf        ,
+		sid     : ServiceID             ,
+		frame   : Wf                    ,
+		inflight: Option<InflightBytes> ,
 	)
 	{
 		let identity = self.identify();
@@ -166,7 +244,7 @@ impl<Wf: WireFormat> Peer<Wf>
 
 		let ctx = self.ctx( sid, None, "Peer: Handle incoming send" );
 
-		let sm = match self.services.get( &sid )
+		let sm = match self.service_map( frame.channel(), &sid )
 		{
 			Some( sm ) => sm,
 
@@ -183,6 +261,17 @@ impl<Wf: WireFormat> Peer<Wf>
 		};
 
 
+		if !self.authorized( &frame, &ctx )
+		{
+			return self.handle( RequestError::from( PeerErr::Unauthorized{ ctx } ) ).await;
+		}
+
+
+		// Keep a copy of the frame in case the handling actor turns out to be dead.
+		//
+		let dead_letter = self.dead_letters.clone().map( |tx| (tx, frame.clone()) );
+
+
 		// Send to handling actor,
 		//
 		let fut = match sm.send_service( frame, ctx )
@@ -195,9 +284,14 @@ impl<Wf: WireFormat> Peer<Wf>
 
 				let err = match e
 				{
-					PeerErr::NoHandler  {..} => PeerErr::NoHandler  {ctx} ,
-					PeerErr::Deserialize{..} => PeerErr::Deserialize{ctx} ,
-					_                        => unreachable!()            ,
+					PeerErr::NoHandler  { ..         } => PeerErr::NoHandler  { ctx         } ,
+					PeerErr::Deserialize{ source, .. } => PeerErr::Deserialize{ ctx, source } ,
+					PeerErr::Validation { reason, .. } => PeerErr::Validation { ctx, reason } ,
+
+					// Wrapping handlers like a draining RelayMap can refuse the send with their own
+					// errors, eg. ShuttingDown. Those already carry the context of the request.
+					//
+					other => other,
 				};
 
 
@@ -208,6 +302,60 @@ impl<Wf: WireFormat> Peer<Wf>
 		};
 
 
+		let fut = match dead_letter
+		{
+			None => fut,
+
+			Some( (mut tx, frame) ) => async move
+			{
+				let result = fut.await;
+
+				if let Err( PeerErr::HandlerDead{..} ) = &result
+				{
+					// If the application dropped the receiver, there is nothing left to do with it.
+					//
+					let _ = tx.send( (sid, frame) ).await;
+				}
+
+				result
+
+			}.boxed(),
+		};
+
+
+		// The bytes of the frame count against the budget until the handler is done.
+		//
+		let fut = async move
+		{
+			let _inflight = inflight;
+			fut.await
+		};
+
+
+		let fut = match self.send_queue.clone()
+		{
+			None => fut.boxed(),
+
+			Some( queue ) =>
+			{
+				let (fut, dropped) = queue.admit( sid, fut ).await;
+
+				if let Some( dropped ) = dropped
+				{
+					debug!( "{}: The send queue is full, dropping incoming send for sid: {}", &identity, &dropped );
+
+					self.send_rejected( dropped ).await;
+				}
+
+				match fut
+				{
+					Some( fut ) => fut.boxed(),
+					None        => return,
+				}
+			}
+		};
+
+
 		if self.nursery.nurse( fut ).is_err()
 		{
 			let ctx = self.ctx( sid, None, "sm.send_service" );
@@ -225,14 +373,17 @@ impl<Wf: WireFormat> Peer<Wf>
 	async fn incoming_call
 	(
 		&mut self           ,
-		cid     : ConnID    ,
-		sid     : ServiceID ,
-		frame   : Wf        ,
+		cid     : ConnID                ,
+		sid     : ServiceID             ,
+		frame   : Wf                    ,
+		inflight: Option<InflightBytes> ,
 	)
 	{
 		if self.closed { return }
 
-		if let Some( ref bp ) = self.backpressure
+		// With fair queuing the slot is taken when the call leaves the queue.
+		//
+		if let Some( bp ) = self.backpressure.as_ref().filter( |_| self.fair_queue.is_none() )
 		{
 			bp.remove_slots( NonZeroUsize::new(1).unwrap() );
 		}
@@ -242,9 +393,30 @@ impl<Wf: WireFormat> Peer<Wf>
 		let ctx = self.ctx( sid, cid, "Peer: Handle incoming call" );
 
 
+		// The remote should never reuse the cid of a call we haven't answered yet. Reject rather
+		// than risk the responses being mixed up.
+		//
+		if !self.inbound.insert( cid )
+		{
+			let err = PeerErr::DuplicateCid{ ctx };
+
+			return self.handle( RequestError::from( err ) ).await;
+		}
+
+
+		// The caller is no longer waiting for the response, don't waste resources on it.
+		//
+		if frame.deadline().map_or( false, |d| d <= SystemTime::now() )
+		{
+			let err = PeerErr::Timeout{ ctx };
+
+			return self.handle( RequestError::from( err ) ).await;
+		}
+
+
 		// Find our handler.
 		//
-		let sm = match self.services.get( &sid )
+		let sm = match self.service_map( frame.channel(), &sid )
 		{
 			Some( sm ) => sm,
 
@@ -259,14 +431,38 @@ impl<Wf: WireFormat> Peer<Wf>
 		};
 
 
+		if !self.authorized( &frame, &ctx )
+		{
+			return self.handle( RequestError::from( PeerErr::Unauthorized{ ctx } ) ).await;
+		}
+
+
+		// The token for this call is also cancelled when the future gets dropped, eg. when the
+		// nursery is dropped after the grace period.
+		//
+		let cancel = CancelSource::child_of( &self.cancel.token() );
+
 		// Get future from service map.
 		//
-		let fut = match sm.call_service( frame, ctx.clone() )
+		let fut = match sm.call_service( frame, ctx.clone(), cancel.token() )
 		{
 			Ok (f) => f,
 			Err(e) => return self.handle( RequestError::from(e) ).await,
 		};
 
+		let fut = async move
+		{
+			let _cancel   = cancel;
+			let _inflight = inflight;
+			fut.await
+		};
+
+
+		if self.fair_queue.is_some()
+		{
+			return self.queue_call( sid, ctx, fut.boxed() ).await;
+		}
+
 
 		// Call handling actor,
 		//
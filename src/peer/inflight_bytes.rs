@@ -0,0 +1,71 @@
+use crate :: { import::*, * };
+
+
+// The budget for the bytes of incoming frames, see `Peer::set_max_inflight_bytes`. It's shared with
+// the task that reads the connection, which already runs when the budget gets set.
+//
+pub(crate) type ByteBudget = Arc<Mutex< Option<Arc<BackPressure>> >>;
+
+
+// The bytes of an incoming frame taken from the budgets, the byte budget and the memory limit. They
+// are given back when this is dropped, that is when the frame has been processed.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct InflightBytes
+{
+	budgets: Vec<Arc<BackPressure>> ,
+	bytes  : NonZeroUsize           ,
+}
+
+
+impl InflightBytes
+{
+	// Take `bytes` from each of the budgets, None if there are none.
+	//
+	pub(crate) fn take( budgets: Vec<Arc<BackPressure>>, bytes: usize ) -> Option<Self>
+	{
+		let bytes = NonZeroUsize::new( bytes )?;
+
+		if budgets.is_empty() { return None }
+
+		for budget in &budgets
+		{
+			budget.remove_slots( bytes );
+		}
+
+		Some( Self { budgets, bytes } )
+	}
+}
+
+
+impl Drop for InflightBytes
+{
+	fn drop( &mut self )
+	{
+		for budget in &self.budgets
+		{
+			budget.add_slots( self.bytes );
+		}
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Limit the total size of the incoming frames this peer holds, in bytes. Frames count from the moment
+	/// they are read from the connection until they are processed, that is until the handler is done for
+	/// sends and calls. While the limit is exceeded, the peer stops reading from the connection, so
+	/// the remote gets back pressure and memory stays bounded when a lot of requests come in at once.
+	///
+	/// This complements [BackPressure], which limits the number of concurrent calls, whatever their size.
+	/// A single frame bigger than the limit is still processed, but nothing else is read until it's done.
+	//
+	pub fn set_max_inflight_bytes( &mut self, max: NonZeroUsize )
+	{
+		let slots = i64::try_from( max.get() ).unwrap_or( i64::MAX );
+
+		*self.byte_budget.lock() = Some( Arc::new( BackPressure::new( slots ) ) );
+	}
+}
@@ -0,0 +1,55 @@
+use crate :: { import::*, * };
+
+
+// Sent by the task reading the connection before it reads the first frame, so the peer runs the
+// on_connect callback once its mailbox runs, before it processes anything that came in.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct Connected;
+
+impl Message for Connected
+{
+	type Return = ();
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<Connected> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: Connected )
+	{
+		if let Some( callback ) = self.on_connect.take()
+		{
+			callback();
+		}
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Run `callback` once the mailbox of the peer runs, before it processes the first incoming frame.
+	/// It runs on the task of the peer, so it should not block. Use it to tie external resources to the
+	/// connection, eg. to add it to a registry, together with [`Peer::on_disconnect`].
+	///
+	/// Only the latest callback is kept and it runs at most once.
+	//
+	pub fn on_connect( &mut self, callback: impl FnOnce() + Send + 'static )
+	{
+		self.on_connect = Some( Box::new( callback ) );
+	}
+
+
+	/// Run `callback` when the connection closes, with the [CloseConnection] that closed it and the
+	/// [CloseReason], so it tells whether the remote closed it and why. It runs on the task of the peer,
+	/// before [`PeerEvent::Closed`] or [`PeerEvent::ClosedByRemote`] is published.
+	///
+	/// Only the latest callback is kept and it runs at most once.
+	//
+	pub fn on_disconnect( &mut self, callback: impl FnOnce( &CloseConnection, CloseReason ) + Send + 'static )
+	{
+		self.on_disconnect = Some( Box::new( callback ) );
+	}
+}
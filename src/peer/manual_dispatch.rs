@@ -0,0 +1,77 @@
+use
+{
+	crate::{ import::*, *       },
+	super::{ RequestError      },
+};
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Dispatch incoming requests yourself, eg. to route on top of the framing without a [ServiceMap].
+	/// Incoming sends and calls are no longer handed to the service maps, but come out of the returned
+	/// stream as raw frames, in the order they were received. Errors of the codec come out too. They are
+	/// still reported as [`PeerEvent::Error`] and the connection still gets closed when it can no longer
+	/// be trusted.
+	///
+	/// The peer keeps taking care of everything else: outgoing calls and their responses, streams, control
+	/// frames and handshakes. Answer a call by sending a frame with [`ServiceID::full`] as sid and the cid
+	/// of the call to the [`Peer::wire_sink`]. Until then the call counts as in progress, eg. in
+	/// [`PeerStatus::inbound_calls`], and a call that reuses its cid is refused with
+	/// [`ConnectionError::DuplicateCid`].
+	///
+	/// The stream is unbounded, so frames pile up when the consumer is slower than the remote. It ends when
+	/// the connection is closed. When it is dropped, incoming sends and calls fail with
+	/// [`PeerErr::HandlerDead`]. Calling this method again replaces the first stream.
+	//
+	pub fn manual_dispatch( &mut self ) -> impl Stream< Item = Result<Wf, WireErr> > + Send + Unpin + 'static
+	{
+		let (tx, rx) = mpsc::unbounded();
+
+		self.manual = Some( tx );
+
+		rx
+	}
+
+
+	// Hand an incoming send or call to the stream from manual_dispatch.
+	//
+	pub(super) async fn manual_request( &mut self, frame: Wf )
+	{
+		let sid = frame.sid();
+		let cid = Some( frame.cid() ).filter( |cid| !cid.is_null() );
+		let ctx = self.ctx( sid, cid, "Peer: manual dispatch" );
+
+		trace!( "{}: Manual dispatch, sid: {}, cid: {:?}", self.identify(), sid, cid );
+
+
+		// The remote should never reuse the cid of a call we haven't answered yet.
+		//
+		if let Some( cid ) = cid
+		{
+			if !self.inbound.insert( cid )
+			{
+				return self.handle( RequestError::from( PeerErr::DuplicateCid{ ctx } ) ).await;
+			}
+		}
+
+
+		if !self.authorized( &frame, &ctx )
+		{
+			return self.handle( RequestError::from( PeerErr::Unauthorized{ ctx } ) ).await;
+		}
+
+
+		let sent = match &self.manual
+		{
+			Some( tx ) => tx.unbounded_send( Ok( frame ) ).is_ok(),
+			None       => false,
+		};
+
+		// The error frame for a call frees up its cid again.
+		//
+		if !sent
+		{
+			self.handle( RequestError::from( PeerErr::HandlerDead{ ctx } ) ).await;
+		}
+	}
+}
@@ -0,0 +1,192 @@
+use
+{
+	crate :: { import::*, *  } ,
+	super :: { InflightBytes } ,
+};
+
+
+// The memory limit of a connection, see `Peer::set_memory_limit`. It's shared with the task that reads
+// the connection, which already runs when the limit gets set.
+//
+pub(crate) type MemoryAccount = Arc<Mutex< Option<Arc<MemoryLimit>> >>;
+
+
+// The bytes a connection is allowed to tie up. Incoming frames are charged to the budget while the
+// decoder reads them, see Peer::partial_frames, and while they are being processed, see InflightBytes.
+// The table of outgoing calls waiting for a response is checked against what is left when a call
+// goes out, see Peer::admit_call.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct MemoryLimit
+{
+	budget: Arc<BackPressure> ,
+	limit : usize             ,
+	close : bool              ,
+}
+
+
+impl MemoryLimit
+{
+	pub(crate) fn budget( &self ) -> Arc<BackPressure>
+	{
+		self.budget.clone()
+	}
+
+
+	// The bytes charged for incoming frames.
+	//
+	pub(crate) fn used( &self ) -> usize
+	{
+		let available = self.budget.available();
+
+		match available < 0
+		{
+			true  => self.limit.saturating_add( available.unsigned_abs() as usize ),
+			false => self.limit.saturating_sub( available as usize ),
+		}
+	}
+
+
+	// The bytes in use, when the limit is exceeded.
+	//
+	pub(crate) fn exceeded( &self ) -> Option<usize>
+	{
+		match self.budget.available() < 0
+		{
+			true  => Some( self.used() ),
+			false => None,
+		}
+	}
+}
+
+
+
+// Sent by the task reading the connection when it stops reading because the limit is exceeded.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct MemoryLimitHit
+{
+	pub(crate) used: usize,
+}
+
+impl Message for MemoryLimitHit
+{
+	type Return = ();
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<MemoryLimitHit> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: MemoryLimitHit )
+	{
+		let ( limit, close ) = match self.memory.lock().as_ref()
+		{
+			Some( m ) => ( m.limit, m.close ),
+			None      => return,
+		};
+
+		warn!( "{}: memory limit exceeded, {} of {} bytes in use.", self.identify(), msg.used, limit );
+
+		self.pharos.send( PeerEvent::MemoryLimitExceeded{ used: msg.used, limit } ).await.expect( "pharos not closed" );
+
+		if close
+		{
+			let close = CloseConnection{ remote: false, reason: "Memory limit exceeded.".to_string() };
+
+			self.close_with( close, CloseReason::MemoryLimit ).await;
+		}
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Limit the memory a connection can tie up, in bytes. This counts the incoming frames from the moment
+	/// the decoder starts reading them until they are processed and an estimate of the table of outgoing
+	/// calls waiting for a response. When incoming frames exceed the limit, the peer stops reading from the
+	/// connection until enough is released and publishes [`PeerEvent::MemoryLimitExceeded`]. With `close`,
+	/// it closes the connection instead.
+	///
+	/// The response table doesn't stop the peer from reading, since that would also hold up the responses
+	/// that empty it. Instead, new outgoing calls fail with [`WireErr::OutOfMemory`] while the table would
+	/// not fit in what the incoming frames leave of the limit.
+	///
+	/// Frames count while they are being read only for the peers created with [`Peer::from_async_read`],
+	/// [`Peer::from_async_read_encrypted`] and [`Peer::from_async_read_compressed`].
+	///
+	/// This combines [`Peer::set_max_inflight_bytes`] with the open calls in a single budget, eg. to bound
+	/// every connection of a multi tenant server. Both can be used together.
+	//
+	pub fn set_memory_limit( &mut self, limit: NonZeroUsize, close: bool )
+	{
+		let slots = i64::try_from( limit.get() ).unwrap_or( i64::MAX );
+
+		let memory = Arc::new( MemoryLimit
+		{
+			budget: Arc::new( BackPressure::new( slots ) ) ,
+			limit : limit.get()                            ,
+			close                                          ,
+		});
+
+		*self.memory.lock() = Some( memory );
+	}
+
+
+	// Refuse a new outgoing call when the response table with an entry for it doesn't fit in what the
+	// incoming frames leave of the memory limit.
+	//
+	pub(crate) fn admit_call( &self, sid: ServiceID ) -> Result<(), PeerErr>
+	{
+		// An entry in the table, with the response it holds once it comes in.
+		//
+		type Entry<Wf> = ( ConnID, ServiceID, oneshot::Sender<Result<Wf, ConnectionError>>, Result<Wf, ConnectionError> );
+
+		let memory = match self.memory.lock().clone()
+		{
+			Some( m ) => m,
+			None      => return Ok(()),
+		};
+
+		let table = ( self.responses.len() + 1 ).saturating_mul( std::mem::size_of::< Entry<Wf> >() );
+		let used  = memory.used().saturating_add( table );
+
+		if used > memory.limit
+		{
+			let ctx    = self.ctx( sid, None, "Handler<Call> for Peer: memory limit" );
+			let source = WireErr::OutOfMemory{ context: "memory limit of the connection".to_string(), size: used };
+
+			return Err( PeerErr::WireFormat{ ctx, source } );
+		}
+
+		Ok(())
+	}
+
+
+	// Charge the frame the decoder is reading to the memory limit until it's complete, see
+	// Decoder::on_progress. From then on listen_incoming charges it as InflightBytes. The charge
+	// is given back when the decoder is dropped halfway through a frame.
+	//
+	pub(crate) fn partial_frames( memory: MemoryAccount ) -> impl FnMut( usize, usize ) -> Result<(), WireErr> + Send + 'static
+	{
+		let mut charged: Option<InflightBytes> = None;
+
+		move |read, len|
+		{
+			charged = None;
+
+			if read < len
+			{
+				if let Some( limit ) = memory.lock().as_ref()
+				{
+					charged = InflightBytes::take( vec![ limit.budget() ], len );
+				}
+			}
+
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,34 @@
+use crate :: { import::*, * };
+
+
+/// Ask a running [Peer] for the connection ids of the outgoing calls still waiting for a response,
+/// eg. to find out which service hangs. With a `sid`, only the calls to that service are returned.
+/// The order is unspecified.
+//
+#[ derive( Debug, Clone, Copy, Default ) ]
+//
+pub struct GetOpenCalls
+{
+	/// Only return the calls to this service.
+	//
+	pub sid: Option<ServiceID>,
+}
+
+impl Message for GetOpenCalls
+{
+	type Return = Vec<ConnID>;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<GetOpenCalls> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: GetOpenCalls ) -> Vec<ConnID>
+	{
+		self.responses.iter()
+
+			.filter( |(_, (sid, _))| msg.sid.map_or( true, |filter| filter == *sid ) )
+			.map   ( |(cid, _)     | *cid                                            )
+			.collect()
+	}
+}
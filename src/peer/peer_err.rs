@@ -1,4 +1,4 @@
-use crate::{ import::*, ConnID, ServiceID, ConnectionError, WireErr };
+use crate::{ import::*, ConnID, ServiceID, ConnectionError, ErrorSource, WireErr };
 
 
 /// Errors that can happen in thespis_impl.
@@ -22,12 +22,40 @@ pub enum PeerErr
 	/// The connection shall remain functional.
 	//
 	Deserialize
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx,
+
+		/// The error returned by the deserializer.
+		//
+		source: Option<ErrorSource>,
+	},
+
+	/// A cid collided with the cid of another call in flight. For outgoing calls, this means no
+	/// free cid could be generated. For incoming calls, the remote reused the cid of a call we
+	/// haven't answered yet.
+	//
+	DuplicateCid
 	{
 		/// The contex in which the error happened.
 		//
 		ctx: PeerErrCtx
 	},
 
+	/// All addresses of a [`FanoutAddr`](crate::FanoutAddr) failed to process a call.
+	//
+	Fanout
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx,
+
+		/// The error of each address, in the order they failed.
+		//
+		errors: Vec<PeerErr>,
+	},
+
 	/// Cannot deliver because the handling actor is no longer running.
 	//
 	HandlerDead
@@ -81,7 +109,11 @@ pub enum PeerErr
 	{
 		/// The contex in which the error happened.
 		//
-		ctx: PeerErrCtx
+		ctx: PeerErrCtx,
+
+		/// The error returned by the serializer.
+		//
+		source: Option<ErrorSource>,
 	},
 
 	/// Failed to spawn a task.
@@ -106,7 +138,8 @@ pub enum PeerErr
 		source: Arc<ThesErr> , // it does not implement clone.
 	},
 
-	/// An operation timed out. Currently used for outgoing calls.
+	/// An operation timed out. Currently used for outgoing calls and for incoming calls
+	/// of which the deadline has passed.
 	//
 	Timeout
 	{
@@ -115,6 +148,79 @@ pub enum PeerErr
 		ctx: PeerErrCtx
 	},
 
+	/// The peer was shut down with [`Peer::shutdown`](crate::Peer::shutdown) while the call was waiting
+	/// for a response.
+	//
+	ShuttingDown
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx
+	},
+
+	/// The call was cancelled with [`CancelAll`](crate::CancelAll) while it was waiting for a response.
+	//
+	Cancelled
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx
+	},
+
+	/// A [`CircuitBreakerAddr`](crate::CircuitBreakerAddr) refused the call because too many calls failed
+	/// recently. The call was not sent.
+	//
+	CircuitOpen
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx
+	},
+
+	/// A [`QuorumAddr`](crate::QuorumAddr) did not get enough identical responses.
+	//
+	NoQuorum
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx,
+
+		/// The number of identical responses that was required.
+		//
+		quorum: usize,
+
+		/// The highest number of identical responses that was received.
+		//
+		agreed: usize,
+
+		/// The errors returned by replicas.
+		//
+		errors: Vec<PeerErr>,
+	},
+
+	/// The guard of the peer refused an incoming request, see [`Peer::set_guard`].
+	//
+	Unauthorized
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx
+	},
+
+	/// An incoming message was rejected by the validator of its service, see `Services::set_validator`
+	/// in [`service_map!`](crate::service_map!). It was not delivered to the handler.
+	//
+	Validation
+	{
+		/// The contex in which the error happened.
+		//
+		ctx: PeerErrCtx,
+
+		/// Why the validator rejected the message.
+		//
+		reason: String,
+	},
+
 	/// Cannot deliver message to unknown service.
 	//
 	UnknownService
@@ -152,7 +258,22 @@ pub enum PeerErr
 
 
 
-impl std::error::Error for PeerErr {}
+impl std::error::Error for PeerErr
+{
+	fn source( &self ) -> Option< &(dyn std::error::Error + 'static) >
+	{
+		match &self
+		{
+			PeerErr::Deserialize{ source, .. } => source.as_ref().map( ErrorSource::inner ),
+			PeerErr::Fanout     { errors, .. } => Some( errors.last()? ),
+			PeerErr::Serialize  { source, .. } => source.as_ref().map( ErrorSource::inner ),
+			PeerErr::Remote     { err   , .. } => Some( err          ),
+			PeerErr::ThesErr    { source, .. } => Some( &**source    ),
+			PeerErr::WireFormat { source, .. } => Some( source       ),
+			_                                  => None,
+		}
+	}
+}
 
 
 impl fmt::Display for PeerErr
@@ -165,9 +286,27 @@ impl fmt::Display for PeerErr
 
 				write!( f, "Cannot use peer after the connection is closed, operation.{}", ctx ),
 
-			PeerErr::Deserialize{ ctx } =>
+			PeerErr::Deserialize{ ctx, source } =>
+			{
+				write!( f, "Failed to deserialize an Actor message.{}", ctx )?;
+				fmt_cause( f, source )
+			}
 
-				write!( f, "Failed to deserialize an Actor message.{}", ctx ),
+			PeerErr::DuplicateCid{ ctx } =>
+
+				write!( f, "The cid is already in use by another call in flight.{}", ctx ),
+
+			PeerErr::Fanout{ ctx, errors } =>
+			{
+				write!( f, "All addresses failed to process the call.{}", ctx )?;
+
+				for e in errors
+				{
+					write!( f, " Error: {}", e )?;
+				}
+
+				Ok(())
+			}
 
 			PeerErr::HandlerDead{ ctx } =>
 
@@ -177,6 +316,18 @@ impl fmt::Display for PeerErr
 
 				write!( f, "No handler has been set for this service.{}", ctx ),
 
+			PeerErr::NoQuorum{ ctx, quorum, agreed, errors } =>
+			{
+				write!( f, "No quorum, required {} identical responses, got {}.{}", quorum, agreed, ctx )?;
+
+				for e in errors
+				{
+					write!( f, " Error: {}", e )?;
+				}
+
+				Ok(())
+			}
+
 			PeerErr::PeerGone{ ctx } =>
 
 				write!( f, "The Peer actor has panicked.{}", ctx ),
@@ -189,9 +340,11 @@ impl fmt::Display for PeerErr
 
 				write!( f, "A remote could not process a message we sent it{:?}.{}", err, ctx ),
 
-			PeerErr::Serialize{ ctx } =>
-
-				write!( f, "Failed to serialize:{}", ctx ),
+			PeerErr::Serialize{ ctx, source } =>
+			{
+				write!( f, "Failed to serialize:{}", ctx )?;
+				fmt_cause( f, source )
+			}
 
 			PeerErr::Spawn{ ctx } =>
 
@@ -205,6 +358,26 @@ impl fmt::Display for PeerErr
 
 				write!( f, "Operation Timed out.{}", ctx ),
 
+			PeerErr::ShuttingDown{ ctx } =>
+
+				write!( f, "The peer was shut down before the response came in.{}", ctx ),
+
+			PeerErr::Cancelled{ ctx } =>
+
+				write!( f, "The call was cancelled before the response came in.{}", ctx ),
+
+			PeerErr::CircuitOpen{ ctx } =>
+
+				write!( f, "The circuit breaker is open, the call was not sent.{}", ctx ),
+
+			PeerErr::Unauthorized{ ctx } =>
+
+				write!( f, "The guard refused to deliver the incoming request.{}", ctx ),
+
+			PeerErr::Validation{ ctx, reason } =>
+
+				write!( f, "The message failed validation: {}.{}", reason, ctx ),
+
 			PeerErr::UnknownService{ ctx } =>
 
 				write!( f, "Cannot deliver message to unknown service.{}", ctx ),
@@ -221,6 +394,19 @@ impl fmt::Display for PeerErr
 }
 
 
+// Append the underlying error, if any, to the description.
+//
+fn fmt_cause( f: &mut fmt::Formatter<'_>, source: &Option<ErrorSource> ) -> fmt::Result
+{
+	match source
+	{
+		Some( source ) => write!( f, " Cause: {}.", source ),
+		None           => Ok(()),
+	}
+}
+
+
+
 impl PeerErr
 {
 	/// TODO: make sure we don't leak any info on other error variants, for WireErr, is there anything to hide?
@@ -246,6 +432,18 @@ impl PeerErr
 
 						format!( "Could not deserialize your message.{}", &ctx ),
 
+					WireErr::Decrypt{..} =>
+
+						format!( "Could not decrypt your message.{}", &ctx ),
+
+					WireErr::Replay{..} =>
+
+						format!( "Your message is too old.{}", &ctx ),
+
+					WireErr::OutOfMemory{ size, .. } =>
+
+						format!( "Not enough memory to receive your message of {} bytes.{}", &size, &ctx ),
+
 					WireErr::Io{..} =>
 
 						format!( "An error happened on the underlying transport.{}", &ctx ),
@@ -253,7 +451,7 @@ impl PeerErr
 
 			}
 
-			Self::Deserialize{ ctx } =>
+			Self::Deserialize{ ctx, .. } =>
 			{
 				format!( "Could not deserialize your message.{}", &ctx )
 			}
@@ -269,16 +467,24 @@ impl PeerErr
 		{
 			PeerErr::ConnectionClosed { ctx, .. } => ctx,
 			PeerErr::Deserialize      { ctx, .. } => ctx,
+			PeerErr::DuplicateCid     { ctx, .. } => ctx,
+			PeerErr::Fanout           { ctx, .. } => ctx,
 			PeerErr::HandlerDead      { ctx, .. } => ctx,
 			PeerErr::NoHandler        { ctx, .. } => ctx,
+			PeerErr::NoQuorum         { ctx, .. } => ctx,
 			PeerErr::PeerGone         { ctx, .. } => ctx,
 			PeerErr::RelayGone        { ctx, .. } => ctx,
 			PeerErr::Remote           { ctx, .. } => ctx,
 			PeerErr::Serialize        { ctx, .. } => ctx,
 			PeerErr::Spawn            { ctx, .. } => ctx,
 			PeerErr::ThesErr          { ctx, .. } => ctx,
+			PeerErr::ShuttingDown     { ctx, .. } => ctx,
+			PeerErr::Cancelled        { ctx, .. } => ctx,
+			PeerErr::CircuitOpen      { ctx, .. } => ctx,
 			PeerErr::Timeout          { ctx, .. } => ctx,
+			PeerErr::Unauthorized     { ctx, .. } => ctx,
 			PeerErr::UnknownService   { ctx, .. } => ctx,
+			PeerErr::Validation       { ctx, .. } => ctx,
 			PeerErr::WireFormat       { ctx, .. } => ctx,
 			PeerErr::PubSubNoCall     { ctx, .. } => ctx,
 		}
@@ -287,6 +493,122 @@ impl PeerErr
 
 
 
+impl PeerErr
+{
+	/// Turn the [ConnectionError] that came back for an outgoing call into a `PeerErr`. `Timeout`,
+	/// `ShuttingDown` and `Cancelled` are generated locally while waiting for the response, so they become
+	/// [`PeerErr::Timeout`], [`PeerErr::ShuttingDown`] and [`PeerErr::Cancelled`]. Everything else is
+	/// [`PeerErr::Remote`].
+	//
+	pub fn from_remote( err: ConnectionError, mut ctx: PeerErrCtx ) -> Self
+	{
+		match err
+		{
+			// It can also come from a relay. We don't allow the user here to distinguish whether
+			// this peer timed out or a relay.
+			//
+			// TODO: Document this as users might be confused when they raise their timeout and it still
+			// times out. It will be logged as a remote error by the code in peer/incoming.rs
+			//
+			ConnectionError::Timeout{..} =>
+			{
+				ctx.context = Some( "Time out waiting for response to outgoing call".to_string() );
+
+				PeerErr::Timeout{ ctx }
+			}
+
+			ConnectionError::ShuttingDown{..} =>
+			{
+				ctx.context = Some( "Peer shut down while waiting for response to outgoing call".to_string() );
+
+				PeerErr::ShuttingDown{ ctx }
+			}
+
+			ConnectionError::Cancelled{..} =>
+			{
+				ctx.context = Some( "Outgoing call cancelled while waiting for response".to_string() );
+
+				PeerErr::Cancelled{ ctx }
+			}
+
+			_ => PeerErr::Remote{ err, ctx },
+		}
+	}
+}
+
+
+/// See [`PeerErr::from_remote`]. The sid and cid of the error end up in the context.
+//
+impl From<ConnectionError> for PeerErr
+{
+	fn from( err: ConnectionError ) -> Self
+	{
+		let ctx = PeerErrCtx::default()
+
+			.context( "Remote could not process our message".to_string() )
+			.sid    ( err.sid()                                          )
+			.cid    ( err.cid()                                          )
+		;
+
+		Self::from_remote( err, ctx )
+	}
+}
+
+
+impl From<WireErr> for PeerErr
+{
+	fn from( source: WireErr ) -> Self
+	{
+		PeerErr::WireFormat{ ctx: PeerErrCtx::default(), source }
+	}
+}
+
+
+impl From<ThesErr> for PeerErr
+{
+	fn from( source: ThesErr ) -> Self
+	{
+		PeerErr::ThesErr{ ctx: PeerErrCtx::default(), source: Arc::new( source ) }
+	}
+}
+
+
+/// The error a remote gets to see when processing its request failed with `err`. The sid and cid
+/// of the context are kept. Errors that are none of the business of the remote become
+/// [`ConnectionError::InternalServerError`]. An error that came back from a relayed
+/// connection is passed on as is.
+//
+impl From<&PeerErr> for ConnectionError
+{
+	fn from( err: &PeerErr ) -> Self
+	{
+		let sid = err.ctx().sid;
+		let cid = err.ctx().cid;
+
+		match err
+		{
+			PeerErr::WireFormat    {..} => ConnectionError::DeserializeWireFormat{ context: err.clone().remote_err() },
+			PeerErr::Deserialize   {..} => ConnectionError::Deserialize   { sid, cid } ,
+			PeerErr::Spawn         {..} => ConnectionError::SpawnFailed   { sid, cid } ,
+			PeerErr::UnknownService{..} => ConnectionError::UnknownService{ sid, cid } ,
+			PeerErr::Unauthorized  {..} => ConnectionError::Unauthorized  { sid, cid } ,
+			PeerErr::DuplicateCid  {..} => ConnectionError::DuplicateCid  { sid, cid } ,
+			PeerErr::PubSubNoCall  {..} => ConnectionError::PubSubNoCall  { sid, cid } ,
+
+			PeerErr::Validation{ reason, .. } => ConnectionError::Validation{ sid, cid, reason: reason.clone() },
+			PeerErr::Remote { err, .. } => err.clone()                                 ,
+
+			PeerErr::Timeout     {..} => ConnectionError::Timeout     { sid: sid.unwrap_or_else( ServiceID::null ) },
+			PeerErr::ShuttingDown{..} => ConnectionError::ShuttingDown{ cid: cid.unwrap_or_else( ConnID::null    ) },
+			PeerErr::Cancelled   {..} => ConnectionError::Cancelled   { cid: cid.unwrap_or_else( ConnID::null    ) },
+
+			_ => ConnectionError::InternalServerError{ sid, cid },
+		}
+	}
+}
+
+
+
 #[ derive( Default, Debug, Clone, PartialEq, Eq ) ]
 //
 pub struct PeerErrCtx
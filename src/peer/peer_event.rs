@@ -1,4 +1,4 @@
-use crate::{ PeerErr, ConnectionError };
+use crate::{ import::*, PeerErr, PeerErrCtx, ConnectionError, ConnID, ServiceID, peer::{ Control, CloseReason } };
 
 
 /// Events that can happen during the lifecycle of the peer. Use the [`observe`] method to subscribe to events.
@@ -13,13 +13,14 @@ use crate::{ PeerErr, ConnectionError };
 //
 pub enum PeerEvent
 {
-	/// The connection is closed. It can no longer be used.
+	/// The connection is closed. It can no longer be used. The [CloseReason] tells why.
 	//
-	Closed,
+	Closed( CloseReason ),
 
-	/// The remote endpoint closed the connection. It can no longer be used.
+	/// The remote endpoint closed the connection. It can no longer be used. The reason is either
+	/// [`CloseReason::Eof`] or [`CloseReason::Remote`].
 	//
-	ClosedByRemote,
+	ClosedByRemote( CloseReason ),
 
 	/// A remote endpoint to which we relayed messages is no longer reachable.
 	//
@@ -36,5 +37,101 @@ pub enum PeerEvent
 	/// our messages.
 	//
 	RemoteError( ConnectionError ),
+
+	/// The remote sent a control frame that the peer doesn't handle itself.
+	//
+	Control( Control ),
+
+	/// The remote advertised how many more concurrent calls it accepts, see [`AdvertiseCapacity`](crate::peer::AdvertiseCapacity).
+	/// `usize::MAX` means the remote has no backpressure.
+	//
+	RemoteCapacity( usize ),
+
+	/// The remote dropped a send of ours because its send queue was full, see
+	/// [`Peer::set_report_dropped_sends`](crate::Peer::set_report_dropped_sends). Producers can slow down
+	/// or retry.
+	//
+	SendRejected
+	{
+		/// The service of the dropped send.
+		//
+		sid: ServiceID,
+	},
+
+	/// The remote sent a second response to an outgoing call of ours, eg. because a request was delivered
+	/// more than once. The caller already got the first response, this one was dropped. Duplicates are also
+	/// counted in [`PeerStatus::duplicate_responses`](crate::PeerStatus::duplicate_responses).
+	//
+	DuplicateResponse
+	{
+		/// The service of the call.
+		//
+		sid: ServiceID,
+
+		/// The cid of the call.
+		//
+		cid: ConnID,
+	},
+
+	/// Writing a frame to the connection takes longer than the threshold set with
+	/// [`Peer::set_stall_threshold`](crate::Peer::set_stall_threshold), eg. because the remote stopped
+	/// reading. `queued_frames` counts the frame being written and the responses to incoming calls
+	/// that wait behind it. Published once per stalled frame.
+	//
+	SinkStalled
+	{
+		/// The number of frames waiting to go out.
+		//
+		queued_frames: usize,
+	},
+
+	/// The connection ties up more memory than allowed with [`Peer::set_memory_limit`](crate::Peer::set_memory_limit).
+	/// The peer stopped reading from it, or closed it. Published each time the limit gets exceeded.
+	//
+	MemoryLimitExceeded
+	{
+		/// The bytes in use.
+		//
+		used: usize,
+
+		/// The limit in bytes.
+		//
+		limit: usize,
+	},
 }
 
+
+
+/// Helpers for a stream of [PeerEvent], like the `Events` returned by `observe`.
+//
+pub trait PeerEventsExt: Stream<Item = PeerEvent> + Unpin + Send
+{
+	/// Resolves with the next event for which `pred` returns true, skipping the others, eg.
+	/// `evts.wait_for( |e| matches!( e, PeerEvent::Closed(_) ) )`. Fails with [`PeerErr::PeerGone`] when
+	/// the stream ends first, which happens when the peer is dropped.
+	//
+	fn wait_for<'a>( &'a mut self, pred: impl FnMut( &PeerEvent ) -> bool + Send + 'a ) -> Return<'a, Result<PeerEvent, PeerErr>>;
+}
+
+
+impl<T> PeerEventsExt for T
+
+	where T: Stream<Item = PeerEvent> + Unpin + Send
+
+{
+	fn wait_for<'a>( &'a mut self, mut pred: impl FnMut( &PeerEvent ) -> bool + Send + 'a ) -> Return<'a, Result<PeerEvent, PeerErr>>
+	{
+		async move
+		{
+			while let Some( evt ) = self.next().await
+			{
+				if pred( &evt ) { return Ok( evt ) }
+			}
+
+			let ctx = PeerErrCtx::default().context( "The event stream ended while waiting for an event".to_string() );
+
+			Err( PeerErr::PeerGone{ ctx } )
+
+		}.boxed()
+	}
+}
@@ -0,0 +1,135 @@
+use
+{
+	crate     :: { import::*, *                              } ,
+	super     :: { RequestError                              } ,
+	byteorder :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
+	futures   :: { future::{ select, Either }                } ,
+	std       :: { time::Instant                             } ,
+};
+
+
+// A ping is a control frame with code `PING`, answered by a control frame with code `PONG` holding
+// the same payload, the number of the ping:
+//
+// nonce u64 LE
+//
+pub(crate) const PING: u8 = 2;
+pub(crate) const PONG: u8 = 3;
+
+const LEN_PING: usize = 8;
+
+
+// Ask the peer to send a ping. Returns the channel on which the round trip time will come in and the
+// timeout of the peer. See `Peer::ping`.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct Ping;
+
+impl Message for Ping
+{
+	type Return = Result< (oneshot::Receiver<Duration>, Duration), PeerErr >;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<Ping> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: Ping ) -> <Ping as Message>::Return
+	{
+		// Forget the pings nobody waits for anymore, eg. because they timed out.
+		//
+		self.pings.retain( |_, (_, tx)| !tx.is_canceled() );
+
+		let nonce = self.ping_counter;
+
+		self.ping_counter = self.ping_counter.wrapping_add( 1 );
+
+		let mut payload = Vec::with_capacity( LEN_PING );
+
+		// unwrap: writing to an in memory buffer.
+		//
+		payload.write_u64::<LittleEndian>( nonce ).unwrap();
+
+		// Sending might wait for the transport, which is part of the round trip.
+		//
+		let start = Instant::now();
+
+		self.send_msg( Self::control_frame( PING, &payload ) ).await?;
+
+		let (tx, rx) = oneshot::channel();
+
+		// The answer can't be processed before this handler returns, so it's not too late.
+		//
+		self.pings.insert( nonce, (start, tx) );
+
+		Ok( (rx, self.timeout) )
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Measure the round trip time to the remote, without calling a service. This sends a control frame
+	/// which the remote answers right away, so it includes the time the frames wait in both peers, but
+	/// not in any handler.
+	///
+	/// Fails with [`PeerErr::ConnectionClosed`] if the connection is closed or closes before the answer
+	/// comes in, and with [`PeerErr::Timeout`] if there is no answer within the timeout of the peer, see
+	/// [`Peer::set_timeout`]. A remote that doesn't know about pings won't answer.
+	//
+	pub async fn ping( addr: &mut Addr<Self> ) -> Result<Duration, PeerErr>
+	{
+		let ctx = || Self::err_ctx( addr, None, None, "Ping".to_string() );
+
+		let (rx, timeout) = addr.call( Ping ).await
+
+			// The peer panicked.
+			//
+			.map_err( |_| PeerErr::PeerGone{ ctx: ctx() } )??;
+
+		match select( rx, Delay::new( timeout ) ).await
+		{
+			Either::Left ( (Ok( rtt ), _) ) => Ok( rtt ),
+			Either::Left ( (Err( _ ) , _) ) => Err( PeerErr::ConnectionClosed{ ctx: ctx() } ),
+			Either::Right( _              ) => Err( PeerErr::Timeout         { ctx: ctx() } ),
+		}
+	}
+
+
+	/// Answer a ping from the remote, or take note of the answer to ours.
+	//
+	pub(crate) async fn incoming_ping( &mut self, frame: Wf, code: u8 )
+	{
+		let mut msg = frame.msg();
+
+		if msg.len() != LEN_PING
+		{
+			let source = WireErr::Deserialize{ context: "ping doesn't hold a nonce".to_string(), source: None };
+			let ctx    = self.ctx( frame.sid(), None, "Process incoming ping" );
+
+			self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+			return;
+		}
+
+		if code == PING
+		{
+			// If this fails the connection is closing, there is nobody left to answer to.
+			//
+			let _ = self.send_msg( Self::control_frame( PONG, msg ) ).await;
+			return;
+		}
+
+		// unwrap: we just checked the length.
+		//
+		let nonce = msg.read_u64::<LittleEndian>().unwrap();
+
+		if let Some( (start, tx) ) = self.pings.remove( &nonce )
+		{
+			// The caller might have given up already.
+			//
+			let _ = tx.send( start.elapsed() );
+		}
+	}
+}
@@ -0,0 +1,61 @@
+use crate::{ import::*, * };
+
+
+/// Control message for [Peer]. Replace all service maps of a running peer with a new set in one go,
+/// eg. to reload configuration without dropping the connection.
+///
+/// The swap is atomic with respect to incoming messages. Every incoming request is either dispatched to
+/// the old set of service maps or to the new one. Requests that have already been dispatched
+/// keep running against the service map they started with, and their responses are still
+/// sent out over the connection. Outgoing calls are not affected. Only the service maps of the default
+/// channel are replaced, those from [`Peer::register_channel`] are left alone.
+///
+/// Just like [`Peer::register_services`], each service should only be provided by one of the service maps.
+/// This will panic in debug mode otherwise.
+///
+/// Returns [`PeerErr::ConnectionClosed`] if the connection is already closed.
+//
+#[ derive( Debug ) ]
+//
+pub struct ReloadServices<Wf = ThesWF>
+{
+	/// The service maps that will replace the current ones.
+	//
+	pub services: Vec< Arc<dyn ServiceMap<Wf>> >,
+}
+
+
+impl<Wf: WireFormat> Message for ReloadServices<Wf>
+{
+	type Return = Result<(), PeerErr>;
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<ReloadServices<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: ReloadServices<Wf> ) -> <ReloadServices<Wf> as Message>::Return
+	{
+		trace!( "{}: ReloadServices", self.identify() );
+
+		if self.closed
+		{
+			let ctx = self.ctx( None, None, "Handler<ReloadServices> for Peer" );
+
+			return Err( PeerErr::ConnectionClosed{ ctx } );
+		}
+
+
+		// There is no await between clearing and registering, so no incoming message can
+		// be processed in between.
+		//
+		self.services.clear();
+
+		for sm in msg.services
+		{
+			self.register_services( sm );
+		}
+
+		Ok(())
+	}
+}
@@ -64,110 +64,104 @@ impl<Wf: WireFormat> Handler<RequestError> for Peer<Wf>
 		//
 		self.pharos.send( PeerEvent::Error( msg.error.clone() ) ).await.expect( "pharos not closed" );
 
-		// If it was a send, don't send errors to the remote. Only call buys into feedback.
+		// If it was a send, don't send errors to the remote. Only call buys into feedback, unless
+		// we were asked to report them, see Peer::set_report_send_errors.
 		//
 		let cid = match msg.error.ctx().cid
 		{
-			Some(c) => c     ,
-			None    => return,
-		};
-
+			Some(c) => c,
 
-		// Send errors back to the remote.
-		//
-		match msg.error
-		{
-			// TODO: need to decide what to do.
-			//
-			PeerErr::WireFormat{..} =>
+			None =>
 			{
-				// Report to remote and close connection as the stream is no longer coherent.
-				//
-				let err = ConnectionError::DeserializeWireFormat{ context: msg.error.remote_err() };
-
-				// If the error happened in the codec, there won't be a cid, but if it happens
-				// while deserializing the actor message, we will already have a cid.
-				//
-				self.send_err( cid, &err, true ).await;
-			}
+				let report = self.report_send_errors
 
+					&& msg.error.ctx().sid.is_some()
 
-			PeerErr::Deserialize{ ctx } =>
-			{
-				// Report to remote and close connection as the stream is no longer coherent.
-				//
-				let err = ConnectionError::Deserialize{ sid: ctx.sid, cid: cid.into() };
-
-				// If the error happened in the codec, there won't be a cid, but if it happens
-				// while deserializing the actor message, we will already have a cid.
-				//
-				self.send_err( cid, &err, false ).await;
-			}
+					&& matches!
+					(
+						msg.error,
 
+						  PeerErr::Deserialize   {..}
+						| PeerErr::NoHandler     {..}
+						| PeerErr::HandlerDead   {..}
+						| PeerErr::UnknownService{..}
+						| PeerErr::Unauthorized  {..}
+						| PeerErr::Validation    {..}
+					)
+				;
 
-			PeerErr::Spawn { ctx } =>
-			{
-				// Report to remote and close connection. When we can't spawn, we can't process
-				// any more incoming message, so it seems sensible to close the connection.
-				//
-				let err = ConnectionError::InternalServerError{ sid: ctx.sid, cid: cid.into() };
+				if report
+				{
+					self.send_err( ConnID::null(), &ConnectionError::from( &msg.error ), None ).await;
+				}
 
-				self.send_err( cid, &err, true ).await;
-			}
-
-
-			  PeerErr::RelayGone  { ctx, .. }
-			| PeerErr::NoHandler  { ctx     }
-			| PeerErr::HandlerDead{ ctx     } =>
-			{
-				// Report to remote, we don't close the connection because we might expose other
-				// services that are still operational, or the actor might be in the process of
-				// being restarted.
-				//
-				let err = ConnectionError::InternalServerError{ sid: ctx.sid, cid: cid.into() };
-
-				self.send_err( cid, &err, false ).await;
+				return
 			}
+		};
 
 
-			// This means we fail to serialize the response of a call. This is no error from the
-			// remote, but from the local process.
+		// Whether to close the connection after reporting the error to the remote.
+		//
+		let close = match &msg.error
+		{
+			// We can no longer trust this connection.
 			//
-			PeerErr::Serialize{ ctx } =>
-			{
-				// Report to remote, we don't close the connection because this might work again later.
-				//
-				let err = ConnectionError::InternalServerError{ sid: ctx.sid, cid: cid.into() };
-
-				self.send_err( cid, &err, false ).await;
-			}
-
-
-			PeerErr::UnknownService{ ctx } =>
-			{
-				// This is not fatal, NOT closing the connection.
-				//
-				let err = ConnectionError::UnknownService{ sid: ctx.sid, cid: cid.into() };
-
-				self.send_err( cid, &err, false ).await;
-			}
+			  PeerErr::WireFormat{ source: WireErr::Decrypt{..}, .. }
+			| PeerErr::WireFormat{ source: WireErr::Replay {..}, .. } => Some( CloseReason::AuthFailed ),
 
+			// A chunked message we had no room for. The other frames are fine.
+			//
+			PeerErr::WireFormat{ source: WireErr::OutOfMemory{..}, .. } => None,
 
-			PeerErr::PubSubNoCall{ ctx } =>
-			{
-				// This is not fatal, NOT closing the connection.
-				//
-				let err = ConnectionError::PubSubNoCall{ sid: ctx.sid, cid: cid.into() };
+			// The stream is no longer coherent. If the error happened in the codec, there won't be a cid,
+			// but if it happens while deserializing the actor message, we will already have a cid.
+			//
+			PeerErr::WireFormat{..} => Some( CloseReason::Protocol ),
 
-				self.send_err( cid, &err, false ).await;
-			}
+			// When we can't spawn, we can't process any more incoming message, so it seems sensible to
+			// close the connection.
+			//
+			PeerErr::Spawn{..} => Some( CloseReason::Spawn ),
 
+			// We don't close the connection for missing handlers because we might expose other services
+			// that are still operational, or the actor might be in the process of being restarted.
+			// Failing to serialize the response of a call is no error from the remote, but from the local
+			// process and this might work again later. A draining RelayMap refuses new requests with
+			// ShuttingDown, but the calls in flight still need the connection. The others are not fatal.
+			//
+			  PeerErr::Deserialize   {..}
+			| PeerErr::RelayGone     {..}
+			| PeerErr::NoHandler     {..}
+			| PeerErr::HandlerDead   {..}
+			| PeerErr::Serialize     {..}
+			| PeerErr::UnknownService{..}
+			| PeerErr::Unauthorized  {..}
+			| PeerErr::Validation    {..}
+			| PeerErr::DuplicateCid  {..}
+			| PeerErr::Timeout       {..}
+			| PeerErr::ShuttingDown  {..}
+			| PeerErr::Cancelled     {..}
+			| PeerErr::CircuitOpen   {..}
+			| PeerErr::PubSubNoCall  {..} => None,
 
 			// We shouldn't accept any other errors unknowingly.
 			// Especially we log the error above the match, so if there is other
 			// error types, we really need to add the variant to the match.
 			//
 			e => { unreachable!(e) }
+		};
+
+
+		// Send errors back to the remote.
+		//
+		self.send_err( cid, &ConnectionError::from( &msg.error ), close ).await;
+
+
+		// Sending the error frees the cid, but the original call is still being processed.
+		//
+		if let PeerErr::DuplicateCid{..} = msg.error
+		{
+			self.inbound.insert( cid );
 		}
 
 	}.boxed() }
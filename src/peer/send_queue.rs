@@ -0,0 +1,263 @@
+use
+{
+	crate   :: { import::*, *                                } ,
+	super   :: { RequestError                                } ,
+	futures :: { future::{ abortable, poll_fn, AbortHandle } } ,
+};
+
+
+// The negative acknowledgment of a dropped send is a control frame with code `SEND_REJECTED`. The
+// payload is the sid of the send that was dropped:
+//
+// sid u64 LE (u128 with the sid128 feature)
+//
+pub(crate) const SEND_REJECTED: u8 = 4;
+
+
+/// What a [Peer] does with an incoming send when its queue is full, see [`Peer::set_send_queue`].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum OverflowPolicy
+{
+	/// Stop processing incoming messages until a queued send has been delivered. The remote gets back
+	/// pressure, but our responses to its calls and the other messages on the connection wait as well.
+	//
+	Block,
+
+	/// Drop the send that just came in. Sends that were already queued are still delivered.
+	//
+	DropNewest,
+
+	/// Drop the send that waits the longest to make room for the one that just came in. This favors
+	/// fresh data, eg. for status updates where only the last value matters.
+	//
+	DropOldest,
+}
+
+
+
+// The incoming sends that wait for their handling actor to accept them. Calls are not queued here,
+// since the remote waits for a response, [BackPressure] limits those.
+//
+pub(crate) struct SendQueue
+{
+	capacity: usize              ,
+	policy  : OverflowPolicy     ,
+	shared  : Arc<Mutex<Shared>> ,
+}
+
+
+#[ derive( Default ) ]
+//
+struct Shared
+{
+	// The sends waiting, the oldest first.
+	//
+	queue  : VecDeque<(u64, ServiceID, AbortHandle)> ,
+	next_id: u64                                     ,
+	dropped: u64                                     ,
+
+	// The peer waiting for room, with OverflowPolicy::Block.
+	//
+	waker  : Option<Waker>                           ,
+}
+
+
+// Removes a send from the queue once it's done, whether it was delivered or dropped.
+//
+struct Slot
+{
+	id    : u64                ,
+	shared: Arc<Mutex<Shared>> ,
+}
+
+
+impl Drop for Slot
+{
+	fn drop( &mut self )
+	{
+		let mut shared = self.shared.lock();
+
+		shared.queue.retain( |(id, _, _)| *id != self.id );
+
+		if let Some( waker ) = shared.waker.take()
+		{
+			waker.wake();
+		}
+	}
+}
+
+
+
+impl SendQueue
+{
+	pub(crate) fn new( capacity: NonZeroUsize, policy: OverflowPolicy ) -> Self
+	{
+		Self
+		{
+			capacity: capacity.get() ,
+			policy                   ,
+			shared  : Arc::default() ,
+		}
+	}
+
+
+	// The number of sends dropped so far because the queue was full.
+	//
+	pub(crate) fn dropped( &self ) -> u64
+	{
+		self.shared.lock().dropped
+	}
+
+
+	// Make room for a new send for service `sid` according to the policy and queue it. Returns None if
+	// the send has to be dropped. The returned future resolves to `Response::Nothing` if the send gets
+	// dropped later on to make room for a newer one. The second element is the sid of the send that was
+	// dropped, if any, either this one or the oldest.
+	//
+	pub(crate) async fn admit<Wf>
+	(
+		&self,
+		sid: ServiceID,
+		fut: impl Future< Output=Result<Response<Wf>, PeerErr> > + Send + 'static,
+	)
+
+		-> ( Option< impl Future< Output=Result<Response<Wf>, PeerErr> > + Send + 'static >, Option<ServiceID> )
+
+		where Wf: Send + 'static
+
+	{
+		if self.policy == OverflowPolicy::Block
+		{
+			poll_fn( |cx|
+			{
+				let mut shared = self.shared.lock();
+
+				if shared.queue.len() < self.capacity
+				{
+					return Poll::Ready(());
+				}
+
+				shared.waker = Some( cx.waker().clone() );
+				Poll::Pending
+
+			}).await;
+		}
+
+
+		let mut shared  = self.shared.lock();
+		let mut dropped = None;
+
+		// With Block we just waited for room and nothing else adds to the queue.
+		//
+		if self.policy != OverflowPolicy::Block && shared.queue.len() >= self.capacity
+		{
+			shared.dropped += 1;
+
+			if self.policy == OverflowPolicy::DropNewest
+			{
+				return ( None, Some( sid ) );
+			}
+
+			if let Some( (_, oldest_sid, oldest) ) = shared.queue.pop_front()
+			{
+				oldest.abort();
+				dropped = Some( oldest_sid );
+			}
+		}
+
+
+		let (fut, handle) = abortable( fut );
+		let id            = shared.next_id;
+
+		shared.next_id += 1;
+		shared.queue.push_back( (id, sid, handle) );
+
+		let slot = Slot{ id, shared: self.shared.clone() };
+
+		let fut = async move
+		{
+			let _slot = slot;
+
+			fut.await.unwrap_or( Ok( Response::Nothing ) )
+		};
+
+		( Some( fut ), dropped )
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Limit the number of incoming sends waiting for their handling actor to accept them, eg. because
+	/// its mailbox is full. `policy` decides what happens when the limit is reached. Dropped sends are
+	/// counted in [`PeerStatus::dropped_sends`]. Calls are always processed, use [BackPressure] to limit
+	/// those.
+	///
+	/// By default there is no limit.
+	//
+	pub fn set_send_queue( &mut self, capacity: NonZeroUsize, policy: OverflowPolicy )
+	{
+		self.send_queue = Some( Arc::new( SendQueue::new( capacity, policy ) ) );
+	}
+
+
+	/// Send a negative acknowledgement back to the remote for each send dropped by the queue set with
+	/// [`Peer::set_send_queue`], so the producer can slow down or retry. The remote publishes it as
+	/// [`PeerEvent::SendRejected`] with the sid of the dropped send. With [`OverflowPolicy::DropOldest`]
+	/// that's the send that waited the longest. Defaults to false.
+	//
+	pub fn set_report_dropped_sends( &mut self, report: bool )
+	{
+		self.report_dropped_sends = report;
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	// Send the negative acknowledgment for a dropped send, if the remote wants to know.
+	//
+	pub(crate) async fn send_rejected( &mut self, sid: ServiceID )
+	{
+		if !self.report_dropped_sends { return }
+
+		let mut payload = Vec::with_capacity( ServiceID::SIZE );
+
+		// unwrap: writing to an in memory buffer.
+		//
+		sid.write_le( &mut payload ).unwrap();
+
+		// If the connection is gone, there is nobody to tell.
+		//
+		let _ = self.send_msg( Self::control_frame( SEND_REJECTED, &payload ) ).await;
+	}
+
+
+	// Process a negative acknowledgment from the remote.
+	//
+	pub(crate) async fn incoming_send_rejected( &mut self, frame: Wf )
+	{
+		let msg = frame.msg();
+
+		let sid = match ServiceID::read_le( &mut &msg[..] )
+		{
+			Ok( sid ) if msg.len() == ServiceID::SIZE => sid,
+
+			_ =>
+			{
+				let source = WireErr::Deserialize{ context: "rejected send notice doesn't hold a sid".to_string(), source: None };
+				let ctx    = self.ctx( frame.sid(), None, "Process incoming rejected send notice" );
+
+				return self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+			}
+		};
+
+		trace!( "{}: remote dropped a send for sid: {}", self.identify(), sid );
+
+		self.pharos.send( PeerEvent::SendRejected{ sid } ).await.expect( "pharos not closed" );
+	}
+}
@@ -0,0 +1,72 @@
+use crate :: { import::*, * };
+
+
+/// Control message for [Peer] to tear down the connection with [`Peer::shutdown`]. Messages you sent to
+/// the peer before this one are processed first, so everything you sent goes out before the connection
+/// closes. When the call to this message returns, the connection is closed.
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+pub struct Shutdown;
+
+impl Message for Shutdown
+{
+	type Return = ();
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<Shutdown> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: Shutdown )
+	{
+		self.shutdown().await
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// Close the connection deterministically. Since actors can't run async code when they are dropped,
+	/// frames buffered in the sink might get lost when the peer is dropped without closing the connection.
+	/// This:
+	///
+	/// - flushes the sink, so everything sent so far reaches the remote,
+	/// - makes outgoing calls that are still waiting for a response fail with [`PeerErr::ShuttingDown`],
+	/// - closes the connection like [`CloseConnection`].
+	///
+	/// When the mailbox of the peer is running, send it [`Shutdown`] instead. Does nothing if the connection
+	/// is already closed.
+	//
+	pub async fn shutdown( &mut self )
+	{
+		if self.closed { return }
+
+		if let Some( out ) = &mut self.outgoing
+		{
+			let flushed = out.flush().await;
+
+			if let Err( source ) = flushed
+			{
+				let ctx = self.ctx( None, None, "Flushing the connection on shutdown" );
+				let err = PeerErr::WireFormat{ ctx, source };
+
+				self.pharos.send( PeerEvent::Error(err) ).await.expect( "pharos not closed" );
+			}
+		}
+
+
+		for (cid, (_, channel)) in self.responses.drain()
+		{
+			// The caller might have given up on the response already.
+			//
+			let _ = channel.send( Err( ConnectionError::ShuttingDown{ cid } ) );
+		}
+
+
+		let close = CloseConnection{ remote: false, reason: "Peer shut down.".to_string() };
+
+		self.close_with( close, CloseReason::Shutdown ).await;
+	}
+}
@@ -0,0 +1,60 @@
+use
+{
+	crate   :: { import::*, *              } ,
+	futures :: { future::{ select, Either } } ,
+};
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// Publish [`PeerEvent::SinkStalled`] when writing a frame to the connection takes longer than
+	/// `threshold`, eg. because the remote stopped reading. That allows shedding load or dropping a
+	/// slow consumer before everything backs up. Disabled by default.
+	//
+	pub fn set_stall_threshold( &mut self, threshold: Duration )
+	{
+		self.stall_threshold = Some( threshold );
+	}
+
+
+	// Write a frame to the connection, publishing SinkStalled once if it doesn't go through within
+	// the stall threshold.
+	//
+	pub(super) async fn write_frame
+	(
+		out      : &mut Box<dyn BoundsOut<Wf>> ,
+		pharos   : &mut Pharos<PeerEvent>      ,
+		threshold: Option<Duration>            ,
+		queued   : &AtomicUsize                ,
+		msg      : Wf                          ,
+	)
+		-> Result<(), WireErr>
+	{
+		let threshold = match threshold
+		{
+			Some( t ) => t,
+			None      => return out.send( msg ).await,
+		};
+
+		let send = out.send( msg );
+		pin_mut!( send );
+
+		match select( send.as_mut(), Delay::new( threshold ) ).await
+		{
+			Either::Left( (res, _) ) => res,
+
+			Either::Right( _ ) =>
+			{
+				// The frame we are writing and the responses waiting behind it.
+				//
+				let queued_frames = 1 + queued.load( Relaxed );
+
+				warn!( "Outgoing connection stalled for more than {:?}, queued frames: {}", threshold, queued_frames );
+
+				pharos.send( PeerEvent::SinkStalled{ queued_frames } ).await.expect( "pharos not closed" );
+
+				send.await
+			}
+		}
+	}
+}
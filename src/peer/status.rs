@@ -0,0 +1,170 @@
+use
+{
+	crate :: { import::*, *     } ,
+	std   :: { net::SocketAddr } ,
+};
+
+
+/// Ask a running [Peer] for a snapshot of the state of its connection. See [PeerStatus].
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+pub struct GetStatus;
+
+impl Message for GetStatus
+{
+	type Return = PeerStatus;
+}
+
+
+
+/// The state of the connection of a [Peer] at the moment it processed [GetStatus].
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct PeerStatus
+{
+	/// False once the connection is closed, either by [`CloseConnection`] or by the remote.
+	//
+	pub connected: bool,
+
+	/// The number of outgoing calls waiting for a response.
+	//
+	pub open_calls: usize,
+
+	/// The number of incoming calls we haven't responded to yet.
+	//
+	pub inbound_calls: usize,
+
+	/// The number of open bidirectional streams, see [`OpenStream`].
+	//
+	pub open_streams: usize,
+
+	/// When the last frame was sent or received. `None` if nothing went over the connection yet.
+	//
+	pub last_activity: Option<SystemTime>,
+
+	/// The total size in bytes of the frames received.
+	//
+	pub bytes_in: u64,
+
+	/// The total size in bytes of the frames sent.
+	//
+	pub bytes_out: u64,
+
+	/// Whether all slots of the [BackPressure] are taken, so new incoming calls wait. Always false
+	/// for a peer without backpressure.
+	//
+	pub backpressure: bool,
+
+	/// The number of free slots of the [BackPressure], see [`Peer::capacity`]. `None` for a peer
+	/// without backpressure.
+	//
+	pub capacity: Option<usize>,
+
+	/// The last capacity the remote advertised with [`AdvertiseCapacity`]. `None` if it never did.
+	//
+	pub remote_capacity: Option<usize>,
+
+	/// The number of incoming sends dropped because the queue set with [`Peer::set_send_queue`] was full.
+	//
+	pub dropped_sends: u64,
+
+	/// The number of duplicate responses to outgoing calls that were dropped, see [`PeerEvent::DuplicateResponse`].
+	//
+	pub duplicate_responses: u64,
+
+	/// The address of the remote end of the transport, if it was given with [`Peer::set_remote_addr`].
+	//
+	pub remote_addr: Option<SocketAddr>,
+
+	/// The total size in bytes of the chunked messages that are being reassembled, see [`Peer::set_max_reassemblies`].
+	//
+	pub reassembly_bytes: usize,
+
+	/// The bytes of incoming frames charged to the memory limit, while they are read and until they are
+	/// processed. `None` without a limit, see [`Peer::set_memory_limit`].
+	//
+	pub memory_used: Option<usize>,
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<GetStatus> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, _msg: GetStatus ) -> PeerStatus
+	{
+		self.status()
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// A snapshot of the state of the connection. Ask a peer whose mailbox is running with [GetStatus].
+	//
+	pub fn status( &self ) -> PeerStatus
+	{
+		PeerStatus
+		{
+			connected          : !self.closed && self.outgoing.is_some()                              ,
+			open_calls         : self.responses.len()                                                 ,
+			inbound_calls      : self.inbound  .len()                                                 ,
+			open_streams       : self.streams  .len()                                                 ,
+			last_activity      : self.last_activity                                                   ,
+			bytes_in           : self.bytes_in                                                        ,
+			bytes_out          : self.bytes_out                                                       ,
+			backpressure       : self.backpressure.as_ref().map_or( false, |bp| bp.available() <= 0 ) ,
+			capacity           : self.capacity()                                                      ,
+			remote_capacity    : self.remote_capacity                                                 ,
+			dropped_sends      : self.send_queue.as_ref().map_or( 0, |q| q.dropped() )                ,
+			duplicate_responses: self.answered.duplicates()                                           ,
+			remote_addr        : self.remote_addr                                                     ,
+			reassembly_bytes   : self.reassembly_bytes()                                              ,
+			memory_used        : self.memory.lock().as_ref().map( |m| m.used() )                      ,
+		}
+	}
+
+
+	/// Remember the address of the remote end of the transport, eg. the one a TCP listener returned
+	/// when accepting the connection, so it can be logged. The peer doesn't use it otherwise. Read it
+	/// back with [`Peer::remote_addr`] or in [PeerStatus].
+	//
+	pub fn set_remote_addr( &mut self, addr: SocketAddr )
+	{
+		self.remote_addr = Some( addr );
+	}
+
+
+	// Keep the statistics for GetStatus up to date for a frame that went in or out.
+	//
+	pub(crate) fn record_frame( &mut self, len: u64, incoming: bool )
+	{
+		match incoming
+		{
+			true  => self.bytes_in  += len,
+			false => self.bytes_out += len,
+		}
+
+		self.last_activity = Some( SystemTime::now() );
+		self.active_at     = std::time::Instant::now();
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Peer<Wf>
+{
+	/// The address of the remote end of the transport of a running peer, see [`Peer::set_remote_addr`].
+	/// Fails with [`PeerErr::PeerGone`] when the mailbox of the peer is no longer running.
+	//
+	pub async fn remote_addr( addr: &mut Addr<Self> ) -> Result<Option<SocketAddr>, PeerErr>
+	{
+		match addr.call( GetStatus ).await
+		{
+			Ok ( status ) => Ok( status.remote_addr ),
+			Err( _      ) => Err( PeerErr::PeerGone{ ctx: Self::err_ctx( addr, None, None, "Peer::remote_addr".to_string() ) } ),
+		}
+	}
+}
@@ -0,0 +1,781 @@
+use
+{
+	crate     :: { import::*, *                              } ,
+	super     :: { RequestError                              } ,
+	serde     :: { de::DeserializeOwned                      } ,
+	futures   :: { task::AtomicWaker                         } ,
+	std       :: { io::Write as IoWrite                      } ,
+};
+
+
+// Every frame of a stream has sid `ServiceID::stream()` and as cid the id the opener chose for the
+// stream. The payload starts with a tag:
+//
+// OPEN   u8 | sid u64 LE | opening message   (opener -> acceptor, sid is a u128 with the sid128 feature)
+// DATA   u8 | item                           (both ways)
+// CLOSE  u8                                  (both ways)
+// WINDOW u8 | bytes u32 LE                   (both ways)
+//
+// Both ends choose the cids for the streams they open, so the direction is part of the tag. That
+// way a stream we opened can't be mixed up with a stream the remote opened with the same cid.
+//
+// Each direction of a stream has a send window of STREAM_WINDOW bytes of DATA payload. The sender
+// waits when it's used up and the receiver hands bytes back with WINDOW frames as the items get
+// read. That way a stream nobody reads can't fill up the memory of the receiver. A remote that
+// keeps sending once the window is used up gets its stream closed.
+//
+const OPEN              : u8 = 0;
+const DATA_TO_ACCEPTOR  : u8 = 1;
+const DATA_TO_OPENER    : u8 = 2;
+const CLOSE_TO_ACCEPTOR : u8 = 3;
+const CLOSE_TO_OPENER   : u8 = 4;
+const WINDOW_TO_ACCEPTOR: u8 = 5;
+const WINDOW_TO_OPENER  : u8 = 6;
+
+const LEN_OPEN_HEADER: usize = 1 + ServiceID::SIZE;
+
+/// The number of bytes of items that can be in flight on each direction of a stream before the
+/// sender has to wait for the receiver to read them.
+//
+pub const STREAM_WINDOW: usize = 256 * 1024;
+
+
+/// Open a bidirectional stream with a service of the remote. `wf` is the message of the service
+/// that opens the stream, the cid is chosen by the peer. Normally you use `RemoteAddr::open_stream`
+/// rather than this.
+///
+/// Once open, both sides can send items until either side closes its [`StreamSink`]. The remote
+/// handler receives the opening message together with its end of the channel in [`Streaming`].
+/// It has to be registered with `Services::register_stream`. If the remote has no such handler,
+/// it closes the stream right away and the [`StreamRx`] ends without items.
+///
+/// Each direction has a flow control window of [`STREAM_WINDOW`] bytes. Once the remote hasn't read
+/// that much of what we sent, the [`StreamSink`] waits, without holding up the other streams and calls
+/// on the connection. The streams end when the connection closes. Streams can't be relayed.
+//
+#[ derive( Debug ) ]
+//
+pub struct OpenStream<Wf>
+{
+	wf: Wf,
+}
+
+
+impl<Wf: WireFormat> OpenStream<Wf>
+{
+	/// Create a new request to open a stream.
+	//
+	pub fn new( wf: Wf ) -> Self
+	{
+		Self { wf }
+	}
+}
+
+
+impl<Wf: WireFormat> Message for OpenStream<Wf>
+{
+	type Return = Result< StreamChannel<Wf>, PeerErr >;
+}
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<OpenStream<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: OpenStream<Wf> ) -> <OpenStream<Wf> as Message>::Return
+	{
+		trace!( "{}: polled Handler<OpenStream>", self.identify() );
+
+		let sid = msg.wf.sid();
+
+		if self.closed
+		{
+			let ctx = self.ctx( sid, None, "Handler<OpenStream> for Peer" );
+
+			return Err( PeerErr::ConnectionClosed{ ctx } );
+		}
+
+		let cid = loop
+		{
+			let cid = ConnID::random();
+
+			if !cid.is_null() && !self.streams.contains_key( &(true, cid) ) { break cid }
+		};
+
+		let mut frame = Self::stream_frame( cid, OPEN, LEN_OPEN_HEADER + msg.wf.msg().len() );
+
+		sid.write_le( &mut frame )
+			.and_then( |_| frame.write_all( msg.wf.msg() ) )
+			.map_err( |e|
+			{
+				let ctx = self.ctx( sid, cid, "Open outgoing stream" );
+
+				PeerErr::Serialize{ ctx, source: Some( e.into() ) }
+
+			})?;
+
+		self.send_msg( frame ).await?;
+
+		let (tx, rx) = mpsc::unbounded();
+
+		let window   = self.new_window( (true, cid) );
+		let received = Arc::new( AtomicUsize::new(0) );
+
+		self.streams.insert( (true, cid), (tx, received.clone()) );
+		self.update_idle();
+
+		// The caller holds our address, so our mailbox is not stopping.
+		//
+		let peer = self.own_addr().ok_or_else( ||
+		{
+			let ctx = self.ctx( sid, cid, "Handler<OpenStream> for Peer" );
+
+			PeerErr::ConnectionClosed{ ctx }
+
+		})?;
+
+		Ok( StreamChannel{ sid, cid, opener: true, peer, rx, window, received } )
+	}
+}
+
+
+
+/// One end of a bidirectional stream, see [`OpenStream`]. Use [`StreamChannel::split`] to send and
+/// receive typed items.
+//
+pub struct StreamChannel<Wf: 'static + WireFormat = ThesWF>
+{
+	sid   : ServiceID                       ,
+	cid   : ConnID                          ,
+	opener: bool                            ,
+	peer  : Addr<Peer<Wf>>                  ,
+	rx    : mpsc::UnboundedReceiver<Wf>     ,
+	window: Arc<SendWindow>                 ,
+
+	// Shared with the peer, see StreamRx.
+	//
+	received: Arc<AtomicUsize>,
+}
+
+
+impl<Wf: WireFormat + Send + 'static> StreamChannel<Wf>
+{
+	/// The service that was called to open the stream.
+	//
+	pub fn sid( &self ) -> ServiceID
+	{
+		self.sid
+	}
+
+
+	/// The id of the stream on this connection.
+	//
+	pub fn cid( &self ) -> ConnID
+	{
+		self.cid
+	}
+
+
+	/// Split in a sink for the items we send and a stream of the items the remote sends.
+	//
+	pub fn split<Out, In>( self ) -> ( StreamSink<Out, Wf>, StreamRx<In, Wf> )
+
+		where Out: Serialize       ,
+		      In : DeserializeOwned,
+	{
+		let ctx = Peer::err_ctx( &self.peer, self.sid, self.cid, "Receive item on stream".to_string() );
+
+		let stream = StreamRx
+		{
+			rx      : self.rx          ,
+			ctx                        ,
+			cid     : self.cid         ,
+			opener  : self.opener      ,
+			peer    : self.peer.clone(),
+			unacked : 0                ,
+			received: self.received    ,
+			_ghost  : PhantomData      ,
+		};
+
+		let sink = StreamSink
+		{
+			sid    : self.sid    ,
+			cid    : self.cid    ,
+			opener : self.opener ,
+			peer   : self.peer   ,
+			window : self.window ,
+			closed : false       ,
+			_ghost : PhantomData ,
+		};
+
+		(sink, stream)
+	}
+}
+
+
+impl<Wf: WireFormat> fmt::Debug for StreamChannel<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "StreamChannel: sid: {}, cid: {}, opener: {}", self.sid, self.cid, self.opener )
+	}
+}
+
+
+
+/// Message for handlers registered with `Services::register_stream`. Holds the message that
+/// opened the stream and the handlers end of the stream.
+//
+#[ derive( Debug ) ]
+//
+pub struct Streaming<S, Wf: 'static + WireFormat = ThesWF>
+{
+	/// The message sent by the remote to open the stream.
+	//
+	pub msg: S,
+
+	/// The handlers end of the stream.
+	//
+	pub channel: StreamChannel<Wf>,
+}
+
+
+impl<S: Message, Wf: WireFormat> Message for Streaming<S, Wf>
+{
+	type Return = ();
+}
+
+
+
+/// Sends items over a stream. Closing it tells the remote there will be no more items, its
+/// [`StreamRx`] ends. Dropping it without closing leaves the stream open on the remote until the
+/// connection closes.
+//
+pub struct StreamSink<T, Wf: 'static + WireFormat = ThesWF>
+{
+	sid   : ServiceID          ,
+	cid   : ConnID             ,
+	opener: bool               ,
+	peer  : Addr<Peer<Wf>>     ,
+	window: Arc<SendWindow>    ,
+	closed: bool               ,
+	_ghost: PhantomData<fn(T)> ,
+}
+
+
+impl<T, Wf: WireFormat> Unpin for StreamSink<T, Wf> {}
+
+
+impl<T, Wf: WireFormat + Send + 'static> StreamSink<T, Wf>
+{
+	fn ctx( &self, context: &str ) -> PeerErrCtx
+	{
+		Peer::err_ctx( &self.peer, self.sid, self.cid, context.to_string() )
+	}
+
+
+	fn thes_err( &self, source: ThesErr ) -> PeerErr
+	{
+		PeerErr::ThesErr{ ctx: self.ctx( "Send on stream" ), source: Arc::new( source ) }
+	}
+}
+
+
+impl<T, Wf> Sink<T> for StreamSink<T, Wf>
+
+	where T : Serialize                  ,
+	      Wf: WireFormat + Send + 'static,
+{
+	type Error = PeerErr;
+
+
+	/// Pending while the remote hasn't read [`STREAM_WINDOW`] bytes of the items we sent.
+	//
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		futures::ready!( self.window.poll_open( cx ) );
+
+		Sink::<Wf>::poll_ready( Pin::new( &mut self.peer ), cx ).map_err( |source| self.thes_err( source ) )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, item: T ) -> Result<(), Self::Error>
+	{
+		let tag       = if self.opener { DATA_TO_ACCEPTOR } else { DATA_TO_OPENER };
+		let mut frame = Peer::<Wf>::stream_frame( self.cid, tag, 0 );
+
+		serde_cbor::to_writer( &mut frame, &item ).map_err( |e|
+		{
+			PeerErr::Serialize{ ctx: self.ctx( "Serialize item for stream" ), source: Some( e.into() ) }
+
+		})?;
+
+		// The last item may overshoot the window, the sender waits until the remote has read enough of it.
+		//
+		self.window.consume( frame.msg().len() );
+
+		Sink::<Wf>::start_send( Pin::new( &mut self.peer ), frame ).map_err( |source| self.thes_err( source ) )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Sink::<Wf>::poll_flush( Pin::new( &mut self.peer ), cx ).map_err( |source| self.thes_err( source ) )
+	}
+
+
+	/// Tells the remote the stream is closed. This doesn't close the connection.
+	//
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		if !self.closed
+		{
+			futures::ready!( Sink::<Wf>::poll_ready( Pin::new( &mut self.peer ), cx ) ).map_err( |source| self.thes_err( source ) )?;
+
+			let tag   = if self.opener { CLOSE_TO_ACCEPTOR } else { CLOSE_TO_OPENER };
+			let frame = Peer::<Wf>::stream_frame( self.cid, tag, 0 );
+
+			Sink::<Wf>::start_send( Pin::new( &mut self.peer ), frame ).map_err( |source| self.thes_err( source ) )?;
+
+			self.closed = true;
+		}
+
+		Sink::<Wf>::poll_flush( Pin::new( &mut self.peer ), cx ).map_err( |source| self.thes_err( source ) )
+	}
+}
+
+
+impl<T, Wf: WireFormat> fmt::Debug for StreamSink<T, Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "StreamSink: sid: {}, cid: {}, closed: {}", self.sid, self.cid, self.closed )
+	}
+}
+
+
+
+/// Receives the items the remote sends over a stream. Ends when the remote closes the stream or
+/// the connection closes.
+//
+pub struct StreamRx<T, Wf: 'static + WireFormat = ThesWF>
+{
+	rx     : mpsc::UnboundedReceiver<Wf> ,
+	ctx    : PeerErrCtx                  ,
+	cid    : ConnID                      ,
+	opener : bool                        ,
+	peer   : Addr<Peer<Wf>>              ,
+	_ghost : PhantomData<fn() -> T>      ,
+
+	// Bytes we have read, but not handed back to the sender yet.
+	//
+	unacked: usize,
+
+	// Bytes the peer received for this stream that we haven't handed back to the sender yet, whether
+	// we have read them or not. The peer closes the stream when the remote sends past the window.
+	//
+	received: Arc<AtomicUsize>,
+}
+
+
+impl<T, Wf: WireFormat> Unpin for StreamRx<T, Wf> {}
+
+
+impl<T, Wf: WireFormat + Send + 'static> StreamRx<T, Wf>
+{
+	// Hand the bytes we read back to the remote once they reach half the window, so the sender
+	// doesn't wait for every item. If the peer can't take the frame right now, we try again on the
+	// next poll. Once the connection is closed there is nobody to tell.
+	//
+	fn update_window( &mut self, cx: &mut Context<'_> )
+	{
+		if self.unacked < STREAM_WINDOW / 2 { return }
+
+		if let Poll::Ready( Ok(()) ) = Sink::<Wf>::poll_ready( Pin::new( &mut self.peer ), cx )
+		{
+			let tag   = if self.opener { WINDOW_TO_ACCEPTOR } else { WINDOW_TO_OPENER };
+			let frame = Peer::<Wf>::window_frame( self.cid, tag, self.unacked );
+
+			if Sink::<Wf>::start_send( Pin::new( &mut self.peer ), frame ).is_ok()
+			{
+				self.received.fetch_sub( self.unacked, SeqCst );
+				self.unacked = 0;
+				let _ = Sink::<Wf>::poll_flush( Pin::new( &mut self.peer ), cx );
+			}
+		}
+	}
+}
+
+
+impl<T, Wf> Stream for StreamRx<T, Wf>
+
+	where T : DeserializeOwned          ,
+	      Wf: WireFormat + Send + 'static,
+{
+	type Item = Result<T, PeerErr>;
+
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option<Self::Item>>
+	{
+		self.update_window( cx );
+
+		let frame = match futures::ready!( self.rx.poll_next_unpin( cx ) )
+		{
+			Some( frame ) => frame,
+			None          => return Poll::Ready( None ),
+		};
+
+		self.unacked += frame.msg().len();
+		self.update_window( cx );
+
+		// The peer only forwards data frames, so the tag is there.
+		//
+		let item = serde_cbor::from_slice( &frame.msg()[ 1.. ] ).map_err( |e|
+		{
+			PeerErr::Deserialize{ ctx: self.ctx.clone(), source: Some( e.into() ) }
+		});
+
+		Poll::Ready( Some( item ) )
+	}
+}
+
+
+impl<T, Wf: WireFormat> fmt::Debug for StreamRx<T, Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "StreamRx: sid: {:?}, cid: {:?}", self.ctx.sid, self.ctx.cid )
+	}
+}
+
+
+
+/// The part of the window of a stream that the sender can still use. Shared between the
+/// [`StreamSink`] and the peer, which adds to it when the remote sends a window update.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct SendWindow
+{
+	// Can go below zero, since the last item may be bigger than what was left.
+	//
+	bytes: AtomicI64,
+	waker: AtomicWaker,
+}
+
+
+impl SendWindow
+{
+	fn new() -> Self
+	{
+		Self { bytes: AtomicI64::new( STREAM_WINDOW as i64 ), waker: AtomicWaker::new() }
+	}
+
+
+	fn poll_open( &self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		if self.bytes.load( SeqCst ) > 0 { return Poll::Ready(()) }
+
+		self.waker.register( cx.waker() );
+
+		// The window might have been updated before we registered the waker.
+		//
+		match self.bytes.load( SeqCst ) > 0
+		{
+			true  => Poll::Ready(()),
+			false => Poll::Pending  ,
+		}
+	}
+
+
+	fn consume( &self, bytes: usize )
+	{
+		self.bytes.fetch_sub( i64::try_from( bytes ).unwrap_or( i64::MAX ), SeqCst );
+	}
+
+
+	fn release( &self, bytes: u32 )
+	{
+		self.bytes.fetch_add( i64::from( bytes ), SeqCst );
+		self.waker.wake();
+	}
+}
+
+
+
+impl<Wf: WireFormat> Peer<Wf>
+{
+	/// A frame for a stream with just the tag. `capacity` is the expected size of the rest of the payload.
+	//
+	pub(crate) fn stream_frame( cid: ConnID, tag: u8, capacity: usize ) -> Wf
+	{
+		let mut frame = Wf::with_capacity( 1 + capacity );
+
+		frame.set_sid( ServiceID::stream() );
+		frame.set_cid( cid                 );
+
+		// Writing to a Vec doesn't fail.
+		//
+		frame.write_all( &[ tag ] ).expect( "write stream tag" );
+
+		frame
+	}
+
+
+	// A frame that hands `bytes` back to the sender on the other side of the stream.
+	//
+	fn window_frame( cid: ConnID, tag: u8, bytes: usize ) -> Wf
+	{
+		let mut frame = Self::stream_frame( cid, tag, 4 );
+		let bytes     = u32::try_from( bytes ).unwrap_or( u32::MAX );
+
+		frame.write_all( &bytes.to_le_bytes() ).expect( "write window update" );
+
+		frame
+	}
+
+
+	// Create the send window for a new stream. The peer only keeps a weak reference, so windows of
+	// streams whose sink has been dropped are cleaned up here.
+	//
+	fn new_window( &mut self, key: (bool, ConnID) ) -> Arc<SendWindow>
+	{
+		let window = Arc::new( SendWindow::new() );
+
+		self.stream_windows.retain( |_, w| w.strong_count() > 0 );
+		self.stream_windows.insert( key, Arc::downgrade( &window ) );
+
+		window
+	}
+
+
+	/// Process a frame of a stream.
+	//
+	pub(crate) async fn incoming_stream( &mut self, frame: Wf )
+	{
+		let cid = frame.cid();
+
+		trace!( "{}: Incoming stream frame, cid: {}", self.identify(), cid );
+
+		match frame.msg().first().copied()
+		{
+			Some( OPEN               ) => self.stream_open( frame ).await,
+			Some( DATA_TO_ACCEPTOR   ) => self.stream_data( false, frame ).await,
+			Some( DATA_TO_OPENER     ) => self.stream_data( true , frame ).await,
+			Some( CLOSE_TO_ACCEPTOR  ) => { self.streams.remove( &(false, cid) ); }
+			Some( CLOSE_TO_OPENER    ) => { self.streams.remove( &(true , cid) ); }
+			Some( WINDOW_TO_ACCEPTOR ) => self.stream_window( false, frame ),
+			Some( WINDOW_TO_OPENER   ) => self.stream_window( true , frame ),
+
+			_ =>
+			{
+				let ctx    = self.ctx( None, None, "Incoming stream frame" );
+				let source = WireErr::Deserialize{ context: format!( "invalid stream frame, cid: {}", cid ), source: None };
+
+				self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+			}
+		}
+	}
+
+
+	// Hand an item to the local end of the stream.
+	//
+	// The remote may only send while it has some window left, so the last item can overshoot it,
+	// but once the reader holds STREAM_WINDOW bytes the remote should wait for a window update.
+	//
+	async fn stream_data( &mut self, opened: bool, frame: Wf )
+	{
+		let key = (opened, frame.cid());
+
+		let frame = match self.streams.get( &key )
+		{
+			Some( (_, received) ) if received.load( SeqCst ) >= STREAM_WINDOW =>
+			{
+				return self.stream_overflow( key ).await;
+			}
+
+			Some( (tx, received) ) =>
+			{
+				let len = frame.msg().len();
+
+				match tx.unbounded_send( frame )
+				{
+					Ok (_) => { received.fetch_add( len, SeqCst ); return }
+
+					// The receiver was dropped, nobody is interested in this stream anymore.
+					//
+					Err(e) =>
+					{
+						self.streams.remove( &key );
+						e.into_inner()
+					}
+				}
+			}
+
+			None =>
+			{
+				warn!( "{}: Received item for unknown stream, cid: {}. Dropping it.", self.identify(), key.1 );
+				frame
+			}
+		};
+
+		// Nobody will read the item, so hand the window back right away, otherwise the remote would
+		// wait forever to send the next one.
+		//
+		let tag = if opened { WINDOW_TO_ACCEPTOR } else { WINDOW_TO_OPENER };
+
+		// If we can't send, the connection is gone and the stream ends on the remote anyway.
+		//
+		let _ = self.send_msg( Self::window_frame( key.1, tag, frame.msg().len() ) ).await;
+	}
+
+
+	// The remote sent past the window of the stream. Our reader gets the items it hasn't read yet and
+	// then the end of the stream. The remote is told the stream is closed and later items are dropped.
+	//
+	async fn stream_overflow( &mut self, key: (bool, ConnID) )
+	{
+		self.streams.remove( &key );
+
+		let tag = if key.0 { CLOSE_TO_ACCEPTOR } else { CLOSE_TO_OPENER };
+
+		// If we can't send, the connection is gone and the stream ends on the remote anyway.
+		//
+		let _ = self.send_msg( Self::stream_frame( key.1, tag, 0 ) ).await;
+
+		let ctx     = self.ctx( None, key.1, "Incoming stream frame" );
+		let context = format!( "the remote sent more than STREAM_WINDOW ({}) bytes on a stream, it is closed", STREAM_WINDOW );
+		let source  = WireErr::Deserialize{ context, source: None };
+
+		self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+	}
+
+
+	// The remote read items we sent, so we can send more.
+	//
+	fn stream_window( &mut self, opened: bool, frame: Wf )
+	{
+		let key   = (opened, frame.cid());
+		let bytes = frame.msg().get( 1..5 ).and_then( |b| b.try_into().ok() ).map( u32::from_le_bytes );
+
+		let bytes = match bytes
+		{
+			Some( b ) => b,
+			None      => return warn!( "{}: Received invalid window update for stream, cid: {}. Dropping it.", self.identify(), key.1 ),
+		};
+
+		// The sink might be gone already, then there is nothing to do.
+		//
+		if let Some( window ) = self.stream_windows.get( &key ).and_then( Weak::upgrade )
+		{
+			window.release( bytes );
+		}
+	}
+
+
+	// The remote opens a stream.
+	//
+	// Errors are reported as events, but not as ConnectionError to the remote, since it doesn't wait
+	// for a response. Instead the stream gets closed right away.
+	//
+	async fn stream_open( &mut self, frame: Wf )
+	{
+		if self.closed { return }
+
+		let cid = frame.cid();
+		let msg = frame.msg();
+
+		if msg.len() < LEN_OPEN_HEADER
+		{
+			let ctx    = self.ctx( None, None, "Incoming stream frame" );
+			let source = WireErr::Deserialize{ context: "stream open frame is too short for the header".to_string(), source: None };
+
+			self.refuse_stream( cid ).await;
+			return self.handle( RequestError::from( PeerErr::WireFormat{ ctx, source } ) ).await;
+		}
+
+		// unwrap: we just checked the length.
+		//
+		let sid = ServiceID::read_le( &mut &msg[ 1..LEN_OPEN_HEADER ] ).unwrap();
+		let ctx = self.ctx( sid, None, "Peer: open incoming stream" );
+
+		if self.streams.contains_key( &(false, cid) )
+		{
+			return self.handle( RequestError::from( PeerErr::DuplicateCid{ ctx } ) ).await;
+		}
+
+
+		let mut open = Wf::with_capacity( msg.len() - LEN_OPEN_HEADER );
+
+		open.set_sid( sid );
+		open.set_cid( cid );
+
+		if let Err( e ) = open.write_all( &msg[ LEN_OPEN_HEADER.. ] )
+		{
+			self.refuse_stream( cid ).await;
+			return self.handle( RequestError::from( PeerErr::Deserialize{ ctx, source: Some( e.into() ) } ) ).await;
+		}
+
+
+		// Only fails when our mailbox is stopping, see Peer::set_auto_close_on_idle.
+		//
+		let peer = match self.own_addr()
+		{
+			Some( peer ) => peer,
+			None         => return self.refuse_stream( cid ).await,
+		};
+
+
+		let sm = match self.services.get( &sid )
+		{
+			Some( sm ) => sm,
+
+			None =>
+			{
+				self.refuse_stream( cid ).await;
+				return self.handle( RequestError::from( PeerErr::UnknownService{ ctx } ) ).await;
+			}
+		};
+
+
+		if !self.authorized( &open, &ctx )
+		{
+			self.refuse_stream( cid ).await;
+			return self.handle( RequestError::from( PeerErr::Unauthorized{ ctx } ) ).await;
+		}
+
+
+		let (tx, rx) = mpsc::unbounded();
+		let window   = self.new_window( (false, cid) );
+		let received = Arc::new( AtomicUsize::new(0) );
+		let channel  = StreamChannel{ sid, cid, opener: false, peer, rx, window, received: received.clone() };
+
+		let fut = match sm.open_stream( open, channel, ctx.clone() )
+		{
+			Ok (f) => f,
+			Err(e) =>
+			{
+				self.refuse_stream( cid ).await;
+				return self.handle( RequestError::from(e) ).await;
+			}
+		};
+
+		self.streams.insert( (false, cid), (tx, received) );
+
+		if self.nursery.nurse( fut ).is_err()
+		{
+			self.streams.remove( &(false, cid) );
+			self.refuse_stream( cid ).await;
+
+			self.handle( RequestError::from( PeerErr::Spawn{ ctx } ) ).await;
+		}
+	}
+
+
+	// Close a stream the remote tried to open.
+	//
+	async fn refuse_stream( &mut self, cid: ConnID )
+	{
+		// If we can't send, the connection is gone and the stream ends on the remote anyway.
+		//
+		let _ = self.send_msg( Self::stream_frame( cid, CLOSE_TO_OPENER, 0 ) ).await;
+	}
+}
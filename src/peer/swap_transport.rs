@@ -0,0 +1,94 @@
+use crate :: { import::*, * };
+
+
+/// Move the connection of a [Peer] to a new transport, eg. after upgrading it to TLS. Outgoing calls
+/// waiting for a response, open streams and all other state of the peer are kept, so the responses
+/// come in over the new transport.
+///
+/// The peer reads the new stream from the next frame on and writes everything after this message to
+/// the new sink. The remote has to swap as well. Frames it writes to the old transport after we
+/// stopped reading it are lost, so make sure it doesn't write anything in between, eg. by only swapping
+/// while the calls in flight are being processed.
+///
+/// The old transport is kept open, but unused, until the connection closes, so the remote doesn't see
+/// it close before it has swapped.
+//
+pub struct SwapTransport<Wf: WireFormat = ThesWF>
+{
+	incoming: Box< dyn BoundsIn <Wf> >,
+	outgoing: Box< dyn BoundsOut<Wf> >,
+}
+
+
+impl<Wf: WireFormat> SwapTransport<Wf>
+{
+	/// The stream and sink of the new transport.
+	//
+	pub fn new( incoming: impl BoundsIn<Wf>, outgoing: impl BoundsOut<Wf> ) -> Self
+	{
+		Self
+		{
+			incoming: Box::new( incoming ),
+			outgoing: Box::new( outgoing ),
+		}
+	}
+}
+
+
+impl<Wf: WireFormat> Message for SwapTransport<Wf>
+{
+	type Return = Result<(), PeerErr>;
+}
+
+
+impl<Wf: WireFormat> fmt::Debug for SwapTransport<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "SwapTransport" )
+	}
+}
+
+
+
+impl<Wf: WireFormat + Send + 'static> Handler<SwapTransport<Wf>> for Peer<Wf>
+{
+	#[async_fn] fn handle( &mut self, msg: SwapTransport<Wf> ) -> Result<(), PeerErr>
+	{
+		trace!( "{}: swap transport", self.identify() );
+
+		let out = match &mut self.outgoing
+		{
+			Some( out ) if !self.closed => out,
+
+			_ =>
+			{
+				let ctx = self.ctx( None, None, "Swap transport" );
+				return Err( PeerErr::ConnectionClosed{ ctx } );
+			}
+		};
+
+		// Whatever we wrote so far should still reach the remote.
+		//
+		if let Err( source ) = out.flush().await
+		{
+			let ctx = self.ctx( None, None, "Flush the old transport before swapping" );
+			return Err( PeerErr::WireFormat{ ctx, source } );
+		}
+
+		// The task reading the connection only stops when the connection closes.
+		//
+		if self.swap_incoming.unbounded_send( msg.incoming ).is_err()
+		{
+			let ctx = self.ctx( None, None, "Swap transport" );
+			return Err( PeerErr::ConnectionClosed{ ctx } );
+		}
+
+		if let Some( old ) = self.outgoing.replace( msg.outgoing )
+		{
+			self.retired.push( old );
+		}
+
+		Ok(())
+	}
+}
@@ -30,13 +30,15 @@ impl<Wf: WireFormat + Send> Handler<Timeout> for Peer<Wf>
 
 		async move
 		{
-			if let Some( tx ) = self.responses.remove( &msg.cid )
+			if let Some( (_, tx) ) = self.responses.remove( &msg.cid )
 			{
 				// If this fails, the receiver is already gone, so ignore the result.
 				//
 				let _ = tx.send( Err( ConnectionError::Timeout{ sid: msg.sid } ) );
 			}
 
+			self.update_idle();
+
 		}.boxed()
 	}
 }
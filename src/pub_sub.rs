@@ -191,7 +191,7 @@ impl<Wf: WireFormat> ServiceMap<Wf> for PubSub<Wf>
 	/// PubSub implements a broadcast type fan out, so it doesn't support `Address::call`,
 	/// as that requires a response. As we send to multiple receivers, which one is supposed to respond?
 	//
-	fn call_service( &self, _frame: Wf, ctx: PeerErrCtx )
+	fn call_service( &self, _frame: Wf, ctx: PeerErrCtx, _cancel: CancelToken )
 
 		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
 	{
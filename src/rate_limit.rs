@@ -0,0 +1,149 @@
+use
+{
+	crate :: { import::*                      } ,
+	std   :: { num::NonZeroU32, time::Instant } ,
+};
+
+
+/// A token bucket that paces the sends on a `RemoteAddr`, for when a producer must not exceed
+/// the rate at which a provider can ingest messages. See `RemoteAddr::set_rate_limit`.
+///
+/// When no token is available, `poll_ready` on the `RemoteAddr` returns pending until one is,
+/// so the sender gets back pressure. Nothing is dropped. Calls are not limited.
+///
+/// Clones share the same bucket, so all the `RemoteAddr`s that have a clone of a `RateLimit`
+/// together stay within the rate. This is unrelated to [`BackPressure`](crate::BackPressure),
+/// which limits the incoming requests a [`Peer`](crate::Peer) processes concurrently.
+//
+pub struct RateLimit
+{
+	bucket  : Arc< Mutex<Bucket> > ,
+
+	// Wait for the next token.
+	//
+	delay   : Option<Delay>        ,
+
+	// Whether poll_ready already took a token which start_send has not used yet.
+	//
+	reserved: bool                 ,
+}
+
+
+struct Bucket
+{
+	tokens  : f64     ,
+	capacity: f64     ,
+	rate    : f64     ,
+	last    : Instant ,
+}
+
+
+impl Bucket
+{
+	/// Take a token, or tell how long to wait for the next one.
+	//
+	fn take( &mut self ) -> Result<(), Duration>
+	{
+		let now = Instant::now();
+
+		self.tokens = ( self.tokens + now.duration_since( self.last ).as_secs_f64() * self.rate ).min( self.capacity );
+		self.last   = now;
+
+		if self.tokens >= 1.0
+		{
+			self.tokens -= 1.0;
+			return Ok(())
+		}
+
+		Err( Duration::from_secs_f64( ( 1.0 - self.tokens ) / self.rate ) )
+	}
+}
+
+
+
+impl RateLimit
+{
+	/// Allow `per_second` messages per second. There is no burst, messages are spaced out evenly.
+	//
+	pub fn new( per_second: NonZeroU32 ) -> Self
+	{
+		Self::with_burst( per_second, NonZeroU32::new(1).unwrap() )
+	}
+
+
+	/// Allow `per_second` messages per second on average, but up to `burst` back to back when the
+	/// sender has been idle. The bucket starts full.
+	//
+	pub fn with_burst( per_second: NonZeroU32, burst: NonZeroU32 ) -> Self
+	{
+		let bucket = Bucket
+		{
+			tokens  : f64::from( burst.get()      ) ,
+			capacity: f64::from( burst.get()      ) ,
+			rate    : f64::from( per_second.get() ) ,
+			last    : Instant::now()                ,
+		};
+
+		Self { bucket: Arc::new( Mutex::new( bucket ) ), delay: None, reserved: false }
+	}
+
+
+	/// Resolves when a token is available and reserves it for the next call to [`RateLimit::consume`].
+	/// Calling this again before consuming doesn't take another token.
+	//
+	pub fn poll_acquire( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		while !self.reserved
+		{
+			if let Some( delay ) = &mut self.delay
+			{
+				if delay.poll_unpin( cx ).is_pending()
+				{
+					return Poll::Pending
+				}
+
+				self.delay = None;
+			}
+
+			match self.bucket.lock().take()
+			{
+				Ok ( ()   ) => self.reserved = true                      ,
+				Err( wait ) => self.delay    = Some( Delay::new( wait ) ) ,
+			}
+		}
+
+		Poll::Ready(())
+	}
+
+
+	/// Use the token reserved by [`RateLimit::poll_acquire`].
+	//
+	pub fn consume( &mut self )
+	{
+		self.reserved = false;
+	}
+}
+
+
+
+/// The clone shares the bucket, but not a token reserved by the original.
+//
+impl Clone for RateLimit
+{
+	fn clone( &self ) -> Self
+	{
+		Self { bucket: self.bucket.clone(), delay: None, reserved: false }
+	}
+}
+
+
+
+impl fmt::Debug for RateLimit
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		let bucket = self.bucket.lock();
+
+		write!( f, "RateLimit: {} per second, burst: {}", bucket.rate, bucket.capacity )
+	}
+}
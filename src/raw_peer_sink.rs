@@ -0,0 +1,143 @@
+use crate :: { import::*, * };
+
+
+/// Sends frames that are already encoded, eg. captured earlier, to a [Peer] as they are, without going
+/// through the serialization of a `RemoteAddr`. Useful for testing, proxying and replaying traffic.
+///
+/// As a `Sink`, frames go out unchanged, so they should be sends, with a null cid. For calls use
+/// [`RawPeerSink::call`], which gives the frame a fresh cid like any other outgoing call, so a
+/// captured call can be replayed several times. It returns the response frame as it came in.
+///
+/// Note that this holds the address of the peer, just like `RemoteAddr`.
+//
+#[ derive( Debug ) ]
+//
+pub struct RawPeerSink<Wf: 'static + WireFormat = ThesWF>
+{
+	peer: Addr<Peer<Wf>>,
+}
+
+
+impl<Wf: 'static + WireFormat> Clone for RawPeerSink<Wf>
+{
+	fn clone( &self ) -> Self
+	{
+		Self { peer: self.peer.clone() }
+	}
+}
+
+
+
+impl<Wf: 'static + WireFormat + Send> RawPeerSink<Wf>
+{
+	/// Send frames over the connection of `peer`.
+	//
+	pub fn new( peer: Addr<Peer<Wf>> ) -> Self
+	{
+		Self { peer }
+	}
+
+
+	/// Make a call with a ready made frame. The cid of the frame is replaced.
+	///
+	/// A [`ConnectionError`] sent back by the remote is turned into a [PeerErr] with
+	/// [`PeerErr::from_remote`].
+	//
+	pub async fn call( &mut self, wf: Wf ) -> Result<Wf, PeerErr>
+	{
+		let sid = wf.sid();
+
+		let rx = self.peer.call( Call::new( wf ) ).await
+
+			// The peer panicked.
+			//
+			.map_err( |_|
+			{
+				let ctx = Peer::err_ctx( &self.peer, sid, None, "Call with raw frame".to_string() );
+
+				PeerErr::PeerGone{ ctx }
+
+			})??;
+
+
+		let re = rx.await
+
+			.map_err( |_|
+			{
+				let ctx = Peer::err_ctx( &self.peer, sid, None, "Peer stopped before receiving response from remote call".to_string() );
+
+				PeerErr::ConnectionClosed{ ctx }
+
+			})?;
+
+
+		re.map_err( |err|
+		{
+			let ctx = Peer::err_ctx( &self.peer, sid, None, "Remote could not process our message".to_string() );
+
+			PeerErr::from_remote( err, ctx )
+		})
+	}
+
+
+	// Wrap errors from the address of the peer.
+	//
+	fn thes_err( &self, source: ThesErr ) -> PeerErr
+	{
+		let ctx = Peer::err_ctx( &self.peer, None, None, "Send on RawPeerSink".to_string() );
+
+		PeerErr::ThesErr{ ctx, source: Arc::new( source ) }
+	}
+}
+
+
+
+impl<Wf: 'static + WireFormat + Send> Sink<Wf> for RawPeerSink<Wf>
+{
+	type Error = PeerErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Sink::<Wf>::poll_ready( Pin::new( &mut self.peer ), cx ).map_err( |e| self.thes_err( e ) )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, wf: Wf ) -> Result<(), Self::Error>
+	{
+		Sink::<Wf>::start_send( Pin::new( &mut self.peer ), wf ).map_err( |e| self.thes_err( e ) )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Sink::<Wf>::poll_flush( Pin::new( &mut self.peer ), cx ).map_err( |e| self.thes_err( e ) )
+	}
+
+
+	/// Will only close when dropped, the connection is closed with [`CloseConnection`].
+	//
+	fn poll_close( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Poll::Ready( Ok(()) )
+	}
+}
+
+
+
+impl<Wf: 'static + WireFormat> Identify for RawPeerSink<Wf>
+{
+	/// Unique id of the peer this sends over
+	//
+	fn id( &self ) -> usize
+	{
+		self.peer.id()
+	}
+
+	/// Name of the peer this sends over
+	//
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		self.peer.name()
+	}
+}
@@ -0,0 +1,92 @@
+use
+{
+	crate :: { import::*, ErrorSource           } ,
+	bytes :: { Bytes                            } ,
+	std   :: { any::{ Any, TypeId }, io::Write  } ,
+	serde :: { de::DeserializeOwned             } ,
+};
+
+
+/// A response that is already serialized. When a handler returns this, the bytes are copied into
+/// the outgoing message as is, without going through serde. Useful to answer with data the handler
+/// already holds in serialized form, eg. a cached blob.
+///
+/// The bytes must be the CBOR encoding of the actual response, see [`RawResponse::encode`]. Both
+/// sides must use `RawResponse` as the return type of the service. The caller gets the same bytes
+/// back and can use [`RawResponse::decode`] to get the value.
+//
+#[ derive( Debug, Clone, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub struct RawResponse( Bytes );
+
+
+impl RawResponse
+{
+	/// Wrap bytes that hold a CBOR encoded response.
+	//
+	pub fn new( bytes: impl Into<Bytes> ) -> Self
+	{
+		Self( bytes.into() )
+	}
+
+
+	/// Serialize a value, to be stored and returned later.
+	//
+	pub fn encode<T: Serialize>( value: &T ) -> Result<Self, serde_cbor::Error>
+	{
+		serde_cbor::to_vec( value ).map( Self::new )
+	}
+
+
+	/// Deserialize the response.
+	//
+	pub fn decode<T: DeserializeOwned>( &self ) -> Result<T, serde_cbor::Error>
+	{
+		serde_cbor::from_slice( &self.0 )
+	}
+
+
+	/// The encoded bytes.
+	//
+	pub fn bytes( &self ) -> &Bytes
+	{
+		&self.0
+	}
+}
+
+
+
+/// Serialize the response of a handler. A [`RawResponse`] is written as is. Used by `service_map!`.
+//
+#[ doc( hidden ) ]
+//
+pub fn write_response<R: Serialize + 'static>( wf: &mut impl Write, resp: &R ) -> Result<(), ErrorSource>
+{
+	let any: &dyn Any = resp;
+
+	match any.downcast_ref::<RawResponse>()
+	{
+		Some( raw ) => wf.write_all( &raw.0 ).map_err( Into::into ),
+		None        => serde_cbor::to_writer( wf, resp ).map_err( Into::into ),
+	}
+}
+
+
+
+/// Deserialize the response to a call. For a [`RawResponse`] the bytes are taken as is. Used by `service_map!`.
+//
+#[ doc( hidden ) ]
+//
+pub fn read_response<R: DeserializeOwned + 'static>( bytes: &[u8] ) -> Result<R, serde_cbor::Error>
+{
+	if TypeId::of::<R>() == TypeId::of::<RawResponse>()
+	{
+		let raw: Box<dyn Any> = Box::new( RawResponse( Bytes::copy_from_slice( bytes ) ) );
+
+		// unwrap: we just checked the type.
+		//
+		return Ok( *raw.downcast::<R>().unwrap() );
+	}
+
+	serde_cbor::from_slice( bytes )
+}
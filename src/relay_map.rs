@@ -1,4 +1,8 @@
-use crate :: { import::*, *, peer::Response };
+use
+{
+	crate   :: { import::*, *, peer::Response } ,
+	futures :: { future                       } ,
+};
 
 
 
@@ -6,6 +10,8 @@ use crate :: { import::*, *, peer::Response };
 /// used for local handlers is that handlers here don't have to implement `Handler<M>` for the actual message type.
 /// They only have to implement `Handler<BytesFormat>` (for sends) and `Handler<peer::Call>` for calls.
 ///
+/// To relay groups of services to different handlers with a single map, use [RelayMapBuilder].
+///
 /// # Example
 /// TODO
 //
@@ -15,23 +21,375 @@ pub struct RelayMap<Wf>
 	// definitely has to be `Sync`. We cannot have a guarantee that cloning a channel sender is thread safe.
 	// We also cannot use `RwLock` because that only protects mut access, but cloning only uses immutable access.
 	//
-	handler : Mutex<ServiceHandler<Wf>> ,
-	services: Vec<ServiceID>            ,
+	groups: Vec<( Mutex<ServiceHandler<Wf>>, Vec<ServiceID> )> ,
+
+	// The index in groups of the handler for each service.
+	//
+	routes: HashMap<ServiceID, usize> ,
+	drain : Arc<Drain>                ,
 }
 
 
 impl<Wf> RelayMap<Wf>
 {
-	/// Create a RelayMap.
+	/// Create a RelayMap. Services that are listed more than once are only kept once.
 	//
 	pub fn new( handler: ServiceHandler<Wf>, services: Vec<ServiceID> ) -> Self
 	{
-		Self { handler: Mutex::new( handler ), services }
+		// unwrap: with a single group, no service can be in more than one.
+		//
+		RelayMapBuilder::new().group( handler, services ).build().unwrap()
+	}
+
+
+	// The handler for a service.
+	//
+	fn handler( &self, sid: &ServiceID, ctx: &PeerErrCtx ) -> Result< &Mutex<ServiceHandler<Wf>>, PeerErr >
+	{
+		match self.routes.get( sid )
+		{
+			Some( idx ) => Ok( &self.groups[ *idx ].0 ),
+			None        => Err( PeerErr::NoHandler{ ctx: ctx.clone() } ),
+		}
+	}
+
+
+	/// Stop accepting new relayed requests and resolve once the ones in flight are done, so a relay
+	/// can be shut down without dropping responses. Calls that come in after this fail on the remote
+	/// with [`ConnectionError::ShuttingDown`], sends are dropped and reported as [`PeerErr::ShuttingDown`].
+	///
+	/// Closing the connections is up to you once this resolves. A drained map doesn't accept requests
+	/// anymore.
+	//
+	pub fn drain( &self ) -> impl Future<Output=()> + Send + 'static
+	{
+		let drain = self.drain.clone();
+
+		drain.draining.store( true, SeqCst );
+
+		future::poll_fn( move |cx| drain.poll_done( cx ) )
+	}
+
+
+	/// Whether [`RelayMap::drain`] has been called.
+	//
+	pub fn is_draining( &self ) -> bool
+	{
+		self.drain.draining.load( SeqCst )
+	}
+
+
+	/// Stop relaying new requests to the backend with actor id `id` (see [`Identify::id`]), so it can be
+	/// taken down for maintenance. This applies to the connections in a [`RelayPool`], new requests go to
+	/// the other connections of the pool. Requests in flight to the backend still finish, remove it with
+	/// [`RelayMap::remove_backend`] once they are done. When all connections of a pool are draining, requests
+	/// for its services are refused with [`PeerErr::ShuttingDown`].
+	///
+	/// Returns false when no pool has a connection with this id.
+	//
+	pub fn drain_backend( &self, id: usize ) -> bool
+	{
+		let mut found = false;
+
+		for (handler, _) in &self.groups
+		{
+			if let ServiceHandler::Pool( p ) = &mut *handler.lock()
+			{
+				found |= p.drain( id );
+			}
+		}
+
+		found
+	}
+
+
+	/// Remove a backend drained with [`RelayMap::drain_backend`] from all pools once no requests to it
+	/// are in flight anymore. Nothing is removed when this fails.
+	//
+	pub fn remove_backend( &self, id: usize ) -> Result<(), RemoveBackendErr>
+	{
+		let mut pools: Vec<_> = self.groups.iter().map( |(handler, _)| handler.lock() ).collect();
+		let mut found         = false;
+
+		for handler in &pools
+		{
+			if let ServiceHandler::Pool( p ) = &**handler
+			{
+				found |= p.check_remove( id )?;
+			}
+		}
+
+		if !found
+		{
+			return Err( RemoveBackendErr::Unknown{ id } );
+		}
+
+		for handler in &mut pools
+		{
+			if let ServiceHandler::Pool( p ) = &mut **handler
+			{
+				p.remove( id );
+			}
+		}
+
+		Ok(())
+	}
+
+
+	/// The services for which at least one backend is still reachable. [`ServiceMap::services`] keeps
+	/// listing everything this map was configured with, so use this one when advertising services,
+	/// eg. for service discovery.
+	///
+	/// A backend counts as dead when its mailbox no longer accepts messages. For [`ServiceHandler::Closure`]
+	/// the closure is asked for the backend of every service. [`ServiceHandler::Route`] and [`ServiceHandler::Sticky`]
+	/// pick a backend per message or per connection, so they cannot be probed and all their services are reported
+	/// as live.
+	//
+	pub fn live_services( &self ) -> Vec<ServiceID>
+
+		where Wf: WireFormat
+	{
+		let mut live_services = Vec::new();
+
+		for (handler, services) in &self.groups
+		{
+			let live = match &*handler.lock()
+			{
+				ServiceHandler::Address( a ) => is_alive( &**a ),
+				ServiceHandler::Route  ( _ ) => true,
+				ServiceHandler::Sticky ( _ ) => true,
+				ServiceHandler::Pool   ( p ) => p.relays().any( is_alive ),
+
+				ServiceHandler::Closure( c ) =>
+				{
+					live_services.extend( services.iter().filter( |sid| is_alive( &*c(sid) ) ) );
+					continue;
+				}
+			};
+
+			if live { live_services.extend( services ) }
+		}
+
+		live_services
+	}
+}
+
+
+
+/// Build a [RelayMap] that relays groups of services to different handlers, eg. services A and B to
+/// one backend and C to another. Each incoming request goes to the handler of the group its sid
+/// is in.
+//
+pub struct RelayMapBuilder<Wf>
+{
+	groups: Vec<( ServiceHandler<Wf>, Vec<ServiceID> )>,
+}
+
+
+impl<Wf> RelayMapBuilder<Wf>
+{
+	/// Create a builder without any groups.
+	//
+	pub fn new() -> Self
+	{
+		Self { groups: Vec::new() }
+	}
+
+
+	/// Relay `services` to `handler`. Services that are listed more than once are only kept once.
+	//
+	pub fn group( mut self, handler: ServiceHandler<Wf>, services: Vec<ServiceID> ) -> Self
+	{
+		self.groups.push( (handler, services) );
+		self
+	}
+
+
+	/// Create the RelayMap. Fails when a service is in more than one group, since it could only be
+	/// relayed to one of them.
+	//
+	pub fn build( self ) -> Result< RelayMap<Wf>, OverlappingGroups >
+	{
+		let mut routes = HashMap::new();
+		let mut groups = Vec::with_capacity( self.groups.len() );
+
+		for (idx, (handler, mut services)) in self.groups.into_iter().enumerate()
+		{
+			let mut seen = HashSet::new();
+
+			services.retain( |sid| seen.insert( *sid ) );
+
+			for sid in &services
+			{
+				if routes.insert( *sid, idx ).is_some()
+				{
+					return Err( OverlappingGroups{ sid: *sid } );
+				}
+			}
+
+			groups.push( (Mutex::new( handler ), services) );
+		}
+
+		Ok( RelayMap { groups, routes, drain: Arc::default() } )
 	}
 }
 
 
 
+/// Returned by [`RelayMapBuilder::build`] when a service is in more than one group.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub struct OverlappingGroups
+{
+	/// The service that is in more than one group.
+	//
+	pub sid: ServiceID,
+}
+
+
+impl fmt::Display for OverlappingGroups
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "RelayMapBuilder: service {} is in more than one group", self.sid )
+	}
+}
+
+
+impl std::error::Error for OverlappingGroups {}
+
+
+impl<Wf> Default for RelayMapBuilder<Wf>
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+
+impl<Wf> fmt::Debug for RelayMapBuilder<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "RelayMapBuilder, groups: {}", self.groups.len() )
+	}
+}
+
+
+
+// Whether the mailbox of a backend still accepts messages. This only polls for readiness once, so a backend
+// that is merely busy counts as alive.
+//
+fn is_alive<Wf: WireFormat>( relay: &dyn Relay<Wf> ) -> bool
+{
+	let mut probe = Address::<Call<Wf>>::clone_box( relay );
+
+	let ready = future::poll_fn( |cx| Sink::<Call<Wf>>::poll_ready( Pin::new( &mut probe ), cx ) ).now_or_never();
+
+	!matches!( ready, Some( Err(_) ) )
+}
+
+
+
+// Keeps track of the requests in flight, so drain knows when they are done.
+//
+#[ derive( Debug, Default ) ]
+//
+struct Drain
+{
+	draining : AtomicBool       ,
+	in_flight: AtomicUsize      ,
+	wakers   : Mutex<Vec<Waker>>,
+}
+
+
+impl Drain
+{
+	// Count a new request as in flight, unless we are draining.
+	//
+	// The count goes up before checking the flag, so drain either sees the request or the request
+	// sees the flag.
+	//
+	fn enter( self: &Arc<Self>, ctx: &PeerErrCtx ) -> Result<Tracked, PeerErr>
+	{
+		self.in_flight.fetch_add( 1, SeqCst );
+
+		let in_flight = Tracked( self.clone() );
+
+		if self.draining.load( SeqCst )
+		{
+			let ctx = ctx.clone().context( "RelayMap is draining, not accepting new requests".to_string() );
+
+			return Err( PeerErr::ShuttingDown{ ctx } );
+		}
+
+		Ok( in_flight )
+	}
+
+
+	fn poll_done( &self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		if self.in_flight.load( SeqCst ) == 0 { return Poll::Ready(()) }
+
+		self.wakers.lock().push( cx.waker().clone() );
+
+		// The last request might have finished before we stored the waker.
+		//
+		match self.in_flight.load( SeqCst )
+		{
+			0 => Poll::Ready(()),
+			_ => Poll::Pending  ,
+		}
+	}
+}
+
+
+// A relayed request in flight, for as long as it lives.
+//
+struct Tracked( Arc<Drain> );
+
+
+impl Drop for Tracked
+{
+	fn drop( &mut self )
+	{
+		if self.0.in_flight.fetch_sub( 1, SeqCst ) == 1
+		{
+			self.0.wakers.lock().drain(..).for_each( Waker::wake );
+		}
+	}
+}
+
+
+// The error for a request to a pool of which all connections are draining.
+//
+fn all_draining( ctx: &PeerErrCtx ) -> PeerErr
+{
+	let ctx = ctx.clone().context( "All backends of the RelayPool are draining".to_string() );
+
+	PeerErr::ShuttingDown{ ctx }
+}
+
+
+// Keep a request counted as in flight until the future resolves.
+//
+fn tracked<Wf: WireFormat>( in_flight: Tracked, fut: impl Future< Output=Result<Response<Wf>, PeerErr> > + Send + 'static )
+
+	-> Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>
+{
+	async move
+	{
+		let _in_flight = in_flight;
+
+		fut.await
+
+	}.boxed()
+}
+
+
+
 impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 {
 	/// Send a message to a handler. This should take care of deserialization.
@@ -46,7 +404,10 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 
 		// This sid should be in our map.
 		//
-		match &*self.handler.lock()
+		let handler = self.handler( &sid, &ctx )?;
+		let track   = self.drain.enter( &ctx )?;
+
+		match &*handler.lock()
 		{
 			ServiceHandler::Address( a ) =>
 			{
@@ -61,7 +422,7 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 					}
 				};
 
-				Ok( task.boxed() )
+				Ok( tracked( track, task ) )
 			}
 
 
@@ -78,7 +439,61 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 					}
 				};
 
-				Ok( task.boxed() )
+				Ok( tracked( track, task ) )
+			}
+
+
+			ServiceHandler::Route( r ) =>
+			{
+				let mut a = r(&msg);
+
+				let task = async move
+				{
+					match a.send( msg ).await
+					{
+						Ok (_) => Ok ( Response::Nothing           ) ,
+						Err(_) => Err( PeerErr::HandlerDead{ ctx } ) ,
+					}
+				};
+
+				Ok( tracked( track, task ) )
+			}
+
+
+			ServiceHandler::Sticky( s ) =>
+			{
+				let mut a = s( &sid, ctx.peer_id );
+
+				let task = async move
+				{
+					match a.send( msg ).await
+					{
+						Ok (_) => Ok ( Response::Nothing           ) ,
+						Err(_) => Err( PeerErr::HandlerDead{ ctx } ) ,
+					}
+				};
+
+				Ok( tracked( track, task ) )
+			}
+
+
+			ServiceHandler::Pool( p ) =>
+			{
+				let (a, in_flight) = p.pick().ok_or_else( || all_draining( &ctx ) )?;
+				let mut a          = a.clone_box();
+
+				let task = async move
+				{
+					let _in_flight = in_flight;
+
+					match a.send( msg ).await
+					{
+						Ok (_) => Ok ( Response::Nothing           ) ,
+						Err(_) => Err( PeerErr::HandlerDead{ ctx } ) ,
+					}
+				};
+
+				Ok( tracked( track, task ) )
 			}
 		}
 	}
@@ -88,32 +503,55 @@ impl<Wf: WireFormat> ServiceMap<Wf> for RelayMap<Wf>
 	/// This should take care of deserialization. The return address is the address of the peer
 	/// to which the serialized answer shall be send.
 	//
-	fn call_service( &self, frame: Wf, ctx: PeerErrCtx )
+	fn call_service( &self, frame: Wf, ctx: PeerErrCtx, _cancel: CancelToken )
 
 		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
 	{
 		trace!( "RelayMap: Incoming Call for relayed actor." );
 
-		let sid = frame.sid();
+		let sid     = frame.sid();
+		let handler = self.handler( &sid, &ctx )?;
+		let track   = self.drain.enter( &ctx )?;
 
-		match &*self.handler.lock()
+		match &*handler.lock()
 		{
-			ServiceHandler::Address( a ) => Ok( make_call( a.clone_box(), frame, ctx ).boxed() ),
-			ServiceHandler::Closure( c ) => Ok( make_call( c(&sid)      , frame, ctx ).boxed() ),
+			ServiceHandler::Address( a ) => Ok( tracked( track, make_call( a.clone_box(), frame, ctx ) ) ),
+			ServiceHandler::Closure( c ) => Ok( tracked( track, make_call( c(&sid)      , frame, ctx ) ) ),
+			ServiceHandler::Route  ( r ) => Ok( tracked( track, make_call( r(&frame)    , frame, ctx ) ) ),
+
+			ServiceHandler::Sticky( s ) =>
+			{
+				let a = s( &sid, ctx.peer_id );
+
+				Ok( tracked( track, make_call( a, frame, ctx ) ) )
+			}
+
+			ServiceHandler::Pool( p ) =>
+			{
+				let (a, in_flight) = p.pick().ok_or_else( || all_draining( &ctx ) )?;
+				let call           = make_call( a.clone_box(), frame, ctx );
+
+				Ok( tracked( track, async move
+				{
+					let _in_flight = in_flight;
+
+					call.await
+				}))
+			}
 		}
 	}
 
 
 	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
 	{
-		Box::new( self.services.iter() )
+		Box::new( self.groups.iter().flat_map( |(_, services)| services ) )
 	}
 }
 
 
 #[ allow(clippy::needless_return) ]
 //
-async fn make_call<T, Wf: WireFormat + Send + 'static>( mut relay: Box<T>, frame: Wf, ctx: PeerErrCtx )
+async fn make_call<T, Wf: WireFormat + Send + 'static>( mut relay: Box<T>, mut frame: Wf, ctx: PeerErrCtx )
 
 	-> Result<Response<Wf>, PeerErr >
 
@@ -121,12 +559,23 @@ async fn make_call<T, Wf: WireFormat + Send + 'static>( mut relay: Box<T>, frame
 
 {
 	let cid        = frame.cid();
+	let sid        = frame.sid();
 	let ctx        = ctx.context( "Process incoming Call to relay".to_string() );
 	let peer_id    = ctx.peer_id;
 	let relay_id   = relay.id();
 	let relay_name = relay.name();
 	let relay_gone = PeerErr::RelayGone{ ctx, relay_id, relay_name };
-	let new_call   = Call::new( frame );
+
+	// If the caller asked for a route trace, add ourselves to it.
+	//
+	if let ( Some( mut route ), Some( id ) ) = ( frame.route(), peer_id )
+	{
+		route.push( id );
+		frame.set_route( Some( &route ) );
+	}
+
+	let route    = frame.route();
+	let new_call = Call::new( frame );
 
 	// Peer for relay still online.
 	// FIXME: use map_err when rustc supports it... currently relay_gone would have to be cloned.
@@ -147,6 +596,15 @@ async fn make_call<T, Wf: WireFormat + Send + 'static>( mut relay: Box<T>, frame
 	{
 		Ok(x) => x,
 
+		// The deadline of the call passed while we were processing it.
+		//
+		Err( PeerErr::Timeout{..} ) =>
+		{
+			let wire_format = Peer::prep_error( cid, &ConnectionError::Timeout{ sid } );
+
+			return Ok( Response::WireFormat(wire_format) );
+		}
+
 		// Sending out call to relayed failed. This normally only happens if the connection
 		// was closed, or a network transport malfunctioned.
 		//
@@ -181,6 +639,14 @@ async fn make_call<T, Wf: WireFormat + Send + 'static>( mut relay: Box<T>, frame
 			//
 			resp.set_cid( cid );
 
+			// The provider doesn't know about the route, so the last relay copies it to the response.
+			// Relays further back find it there already.
+			//
+			if resp.route().is_none()
+			{
+				resp.set_route( route.as_deref() );
+			}
+
 			Ok( Response::CallResponse(CallResponse::new(resp)) )
 		},
 
@@ -212,13 +678,20 @@ impl<Wf> fmt::Debug for RelayMap<Wf>
 {
 	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
 	{
-		write!( f, "RelayMap, handler: {}, services:\n{{\n", &*self.handler.lock() )?;
+		write!( f, "RelayMap" )?;
 
-		for sid in &self.services
+		for (handler, services) in &self.groups
 		{
-			writeln!( f, "\tsid: 0x{:02x}", sid )?;
+			write!( f, ", handler: {}, services:\n{{\n", &*handler.lock() )?;
+
+			for sid in services
+			{
+				writeln!( f, "\tsid: 0x{:02x}", sid )?;
+			}
+
+			write!( f, "}}" )?;
 		}
 
-		write!( f, "}}" )
+		Ok(())
 	}
 }
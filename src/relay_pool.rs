@@ -0,0 +1,279 @@
+use crate :: { import::*, * };
+
+
+/// How a [`RelayPool`] picks the connection for the next message.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum PoolPolicy
+{
+	/// Use the connection with the fewest messages in flight. When several connections are equally
+	/// busy, the first one is used.
+	//
+	LeastBusy,
+
+	/// Use each connection in turn.
+	//
+	RoundRobin,
+}
+
+
+
+/// A pool of connections to the same backend, to be used with [`ServiceHandler::Pool`]. When a single
+/// connection becomes a bottleneck for relaying, open several and let the pool spread the messages
+/// over them.
+///
+/// A message counts as in flight from the moment it is handed to a connection until it has been sent
+/// out (for sends) or until the response came back (for calls).
+///
+/// Connections can be taken out of the pool for maintenance with [`RelayMap::drain_backend`] and
+/// [`RelayMap::remove_backend`].
+//
+pub struct RelayPool<Wf = ThesWF>
+{
+	conns : Vec< PoolConn<Wf> > ,
+	policy: PoolPolicy          ,
+	next  : AtomicUsize         ,
+}
+
+
+struct PoolConn<Wf>
+{
+	addr     : Box<dyn Relay<Wf>> ,
+	in_flight: Arc<AtomicUsize>   ,
+
+	// No new messages are handed to this connection.
+	//
+	draining : bool               ,
+}
+
+
+
+impl<Wf> RelayPool<Wf>
+{
+	/// Create a pool from connections to the same backend.
+	///
+	/// # Panics
+	///
+	/// When `conns` is empty.
+	//
+	pub fn new( conns: Vec< Box<dyn Relay<Wf>> >, policy: PoolPolicy ) -> Self
+	{
+		assert!( !conns.is_empty(), "RelayPool needs at least one connection" );
+
+		let conns = conns.into_iter().map( |addr| PoolConn{ addr, in_flight: Arc::new( AtomicUsize::new(0) ), draining: false } ).collect();
+
+		Self { conns, policy, next: AtomicUsize::new(0) }
+	}
+
+
+	/// The number of connections in the pool.
+	//
+	pub fn len( &self ) -> usize
+	{
+		self.conns.len()
+	}
+
+
+	/// Whether all connections have been removed with [`RelayMap::remove_backend`].
+	//
+	pub fn is_empty( &self ) -> bool
+	{
+		self.conns.is_empty()
+	}
+
+
+	/// The number of messages in flight on each connection, in the order they were passed to
+	/// [`RelayPool::new`]. Removed connections are left out.
+	//
+	pub fn in_flight( &self ) -> Vec<usize>
+	{
+		self.conns.iter().map( |c| c.in_flight.load( SeqCst ) ).collect()
+	}
+
+
+	/// The connections in the pool.
+	//
+	pub(crate) fn relays( &self ) -> impl Iterator< Item = &dyn Relay<Wf> > + '_
+	{
+		self.conns.iter().map( |c| &*c.addr )
+	}
+
+
+	/// Pick a connection according to the policy, skipping the ones that are draining. The message
+	/// counts as in flight until the returned guard is dropped. None when all connections are draining.
+	//
+	pub(crate) fn pick( &self ) -> Option<( &dyn Relay<Wf>, InFlight )>
+	{
+		let idx = match self.policy
+		{
+			PoolPolicy::LeastBusy => self.conns.iter().enumerate()
+
+				.filter( |(_, c)| !c.draining )
+				.min_by_key( |(_, c)| c.in_flight.load( SeqCst ) )
+				.map( |(i, _)| i )?,
+
+			PoolPolicy::RoundRobin =>
+			{
+				let start = self.next.fetch_add( 1, Relaxed );
+
+				(0..self.conns.len())
+
+					.map( |i| start.wrapping_add( i ) % self.conns.len() )
+					.find( |i| !self.conns[ *i ].draining )?
+			}
+		};
+
+		let conn = &self.conns[ idx ];
+
+		conn.in_flight.fetch_add( 1, SeqCst );
+
+		Some(( &*conn.addr, InFlight( conn.in_flight.clone() ) ))
+	}
+
+
+	/// Stop handing new messages to the connections with actor id `id`. Returns whether there are any.
+	//
+	pub(crate) fn drain( &mut self, id: usize ) -> bool
+	{
+		let mut found = false;
+
+		for conn in self.conns.iter_mut().filter( |c| c.addr.id() == id )
+		{
+			conn.draining = true;
+			found         = true;
+		}
+
+		found
+	}
+
+
+	/// Check whether the connections with actor id `id` can be removed.
+	//
+	pub(crate) fn check_remove( &self, id: usize ) -> Result<bool, RemoveBackendErr>
+	{
+		let mut found = false;
+
+		for conn in self.conns.iter().filter( |c| c.addr.id() == id )
+		{
+			found = true;
+
+			if !conn.draining
+			{
+				return Err( RemoveBackendErr::NotDraining{ id } );
+			}
+
+			let in_flight = conn.in_flight.load( SeqCst );
+
+			if in_flight > 0
+			{
+				return Err( RemoveBackendErr::Busy{ id, in_flight } );
+			}
+		}
+
+		Ok( found )
+	}
+
+
+	/// Remove the connections with actor id `id`.
+	//
+	pub(crate) fn remove( &mut self, id: usize )
+	{
+		self.conns.retain( |c| c.addr.id() != id );
+	}
+}
+
+
+
+/// Why [`RelayMap::remove_backend`] refused to remove a backend.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub enum RemoveBackendErr
+{
+	/// No [`RelayPool`] in the map has a connection with this id.
+	//
+	Unknown
+	{
+		/// The id of the backend.
+		//
+		id: usize,
+	},
+
+	/// The backend still gets new messages, call [`RelayMap::drain_backend`] first.
+	//
+	NotDraining
+	{
+		/// The id of the backend.
+		//
+		id: usize,
+	},
+
+	/// Messages relayed to the backend are still in flight.
+	//
+	Busy
+	{
+		/// The id of the backend.
+		//
+		id: usize,
+
+		/// The number of messages in flight.
+		//
+		in_flight: usize,
+	},
+}
+
+
+impl fmt::Display for RemoveBackendErr
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		match self
+		{
+			Self::Unknown    { id            } => write!( f, "No relay pool has a connection to backend: {}", id ),
+			Self::NotDraining{ id            } => write!( f, "Backend {} is not draining", id ),
+			Self::Busy       { id, in_flight } => write!( f, "Backend {} still has {} messages in flight", id, in_flight ),
+		}
+	}
+}
+
+
+impl std::error::Error for RemoveBackendErr {}
+
+
+
+/// Counts a message as in flight on a pooled connection for as long as it lives.
+//
+#[ derive( Debug ) ]
+//
+pub(crate) struct InFlight( Arc<AtomicUsize> );
+
+
+impl Drop for InFlight
+{
+	fn drop( &mut self )
+	{
+		self.0.fetch_sub( 1, SeqCst );
+	}
+}
+
+
+
+impl<Wf> fmt::Debug for RelayPool<Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "RelayPool: policy: {:?}, connections: [", self.policy )?;
+
+		for (i, c) in self.conns.iter().enumerate()
+		{
+			if i > 0 { write!( f, ", " )?; }
+
+			write!( f, "{}", c.addr.id() )?;
+
+			if c.draining { write!( f, " (draining)" )?; }
+		}
+
+		write!( f, "]" )
+	}
+}
@@ -0,0 +1,207 @@
+use
+{
+	crate :: { import::*, *, peer::Response } ,
+	std   :: { hash::Hash, time::Instant    } ,
+};
+
+
+/// A [ServiceMap] in front of another one that answers calls from a cache of earlier responses, so the
+/// handler doesn't run again. Only use this for idempotent services.
+///
+/// The key of a call is computed by a function over the incoming frame. Return `None` for calls that
+/// shouldn't be cached. The key could be the sid and the payload, for a content based cache. Only
+/// successful responses are stored. Sends and streams are passed on to the inner service map as is.
+///
+/// Entries expire after [`ResponseCache::ttl`]. When [`ResponseCache::max_entries`] is reached, the
+/// oldest entry makes room for the new one. A response from the cache carries the trace id and the
+/// route of the call it answers, see [`WireFormat::trace_id`] and [`WireFormat::set_route`].
+//
+pub struct ResponseCache<K, Wf: 'static = ThesWF>
+{
+	inner      : Arc< dyn ServiceMap<Wf> >                       ,
+	key        : Arc< dyn Fn( &Wf ) -> Option<K> + Send + Sync > ,
+	entries    : Arc< Mutex< HashMap<K, Entry<Wf>> > >           ,
+	ttl        : Duration                                        ,
+	max_entries: usize                                           ,
+}
+
+
+struct Entry<Wf>
+{
+	response: Wf      ,
+	stored  : Instant ,
+}
+
+
+
+impl<K, Wf> ResponseCache<K, Wf>
+
+	where K : Hash + Eq + Clone + Send + 'static ,
+	      Wf: WireFormat                          ,
+{
+	/// Cache the responses of `inner`, keyed by `key`. By default entries live for a minute and there
+	/// are at most 1024 of them.
+	//
+	pub fn new( inner: Arc< dyn ServiceMap<Wf> >, key: impl Fn( &Wf ) -> Option<K> + Send + Sync + 'static ) -> Self
+	{
+		Self
+		{
+			inner                                                 ,
+			key        : Arc::new( key )                          ,
+			entries    : Arc::new( Mutex::new( HashMap::new() ) ) ,
+			ttl        : Duration::from_secs( 60 )                ,
+			max_entries: 1024                                     ,
+		}
+	}
+
+
+	/// How long a response is served from the cache.
+	//
+	pub fn ttl( mut self, ttl: Duration ) -> Self
+	{
+		self.ttl = ttl;
+		self
+	}
+
+
+	/// The maximum number of responses kept.
+	//
+	pub fn max_entries( mut self, max_entries: NonZeroUsize ) -> Self
+	{
+		self.max_entries = max_entries.get();
+		self
+	}
+
+
+	// A copy of the cached response, if it hasn't expired.
+	//
+	fn lookup( &self, key: &K ) -> Option<Wf>
+	{
+		let mut entries = self.entries.lock();
+
+		match entries.get( key )
+		{
+			Some( entry ) if entry.stored.elapsed() < self.ttl => Some( entry.response.clone() ),
+
+			Some( _ ) =>
+			{
+				entries.remove( key );
+				None
+			}
+
+			None => None,
+		}
+	}
+
+
+	// Store a response, making room if needed.
+	//
+	fn store( entries: &Mutex< HashMap<K, Entry<Wf>> >, ttl: Duration, max_entries: usize, key: K, response: Wf )
+	{
+		let mut entries = entries.lock();
+
+		entries.retain( |_, entry| entry.stored.elapsed() < ttl );
+
+		if entries.len() >= max_entries
+		{
+			let oldest = entries.iter().min_by_key( |(_, entry)| entry.stored ).map( |(k, _)| k.clone() );
+
+			if let Some( oldest ) = oldest
+			{
+				entries.remove( &oldest );
+			}
+		}
+
+		entries.insert( key, Entry{ response, stored: Instant::now() } );
+	}
+}
+
+
+
+impl<K, Wf> ServiceMap<Wf> for ResponseCache<K, Wf>
+
+	where K : Hash + Eq + Clone + Send + 'static ,
+	      Wf: WireFormat                          ,
+{
+	fn send_service( &self, msg: Wf, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.inner.send_service( msg, ctx )
+	}
+
+
+	fn call_service( &self, msg: Wf, ctx: PeerErrCtx, cancel: CancelToken )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		let key = match (self.key)( &msg )
+		{
+			Some( key ) => key,
+			None        => return self.inner.call_service( msg, ctx, cancel ),
+		};
+
+		let cid = msg.cid();
+
+		if let Some( mut response ) = self.lookup( &key )
+		{
+			trace!( "ResponseCache: serving call from cache, sid: {}, cid: {}", msg.sid(), cid );
+
+			// The trailers belong to the call that got the response first. The trace id is the one of
+			// this caller and the route ends here, since the call doesn't go any further.
+			//
+			response.set_cid     ( cid                    );
+			response.set_trace_id( msg.trace_id()         );
+			response.set_route   ( msg.route().as_deref() );
+
+			return Ok( async move { Ok( Response::CallResponse( CallResponse::new( response ) ) ) }.boxed() );
+		}
+
+		let fut         = self.inner.call_service( msg, ctx, cancel )?;
+		let entries     = self.entries.clone();
+		let ttl         = self.ttl;
+		let max_entries = self.max_entries;
+
+		Ok( async move
+		{
+			let response = fut.await?;
+
+			// Error responses have a null sid, don't cache those.
+			//
+			if let Response::CallResponse( resp ) = &response
+			{
+				if resp.frame().sid().is_full()
+				{
+					Self::store( &entries, ttl, max_entries, key, resp.frame().clone() );
+				}
+			}
+
+			Ok( response )
+
+		}.boxed() )
+	}
+
+
+	fn open_stream( &self, msg: Wf, channel: StreamChannel<Wf>, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.inner.open_stream( msg, channel, ctx )
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		self.inner.services()
+	}
+}
+
+
+
+impl<K, Wf> fmt::Debug for ResponseCache<K, Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "ResponseCache, entries: {}, inner: {:?}", self.entries.lock().len(), self.inner )
+	}
+}
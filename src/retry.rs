@@ -0,0 +1,185 @@
+use crate :: { import::*, * };
+
+
+/// An [Address] that retries calls which fail with a transient error, waiting longer before each
+/// new attempt. Only use this for idempotent services, since the remote might have processed a call
+/// for which we didn't get the response.
+///
+/// By default [`PeerErr::ConnectionClosed`] and [`PeerErr::Timeout`] are retried, see [`RetryAddr::retry_if`]
+/// to change that. The wait starts at the backoff passed to [`RetryAddr::new`] and doubles after each
+/// attempt, up to [`RetryAddr::max_backoff`].
+///
+/// `Sink::send` is forwarded as is, there is no feedback to decide whether to retry a send.
+//
+pub struct RetryAddr<S: Message>
+{
+	addr        : BoxAddress<S, PeerErr>                          ,
+	max_attempts: usize                                           ,
+	backoff     : Duration                                        ,
+	max_backoff : Duration                                        ,
+	retryable   : Arc< dyn Fn( &PeerErr ) -> bool + Send + Sync > ,
+}
+
+
+
+impl<S: Message> RetryAddr<S>
+{
+	/// Make at most `max_attempts` attempts for each call, waiting `backoff` before the first retry.
+	//
+	pub fn new( addr: BoxAddress<S, PeerErr>, max_attempts: NonZeroUsize, backoff: Duration ) -> Self
+	{
+		Self
+		{
+			addr                                    ,
+			backoff                                 ,
+			max_attempts: max_attempts.get()        ,
+			max_backoff : Duration::from_secs( 30 ) ,
+			retryable   : Arc::new( is_transient )  ,
+		}
+	}
+
+
+	/// The longest wait between two attempts. Defaults to 30 seconds.
+	//
+	pub fn max_backoff( mut self, max_backoff: Duration ) -> Self
+	{
+		self.max_backoff = max_backoff;
+		self
+	}
+
+
+	/// Decide which errors are worth retrying.
+	//
+	pub fn retry_if( mut self, retryable: impl Fn( &PeerErr ) -> bool + Send + Sync + 'static ) -> Self
+	{
+		self.retryable = Arc::new( retryable );
+		self
+	}
+}
+
+
+// The errors retried by default.
+//
+fn is_transient( err: &PeerErr ) -> bool
+{
+	matches!( err, PeerErr::ConnectionClosed{..} | PeerErr::Timeout{..} )
+}
+
+
+
+impl<S> Address<S> for RetryAddr<S>
+
+	where  S                    : Message + Clone + Send,
+	      <S as Message>::Return: Send,
+
+{
+	fn call( &mut self, msg: S ) -> Return<'_, Result< <S as Message>::Return, PeerErr >>
+	{
+		async move
+		{
+			let mut backoff = self.backoff;
+			let mut attempt = 1;
+
+			loop
+			{
+				match self.addr.call( msg.clone() ).await
+				{
+					Err( e ) if attempt < self.max_attempts && (self.retryable)( &e ) =>
+					{
+						debug!( "RetryAddr: attempt {} failed, retrying in {:?}: {}", attempt, backoff, e );
+
+						Delay::new( backoff ).await;
+
+						backoff  = backoff.checked_mul( 2 ).unwrap_or( self.max_backoff ).min( self.max_backoff );
+						attempt += 1;
+					}
+
+					res => return res,
+				}
+			}
+
+		}.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<S, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+
+impl<S> Sink<S> for RetryAddr<S>
+
+	where S: Message,
+
+{
+	type Error = PeerErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.addr.poll_ready_unpin( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: S ) -> Result<(), Self::Error>
+	{
+		self.addr.start_send_unpin( msg )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.addr.poll_flush_unpin( cx )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.addr.poll_close_unpin( cx )
+	}
+}
+
+
+
+impl<S: Message> Identify for RetryAddr<S>
+{
+	fn id( &self ) -> usize
+	{
+		self.addr.id()
+	}
+
+	fn name( &self ) -> Option<Arc<str>>
+	{
+		self.addr.name()
+	}
+}
+
+
+
+impl<S: Message> Clone for RetryAddr<S>
+{
+	fn clone( &self ) -> Self
+	{
+		Self
+		{
+			addr        : self.addr.clone_box()  ,
+			max_attempts: self.max_attempts      ,
+			backoff     : self.backoff           ,
+			max_backoff : self.max_backoff       ,
+			retryable   : self.retryable.clone() ,
+		}
+	}
+}
+
+
+
+impl<S: Message> fmt::Debug for RetryAddr<S>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "RetryAddr: {}, max attempts: {}", self.addr.id(), self.max_attempts )
+	}
+}
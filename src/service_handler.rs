@@ -19,6 +19,16 @@ impl<T, Wf> Relay<Wf> for T
 
 pub type RelayClosure<Wf = ThesWF> = Box< dyn Fn( &ServiceID ) -> Box<dyn Relay<Wf>> + Send>;
 
+/// A closure that picks the relay for each message based on the whole frame, so it can look at the payload.
+//
+pub type RouteClosure<Wf = ThesWF> = Box< dyn Fn( &Wf ) -> Box<dyn Relay<Wf>> + Send>;
+
+/// A closure that picks the relay based on the service and the id of the peer the request came in on,
+/// so it can send all requests of a connection to the same backend. The id is `None` when the request
+/// doesn't come from a [Peer].
+//
+pub type StickyClosure<Wf = ThesWF> = Box< dyn Fn( &ServiceID, Option<usize> ) -> Box<dyn Relay<Wf>> + Send>;
+
 
 /// A wrapper type to be able to pass both an BoxAddress or a closure to RelayMap.
 ///
@@ -44,6 +54,22 @@ pub enum ServiceHandler<Wf = ThesWF>
 	/// A closure that yields an Address.
 	//
 	Closure( RelayClosure<Wf> ),
+
+	/// A closure that yields an Address based on the incoming frame. Use this when routing depends on the
+	/// content of the message, eg. a tenant id in the first bytes of the payload (see [`WireFormat::msg`]).
+	///
+	/// There is no `From` impl for this variant, since it would overlap with the one for [`RelayClosure`].
+	//
+	Route( RouteClosure<Wf> ),
+
+	/// A closure that yields an Address based on the service and the connection the request came in on.
+	/// Use this for session affinity, eg. by hashing the peer id to pick a backend.
+	//
+	Sticky( StickyClosure<Wf> ),
+
+	/// A pool of connections to the same backend. See [`RelayPool`].
+	//
+	Pool( RelayPool<Wf> ),
 }
 
 
@@ -58,6 +84,16 @@ impl<Wf> From< Box<dyn Relay<Wf>> > for ServiceHandler<Wf>
 
 
 
+impl<Wf> From< RelayPool<Wf> > for ServiceHandler<Wf>
+{
+	fn from( pool: RelayPool<Wf> ) -> Self
+	{
+		ServiceHandler::Pool( pool )
+	}
+}
+
+
+
 impl<Wf> From< RelayClosure<Wf> > for ServiceHandler<Wf>
 {
 	fn from( cl: RelayClosure<Wf> ) -> Self
@@ -68,6 +104,16 @@ impl<Wf> From< RelayClosure<Wf> > for ServiceHandler<Wf>
 
 
 
+impl<Wf> From< StickyClosure<Wf> > for ServiceHandler<Wf>
+{
+	fn from( cl: StickyClosure<Wf> ) -> Self
+	{
+		ServiceHandler::Sticky( cl )
+	}
+}
+
+
+
 // Would have been nice to have file and line number for the closure here, but it's rather hard to do.
 //
 impl<Wf> fmt::Debug for ServiceHandler<Wf>
@@ -79,6 +125,9 @@ impl<Wf> fmt::Debug for ServiceHandler<Wf>
 		match self
 		{
 			Self::Closure(_) => { write!( f, "Closure" )?; }
+			Self::Route  (_) => { write!( f, "Route"   )?; }
+			Self::Sticky (_) => { write!( f, "Sticky"  )?; }
+			Self::Pool   (p) => { write!( f, "{:?}", p )?; }
 
 			Self::Address(a) =>
 			{
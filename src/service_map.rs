@@ -1,4 +1,8 @@
-use crate::{ *, import::*, peer::Response } ;
+use
+{
+	crate :: { *, import::*, peer::Response } ,
+	serde :: { de::DeserializeOwned         } ,
+};
 
 
 /// This interface is what the Peer type uses to deliver messages. An implementation is provided
@@ -22,16 +26,434 @@ pub trait ServiceMap<Wf = ThesWF>: fmt::Debug + Send + Sync
 	/// Call a Service.
 	/// This should take care of deserialization. The return address is the address of the peer
 	/// to which the serialized answer shall be send.
+	///
+	/// `cancel` is cancelled by the peer when the connection closes. Pass it on to handlers that
+	/// want to stop working when their response can no longer be delivered.
 	//
-	fn call_service( &self, msg: Wf, ctx: PeerErrCtx )
+	fn call_service( &self, msg: Wf, ctx: PeerErrCtx, cancel: CancelToken )
 
 		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
 	;
 
 
+	/// Open a bidirectional stream, see [`OpenStream`]. `msg` is the message that opens the stream.
+	/// The returned future should hand `channel` to the handler.
+	///
+	/// The default implementation doesn't support streams and returns [`PeerErr::NoHandler`].
+	//
+	fn open_stream( &self, msg: Wf, channel: StreamChannel<Wf>, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<Wf>, PeerErr> > + Send >>, PeerErr >
+
+		where Wf: WireFormat,
+	{
+		let _ = ( msg, channel );
+
+		Err( PeerErr::NoHandler{ ctx } )
+	}
+
+
 	/// Get a list of all services provided by this service map.
 	//
 	// TODO: Find a way to avoid the heap allocation.
 	//
 	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >;
 }
+
+
+
+/// How the messages of a service are encoded on the wire, see the `services` parameter of
+/// [service_map!](crate::service_map). Both sides of a connection need to use the same codec for a service.
+//
+pub struct ServiceCodec<S>
+{
+	/// Write the message.
+	//
+	pub encode: fn( &S, &mut dyn io::Write ) -> Result<(), ErrorSource>,
+
+	/// Read a message written by `encode`.
+	//
+	pub decode: fn( &[u8] ) -> Result<S, ErrorSource>,
+}
+
+
+impl<S: Serialize + DeserializeOwned> ServiceCodec<S>
+{
+	/// The default encoding, with serde_cbor.
+	//
+	pub fn cbor() -> Self
+	{
+		Self
+		{
+			encode: |msg, out| serde_cbor::to_writer( out, msg ).map_err( Into::into ) ,
+			decode: |bytes   | serde_cbor::from_slice( bytes  ).map_err( Into::into ) ,
+		}
+	}
+}
+
+
+impl<S> Clone for ServiceCodec<S>
+{
+	fn clone( &self ) -> Self
+	{
+		Self { encode: self.encode, decode: self.decode }
+	}
+}
+
+impl<S> Copy for ServiceCodec<S> {}
+
+
+impl<S> fmt::Debug for ServiceCodec<S>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "ServiceCodec<{}>", std::any::type_name::<S>() )
+	}
+}
+
+
+
+/// Returned by `Services::try_register_handler` when the service already has a handler. The handler
+/// that was registered first is kept.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub struct AlreadyRegistered
+{
+	/// The service that already has a handler.
+	//
+	pub sid: ServiceID,
+}
+
+
+impl fmt::Display for AlreadyRegistered
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "A handler is already registered for service: {}", self.sid )
+	}
+}
+
+
+impl std::error::Error for AlreadyRegistered {}
+
+
+
+/// Information about a handler registered with a service map, see `Services::handler_info`.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct HandlerInfo
+{
+	/// The service this handler handles.
+	//
+	pub sid: ServiceID,
+
+	/// The id of the handling actor, from [`Identify::id`].
+	//
+	pub actor_id: usize,
+
+	/// The name of the handling actor, from [`Identify::name`].
+	//
+	pub name: Option<Arc<str>>,
+}
+
+
+
+/// The services a service map exposes, see `Services::snapshot`. It can be serialized, so a supervisor
+/// can check after a restart that the new process exposes the same services again. The actor ids and names
+/// are informational, only the sids are compared by [`ServicesSnapshot::same_services`].
+//
+#[ derive( Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub struct ServicesSnapshot
+{
+	/// The handlers that were registered, in the order of `Services::handler_info`.
+	//
+	pub handlers: Vec<HandlerSnapshot>,
+}
+
+
+/// A handler in a [`ServicesSnapshot`].
+//
+#[ derive( Debug, Clone, PartialEq, Eq, Serialize, Deserialize ) ]
+//
+pub struct HandlerSnapshot
+{
+	/// The service this handler handled.
+	//
+	pub sid: ServiceID,
+
+	/// The id of the handling actor. It's only meaningful within the process that took the snapshot.
+	//
+	pub actor_id: usize,
+
+	/// The name of the handling actor.
+	//
+	pub name: Option<String>,
+}
+
+
+impl ServicesSnapshot
+{
+	/// The services in the snapshot.
+	//
+	pub fn sids( &self ) -> impl Iterator<Item = ServiceID> + '_
+	{
+		self.handlers.iter().map( |h| h.sid )
+	}
+
+
+	/// Whether both snapshots contain the same services, whatever the order or the handling actors.
+	//
+	pub fn same_services( &self, other: &ServicesSnapshot ) -> bool
+	{
+		self.sids().collect::<HashSet<_>>() == other.sids().collect::<HashSet<_>>()
+	}
+}
+
+
+impl From< &HandlerInfo > for HandlerSnapshot
+{
+	fn from( info: &HandlerInfo ) -> Self
+	{
+		Self
+		{
+			sid     : info.sid                                    ,
+			actor_id: info.actor_id                               ,
+			name    : info.name.as_ref().map( |n| n.to_string() ) ,
+		}
+	}
+}
+
+
+
+/// Returned by `Services::restore` when the service map to take the handlers from has no handler for
+/// some of the services in the snapshot. Nothing is restored in that case.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct MissingServices
+{
+	/// The services that have no handler.
+	//
+	pub sids: Vec<ServiceID>,
+}
+
+
+impl fmt::Display for MissingServices
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "No handler to restore services:" )?;
+
+		for sid in &self.sids
+		{
+			write!( f, " {}", sid )?;
+		}
+
+		Ok(())
+	}
+}
+
+
+impl std::error::Error for MissingServices {}
+
+
+
+/// A message delivered together with the trace id of its frame to handlers registered with
+/// `Services::register_traced`, see [`WireFormat::trace_id`]. The response is the response of the
+/// wrapped message.
+//
+#[ derive( Debug ) ]
+//
+pub struct Traced<S>
+{
+	/// The message sent by the remote.
+	//
+	pub msg: S,
+
+	/// The trace id the remote set on the frame, if any.
+	//
+	pub trace_id: Option<u64>,
+}
+
+
+impl<S: Message> Message for Traced<S>
+{
+	type Return = <S as Message>::Return;
+}
+
+
+
+/// A message delivered together with the headers of its frame to handlers registered with
+/// `Services::register_with_meta`, see [`WireFormat::meta`]. The response is the response of the
+/// wrapped message.
+//
+#[ derive( Debug ) ]
+//
+pub struct WithMeta<S>
+{
+	/// The message sent by the remote.
+	//
+	pub msg: S,
+
+	/// The headers the remote set on the frame, empty if there are none.
+	//
+	pub meta: Metadata,
+}
+
+
+impl<S: Message> Message for WithMeta<S>
+{
+	type Return = <S as Message>::Return;
+}
+
+
+
+/// The handler of a service in the service map generated by `service_map!`. Not meant to be used directly.
+//
+#[ doc( hidden ) ]
+//
+pub enum LocalHandler<S: Message, Wf: 'static + WireFormat = ThesWF>
+{
+	/// Registered with `register_handler`.
+	//
+	Plain( BoxAddress<S, ThesErr> ),
+
+	/// Registered with `register_cancellable`, receives a [`CancelToken`] with every message.
+	//
+	Cancellable( BoxAddress<Cancellable<S>, ThesErr> ),
+
+	/// Registered with `register_traced`, receives the trace id of the frame with every message.
+	//
+	Traced( BoxAddress<Traced<S>, ThesErr> ),
+
+	/// Registered with `register_with_meta`, receives the headers of the frame with every message.
+	//
+	WithMeta( BoxAddress<WithMeta<S>, ThesErr> ),
+
+	/// Registered with `register_stream`, only handles requests to open a stream.
+	//
+	Stream( BoxAddress<Streaming<S, Wf>, ThesErr> ),
+
+	/// Created by `Services::incoming`, only receives sends. Messages that fail to deserialize are
+	/// passed on as errors.
+	//
+	Channel( mpsc::UnboundedSender< Result<S, PeerErr> > ),
+}
+
+
+impl<S: Message, Wf: WireFormat> LocalHandler<S, Wf>
+{
+	pub fn clone_box( &self ) -> Self
+	{
+		match self
+		{
+			Self::Plain      ( h ) => Self::Plain      ( h.clone_box() ),
+			Self::Cancellable( h ) => Self::Cancellable( h.clone_box() ),
+			Self::Traced     ( h ) => Self::Traced     ( h.clone_box() ),
+			Self::WithMeta   ( h ) => Self::WithMeta   ( h.clone_box() ),
+			Self::Stream     ( h ) => Self::Stream     ( h.clone_box() ),
+			Self::Channel    ( c ) => Self::Channel    ( c.clone()     ),
+		}
+	}
+
+
+	pub fn id( &self ) -> usize
+	{
+		match self
+		{
+			Self::Plain      ( h ) => h.id(),
+			Self::Cancellable( h ) => h.id(),
+			Self::Traced     ( h ) => h.id(),
+			Self::WithMeta   ( h ) => h.id(),
+			Self::Stream     ( h ) => h.id(),
+			Self::Channel    ( _ ) => 0,
+		}
+	}
+
+
+	pub fn name( &self ) -> Option<Arc<str>>
+	{
+		match self
+		{
+			Self::Plain      ( h ) => h.name(),
+			Self::Cancellable( h ) => h.name(),
+			Self::Traced     ( h ) => h.name(),
+			Self::WithMeta   ( h ) => h.name(),
+			Self::Stream     ( h ) => h.name(),
+			Self::Channel    ( _ ) => None,
+		}
+	}
+
+
+	/// Stream handlers can't receive sends and calls, check this before calling `send` or `call`.
+	//
+	pub fn is_stream( &self ) -> bool
+	{
+		matches!( self, Self::Stream(_) )
+	}
+
+
+	/// Channel handlers only receive sends, they are handed the message before it's deserialized.
+	/// Check this before calling `send` or `call`.
+	//
+	pub fn is_channel( &self ) -> bool
+	{
+		matches!( self, Self::Channel(_) )
+	}
+
+
+	/// # Panics
+	///
+	/// For stream and channel handlers.
+	//
+	pub async fn send( &mut self, msg: S, token: CancelToken, trace_id: Option<u64>, meta: Option<Metadata> ) -> Result<(), ThesErr>
+	{
+		match self
+		{
+			Self::Plain      ( h ) => h.send( msg ).await,
+			Self::Cancellable( h ) => h.send( Cancellable{ msg, token } ).await,
+			Self::Traced     ( h ) => h.send( Traced{ msg, trace_id } ).await,
+			Self::WithMeta   ( h ) => h.send( WithMeta{ msg, meta: meta.unwrap_or_default() } ).await,
+			Self::Stream     ( _ ) => unreachable!( "send to stream handler"  ),
+			Self::Channel    ( _ ) => unreachable!( "send to channel handler" ),
+		}
+	}
+
+
+	/// # Panics
+	///
+	/// For stream and channel handlers.
+	//
+	pub async fn call( &mut self, msg: S, token: CancelToken, trace_id: Option<u64>, meta: Option<Metadata> ) -> Result<<S as Message>::Return, ThesErr>
+	{
+		match self
+		{
+			Self::Plain      ( h ) => h.call( msg ).await,
+			Self::Cancellable( h ) => h.call( Cancellable{ msg, token } ).await,
+			Self::Traced     ( h ) => h.call( Traced{ msg, trace_id } ).await,
+			Self::WithMeta   ( h ) => h.call( WithMeta{ msg, meta: meta.unwrap_or_default() } ).await,
+			Self::Stream     ( _ ) => unreachable!( "call to stream handler"  ),
+			Self::Channel    ( _ ) => unreachable!( "call to channel handler" ),
+		}
+	}
+}
+
+
+impl<S: Message, Wf: WireFormat> fmt::Debug for LocalHandler<S, Wf>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		match self
+		{
+			Self::Plain      ( h ) => write!( f, "LocalHandler::Plain: {}"      , h.id() ),
+			Self::Cancellable( h ) => write!( f, "LocalHandler::Cancellable: {}", h.id() ),
+			Self::Traced     ( h ) => write!( f, "LocalHandler::Traced: {}"     , h.id() ),
+			Self::WithMeta   ( h ) => write!( f, "LocalHandler::WithMeta: {}"   , h.id() ),
+			Self::Stream     ( h ) => write!( f, "LocalHandler::Stream: {}"     , h.id() ),
+			Self::Channel    ( _ ) => write!( f, "LocalHandler::Channel"                 ),
+		}
+	}
+}
@@ -18,6 +18,19 @@
 /// parameters to the macro in order to be able to communicate, eg. if you refer to the service types
 /// as some path (eg. `module::Type`), both server and client need to do so.
 ///
+/// ## Evolving message types
+///
+/// Messages and their return types are encoded with serde_cbor as maps keyed by field name, so peers
+/// built from different versions of a type can still talk as long as the changes are additive:
+///
+/// - fields the receiver doesn't know are skipped, unless the type uses `#[serde(deny_unknown_fields)]`.
+/// - fields the sender doesn't know about are missing, which fails unless they are marked
+///   `#[serde(default)]` on the receiving side.
+///
+/// So mark new fields `#[serde(default)]` and peers keep working in both directions. When decoding
+/// does fail, the [PeerErr::Deserialize](crate::PeerErr::Deserialize) names the type being decoded
+/// and its cause names the missing field.
+///
 /// Types created by this macro, for the following invocation:
 ///
 /// ```ignore
@@ -84,9 +97,26 @@ macro_rules! service_map
 	//
 	wire_format: $wf: path;
 
+	/// Optional. When set to `true`, `RemoteAddr` gets an extra method `call_concrete` which returns
+	/// a named future type (`RemoteCall`) instead of a boxed one. This saves boxing the future on
+	/// every call. The call still allocates its response channel and mailbox envelope, the
+	/// `call_futures` bench checks that it allocates less than `Address::call` overall.
+	/// `Address::call` keeps working as usual.
+	//
+	$( concrete_futures: $concrete: tt; )?
+
+	/// Optional. When set to `true`, only the `Service` impls and the remote addresses are generated,
+	/// not the `Services` map to register local handlers. Use this in processes that only call the
+	/// services, to save compile time and binary size. Such processes can't expose the services.
+	//
+	$( client_only: $client_only: tt; )?
+
 	/// Comma separated list of Services you want to include. They must be in scope.
+	///
+	/// A service can be followed by `=> codec` to encode its messages with a [ServiceCodec](crate::ServiceCodec)
+	/// instead of CBOR, eg. `services: Add, Note => crate::notes_codec();`. Both sides need to use the same codec.
 	//
-	services: $($services: path),+ $(,)? $(;)?
+	services: $( $services: path $( => $codec: expr )? ),+ $(,)? $(;)?
 ) =>
 
 {
@@ -108,11 +138,10 @@ use
 
 	$crate::external_deps::
 	{
-		once_cell       :: { sync::Lazy                                          } ,
 		futures         :: { future::FutureExt, task::{ Context, Poll }, SinkExt } ,
+		futures         :: { future::BoxFuture, lock::Mutex as FutMutex          } ,
 		thespis         :: { *                                                   } ,
 		thespis_impl    :: { Addr, ThesErr, ThesRes                              } ,
-		serde_cbor      :: { self, from_slice as des                             } ,
 		serde           :: { Serialize, Deserialize, de::DeserializeOwned        } ,
 		log             :: { error                                               } ,
 		parking_lot     :: { Mutex                                               } ,
@@ -147,6 +176,15 @@ pub trait Service
 	/// programs written in other languages can also communicate with your services.
 	//
 	fn sid() -> ServiceID where Self: Sized;
+
+
+	/// How the messages of this service are encoded on the wire. CBOR unless a codec is given for the
+	/// service in the macro invocation. The return type is always encoded with CBOR.
+	//
+	fn codec() -> ServiceCodec<Self> where Self: Sized
+	{
+		ServiceCodec::cbor()
+	}
 }
 
 
@@ -158,455 +196,284 @@ $(
 	impl Service for $services
 	{
 		/// A service ID that is unique for this type, based on a hash of the namespace and type name.
+		/// It's computed at compile time.
 		//
 		fn sid() -> ServiceID
 		{
-			static INSTANCE : Lazy< ServiceID > = Lazy::new( ||
+			const SID: ServiceID = ServiceID::from_seed( stringify!( $ns::$services ).as_bytes() );
 
-				ServiceID::from_seed( stringify!( $ns::$services ).as_bytes() )
-			);
-
-			*INSTANCE
+			SID
 		}
+
+		$(
+			fn codec() -> ServiceCodec<Self>
+			{
+				$codec
+			}
+		)?
 	}
 
 )+
 
 
-/// The actual service map.
-/// Use it to get a recipient to a remote service.
+// Register the names of our services, so a ServiceID can be displayed with its name.
 //
-pub struct Services
+fn register_service_names()
 {
-	// The addresses to the actors that handle incoming messages.
-	//
-	handlers: HashMap< ServiceID, Mutex<Box<dyn Any + Send>> >,
+	$(
+		paste::expr!
+		{
+			static [< __ONCE__ $services >]: Once = Once::new();
+
+			[< __ONCE__ $services >].call_once( ||
+			{
+				ServiceID::register_service( $services::sid(), concat!( stringify!($ns) , "::", stringify!($services) ) );
+			});
+		}
+	)+
 }
 
 
 
-/// Will print something like:
+$crate::__service_map_services!( [ $( $client_only )? ]; $ns; $wf; $( $services ),+ );
+
+
+
+/// Concrete type for creating recipients for remote Services in this thespis::ServiceMap.
+/// Note that this holds the Peers address, so the peer mailbox will only get dropped when you
+/// drop this, even if the connection get's closed by the remote.
 ///
-/// ```ignore
-/// remotes::Services
-/// {
-///    Add  - sid: 0xbcc09d3812378e171ad366d75f687757 - handler: id(0), name(actor_name)
-///    Show - sid: 0xbcc09d3812378e17e1a1e89b512c025a - handler: id(0), name(actor_name)
-/// }
-/// ```
+/// If you use this, be sure to check the result that is returned which will err if the connection
+/// is closed.
 //
-impl fmt::Debug for Services
+#[ derive( Clone, Debug ) ]
+//
+pub struct RemoteAddr
 {
-	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
-	{
-		let mut width: usize = 0;
+	// FIXME: do not rely on Addr, we should be generic over Address, but not
+	//       choose an implementation. This is a complicated one. While this is in the public
+	//       API, so it would be good, having a trait object that is Address<$wf> + Address<Call>
+	//       and is still cloneable is complicated.
+	//
+	//       It could be done by unifying both message types (eg. an enum), but then what is the return
+	//       type of this message. It would have to be an enum as well, and every caller would have to
+	//       match on it. For now we will keep our dependency on Peer and Addr.
+	//
+	peer: Addr<Peer<$wf>>,
 
-		$(
-			width = std::cmp::max( width, stringify!( $services ).len() );
-		)+
+	// Paces sends when set.
+	//
+	rate: Option<RateLimit>,
 
-		write!( f, "{}::Services\n{{\n", stringify!( $ns ) )?;
+	// The channel set on outgoing requests, see set_channel.
+	//
+	channel: Option<u64>,
+}
 
 
-		$(
-			let sid = <$services as Service>::sid();
+impl RemoteAddr
+{
+	/// Create an RemoteAddr which implements Addr<M> for all the services in this service_map.
+	//
+	pub fn new( peer: Addr<Peer<$wf>> ) -> Self
+	{
+		register_service_names();
 
-			write!
-			(
-				f,
+		Self { peer, rate: None, channel: None }
+	}
 
-				"\t{:width$} - sid: 0x{:02x} - handler: ",
 
-				stringify!( $services ),
-				sid,
+	/// Limit the rate of sends on this RemoteAddr. `poll_ready` will return pending until the
+	/// limit allows the next send. Calls are not affected. Clones of this RemoteAddr share
+	/// the limit.
+	//
+	pub fn set_rate_limit( &mut self, limit: RateLimit )
+	{
+		self.rate = Some( limit );
+	}
 
-				width = width
-			)?;
 
-			if let Some(h) = self.handlers.get( &sid )
-			{
-				let h = h.lock();
+	/// Send the requests from this RemoteAddr on `channel`, so the remote dispatches them to the service
+	/// maps it registered for that channel with [`Peer::register_channel`]. `None`, the default, uses the
+	/// default channel. Clones made afterwards keep the channel. Streams always use the default channel.
+	//
+	pub fn set_channel( &mut self, channel: Option<u64> )
+	{
+		self.channel = channel;
+	}
 
-				// This expect shouldn't ever fail. We manually make the receiver in this file.
-				//
-				let handler: &BoxAddress<$services, ThesErr> = h.downcast_ref().expect( "downcast receiver in Debug for Services" );
 
-				match handler.name()
-				{
-					Some(n) => write!( f, "id({}), name({})", &handler.id(), &n )?,
-					None    => write!( f, "id({})", &handler.id() )?,
-				};
-			}
+	/// Open a bidirectional stream with the service `S` of the remote, see [`OpenStream`]. `msg` is
+	/// handed to the remote handler together with its end of the stream. We send items of type `Out`
+	/// and receive items of type `In`.
+	//
+	pub async fn open_stream<S, Out, In>( &mut self, msg: S ) -> Result< (StreamSink<Out, $wf>, StreamRx<In, $wf>), PeerErr >
 
-			else
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+		       Out                  : Serialize,
+		       In                   : DeserializeOwned,
+	{
+		let wf = self.build_wf( msg, ConnID::null() )?;
+
+		let channel = self.peer.call( OpenStream::new( wf ) ).await
+
+			// The peer panicked.
+			//
+			.map_err( |_|
 			{
-				write!( f, "none" )?;
-			}
+				let ctx = Peer::err_ctx( &self.peer, <S as Service>::sid(), None, "Open stream".to_string() );
 
-			write!( f, "\n" )?;
-		)+
+				PeerErr::PeerGone{ ctx }
 
-		write!( f, "}}" )
+			})??;
+
+		Ok( channel.split() )
 	}
-}
 
 
+	/// Call a remote service without waiting for the response, see [`DetachedCall`]. Unlike `send`,
+	/// the remote processes it as a call, subject to its backpressure. Resolves once the frame is
+	/// sent out.
+	//
+	pub async fn call_detached<S>( &mut self, msg: S ) -> Result< (), PeerErr >
 
-/// This downcasts in order to clone the handlers
-//
-impl Clone for Services
-{
-	fn clone( &self ) -> Self
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
 	{
-		#[ allow(clippy::mutable_key_type) ] // false positive.
-		//
-		let mut handlers: HashMap< ServiceID, Mutex<Box<dyn Any + Send>> > = HashMap::new();
-
-		for (k, v) in &self.handlers
-		{
-			match k
-			{
-				$(
-					_ if *k == <$services as Service>::sid() =>
-					{
-						// This should never fail, we make this type in this file.
-						//
-						let v = v.lock();
-						let h: &BoxAddress<$services, ThesErr> = v.downcast_ref().expect( "downcast receiver in Clone" );
-
-						handlers.insert( *k, Mutex::new( Box::new(h.clone_box()) ) );
-					},
-				)+
+		let wf = self.build_wf( msg, ConnID::null() )?;
 
+		self.peer.call( DetachedCall::new( wf ) ).await
 
-				// every sid in our handlers map should also be a valid service in this service map,
-				// so this should never happen
-				//
-				_ => { unreachable!() },
-			}
+			// The peer panicked.
+			//
+			.map_err( |_|
+			{
+				let ctx = Peer::err_ctx( &self.peer, <S as Service>::sid(), None, "Detached call to remote service".to_string() );
 
-		}
+				PeerErr::PeerGone{ ctx }
 
-		Self { handlers }
+			})?
 	}
-}
-
 
 
-impl Services
-{
-	/// Create a new service map
+	/// Call a remote service and wait for the response, like `call`, but also get the trace id the remote
+	/// put on the response, see [`WireFormat::trace_id`]. Handlers registered with `Services::register_traced`
+	/// get `trace_id` with the message. It's up to you to make it unique enough for your logs.
 	//
-	pub fn new() -> Self
+	pub async fn call_traced<S>( &mut self, msg: S, trace_id: u64 ) -> Result< (<S as Message>::Return, Option<u64>), PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
 	{
-		$(
-			paste::expr!
-			{
-				static [< __ONCE__ $services >]: Once = Once::new();
+		let mut wf = self.build_wf( msg, ConnID::null() )?;
+		wf.set_trace_id( Some( trace_id ) );
 
-				[< __ONCE__ $services >].call_once( ||
-				{
-					ServiceID::register_service( $services::sid(), concat!( stringify!($ns) , "::", stringify!($services) ) );
-				});
-			}
-		)+
+		let re    = self.call_wf::<S>( Call::new( wf ) ).await?;
+		let trace = re.as_ref().ok().and_then( |resp| resp.trace_id() );
 
-		Self{ handlers: HashMap::new() }
+		Ok(( Self::decode_response::<S>( self.peer.id(), self.peer.name(), re )?, trace ))
 	}
 
 
-	/// Register a handler for a given service type
-	/// Calling this method twice for the same type will override the first handler.
+	/// Call a remote service and wait for the response, like `call`, but attach headers to the request,
+	/// eg. an auth token or a tenant id, see [`WireFormat::meta`]. Handlers registered with
+	/// `Services::register_with_meta` get them with the message.
 	//
-	pub fn register_handler<S>( &mut self, handler: BoxAddress<S, ThesErr> )
+	pub async fn call_with_meta<S>( &mut self, msg: S, meta: Metadata ) -> Result< <S as Message>::Return, PeerErr >
 
-		where  S                    : Service,
-		      <S as Message>::Return: Serialize + DeserializeOwned,
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
 	{
-		self.handlers.insert( <S as Service>::sid(), Mutex::new(Box::new( handler )) );
+		let mut wf = self.build_wf( msg, ConnID::null() )?;
+		wf.set_meta( Some( &meta ) );
+
+		let re = self.call_wf::<S>( Call::new( wf ) ).await?;
+
+		Self::decode_response::<S>( self.peer.id(), self.peer.name(), re )
 	}
 
 
-	// Helper function for call_service below.
-	// The receiver passed in here keeps a mutex locked. This method should never be async, nor await anything.
+	/// The number of bytes `msg` will take on the wire, header included, eg. to enforce an egress quota
+	/// before sending. The payload is encoded with the codec of the service, but only counted, so no frame
+	/// is built.
 	//
-	fn call_service_gen<S>
-	(
-		    msg      :  $wf                   ,
-		    receiver : &Box< dyn Any + Send > ,
-		mut ctx      :  PeerErrCtx            ,
-
-	) -> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
+	pub fn estimate_size<S>( msg: &S ) -> Result< usize, PeerErr >
 
 		where  S                    : Service + Send,
-		      <S as Message>::Return: Serialize + DeserializeOwned + Send + ,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
 
 	{
-		let sid = <S as Service>::sid();
+		struct Counter( usize );
 
-		// Deserialize the message.
-		//
-		let message: S = match des( &msg.msg() )
+		impl ::std::io::Write for Counter
 		{
-			Ok (x) => x,
-			Err(_) => return Err( PeerErr::Deserialize{ ctx } )
-		};
+			fn write( &mut self, buf: &[u8] ) -> ::std::io::Result<usize>
+			{
+				self.0 += buf.len();
+				Ok( buf.len() )
+			}
 
+			fn flush( &mut self ) -> ::std::io::Result<()>
+			{
+				Ok(())
+			}
+		}
 
-		// Downcast the receiver, should never fail as we make it in this file.
-		//
-		let backup: &BoxAddress<S, ThesErr> = receiver.downcast_ref()
 
-			.expect( "downcast receiver in call_service_gen" );
+		let mut counter = Counter( 0 );
 
+		( <S as Service>::codec().encode )( msg, &mut counter ).map_err( |e|
+		{
+			let mut ctx = PeerErrCtx::default();
+			ctx.context = "Estimate the size of an outgoing request".to_string().into();
+			ctx.sid     = <S as Service>::sid().into();
 
-		let mut rec = backup.clone_box() ;
-		let     cid = msg.cid()      ;
+			PeerErr::Serialize{ ctx, source: Some( e ) }
 
-		Ok( async move
-		{
-			// Call the service and wait for the response
-			//
-			let response = match rec.call( message ).await
-			{
-				Ok(x) => x,
+		})?;
 
-				Err(_) =>
-				{
-					// unwrap: we pass it in from below in this file, it has a context guaranteed.
-					//
-					ctx.context.as_mut().map( |c| c.push_str( " - Process call for local Actor" ) );
+		// An empty frame is just the header.
+		//
+		Ok( <$wf>::default().len() as usize + counter.0 )
+	}
 
-					return Err( PeerErr::HandlerDead{ ctx } );
-				}
-			};
 
+	/// Take the raw message and turn it into a WireFormat
+	//
+	fn build_wf<S>( &self, msg: S, cid: ConnID ) -> Result< $wf, PeerErr >
 
-			// Create a $wf response.
-			// The sid must be full to differentiate a response from a request. If the request
-			// has timed out, the remote peer will no longer have the cid in their list of open requests,
-			// so they would not know this was a response otherwise.
-			//
-			let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
-			wf.set_sid( ServiceID::full() );
-			wf.set_cid( cid               );
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
 
-			// serialize the response
-			//
-			serde_cbor::to_writer( &mut wf, &response ).map_err( |_|
-			{
-				ctx.context.as_mut().map( |c| c.push_str( " - Response to remote call" ) );
+	{
+		let sid = <S as Service>::sid();
 
-				PeerErr::Serialize{ ctx }
+		let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
+		wf.set_sid    ( sid          );
+		wf.set_cid    ( cid          );
+		wf.set_channel( self.channel );
 
-			})?;
+		// serialize the response
+		//
+		( <S as Service>::codec().encode )( &msg, &mut wf ).map_err( |e|
+		{
+			let mut ctx = PeerErrCtx::default();
+			ctx.context = "Outgoing request".to_string().into();
+			ctx.sid     = sid.into();
+			ctx.cid     = cid.into();
 
+			PeerErr::Serialize{ ctx, source: Some( e ) }
 
-			Ok( Response::CallResponse( CallResponse::new(wf) ))
+		})?;
 
-		}.boxed() )
+		Ok( wf )
 	}
-}
 
 
-impl ServiceMap<$wf> for Services
-{
-	// We need to make a Vec here because the hashmap.keys() doesn't have a static lifetime.
+	/// Take the raw message and turn it into a Call
 	//
-	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
-	{
-		Box::new( self.handlers.keys() )
-	}
-
-
-
-	/// Will match the type of the service id to deserialize the message and send it to the handling actor.
-	///
-	/// This can return the following errors:
-	/// - PeerErr::UnknownService
-	/// - PeerErr::Deserialize
-	//
-	fn send_service( &self, msg: $wf, ctx: PeerErrCtx )
-
-		-> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
-
-	{
-		let sid = msg.sid();
-		let ctx = ctx.context( "Services::send_service".to_string() );
-
-		// This sid should be in our map.
-		//
-		let receiver = self.handlers.get( &sid )
-
-			.ok_or_else( || PeerErr::NoHandler{ ctx: ctx.clone() } )?
-			.lock()
-		;
-
-
-		// Map the sid to a type S.
-		//
-		match sid
-		{
-			$(
-				_ if sid == <$services as Service>::sid() =>
-				{
-					// This should always succeed, receiver is made in this very file.
-					//
-					let rec: &BoxAddress<$services, ThesErr> = receiver.downcast_ref()
-
-						.expect( "downcast receiver in send_service" );
-
-
-					// Deserialize.
-					//
-					let message: $services = match des( &msg.msg() )
-					{
-						Ok (x) => x,
-						Err(_) => return Err( PeerErr::Deserialize{ ctx } ),
-					};
-
-
-					// We need to clone the receiver so it can be inside the future as &mut self.
-					//
-					let mut rec = rec.clone_box();
-
-					Ok( async move
-					{
-						match rec.send( message ).await
-						{
-							Ok (_) => Ok ( Response::Nothing                 ),
-							Err(_) => Err( PeerErr::HandlerDead{ ctx } ),
-						}
-
-					}.boxed() )
-				},
-			)+
-
-			_ =>
-			{
-				Err( PeerErr::NoHandler{ ctx } )
-			}
-		}
-	}
-
-
-
-	/// Will match the type of the service id to deserialize the message and call the handling actor.
-	/// It returns a future that actually calls the handling actor. This futures is spawned by Peer.
-	///
-	/// This can return the following errors:
-	/// - PeerErr::UnknownService
-	/// - PeerErr::Deserialize
-	/// - PeerErr::ThesErr -> Spawn error
-	//
-	fn call_service
-	(
-		&self              ,
-		msg   : $wf        ,
-		ctx   : PeerErrCtx ,
-
-	) -> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
-	{
-		let sid = msg.sid();
-		let ctx = ctx.context( "Services::call_service".to_string() );
-
-		let receiver = match self.handlers.get( &sid )
-		{
-			// FIXME: don't block the thread.
-			// Not easy to solve. It doesn't cross await points and it shouldn't lock for very long.
-			//
-			Some(x) => x.lock(),
-
-			None =>
-			{
-				return Err( PeerErr::NoHandler{ ctx } )
-			}
-		};
-
-		match sid
-		{
-			$(
-				_ if sid == <$services as Service>::sid() =>
-				{
-					Self::call_service_gen::<$services>( msg, &*receiver, ctx )
-				}
-			)+
-
-
-			_ => return Err( PeerErr::UnknownService{ ctx } )
-		}
-	}
-}
-
-
-
-/// Concrete type for creating recipients for remote Services in this thespis::ServiceMap.
-/// Note that this holds the Peers address, so the peer mailbox will only get dropped when you
-/// drop this, even if the connection get's closed by the remote.
-///
-/// If you use this, be sure to check the result that is returned which will err if the connection
-/// is closed.
-//
-#[ derive( Clone, Debug ) ]
-//
-pub struct RemoteAddr
-{
-	// FIXME: do not rely on Addr, we should be generic over Address, but not
-	//       choose an implementation. This is a complicated one. While this is in the public
-	//       API, so it would be good, having a trait object that is Address<$wf> + Address<Call>
-	//       and is still cloneable is complicated.
-	//
-	//       It could be done by unifying both message types (eg. an enum), but then what is the return
-	//       type of this message. It would have to be an enum as well, and every caller would have to
-	//       match on it. For now we will keep our dependency on Peer and Addr.
-	//
-	peer: Addr<Peer<$wf>>
-}
-
-
-impl RemoteAddr
-{
-	/// Create an RemoteAddr which implements Addr<M> for all the services in this service_map.
-	//
-	pub fn new( peer: Addr<Peer<$wf>> ) -> Self
-	{
-		Self { peer }
-	}
-
-
-	/// Take the raw message and turn it into a WireFormat
-	//
-	fn build_wf<S>( msg: S, cid: ConnID ) -> Result< $wf, PeerErr >
-
-		where  S                    : Service + Send,
-		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
-
-	{
-		let sid = <S as Service>::sid();
-
-		let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
-		wf.set_sid( sid );
-		wf.set_cid( cid );
-
-		// serialize the response
-		//
-		serde_cbor::to_writer( &mut wf, &msg ).map_err( |_|
-		{
-			let mut ctx = PeerErrCtx::default();
-			ctx.context = "Outgoing request".to_string().into();
-			ctx.sid     = sid.into();
-
-			PeerErr::Serialize{ ctx }
-
-		})?;
-
-		Ok( wf )
-	}
-
-
-	/// Take the raw message and turn it into a Call
-	//
-	fn build_call<S>( msg: S ) -> Result< Call<$wf>, PeerErr >
+	fn build_call<S>( &self, msg: S ) -> Result< Call<$wf>, PeerErr >
 
 		where  S                    : Service + Send,
 		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
@@ -618,45 +485,32 @@ impl RemoteAddr
 		// heap allocated data.
 		//
 		let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
-		wf.set_sid( sid );
+		wf.set_sid    ( sid          );
+		wf.set_channel( self.channel );
 
 		// serialize the response
 		//
-		serde_cbor::to_writer( &mut wf, &msg ).map_err( |_|
+		( <S as Service>::codec().encode )( &msg, &mut wf ).map_err( |e|
 		{
 			let mut ctx = PeerErrCtx::default();
 			ctx.context = "Outgoing request".to_string().into();
 			ctx.sid     = sid.into();
 
-			PeerErr::Serialize{ ctx }
+			PeerErr::Serialize{ ctx, source: Some( e ) }
 
 		})?;
 
 		Ok( Call::new( wf ) )
 	}
-}
-
 
 
-impl<S> Address<S> for RemoteAddr
-
-	where  S                    : Service + Send,
-	      <S as Message>::Return: Serialize + DeserializeOwned + Send,
-
-{
-	/// Call a remote actor.
-	///
-	/// ### potential errors
-	///
-	/// 1. serialization of the outgoing message
-	/// 2.
+	/// Hand a call to the peer and wait for what comes back from the remote.
 	//
-	fn call( &mut self, msg: S ) -> Return<Result< <S as Message>::Return, PeerErr >> { async move
-	{
-		// Serialization can fail
-		//
-		let call = Self::build_call( msg )?;
+	async fn call_wf<S>( &mut self, call: Call<$wf> ) -> Result< Result<$wf, ConnectionError>, PeerErr >
 
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+	{
 		// Can fail if the peer is down already.
 		//
 		let rx = self.peer.call( call ).await
@@ -701,29 +555,53 @@ impl<S> Address<S> for RemoteAddr
 
 			})?;
 
+		Ok( re )
+	}
+
 
-		// A response came back from the other side.
-		//
+	/// Turn what came back over the channel for an outgoing call into either the return type of
+	/// the service or the appropriate error.
+	//
+	fn decode_response<S>
+	(
+		peer_id  : usize                        ,
+		peer_name: Option<Arc<str>>             ,
+		re       : Result<$wf, ConnectionError> ,
+	)
+		-> Result< <S as Message>::Return, PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+
+	{
 		match re
 		{
 			Ok ( resp ) =>
 			{
 				// Deserialize the payload and return it to the caller.
 				//
-				Ok( des( &resp.msg() )
+				Ok( $crate::read_response( &resp.msg() )
 
-					.map_err( |_|
+					.map_err( |e|
 					{
+						// The cause from serde names the field, the type tells which version of it failed.
+						//
+						let context = format!
+						(
+							"Response to call from remote actor, decoding {}",
+							::std::any::type_name::< <S as Message>::Return >(),
+						);
+
 						let ctx = PeerErrCtx
 						{
-							context  : Some( "Response to call from remote actor".to_string() ) ,
-							peer_id  : self.peer.id().into()                                    ,
-							peer_name: self.peer.name()                                         ,
-							sid      : <S as Service>::sid().into()                             ,
-							cid      : resp.cid().into()                                        ,
+							context  : Some( context )              ,
+							peer_id  : peer_id.into()               ,
+							peer_name                               ,
+							sid      : <S as Service>::sid().into() ,
+							cid      : resp.cid().into()            ,
 						};
 
-						PeerErr::Deserialize{ ctx }
+						PeerErr::Deserialize{ ctx, source: Some( e.into() ) }
 
 					})?
 				)
@@ -733,41 +611,46 @@ impl<S> Address<S> for RemoteAddr
 			//
 			Err( err ) =>
 			{
-				let mut ctx = PeerErrCtx
+				let ctx = PeerErrCtx
 				{
 					context  : Some( "Remote could not process our message".to_string() ) ,
-					peer_id  : self.peer.id().into()                                      ,
-					peer_name: self.peer.name()                                           ,
+					peer_id  : peer_id.into()                                             ,
+					peer_name                                                             ,
 					sid      : <S as Service>::sid().into()                               ,
 					cid      : None                                                       ,
 				};
 
-				match err
-				{
-					// This is a special case, since it get's returned from the channel it needed to be a
-					// ConnectionError, but it doesn't actually come from the remote, so translate into
-					// it's own error variant rather than PeerErr::Remote.
-					//
-					// It can however come from a relay. We don't allow the user here to distinguish whether
-					// this peer timed out or a relay.
-					//
-					// TODO: Document this as users might be confused when they raise their timeout and it still
-					// times out. It will be logged as a remote error by the code in peer/incoming.rs
-					//
-					ConnectionError::Timeout{..} =>
-					{
-						ctx.context = Some( "Time out waiting for response to outgoing call".to_string() );
-
-						Err( PeerErr::Timeout{ ctx } )
-					}
-
-					_ =>
-					{
-						Err( PeerErr::Remote{ err, ctx } )
-					}
-				}
+				Err( PeerErr::from_remote( err, ctx ) )
 			},
 		}
+	}
+}
+
+
+
+impl<S> Address<S> for RemoteAddr
+
+	where  S                    : Service + Send,
+	      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+
+{
+	/// Call a remote actor.
+	///
+	/// ### potential errors
+	///
+	/// 1. serialization of the outgoing message
+	/// 2.
+	//
+	fn call( &mut self, msg: S ) -> Return<Result< <S as Message>::Return, PeerErr >> { async move
+	{
+		// Serialization can fail
+		//
+		let call = self.build_call( msg )?;
+		let re   = self.call_wf::<S>( call ).await?;
+
+		// A response came back from the other side.
+		//
+		Self::decode_response::<S>( self.peer.id(), self.peer.name(), re )
 
 	}.boxed() }
 
@@ -776,7 +659,7 @@ impl<S> Address<S> for RemoteAddr
 	//
 	fn clone_box( &self ) -> BoxAddress<S, PeerErr>
 	{
-		Box::new( Self { peer: self.peer.clone() } )
+		Box::new( self.clone() )
 	}
 }
 
@@ -794,6 +677,14 @@ impl<S> Sink<S> for RemoteAddr
 
 	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context ) -> Poll<Result<(), Self::Error>>
 	{
+		if let Some( rate ) = &mut self.rate
+		{
+			if rate.poll_acquire( cx ).is_pending()
+			{
+				return Poll::Pending;
+			}
+		}
+
 		Sink::<$wf>::poll_ready( Pin::new( &mut self.peer ), cx )
 
 			.map_err( |source|
@@ -807,7 +698,14 @@ impl<S> Sink<S> for RemoteAddr
 
 	fn start_send( mut self: Pin<&mut Self>, msg: S ) -> Result<(), Self::Error>
 	{
-		Sink::<$wf>::start_send( Pin::new( &mut self.peer ), Self::build_wf( msg, ConnID::null() )? )
+		if let Some( rate ) = &mut self.rate
+		{
+			rate.consume();
+		}
+
+		let wf = self.build_wf( msg, ConnID::null() )?;
+
+		Sink::<$wf>::start_send( Pin::new( &mut self.peer ), wf )
 
 			.map_err( |source|
 			{
@@ -857,4 +755,1411 @@ impl Identify for RemoteAddr
 	}
 }
 
+
+/// An address for the services of a remote that only connects on the first call or send, so you can
+/// create it before the remote is up. It's created from a factory that establishes the connection
+/// and returns the address of the [Peer]. Once connected, that peer is used for all calls and sends,
+/// also by clones of this address. Concurrent first calls wait for the same connection attempt. When
+/// it fails, the caller gets the error and the next call tries again.
+///
+/// Since connecting is async, this doesn't implement `Address`. Use [`LazyRemoteAddr::remote_addr`]
+/// to get a [RemoteAddr]. If the connection closes later, calls fail just like with a [RemoteAddr].
+//
+#[ derive( Clone ) ]
+//
+pub struct LazyRemoteAddr
+{
+	connect: Arc< dyn Fn() -> BoxFuture<'static, Result<Addr<Peer<$wf>>, PeerErr>> + Send + Sync >,
+	peer   : Arc< FutMutex< Option<Addr<Peer<$wf>>> > >,
+}
+
+
+impl LazyRemoteAddr
+{
+	/// `connect` is called to establish the connection, on the first call or send.
+	//
+	pub fn new<F, Fut>( connect: F ) -> Self
+
+		where F  : Fn() -> Fut + Send + Sync + 'static                               ,
+		      Fut: Future< Output = Result<Addr<Peer<$wf>>, PeerErr> > + Send + 'static ,
+	{
+		Self
+		{
+			connect: Arc::new( move || connect().boxed() ) ,
+			peer   : Arc::new( FutMutex::new( None ) )     ,
+		}
+	}
+
+
+	/// Connect if that didn't happen yet and get a [RemoteAddr] to the peer.
+	//
+	pub async fn remote_addr( &self ) -> Result<RemoteAddr, PeerErr>
+	{
+		// Holding the lock while connecting makes concurrent first calls wait for this attempt.
+		//
+		let mut peer = self.peer.lock().await;
+
+		let addr = match &*peer
+		{
+			Some( addr ) => addr.clone(),
+
+			None =>
+			{
+				let addr = (self.connect)().await?;
+
+				*peer = Some( addr.clone() );
+
+				addr
+			}
+		};
+
+		Ok( RemoteAddr::new( addr ) )
+	}
+
+
+	/// Call a remote actor, connecting first if needed.
+	//
+	pub async fn call<S>( &self, msg: S ) -> Result< <S as Message>::Return, PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+	{
+		self.remote_addr().await?.call( msg ).await
+	}
+
+
+	/// Send to a remote actor, connecting first if needed.
+	//
+	pub async fn send<S>( &self, msg: S ) -> Result<(), PeerErr>
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+	{
+		self.remote_addr().await?.send( msg ).await
+	}
+}
+
+
+impl fmt::Debug for LazyRemoteAddr
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		match self.peer.try_lock().as_deref()
+		{
+			Some( Some( peer ) ) => write!( f, "LazyRemoteAddr: connected, peer id: {}", peer.id() ),
+			Some( None         ) => write!( f, "LazyRemoteAddr: not connected"                      ),
+			None                 => write!( f, "LazyRemoteAddr: connecting"                         ),
+		}
+	}
+}
+
+
+
+/// Calls and sends the services of this service map over a [LocalPeer], for single threaded use.
+/// The futures are not `Send`.
+//
+#[ derive( Clone, Debug ) ]
+//
+pub struct LocalRemoteAddr
+{
+	peer: LocalPeer<$wf>,
+}
+
+
+impl LocalRemoteAddr
+{
+	/// Create a LocalRemoteAddr for the connection of `peer`.
+	//
+	pub fn new( peer: LocalPeer<$wf> ) -> Self
+	{
+		Self { peer }
+	}
+
+
+	/// Call a remote service.
+	//
+	pub async fn call<S>( &self, msg: S ) -> Result< <S as Message>::Return, PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+
+	{
+		let wf = Self::build_wf( &msg )?;
+		let re = self.peer.call( wf ).await?;
+
+		RemoteAddr::decode_response::<S>( 0, None, re )
+	}
+
+
+	/// Send to a remote service.
+	//
+	pub fn send<S>( &self, msg: S ) -> Result< (), PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+
+	{
+		let wf = Self::build_wf( &msg )?;
+
+		self.peer.send( wf )
+	}
+
+
+	fn build_wf<S>( msg: &S ) -> Result< $wf, PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+
+	{
+		let sid = <S as Service>::sid();
+
+		let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
+		wf.set_sid( sid );
+
+		( <S as Service>::codec().encode )( msg, &mut wf ).map_err( |e|
+		{
+			let mut ctx = PeerErrCtx::default();
+			ctx.context = "Outgoing request to LocalPeer".to_string().into();
+			ctx.sid     = sid.into();
+
+			PeerErr::Serialize{ ctx, source: Some( e ) }
+
+		})?;
+
+		Ok( wf )
+	}
+}
+
+
+
+$( $crate::__service_map_concrete_futures!( $concrete, $wf ); )?
+
 }}} // End of macro
+
+
+
+/// Generates the `Services` handler map of `service_map!`, unless the `client_only` option is set.
+/// Not meant to be used directly.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_map_services
+{
+	( [ true ]; $ns: ident; $wf: path; $($services: path),+ ) => {};
+
+	( [ $( false )? ]; $ns: ident; $wf: path; $($services: path),+ ) =>
+	{
+
+/// The actual service map.
+/// Use it to get a recipient to a remote service.
+//
+pub struct Services
+{
+	// The addresses to the actors that handle incoming messages.
+	//
+	handlers: HashMap< ServiceID, Mutex<Box<dyn Any + Send>> >,
+
+	// The validators set with set_validator, each one a Validator<S>.
+	//
+	validators: HashMap< ServiceID, Arc<dyn Any + Send + Sync> >,
+}
+
+
+// Checks an incoming message of service S after it was deserialized.
+//
+type Validator<S> = Box< dyn Fn( &S ) -> Result<(), String> + Send + Sync >;
+
+
+
+/// Will print something like:
+///
+/// ```ignore
+/// remotes::Services
+/// {
+///    Add  - sid: 0xbcc09d3812378e171ad366d75f687757 - handler: id(0), name(actor_name)
+///    Show - sid: 0xbcc09d3812378e17e1a1e89b512c025a - handler: id(0), name(actor_name)
+/// }
+/// ```
+//
+impl fmt::Debug for Services
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		let mut width: usize = 0;
+
+		$(
+			width = std::cmp::max( width, stringify!( $services ).len() );
+		)+
+
+		write!( f, "{}::Services\n{{\n", stringify!( $ns ) )?;
+
+
+		$(
+			let sid = <$services as Service>::sid();
+
+			write!
+			(
+				f,
+
+				"\t{:width$} - sid: 0x{:02x} - handler: ",
+
+				stringify!( $services ),
+				sid,
+
+				width = width
+			)?;
+
+			if let Some(h) = self.handlers.get( &sid )
+			{
+				let h = h.lock();
+
+				// This expect shouldn't ever fail. We manually make the receiver in this file.
+				//
+				let handler: &LocalHandler<$services, $wf> = h.downcast_ref().expect( "downcast receiver in Debug for Services" );
+
+				match handler.name()
+				{
+					Some(n) => write!( f, "id({}), name({})", &handler.id(), &n )?,
+					None    => write!( f, "id({})", &handler.id() )?,
+				};
+			}
+
+			else
+			{
+				write!( f, "none" )?;
+			}
+
+			write!( f, "\n" )?;
+		)+
+
+		write!( f, "}}" )
+	}
+}
+
+
+
+/// This downcasts in order to clone the handlers
+//
+impl Clone for Services
+{
+	fn clone( &self ) -> Self
+	{
+		#[ allow(clippy::mutable_key_type) ] // false positive.
+		//
+		let mut handlers: HashMap< ServiceID, Mutex<Box<dyn Any + Send>> > = HashMap::new();
+
+		for (k, v) in &self.handlers
+		{
+			match k
+			{
+				$(
+					_ if *k == <$services as Service>::sid() =>
+					{
+						// This should never fail, we make this type in this file.
+						//
+						let v = v.lock();
+						let h: &LocalHandler<$services, $wf> = v.downcast_ref().expect( "downcast receiver in Clone" );
+
+						handlers.insert( *k, Mutex::new( Box::new(h.clone_box()) ) );
+					},
+				)+
+
+
+				// every sid in our handlers map should also be a valid service in this service map,
+				// so this should never happen
+				//
+				_ => { unreachable!() },
+			}
+
+		}
+
+		Self { handlers, validators: self.validators.clone() }
+	}
+}
+
+
+
+impl Services
+{
+	/// Create a new service map
+	//
+	pub fn new() -> Self
+	{
+		register_service_names();
+
+		Self{ handlers: HashMap::new(), validators: HashMap::new() }
+	}
+
+
+	/// Register a handler for a given service type
+	/// Calling this method twice for the same type will override the first handler.
+	//
+	pub fn register_handler<S>( &mut self, handler: BoxAddress<S, ThesErr> )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		self.handlers.insert( <S as Service>::sid(), Mutex::new(Box::new( LocalHandler::<S, $wf>::Plain( handler ) )) );
+	}
+
+
+	/// Like `register_handler`, but refuses to replace a handler that is already registered for this type,
+	/// whatever the method it was registered with. Use it to catch a service that gets registered twice by
+	/// mistake. The first handler is kept.
+	//
+	pub fn try_register_handler<S>( &mut self, handler: BoxAddress<S, ThesErr> ) -> Result<(), AlreadyRegistered>
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		let sid = <S as Service>::sid();
+
+		if self.handlers.contains_key( &sid )
+		{
+			return Err( AlreadyRegistered{ sid } );
+		}
+
+		self.register_handler( handler );
+
+		Ok(())
+	}
+
+
+	/// Handle service `S` with an async closure instead of an actor, eg. `sm.register_fn( |_: Show| async { 5 } )`.
+	/// See [`FnHandler`]. Calling this method twice for the same type will override the first handler.
+	//
+	pub fn register_fn<S, F, Fut>( &mut self, f: F )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+		       F                    : Fn(S) -> Fut + Send + Sync + 'static,
+		       Fut                  : Future< Output = <S as Message>::Return > + Send + 'static,
+	{
+		self.register_handler::<S>( Box::new( FnHandler::new( f ) ) );
+	}
+
+
+	/// Register a handler that receives a [`CancelToken`] together with each message, wrapped in
+	/// [`Cancellable`]. For calls, the token is cancelled when the connection closes or when the call
+	/// is dropped, so long running handlers can stop working when the response can no longer be
+	/// delivered. Sends get a token that is never cancelled, since they are processed even after
+	/// the connection closes.
+	///
+	/// Calling this method twice for the same type will override the first handler, also when it was
+	/// registered with `register_handler`.
+	//
+	pub fn register_cancellable<S>( &mut self, handler: BoxAddress<Cancellable<S>, ThesErr> )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		self.handlers.insert( <S as Service>::sid(), Mutex::new(Box::new( LocalHandler::<S, $wf>::Cancellable( handler ) )) );
+	}
+
+
+	/// Register a handler that receives the trace id of the frame together with each message, wrapped
+	/// in [`Traced`], eg. to correlate its logs with those of the caller. See `RemoteAddr::call_traced`.
+	/// The response to a call always carries the trace id of the request, whatever the handler.
+	///
+	/// Calling this method twice for the same type will override the first handler, also when it was
+	/// registered with another method.
+	//
+	pub fn register_traced<S>( &mut self, handler: BoxAddress<Traced<S>, ThesErr> )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		self.handlers.insert( <S as Service>::sid(), Mutex::new(Box::new( LocalHandler::<S, $wf>::Traced( handler ) )) );
+	}
+
+
+	/// Register a handler that receives the headers of the frame together with each message, wrapped
+	/// in [`WithMeta`], eg. to check an auth token. See `RemoteAddr::call_with_meta`.
+	///
+	/// Calling this method twice for the same type will override the first handler, also when it was
+	/// registered with another method.
+	//
+	pub fn register_with_meta<S>( &mut self, handler: BoxAddress<WithMeta<S>, ThesErr> )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		self.handlers.insert( <S as Service>::sid(), Mutex::new(Box::new( LocalHandler::<S, $wf>::WithMeta( handler ) )) );
+	}
+
+
+	/// Register a handler for bidirectional streams opened with this service, see [`OpenStream`]. The
+	/// handler receives the opening message together with its end of the stream in [`Streaming`].
+	/// It doesn't handle sends and calls of the service, the remote gets a `NoHandler` error for those.
+	///
+	/// Calling this method twice for the same type will override the first handler, also when it was
+	/// registered with `register_handler` or `register_cancellable`.
+	//
+	pub fn register_stream<S>( &mut self, handler: BoxAddress<Streaming<S, $wf>, ThesErr> )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		self.handlers.insert( <S as Service>::sid(), Mutex::new(Box::new( LocalHandler::<S, $wf>::Stream( handler ) )) );
+	}
+
+
+	/// Receive the sends of service `S` as a stream instead of registering a handler, eg. for pipeline
+	/// style consumers. Messages come out in the order they were received. Those that don't deserialize
+	/// come out as [`PeerErr::Deserialize`], those rejected by the validator set with `set_validator` as
+	/// [`PeerErr::Validation`]. The stream continues after them. Calls to `S` get a
+	/// `NoHandler` error.
+	///
+	/// The stream is unbounded, so messages pile up when the consumer is slower than the remote. The
+	/// stream ends when the peer is dropped and this service map with it. Calling this method twice
+	/// for the same type replaces the first stream, also when a handler was registered for it.
+	//
+	pub fn incoming<S>( &mut self )
+
+		-> impl $crate::external_deps::futures::Stream< Item = Result<S, PeerErr> > + Send + Unpin + 'static
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		let (tx, rx) = $crate::external_deps::futures::channel::mpsc::unbounded();
+
+		self.handlers.insert( <S as Service>::sid(), Mutex::new(Box::new( LocalHandler::<S, $wf>::Channel( tx ) )) );
+
+		rx
+	}
+
+
+	/// Register one actor as the handler for several services at once, eg.
+	/// `register_handler_for::<(Add, Show)>( &addr )`. The address is cloned for each service. This
+	/// works for tuples of up to 12 services, see [`HandlerFor`].
+	///
+	/// Calling this method twice for the same type will override the first handler.
+	//
+	pub fn register_handler_for<L>( &mut self, handler: &impl HandlerFor<L> )
+	{
+		handler.register_for( self );
+	}
+
+
+	/// The handlers that are registered, in the order the services are listed in the macro invocation.
+	/// This is the same information the Debug implementation shows.
+	//
+	pub fn handler_info( &self ) -> Vec<HandlerInfo>
+	{
+		let mut info = Vec::new();
+
+		$(
+			let sid = <$services as Service>::sid();
+
+			if let Some(h) = self.handlers.get( &sid )
+			{
+				let h = h.lock();
+
+				// This expect shouldn't ever fail. We manually make the receiver in this file.
+				//
+				let handler: &LocalHandler<$services, $wf> = h.downcast_ref().expect( "downcast receiver in handler_info" );
+
+				info.push( HandlerInfo{ sid, actor_id: handler.id(), name: handler.name() } );
+			}
+		)+
+
+		info
+	}
+
+
+	/// Check the incoming messages of service `S` after they are deserialized and before they reach the
+	/// handler, eg. to reject negative amounts in one place rather than in every handler. A message for
+	/// which `validate` returns an error is not delivered. A call fails on the remote with
+	/// [`ConnectionError::Validation`] carrying the returned reason, a send is dropped and reported as
+	/// [`PeerErr::Validation`] in the events of the peer. Setting a validator again for the same type
+	/// replaces the first one. It's kept when the handler is replaced.
+	//
+	pub fn set_validator<S, F>( &mut self, validate: F )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+		       F                    : Fn( &S ) -> Result<(), String> + Send + Sync + 'static,
+	{
+		let validate: Validator<S> = Box::new( validate );
+
+		self.validators.insert( <S as Service>::sid(), Arc::new( validate ) );
+	}
+
+
+	/// Take a snapshot of the registered services, eg. to check after a hot restart that the new process
+	/// exposes the same services, see [`ServicesSnapshot::same_services`].
+	//
+	pub fn snapshot( &self ) -> ServicesSnapshot
+	{
+		ServicesSnapshot{ handlers: self.handler_info().iter().map( HandlerSnapshot::from ).collect() }
+	}
+
+
+	/// Register the handlers `from` has for the services in `snapshot`, so this service map exposes the
+	/// services from the snapshot again. The handlers are cloned, handlers for services that aren't in the
+	/// snapshot are left alone. When `from` lacks a handler for some of the services, nothing is registered.
+	//
+	pub fn restore( &mut self, snapshot: &ServicesSnapshot, from: &Services ) -> Result<(), MissingServices>
+	{
+		let sids: Vec<ServiceID> = snapshot.sids().collect();
+
+		let missing: Vec<ServiceID> = sids.iter()
+
+			.filter( |sid| !from.handlers.contains_key( sid ) )
+			.copied()
+			.collect()
+		;
+
+		if !missing.is_empty()
+		{
+			return Err( MissingServices{ sids: missing } );
+		}
+
+		for sid in sids
+		{
+			// This should never fail, we make this type in this file.
+			//
+			let handler = from.handlers[ &sid ].lock();
+
+			$(
+				if sid == <$services as Service>::sid()
+				{
+					let h: &LocalHandler<$services, $wf> = handler.downcast_ref().expect( "downcast receiver in restore" );
+
+					self.handlers.insert( sid, Mutex::new( Box::new(h.clone_box()) ) );
+				}
+			)+
+		}
+
+		Ok(())
+	}
+
+
+	// Run the validator for S set with set_validator on an incoming message, if any.
+	//
+	fn validate<S>( &self, msg: &S, ctx: &PeerErrCtx ) -> Result<(), PeerErr>
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		let validate = match self.validators.get( &<S as Service>::sid() )
+		{
+			Some(v) => v,
+			None    => return Ok(()),
+		};
+
+		// This should never fail, we make this type in this file.
+		//
+		let validate: &Validator<S> = validate.downcast_ref().expect( "downcast validator" );
+
+		validate( msg ).map_err( |reason| PeerErr::Validation{ ctx: ctx.clone(), reason } )
+	}
+
+
+	// Helper function for call_service below.
+	// The receiver passed in here keeps a mutex locked. This method should never be async, nor await anything.
+	//
+	fn call_service_gen<S>
+	(
+		    &self                             ,
+		    msg      :  $wf                   ,
+		    receiver : &Box< dyn Any + Send > ,
+		mut ctx      :  PeerErrCtx            ,
+		    cancel   :  CancelToken           ,
+
+	) -> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send + ,
+
+	{
+		let sid = <S as Service>::sid();
+
+		// Deserialize the message.
+		//
+		let message: S = match ( <S as Service>::codec().decode )( &msg.msg() )
+		{
+			Ok (x) => x,
+			Err(e) => return Err( PeerErr::Deserialize{ ctx, source: Some( e ) } )
+		};
+
+		self.validate( &message, &ctx )?;
+
+
+		// Downcast the receiver, should never fail as we make it in this file.
+		//
+		let backup: &LocalHandler<S, $wf> = receiver.downcast_ref()
+
+			.expect( "downcast receiver in call_service_gen" );
+
+		if backup.is_stream() || backup.is_channel()
+		{
+			return Err( PeerErr::NoHandler{ ctx } );
+		}
+
+
+		let mut rec   = backup.clone_box() ;
+		let     cid   = msg.cid()          ;
+		let     trace = msg.trace_id()     ;
+		let     meta  = msg.meta()         ;
+
+		Ok( async move
+		{
+			// Call the service and wait for the response
+			//
+			let response = match rec.call( message, cancel, trace, meta ).await
+			{
+				Ok(x) => x,
+
+				Err(_) =>
+				{
+					// unwrap: we pass it in from below in this file, it has a context guaranteed.
+					//
+					ctx.context.as_mut().map( |c| c.push_str( " - Process call for local Actor" ) );
+
+					return Err( PeerErr::HandlerDead{ ctx } );
+				}
+			};
+
+
+			// Create a $wf response.
+			// The sid must be full to differentiate a response from a request. If the request
+			// has timed out, the remote peer will no longer have the cid in their list of open requests,
+			// so they would not know this was a response otherwise.
+			//
+			let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
+			wf.set_sid     ( ServiceID::full() );
+			wf.set_cid     ( cid               );
+			wf.set_trace_id( trace             );
+
+			// serialize the response, unless the handler returned a RawResponse.
+			//
+			$crate::write_response( &mut wf, &response ).map_err( |e|
+			{
+				ctx.context.as_mut().map( |c| c.push_str( " - Response to remote call" ) );
+
+				PeerErr::Serialize{ ctx, source: Some( e ) }
+
+			})?;
+
+
+			Ok( Response::CallResponse( CallResponse::new(wf) ))
+
+		}.boxed() )
+	}
+}
+
+
+impl ServiceMap<$wf> for Services
+{
+	// We need to make a Vec here because the hashmap.keys() doesn't have a static lifetime.
+	//
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		Box::new( self.handlers.keys() )
+	}
+
+
+
+	/// Will match the type of the service id to deserialize the message and send it to the handling actor.
+	///
+	/// This can return the following errors:
+	/// - PeerErr::UnknownService
+	/// - PeerErr::Deserialize
+	/// - PeerErr::Validation, see `Services::set_validator`
+	//
+	fn send_service( &self, msg: $wf, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
+
+	{
+		let sid = msg.sid();
+		let ctx = ctx.context( "Services::send_service".to_string() ).sid( sid );
+
+		// This sid should be in our map.
+		//
+		let receiver = self.handlers.get( &sid )
+
+			.ok_or_else( || PeerErr::NoHandler{ ctx: ctx.clone() } )?
+			.lock()
+		;
+
+
+		// Map the sid to a type S.
+		//
+		match sid
+		{
+			$(
+				_ if sid == <$services as Service>::sid() =>
+				{
+					// This should always succeed, receiver is made in this very file.
+					//
+					let rec: &LocalHandler<$services, $wf> = receiver.downcast_ref()
+
+						.expect( "downcast receiver in send_service" );
+
+					if rec.is_stream()
+					{
+						return Err( PeerErr::NoHandler{ ctx } );
+					}
+
+
+					// Hand the message to the stream from Services::incoming, also when it doesn't deserialize.
+					//
+					// This happens right away rather than in the returned future to keep the messages in order.
+					//
+					if let LocalHandler::Channel( tx ) = rec
+					{
+						let message = ( <$services as Service>::codec().decode )( &msg.msg() )
+
+							.map_err( |e| PeerErr::Deserialize{ ctx: ctx.clone(), source: Some( e ) } )
+
+							.and_then( |m| self.validate( &m, &ctx ).map( |_| m ) )
+						;
+
+						let sent = tx.unbounded_send( message );
+
+						return Ok( async move
+						{
+							match sent
+							{
+								Ok (_) => Ok ( Response::Nothing           ),
+								Err(_) => Err( PeerErr::HandlerDead{ ctx } ),
+							}
+
+						}.boxed() )
+					}
+
+
+					// Deserialize.
+					//
+					let message: $services = match ( <$services as Service>::codec().decode )( &msg.msg() )
+					{
+						Ok (x) => x,
+						Err(e) => return Err( PeerErr::Deserialize{ ctx, source: Some( e ) } ),
+					};
+
+					self.validate( &message, &ctx )?;
+
+
+					// We need to clone the receiver so it can be inside the future as &mut self.
+					//
+					let mut rec   = rec.clone_box();
+					let     trace = msg.trace_id();
+					let     meta  = msg.meta();
+
+					Ok( async move
+					{
+						match rec.send( message, CancelToken::never(), trace, meta ).await
+						{
+							Ok (_) => Ok ( Response::Nothing                 ),
+							Err(_) => Err( PeerErr::HandlerDead{ ctx } ),
+						}
+
+					}.boxed() )
+				},
+			)+
+
+			_ =>
+			{
+				Err( PeerErr::NoHandler{ ctx } )
+			}
+		}
+	}
+
+
+
+	/// Will match the type of the service id to deserialize the message and call the handling actor.
+	/// It returns a future that actually calls the handling actor. This futures is spawned by Peer.
+	///
+	/// This can return the following errors:
+	/// - PeerErr::UnknownService
+	/// - PeerErr::Deserialize
+	/// - PeerErr::Validation, see `Services::set_validator`
+	/// - PeerErr::ThesErr -> Spawn error
+	//
+	fn call_service
+	(
+		&self               ,
+		msg   : $wf         ,
+		ctx   : PeerErrCtx  ,
+		cancel: CancelToken ,
+
+	) -> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
+	{
+		let sid = msg.sid();
+		let ctx = ctx.context( "Services::call_service".to_string() ).sid( sid ).cid( msg.cid() );
+
+		let receiver = match self.handlers.get( &sid )
+		{
+			// FIXME: don't block the thread.
+			// Not easy to solve. It doesn't cross await points and it shouldn't lock for very long.
+			//
+			Some(x) => x.lock(),
+
+			None =>
+			{
+				return Err( PeerErr::NoHandler{ ctx } )
+			}
+		};
+
+		match sid
+		{
+			$(
+				_ if sid == <$services as Service>::sid() =>
+				{
+					self.call_service_gen::<$services>( msg, &*receiver, ctx, cancel )
+				}
+			)+
+
+
+			_ => return Err( PeerErr::UnknownService{ ctx } )
+		}
+	}
+
+
+
+	/// Will match the type of the service id to deserialize the message that opens the stream and
+	/// send it to the handling actor together with the channel.
+	///
+	/// This can return the following errors:
+	/// - PeerErr::NoHandler, also when the handler was not registered with `register_stream`.
+	/// - PeerErr::Deserialize
+	/// - PeerErr::Validation, see `Services::set_validator`
+	//
+	fn open_stream( &self, msg: $wf, channel: StreamChannel<$wf>, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<$wf>, PeerErr> > + Send >>, PeerErr >
+
+	{
+		let sid = msg.sid();
+		let ctx = ctx.context( "Services::open_stream".to_string() ).sid( sid );
+
+		let receiver = self.handlers.get( &sid )
+
+			.ok_or_else( || PeerErr::NoHandler{ ctx: ctx.clone() } )?
+			.lock()
+		;
+
+		match sid
+		{
+			$(
+				_ if sid == <$services as Service>::sid() =>
+				{
+					// This should always succeed, receiver is made in this very file.
+					//
+					let rec: &LocalHandler<$services, $wf> = receiver.downcast_ref()
+
+						.expect( "downcast receiver in open_stream" );
+
+
+					let mut rec = match rec
+					{
+						LocalHandler::Stream( h ) => h.clone_box(),
+						_                         => return Err( PeerErr::NoHandler{ ctx } ),
+					};
+
+
+					let message: $services = match ( <$services as Service>::codec().decode )( &msg.msg() )
+					{
+						Ok (x) => x,
+						Err(e) => return Err( PeerErr::Deserialize{ ctx, source: Some( e ) } ),
+					};
+
+					self.validate( &message, &ctx )?;
+
+
+					Ok( async move
+					{
+						match rec.send( Streaming{ msg: message, channel } ).await
+						{
+							Ok (_) => Ok ( Response::Nothing           ),
+							Err(_) => Err( PeerErr::HandlerDead{ ctx } ),
+						}
+
+					}.boxed() )
+				},
+			)+
+
+			_ =>
+			{
+				Err( PeerErr::NoHandler{ ctx } )
+			}
+		}
+	}
+}
+
+
+
+/// A service map for a [LocalPeer]. The handlers are async closures that don't have to be `Send`, so
+/// they can keep their state in `Rc`. Use `Services` for a [Peer].
+//
+#[ derive( Default ) ]
+//
+pub struct LocalServices
+{
+	// Each one a LocalFn<S>.
+	//
+	handlers: HashMap< ServiceID, Box<dyn Any> >,
+}
+
+
+// A handler of LocalServices for service S.
+//
+type LocalFn<S> = ::std::rc::Rc< dyn Fn(S) -> $crate::external_deps::futures::future::LocalBoxFuture< 'static, <S as Message>::Return > >;
+
+
+impl LocalServices
+{
+	/// Create an empty service map.
+	//
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+
+
+	/// Handle service `S` with an async closure. Calling this method twice for the same type will
+	/// override the first handler.
+	//
+	pub fn register_fn<S, F, Fut>( &mut self, f: F )
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+		       F                    : Fn(S) -> Fut + 'static,
+		       Fut                  : Future< Output = <S as Message>::Return > + 'static,
+	{
+		let f: LocalFn<S> = ::std::rc::Rc::new( move |msg| f( msg ).boxed_local() );
+
+		self.handlers.insert( <S as Service>::sid(), Box::new( f ) );
+	}
+
+
+	// Find the handler for S and deserialize the message.
+	//
+	fn local_request<S>( &self, msg: &$wf, ctx: &PeerErrCtx ) -> Result< (LocalFn<S>, S), PeerErr >
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		// This should never fail, we make this type in this file.
+		//
+		let f: LocalFn<S> = self.handlers.get( &<S as Service>::sid() )
+
+			.map( |h| h.downcast_ref::< LocalFn<S> >().expect( "downcast handler in LocalServices" ).clone() )
+			.ok_or_else( || PeerErr::NoHandler{ ctx: ctx.clone() } )?
+		;
+
+		let message: S = ( <S as Service>::codec().decode )( &msg.msg() )
+
+			.map_err( |e| PeerErr::Deserialize{ ctx: ctx.clone(), source: Some( e ) } )?
+		;
+
+		Ok( (f, message) )
+	}
+
+
+	fn local_call<S>( &self, msg: $wf, mut ctx: PeerErrCtx )
+
+		-> Result< $crate::external_deps::futures::future::LocalBoxFuture< 'static, Result<$wf, PeerErr> >, PeerErr >
+
+		where  S                    : Service,
+		      <S as Message>::Return: Serialize + DeserializeOwned,
+	{
+		let (f, message) = self.local_request::<S>( &msg, &ctx )?;
+
+		let cid   = msg.cid();
+		let trace = msg.trace_id();
+
+		Ok( async move
+		{
+			let response = f( message ).await;
+
+			let mut wf = <$wf>::with_capacity( ::std::mem::size_of::<S>() * 2 );
+			wf.set_sid     ( ServiceID::full() );
+			wf.set_cid     ( cid               );
+			wf.set_trace_id( trace             );
+
+			$crate::write_response( &mut wf, &response ).map_err( |e|
+			{
+				ctx.context.as_mut().map( |c| c.push_str( " - Response to remote call" ) );
+
+				PeerErr::Serialize{ ctx, source: Some( e ) }
+
+			})?;
+
+			Ok( wf )
+
+		}.boxed_local() )
+	}
+}
+
+
+impl LocalServiceMap<$wf> for LocalServices
+{
+	fn send_service( &self, msg: $wf, ctx: PeerErrCtx )
+
+		-> Result< $crate::external_deps::futures::future::LocalBoxFuture< 'static, Result<(), PeerErr> >, PeerErr >
+
+	{
+		let sid = msg.sid();
+		let ctx = ctx.sid( sid );
+
+		match sid
+		{
+			$(
+				_ if sid == <$services as Service>::sid() =>
+				{
+					let (f, message) = self.local_request::<$services>( &msg, &ctx )?;
+
+					Ok( async move { f( message ).await; Ok(()) }.boxed_local() )
+				},
+			)+
+
+			_ => Err( PeerErr::UnknownService{ ctx } ),
+		}
+	}
+
+
+	fn call_service( &self, msg: $wf, ctx: PeerErrCtx )
+
+		-> Result< $crate::external_deps::futures::future::LocalBoxFuture< 'static, Result<$wf, PeerErr> >, PeerErr >
+
+	{
+		let sid = msg.sid();
+		let ctx = ctx.sid( sid );
+
+		match sid
+		{
+			$(
+				_ if sid == <$services as Service>::sid() => self.local_call::<$services>( msg, ctx ),
+			)+
+
+			_ => Err( PeerErr::UnknownService{ ctx } ),
+		}
+	}
+}
+
+
+impl fmt::Debug for LocalServices
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "{}::LocalServices, handlers: {}", stringify!( $ns ), self.handlers.len() )
+	}
+}
+
+
+
+/// Implemented for every address that handles all the services in the tuple `L`, so it can be
+/// registered for all of them with [`Services::register_handler_for`].
+//
+pub trait HandlerFor<L>
+{
+	/// Register a clone of self as the handler for each service in `L`.
+	//
+	fn register_for( &self, services: &mut Services );
+}
+
+
+$crate::__service_map_handler_for!( S1 );
+$crate::__service_map_handler_for!( S1, S2 );
+$crate::__service_map_handler_for!( S1, S2, S3 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5, S6 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5, S6, S7 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5, S6, S7, S8 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5, S6, S7, S8, S9 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5, S6, S7, S8, S9, S10 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5, S6, S7, S8, S9, S10, S11 );
+$crate::__service_map_handler_for!( S1, S2, S3, S4, S5, S6, S7, S8, S9, S10, S11, S12 );
+
+	};
+}
+
+
+
+/// Implements `HandlerFor` for a tuple of services in a module generated by `service_map!`.
+/// Not meant to be used directly.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_map_handler_for
+{
+	( $($s: ident),+ ) =>
+	{
+		impl<H, $($s),+> HandlerFor<( $($s,)+ )> for H
+
+			where $(  H                     : Address<$s, Error=ThesErr>        , )+
+			      $(  $s                    : Service                           , )+
+			      $( <$s as Message>::Return: Serialize + DeserializeOwned      , )+
+		{
+			fn register_for( &self, services: &mut Services )
+			{
+				$( services.register_handler::<$s>( Address::<$s>::clone_box( self ) ); )+
+			}
+		}
+	};
+}
+
+
+
+/// Generates `RemoteAddr::call_concrete` and the `RemoteCall` future for `service_map!` when the
+/// `concrete_futures` option is set. Not meant to be used directly.
+//
+#[ doc( hidden ) ]
+#[ macro_export ]
+//
+macro_rules! __service_map_concrete_futures
+{
+	( false, $wf: path ) => {};
+
+	( true, $wf: path ) =>
+	{
+
+impl RemoteAddr
+{
+	/// Call a remote service. This does the same as `Address::call`, but returns a named future
+	/// instead of a boxed one, so the future itself is not boxed. The call is handed to the peer as a
+	/// [`ChannelCall`] through the `Sink` impl of its address, which still allocates the oneshot
+	/// channel for the response and the envelope in the mailbox of the peer.
+	//
+	pub fn call_concrete<S>( &mut self, msg: S ) -> RemoteCall<'_, S>
+
+		where  S                    : Service + Send,
+		      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+
+	{
+		let peer_id   = self.peer.id();
+		let peer_name = self.peer.name();
+
+		// Serialization can fail
+		//
+		let state = match self.build_call( msg )
+		{
+			Ok (call) => RemoteCallState::Sending( Some( call ) ),
+			Err(err ) => RemoteCallState::Failed ( Some( err  ) ),
+		};
+
+		RemoteCall { peer: &mut self.peer, state, peer_id, peer_name, _ghost: ::std::marker::PhantomData }
+	}
+}
+
+
+
+/// The future returned by [`RemoteAddr::call_concrete`]. It resolves to the same output as
+/// `Address::call` on `RemoteAddr`.
+//
+pub struct RemoteCall<'a, S>
+{
+	peer     : &'a mut Addr<Peer<$wf>>               ,
+	state    : RemoteCallState                       ,
+	peer_id  : usize                                 ,
+	peer_name: Option<Arc<str>>                      ,
+	_ghost   : ::std::marker::PhantomData<fn() -> S> ,
+}
+
+
+enum RemoteCallState
+{
+	// Serializing the message failed, the error is returned on the first poll.
+	//
+	Failed( Option<PeerErr> ),
+
+	// Waiting for room in the mailbox of the peer.
+	//
+	Sending( Option<Call<$wf>> ),
+
+	// Making sure the mailbox of the peer got the call.
+	//
+	Flushing( Option< $crate::external_deps::futures::channel::oneshot::Receiver< <Call<$wf> as Message>::Return > > ),
+
+	// The peer sends the call out over the connection.
+	//
+	Accepted( $crate::external_deps::futures::channel::oneshot::Receiver< <Call<$wf> as Message>::Return > ),
+
+	// Waiting for the response from the remote.
+	//
+	Waiting( $crate::external_deps::futures::channel::oneshot::Receiver<Result<$wf, ConnectionError>> ),
+
+	Done,
+}
+
+
+
+impl<'a, S> RemoteCall<'a, S>
+
+	where  S: Service,
+	      <S as Message>::Return: Serialize + DeserializeOwned,
+
+{
+	fn ctx( peer_id: usize, peer_name: &Option<Arc<str>>, context: &str ) -> PeerErrCtx
+	{
+		PeerErrCtx::default()
+
+			.peer_id  ( peer_id                  )
+			.peer_name( peer_name.clone()        )
+			.sid      ( <S as Service>::sid()    )
+			.context  ( context.to_string()      )
+	}
+
+
+	// The mailbox of the peer closed, so nobody will process the call.
+	//
+	fn peer_gone<T>( &mut self ) -> Poll< Result<T, PeerErr> >
+	{
+		let ctx = Self::ctx( self.peer_id, &self.peer_name, "Call remote service" );
+
+		self.state = RemoteCallState::Done;
+
+		Poll::Ready( Err( PeerErr::PeerGone{ ctx } ) )
+	}
+}
+
+
+
+impl<'a, S> Future for RemoteCall<'a, S>
+
+	where  S                    : Service + Send,
+	      <S as Message>::Return: Serialize + DeserializeOwned + Send,
+
+{
+	type Output = Result< <S as Message>::Return, PeerErr >;
+
+	fn poll( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Self::Output>
+	{
+		let this = self.get_mut();
+
+		loop { match &mut this.state
+		{
+			RemoteCallState::Failed( err ) =>
+			{
+				let err = err.take().expect( "RemoteCall polled after completion" );
+
+				this.state = RemoteCallState::Done;
+
+				return Poll::Ready( Err(err) );
+			}
+
+
+			RemoteCallState::Sending( call ) =>
+			{
+				let ready = match Sink::<ChannelCall<$wf>>::poll_ready( Pin::new( &mut *this.peer ), cx )
+				{
+					Poll::Pending      => return Poll::Pending,
+					Poll::Ready( res ) => res,
+				};
+
+				let call      = call.take().expect( "RemoteCall polled after completion" );
+				let (msg, rx) = ChannelCall::new( call );
+
+				// The mailbox of the peer is closed.
+				//
+				if ready.and_then( |_| Sink::start_send( Pin::new( &mut *this.peer ), msg ) ).is_err()
+				{
+					return this.peer_gone();
+				}
+
+				this.state = RemoteCallState::Flushing( Some( rx ) );
+			}
+
+
+			RemoteCallState::Flushing( rx ) =>
+			{
+				match Sink::<ChannelCall<$wf>>::poll_flush( Pin::new( &mut *this.peer ), cx )
+				{
+					Poll::Pending          => return Poll::Pending,
+					Poll::Ready( Err(_ ) ) => return this.peer_gone(),
+					Poll::Ready( Ok (()) ) => {}
+				}
+
+				let rx = rx.take().expect( "RemoteCall polled after completion" );
+
+				this.state = RemoteCallState::Accepted( rx );
+			}
+
+
+			RemoteCallState::Accepted( rx ) =>
+			{
+				let rx = match Pin::new( rx ).poll( cx )
+				{
+					Poll::Pending => return Poll::Pending,
+
+					Poll::Ready( Ok(Ok( rx )) ) => rx,
+
+					// The actual sending out over the network failed.
+					//
+					Poll::Ready( Ok(Err(_)) ) =>
+					{
+						let ctx = Self::ctx( this.peer_id, &this.peer_name, "Call remote service" );
+
+						this.state = RemoteCallState::Done;
+
+						return Poll::Ready( Err( PeerErr::ConnectionClosed{ ctx } ) );
+					}
+
+					// The peer stopped before processing the call.
+					//
+					Poll::Ready( Err(_) ) => return this.peer_gone(),
+				};
+
+				this.state = RemoteCallState::Waiting( rx );
+			}
+
+
+			RemoteCallState::Waiting( rx ) =>
+			{
+				let re = match Pin::new( rx ).poll( cx )
+				{
+					Poll::Pending => return Poll::Pending,
+
+					Poll::Ready( Ok(re) ) => re,
+
+					// Channel was canceled.
+					//
+					Poll::Ready( Err(_) ) =>
+					{
+						let ctx = Self::ctx( this.peer_id, &this.peer_name, "Peer stopped before receiving response from remote call" );
+
+						this.state = RemoteCallState::Done;
+
+						return Poll::Ready( Err( PeerErr::ConnectionClosed{ ctx } ) );
+					}
+				};
+
+				this.state = RemoteCallState::Done;
+
+				return Poll::Ready( RemoteAddr::decode_response::<S>( this.peer_id, this.peer_name.clone(), re ) );
+			}
+
+
+			RemoteCallState::Done => panic!( "RemoteCall polled after completion" ),
+		}}
+	}
+}
+
+
+
+impl<'a, S> fmt::Debug for RemoteCall<'a, S>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		let state = match self.state
+		{
+			RemoteCallState::Failed  (_) => "Failed"  ,
+			RemoteCallState::Sending (_) => "Sending" ,
+			RemoteCallState::Flushing(_) => "Flushing",
+			RemoteCallState::Accepted(_) => "Accepted",
+			RemoteCallState::Waiting (_) => "Waiting" ,
+			RemoteCallState::Done        => "Done"    ,
+		};
+
+		write!( f, "RemoteCall: peer id: {}, state: {}", self.peer_id, state )
+	}
+}
+
+	};
+}
@@ -4,50 +4,144 @@ use
 	crate     :: { import::*, PeerErr, wire_format::*        } ,
 	byteorder :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
 	std       :: { io::{ Seek, Write as IoWrite }            } ,
+	std       :: { time::{ SystemTime, UNIX_EPOCH }          } ,
 };
 
 
 mod encoder;
 mod decoder;
 mod decoder_noheap;
+mod delimited;
+mod timestamped;
+mod varint;
+
+#[ cfg( feature = "encrypt"  ) ] mod encrypt;
+#[ cfg( feature = "compress" ) ] mod compress;
 
 pub use encoder::*;
 pub use decoder::*;
 pub use decoder_noheap::*;
+pub use delimited::*;
+pub use timestamped::*;
+pub use varint::*;
+
+#[ cfg( feature = "encrypt"  ) ] pub use encrypt::*;
+#[ cfg( feature = "compress" ) ] pub use compress::*;
 
 const LEN_LEN: usize = 8; // u64
-const LEN_SID: usize = 8; // u64
+const LEN_SID: usize = ServiceID::SIZE; // u64, or u128 with the sid128 feature
 const LEN_CID: usize = 8; // u64
+const LEN_DDL: usize = 8; // u64
 
 
 const IDX_LEN: usize = 0;
 const IDX_SID: usize = LEN_LEN;
 const IDX_CID: usize = IDX_SID + LEN_SID;
-const IDX_MSG: usize = IDX_CID + LEN_CID;
+const IDX_DDL: usize = IDX_CID + LEN_CID;
+const IDX_MSG: usize = IDX_DDL + LEN_DDL;
 
 const LEN_HEADER: usize = IDX_MSG;
 
+// In the length field, marks a frame of which the null sid and connID are left out on the wire.
+//
+const ELIDED_FLAG: u64   = 1 << 63;
+const LEN_ELIDED : usize = LEN_SID + LEN_CID;
+
+// In the length field of a frame with the header left out, marks a control frame. Its code is in
+// bits 48 to 55, the length of a frame never comes near that.
+//
+const CONTROL_FLAG: u64 = 1 << 62;
+const CODE_SHIFT  : u64 = 48;
+const LEN_MASK    : u64 = ( 1 << CODE_SHIFT ) - 1;
+
+const LEN_HOP     : usize = 8; // u64
+const LEN_TRACE   : usize = 8; // u64
+const LEN_META    : usize = 8; // u64
+const LEN_CHANNEL : usize = 8; // u64
+const ROUTE_FLAG  : u64   = 1 << 63;
+const TRACE_FLAG  : u64   = 1 << 62;
+const META_FLAG   : u64   = 1 << 61;
+const CHANNEL_FLAG: u64   = 1 << 60;
+const FLAGS       : u64   = ROUTE_FLAG | TRACE_FLAG | META_FLAG | CHANNEL_FLAG;
+
+
+// The length field of a frame of which the header is left out, see Encoder::elide_null_header. `sid`
+// is either null or a control sid.
+//
+fn elided_len_field( len: u64, sid: ServiceID ) -> u64
+{
+	match sid.control_code()
+	{
+		Some( code ) => len | ELIDED_FLAG | CONTROL_FLAG | u64::from( code ) << CODE_SHIFT,
+		None         => len | ELIDED_FLAG,
+	}
+}
+
+
+// Split the length field of an incoming frame into the length of the whole frame and, when the
+// header was left out, the sid to put back. The connID is null in that case.
+//
+fn read_len_field( field: u64 ) -> ( u64, Option<ServiceID> )
+{
+	if field & ELIDED_FLAG == 0
+	{
+		return ( field, None );
+	}
+
+	let len = field & LEN_MASK;
+
+	match field & CONTROL_FLAG != 0
+	{
+		true  => ( len, Some( ServiceID::control( ( field >> CODE_SHIFT ) as u8 ) ) ),
+		false => ( len, Some( ServiceID::null() )                                  ),
+	}
+}
+
 
 
 /// A multi service message.
 ///
 /// The message format is as follows:
 ///
-/// The format is little endian.
+/// All numbers are little endian, whatever the endianness of the platform. This is part of the wire
+/// contract, implementations in other languages can rely on it.
 ///
-/// length  : the length in bytes of the payload
+/// length  : the length in bytes of the whole frame, including the length field itself
 /// sid     : user chosen sid for the service
 /// connID  : in case of a call, which requires a response, a unique random number
 ///           in case of a send, which does not require response, zero
+/// deadline: milliseconds since the unix epoch after which the caller no longer wants
+///           a response, zero if there is no deadline
 /// message : the request message serialized with the specified codec
 ///
 /// ```text
-/// u64 length + payload ------------------------------------------|
-///              8 bytes sid | 8 bytes connID | serialized message |
-///              u64 LE      | u64 LE         | variable           |
-/// ----------------------------------------------------------------
+/// u64 length + payload -------------------------------------------------------------|
+///              8 bytes sid | 8 bytes connID | 8 bytes deadline | serialized message |
+///              u64 LE      | u64 LE         | u64 LE           | variable           |
+/// -----------------------------------------------------------------------------------
 /// ```
 ///
+/// The four highest bits of the deadline field are flags for optional metadata after the message.
+/// When the third highest bit is set, the message is followed by headers, see [`WireFormat::meta`].
+/// Each header is a key and a value, both a u32 LE length followed by UTF-8. The headers are followed
+/// by their total size in bytes. When the fourth highest bit is set, the channel id comes next, see
+/// [`WireFormat::channel`]. When the second highest bit is set, a trace id follows, see
+/// [`WireFormat::trace_id`]. When the highest bit is set, the frame ends with a route trace, see
+/// [`WireFormat::route`]. It holds the ids of the relaying peers followed by their number:
+///
+/// ```text
+/// serialized message | headers  | headers size | channel | trace id | hop ids      | number of hops |
+/// variable           | variable | u64 LE       | u64 LE  | u64 LE   | n x u64 LE   | u64 LE         |
+/// ```
+///
+/// With the `sid128` feature, the sid is 16 bytes, a u128 LE, see [`ServiceID`].
+///
+/// When both the sid and the connID are null, an [Encoder] can leave them out on the wire, see
+/// [`Encoder::elide_null_header`]. It sets the highest bit of the length field, which keeps the length
+/// of the whole frame, so the decoders know to put them back. Frames never come near that size. The
+/// same goes for control frames, which have a null connID and a sid from [`ServiceID::control`]. For
+/// those the next bit is set as well and bits 48 to 55 hold the control code.
+///
 /// As soon as a codec determines from the length field that the entire message is read,
 /// they can create a Multiservice from the bytes. In general creating a Multiservice
 /// object should not perform a copy of the serialized message. It just provides a window
@@ -86,6 +180,48 @@ const LEN_HEADER: usize = IDX_MSG;
 pub struct ThesWF
 {
 	data: io::Cursor< Vec<u8> >,
+	home: BufferHome           ,
+}
+
+
+// Where the buffer of a frame goes when it's dropped, see WireFormat::return_to. It doesn't take part
+// in comparing frames and clones don't inherit it, since they have a buffer of their own.
+//
+#[ derive( Default ) ]
+//
+struct BufferHome( Option<Arc<dyn BufferProvider>> );
+
+
+impl Clone for BufferHome
+{
+	fn clone( &self ) -> Self
+	{
+		Self( None )
+	}
+}
+
+
+impl PartialEq for BufferHome
+{
+	fn eq( &self, _other: &Self ) -> bool
+	{
+		true
+	}
+}
+
+impl Eq for BufferHome {}
+
+
+impl fmt::Debug for BufferHome
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		match self.0
+		{
+			Some(_) => write!( f, "BufferHome( provider )" ),
+			None    => write!( f, "BufferHome( None )"     ),
+		}
+	}
 }
 
 
@@ -105,11 +241,140 @@ impl ThesWF
 		self.data.get_ref()
 	}
 
+	/// Like [`WireFormat::with_capacity`], but the buffer comes from `buffers` and goes back to it when
+	/// the frame is dropped. Fails gracefully with [`WireErr::OutOfMemory`] when there is no buffer to
+	/// be had.
+	//
+	pub fn try_with_capacity( size: usize, buffers: &Arc<dyn BufferProvider> ) -> Result<Self, WireErr>
+	{
+		let mut buf = buffers.alloc( size + LEN_HEADER )?;
+
+		// Keeps the capacity and the zeroed header.
+		//
+		buf.truncate( LEN_HEADER );
+
+		let mut wf = Self { data: io::Cursor::new( buf ), home: BufferHome( Some( buffers.clone() ) ) };
+
+		wf.set_len( LEN_HEADER as u64 );
+
+		Ok( wf )
+	}
+
+
 	fn set_len( &mut self, len: u64 ) -> &mut Self
 	{
 		self.data.get_mut()[ IDX_LEN..IDX_LEN+LEN_LEN ].as_mut().write_u64::<LittleEndian>( len ).unwrap();
 		self
 	}
+
+
+	// The deadline field, which also holds the flags for the headers, the trace id and the route trace.
+	//
+	fn deadline_field( &self ) -> u64
+	{
+		self.data.get_ref()[ IDX_DDL..IDX_DDL+LEN_DDL ].as_ref().read_u64::<LittleEndian>().unwrap()
+	}
+
+
+	fn set_deadline_field( &mut self, field: u64 )
+	{
+		self.data.get_mut()[ IDX_DDL..IDX_DDL+LEN_DDL ].as_mut().write_u64::<LittleEndian>( field ).unwrap();
+	}
+
+
+	// The size in bytes of the route trace at the end of the frame, 0 if there is none.
+	// try_from verifies that it fits in the frame.
+	//
+	fn route_len( &self ) -> usize
+	{
+		if self.deadline_field() & ROUTE_FLAG == 0 { return 0 }
+
+		let buf  = self.as_buf();
+		let hops = buf[ buf.len()-LEN_HOP.. ].as_ref().read_u64::<LittleEndian>().unwrap();
+
+		// Doesn't overflow, try_from checked that the hops fit in the frame.
+		//
+		(hops as usize + 1) * LEN_HOP
+	}
+
+
+	// The size in bytes of the trace id, 0 if there is none.
+	//
+	fn trace_len( &self ) -> usize
+	{
+		match self.deadline_field() & TRACE_FLAG
+		{
+			0 => 0,
+			_ => LEN_TRACE,
+		}
+	}
+
+
+	// The size in bytes of the channel id, 0 if there is none.
+	//
+	fn channel_len( &self ) -> usize
+	{
+		match self.deadline_field() & CHANNEL_FLAG
+		{
+			0 => 0,
+			_ => LEN_CHANNEL,
+		}
+	}
+
+
+	// The size in bytes of the headers, their size field included, 0 if there are none.
+	// try_from verifies that they fit in the frame.
+	//
+	fn meta_len( &self ) -> usize
+	{
+		if self.deadline_field() & META_FLAG == 0 { return 0 }
+
+		let buf  = self.as_buf();
+		let end  = buf.len() - self.channel_len() - self.trace_len() - self.route_len();
+		let size = buf[ end-LEN_META..end ].as_ref().read_u64::<LittleEndian>().unwrap();
+
+		// Doesn't overflow, try_from checked that the headers fit in the frame.
+		//
+		size as usize + LEN_META
+	}
+
+
+	// The size in bytes of the metadata after the message.
+	//
+	fn trailer_len( &self ) -> usize
+	{
+		self.meta_len() + self.channel_len() + self.trace_len() + self.route_len()
+	}
+
+
+	// The headers, without their size field. None if they are malformed.
+	//
+	fn parse_meta( mut bytes: &[u8] ) -> Option<Metadata>
+	{
+		fn string( bytes: &mut &[u8] ) -> Option<String>
+		{
+			let len = bytes.read_u32::<LittleEndian>().ok()? as usize;
+
+			if bytes.len() < len { return None }
+
+			let (s, rest) = bytes.split_at( len );
+			*bytes = rest;
+
+			String::from_utf8( s.to_vec() ).ok()
+		}
+
+		let mut meta = Metadata::new();
+
+		while !bytes.is_empty()
+		{
+			let key   = string( &mut bytes )?;
+			let value = string( &mut bytes )?;
+
+			meta.insert( key, value );
+		}
+
+		Some( meta )
+	}
 }
 
 
@@ -127,13 +392,13 @@ impl WireFormat for ThesWF
 	{
 		// TODO: is this the most efficient way?
 		//
-		self.data.get_ref()[ IDX_SID..IDX_SID+LEN_SID ].as_ref().read_u64::<LittleEndian>().unwrap().into()
+		ServiceID::read_le( &mut self.data.get_ref()[ IDX_SID..IDX_SID+LEN_SID ].as_ref() ).unwrap()
 	}
 
 
 	fn set_sid( &mut self, sid: ServiceID ) -> &mut Self
 	{
-		self.data.get_mut()[ IDX_SID..IDX_SID+LEN_SID ].as_mut().write_u64::<LittleEndian>( sid.into() ).unwrap();
+		sid.write_le( &mut self.data.get_mut()[ IDX_SID..IDX_SID+LEN_SID ].as_mut() ).unwrap();
 		self
 	}
 
@@ -154,11 +419,212 @@ impl WireFormat for ThesWF
 	}
 
 
+	/// The deadline is stored with millisecond precision. A deadline before the unix epoch
+	/// is stored as the epoch itself, which is in the past anyway.
+	//
+	fn deadline( &self ) -> Option<SystemTime>
+	{
+		let millis = self.deadline_field() & !FLAGS;
+
+		match millis
+		{
+			0 => None,
+			_ => Some( UNIX_EPOCH + Duration::from_millis( millis ) ),
+		}
+	}
+
+
+	fn set_deadline( &mut self, deadline: Option<SystemTime> ) -> &mut Self
+	{
+		// Zero means no deadline, so the epoch itself is stored as 1ms after it.
+		//
+		let millis = deadline.map( |d|
+		{
+			let since = d.duration_since( UNIX_EPOCH ).unwrap_or_default().as_millis();
+
+			u64::try_from( since ).unwrap_or( CHANNEL_FLAG - 1 ).clamp( 1, CHANNEL_FLAG - 1 )
+
+		}).unwrap_or( 0 );
+
+		let flags = self.deadline_field() & FLAGS;
+
+		self.set_deadline_field( millis | flags );
+		self
+	}
+
+
+	fn route( &self ) -> Option< Vec<usize> >
+	{
+		let trailer = self.route_len();
+
+		if trailer == 0 { return None }
+
+		let buf  = self.as_buf();
+		let hops = &buf[ buf.len()-trailer..buf.len()-LEN_HOP ];
+
+		Some( hops.chunks_exact( LEN_HOP ).map( |mut hop|
+
+			usize::try_from( hop.read_u64::<LittleEndian>().unwrap() ).unwrap_or( usize::MAX )
+
+		).collect() )
+	}
+
+
+	fn set_route( &mut self, route: Option<&[usize]> ) -> &mut Self
+	{
+		let end  = self.as_buf().len() - self.route_len();
+		let flag = route.map_or( 0, |_| ROUTE_FLAG );
+		let ddl  = self.deadline_field() & !ROUTE_FLAG;
+
+		self.data.get_mut().truncate( end );
+		self.set_deadline_field( ddl | flag );
+
+		if let Some( route ) = route
+		{
+			let buf = self.data.get_mut();
+
+			// unwrap: writing to a Vec can't fail.
+			//
+			for hop in route
+			{
+				buf.write_u64::<LittleEndian>( *hop as u64 ).unwrap();
+			}
+
+			buf.write_u64::<LittleEndian>( route.len() as u64 ).unwrap();
+		}
+
+		let len = self.as_buf().len() as u64;
+		self.set_len( len )
+	}
+
+
+	fn trace_id( &self ) -> Option<u64>
+	{
+		if self.trace_len() == 0 { return None }
+
+		let buf   = self.as_buf();
+		let start = buf.len() - self.route_len() - LEN_TRACE;
+
+		Some( buf[ start..start+LEN_TRACE ].as_ref().read_u64::<LittleEndian>().unwrap() )
+	}
+
+
+	fn set_trace_id( &mut self, trace_id: Option<u64> ) -> &mut Self
+	{
+		// The trace id sits right in front of the route trace.
+		//
+		let end   = self.as_buf().len() - self.route_len();
+		let start = end - self.trace_len();
+		let flag  = trace_id.map_or( 0, |_| TRACE_FLAG );
+		let field = self.deadline_field() & !TRACE_FLAG;
+
+		let bytes = trace_id.map( u64::to_le_bytes );
+
+		self.data.get_mut().splice( start..end, bytes.iter().flatten().copied() );
+		self.set_deadline_field( field | flag );
+
+		let len = self.as_buf().len() as u64;
+		self.set_len( len )
+	}
+
+
+	fn meta( &self ) -> Option<Metadata>
+	{
+		let len = self.meta_len();
+
+		if len == 0 { return None }
+
+		let buf   = self.as_buf();
+		let start = buf.len() - self.trailer_len();
+
+		// try_from checked that they parse.
+		//
+		Self::parse_meta( &buf[ start..start+len-LEN_META ] )
+	}
+
+
+	fn set_meta( &mut self, meta: Option<&Metadata> ) -> &mut Self
+	{
+		// The headers sit right after the message. Sorted so the same headers always give the same frame.
+		//
+		let start = self.as_buf().len() - self.trailer_len();
+		let end   = start + self.meta_len();
+		let flag  = meta.map_or( 0, |_| META_FLAG );
+		let field = self.deadline_field() & !META_FLAG;
+
+		let mut bytes = Vec::new();
+
+		if let Some( meta ) = meta
+		{
+			let mut headers: Vec<_> = meta.iter().collect();
+			headers.sort_unstable();
+
+			// unwrap: writing to a Vec can't fail.
+			//
+			for s in headers.into_iter().flat_map( |(k, v)| [ k, v ] )
+			{
+				let len = u32::try_from( s.len() ).expect( "header smaller than 4GiB" );
+
+				bytes.write_u32::<LittleEndian>( len ).unwrap();
+				bytes.extend_from_slice( s.as_bytes() );
+			}
+
+			let size = bytes.len() as u64;
+			bytes.write_u64::<LittleEndian>( size ).unwrap();
+		}
+
+		self.data.get_mut().splice( start..end, bytes );
+		self.set_deadline_field( field | flag );
+
+		let len = self.as_buf().len() as u64;
+		self.set_len( len )
+	}
+
+
+	fn channel( &self ) -> Option<u64>
+	{
+		if self.channel_len() == 0 { return None }
+
+		let buf   = self.as_buf();
+		let start = buf.len() - self.route_len() - self.trace_len() - LEN_CHANNEL;
+
+		Some( buf[ start..start+LEN_CHANNEL ].as_ref().read_u64::<LittleEndian>().unwrap() )
+	}
+
+
+	fn set_channel( &mut self, channel: Option<u64> ) -> &mut Self
+	{
+		// The channel sits between the headers and the trace id.
+		//
+		let end   = self.as_buf().len() - self.route_len() - self.trace_len();
+		let start = end - self.channel_len();
+		let flag  = channel.map_or( 0, |_| CHANNEL_FLAG );
+		let field = self.deadline_field() & !CHANNEL_FLAG;
+
+		let bytes = channel.map( u64::to_le_bytes );
+
+		self.data.get_mut().splice( start..end, bytes.iter().flatten().copied() );
+		self.set_deadline_field( field | flag );
+
+		let len = self.as_buf().len() as u64;
+		self.set_len( len )
+	}
+
+
+	fn return_to( &mut self, buffers: Arc<dyn BufferProvider> ) -> &mut Self
+	{
+		self.home = BufferHome( Some( buffers ) );
+		self
+	}
+
+
 	/// The serialized payload message.
 	//
 	fn msg( &self ) -> &[u8]
 	{
-		&self.data.get_ref()[ IDX_MSG.. ]
+		let buf = self.as_buf();
+
+		&buf[ IDX_MSG..buf.len()-self.trailer_len() ]
 	}
 
 	/// The total length of the ThesWF in bytes (header+payload)
@@ -176,7 +642,8 @@ impl WireFormat for ThesWF
 
 		let mut wf = Self
 		{
-			data: io::Cursor::new( Vec::with_capacity( size + LEN_HEADER ) )
+			data: io::Cursor::new( Vec::with_capacity( size + LEN_HEADER ) ) ,
+			home: BufferHome::default()                                      ,
 		};
 
 		wf.data.write( &[0u8; LEN_HEADER] ).unwrap();
@@ -192,6 +659,20 @@ impl io::Write for ThesWF
 {
 	fn write( &mut self, buf: &[u8] ) -> io::Result<usize>
 	{
+		// The payload goes in front of the route trace.
+		//
+		let trailer = self.trailer_len();
+
+		if trailer > 0
+		{
+			let end = self.as_buf().len() - trailer;
+
+			self.data.get_mut().splice( end..end, buf.iter().copied() );
+			self.set_len( self.len() + buf.len() as u64 );
+
+			return Ok( buf.len() );
+		}
+
 		self.data.seek( io::SeekFrom::End(0) )?;
 
 		self.data.write( buf ).map(|written|
@@ -220,7 +701,8 @@ impl Default for ThesWF
 	{
 		let mut wf = Self
 		{
-			data: io::Cursor::new( Vec::with_capacity( LEN_HEADER *2 ) )
+			data: io::Cursor::new( Vec::with_capacity( LEN_HEADER *2 ) ) ,
+			home: BufferHome::default()                                  ,
 		};
 
 		wf.write( &[0u8; LEN_HEADER] ).unwrap();
@@ -231,6 +713,31 @@ impl Default for ThesWF
 
 
 
+impl Drop for ThesWF
+{
+	fn drop( &mut self )
+	{
+		if let Some( buffers ) = self.home.0.take()
+		{
+			buffers.release( std::mem::take( self.data.get_mut() ) );
+		}
+	}
+}
+
+
+
+impl AsRef<[u8]> for ThesWF
+{
+	/// The whole frame as it goes on the wire, length field included.
+	//
+	fn as_ref( &self ) -> &[u8]
+	{
+		self.as_buf()
+	}
+}
+
+
+
 impl TryFrom< Vec<u8> > for ThesWF
 {
 	type Error = WireErr;
@@ -243,10 +750,54 @@ impl TryFrom< Vec<u8> > for ThesWF
 		//
 		if data.len() < LEN_HEADER
 		{
-			return Err( WireErr::Deserialize{ context: "ThesWF: not enough bytes even for the header.".to_string() } );
+			return Err( WireErr::Deserialize{ context: "ThesWF: not enough bytes even for the header.".to_string(), source: None } );
+		}
+
+		let wf = Self { data: io::Cursor::new(data), home: BufferHome::default() };
+
+		if wf.trace_len() + wf.channel_len() > wf.as_buf().len() - LEN_HEADER
+		{
+			return Err( WireErr::Deserialize{ context: "ThesWF: the trace id or the channel doesn't fit in the frame.".to_string(), source: None } );
+		}
+
+		if wf.deadline_field() & ROUTE_FLAG != 0
+		{
+			let room = wf.as_buf().len() - LEN_HEADER - wf.trace_len() - wf.channel_len();
+
+			let fits = room >= LEN_HOP && wf.as_buf()[ wf.as_buf().len()-LEN_HOP.. ].as_ref()
+
+				.read_u64::<LittleEndian>().unwrap()
+				.checked_add( 1 )
+				.and_then( |n| n.checked_mul( LEN_HOP as u64 ) )
+				.map_or( false, |trailer| trailer <= room as u64 )
+			;
+
+			if !fits
+			{
+				return Err( WireErr::Deserialize{ context: "ThesWF: the route trace doesn't fit in the frame.".to_string(), source: None } );
+			}
 		}
 
-		Ok( Self { data: io::Cursor::new(data) } )
+		if wf.deadline_field() & META_FLAG != 0
+		{
+			let buf  = wf.as_buf();
+			let end  = buf.len() - wf.channel_len() - wf.trace_len() - wf.route_len();
+			let room = end - LEN_HEADER;
+
+			let fits = room >= LEN_META && buf[ end-LEN_META..end ].as_ref()
+
+				.read_u64::<LittleEndian>().unwrap()
+				.checked_add( LEN_META as u64 )
+				.map_or( false, |size| size <= room as u64 )
+			;
+
+			if !fits || wf.meta().is_none()
+			{
+				return Err( WireErr::Deserialize{ context: "ThesWF: the headers don't fit in the frame or are malformed.".to_string(), source: None } );
+			}
+		}
+
+		Ok( wf )
 	}
 }
 
@@ -263,11 +814,23 @@ mod tests
 	//   - with_capacity
 	// - set_len/len equality and check the actual data
 	// - set_sid/sid equality and check the actual data
+	// - with sid128, the sid is the full 128 bits on the wire
 	// - set_cid/cid equality and check the actual data
+	// - set_deadline/deadline equality, zero means no deadline
+	// - set_route/route equality, the payload stays in front of the route and the deadline is kept
+	// - set_trace_id/trace_id equality, it sits between the payload and the route and the deadline is kept
+	// - set_channel/channel equality, it sits between the headers and the trace id and the deadline is kept
+	// - the exact byte layout of a frame
+	// - try_from rejects a route trace that doesn't fit in the frame
+	// - the key only depends on sid and cid
+	// - progress gets reported while decoding a frame that arrives in pieces, with both decoders
+	// - an error from the progress callback of Decoder aborts the frame and closes the stream
+	// - the codecs pass the test suite for ThesWF and for another wire format
 	//
 	use super::{ *, assert_eq };
-	use crate::{ wire_format::TestSuite };
+	use crate::{ wire_format::{ TestSuite, DummyWF } };
 	use futures::io::{ WriteHalf, ReadHalf };
+	use futures::stream::TryStreamExt;
 
 
 	#[test]
@@ -282,6 +845,7 @@ mod tests
 
 		assert!( wf.sid().is_null() );
 		assert!( wf.cid().is_null() );
+		assert!( wf.deadline().is_none() );
 
 		assert_eq!( 0, wf.msg().len() );
 	}
@@ -327,6 +891,30 @@ mod tests
 	}
 
 
+	#[ cfg( feature = "sid128" ) ]
+	#[test]
+	//
+	fn set_sid_128()
+	{
+		let mut wf = ThesWF::default();
+		let sid = ServiceID::from_seed( b"namespace::Typename" );
+		let raw: u128 = sid.into();
+
+		// The low half is the 64 bit sid, the high half is hashed with another seed.
+		//
+		let mut h = XxHash64::default();
+		h.write( b"namespace::Typename" );
+
+		assert_eq!( h.finish(), raw as u64 );
+		assert_ne!( 0, raw >> 64 );
+
+		wf.set_sid( sid );
+		assert_eq!( wf.sid(), sid );
+		assert_eq!( 40, LEN_HEADER );
+		assert_eq!( &raw.to_le_bytes()[..], &wf.as_buf()[ IDX_SID..IDX_SID+LEN_SID ] );
+	}
+
+
 	#[test]
 	//
 	fn set_cid()
@@ -339,6 +927,274 @@ mod tests
 	}
 
 
+	#[test]
+	//
+	fn set_deadline()
+	{
+		let mut wf = ThesWF::default();
+		let deadline = UNIX_EPOCH + Duration::from_millis( 1_600_000_000_123 );
+
+		wf.set_deadline( Some( deadline ) );
+		assert_eq!( wf.deadline(), Some( deadline ) );
+
+		wf.set_deadline( None );
+		assert_eq!( wf.deadline(), None );
+	}
+
+
+	#[test]
+	//
+	fn set_route()
+	{
+		let mut wf = ThesWF::default();
+		let deadline = UNIX_EPOCH + Duration::from_millis( 1_600_000_000_123 );
+
+		wf.set_deadline( Some( deadline ) );
+		wf.write_all( b"hello" ).unwrap();
+		assert_eq!( wf.route(), None );
+
+		wf.set_route( Some( &[] ) );
+		assert_eq!( wf.route(), Some( vec![] ) );
+
+		wf.set_route( Some( &[ 3, 7 ] ) );
+		wf.write_all( b" world" ).unwrap();
+
+		assert_eq!( wf.route()   , Some( vec![ 3, 7 ] )                    );
+		assert_eq!( wf.msg()     , b"hello world"                          );
+		assert_eq!( wf.deadline(), Some( deadline )                        );
+		assert_eq!( wf.len()     , ( LEN_HEADER + 11 + 3*LEN_HOP ) as u64  );
+
+		assert_eq!( wf, ThesWF::try_from( wf.as_buf().to_vec() ).unwrap() );
+
+		wf.set_route( None );
+
+		assert_eq!( wf.route()   , None             );
+		assert_eq!( wf.msg()     , b"hello world"   );
+		assert_eq!( wf.deadline(), Some( deadline ) );
+	}
+
+
+	#[test]
+	//
+	fn set_trace_id()
+	{
+		let mut wf = ThesWF::default();
+		let deadline = UNIX_EPOCH + Duration::from_millis( 1_600_000_000_123 );
+
+		wf.set_deadline( Some( deadline ) );
+		wf.write_all( b"hello" ).unwrap();
+		assert_eq!( wf.trace_id(), None );
+
+		wf.set_route( Some( &[ 3, 7 ] ) );
+		wf.set_trace_id( Some( 42 ) );
+		wf.write_all( b" world" ).unwrap();
+
+		assert_eq!( wf.trace_id(), Some( 42 )                                        );
+		assert_eq!( wf.route()   , Some( vec![ 3, 7 ] )                              );
+		assert_eq!( wf.msg()     , b"hello world"                                    );
+		assert_eq!( wf.deadline(), Some( deadline )                                  );
+		assert_eq!( wf.len()     , ( LEN_HEADER + 11 + LEN_TRACE + 3*LEN_HOP ) as u64 );
+
+		assert_eq!( wf, ThesWF::try_from( wf.as_buf().to_vec() ).unwrap() );
+
+		wf.set_trace_id( Some( 43 ) );
+		assert_eq!( wf.trace_id(), Some( 43 ) );
+
+		wf.set_route( None );
+		wf.set_trace_id( None );
+
+		assert_eq!( wf.trace_id(), None                       );
+		assert_eq!( wf.msg()     , b"hello world"             );
+		assert_eq!( wf.deadline(), Some( deadline )           );
+		assert_eq!( wf.len()     , ( LEN_HEADER + 11 ) as u64 );
+	}
+
+
+	#[test]
+	//
+	fn set_meta()
+	{
+		let mut wf = ThesWF::default();
+		let deadline = UNIX_EPOCH + Duration::from_millis( 1_600_000_000_123 );
+
+		let meta: Metadata = vec!
+		[
+			( "tenant".to_string(), "acme"    .to_string() ),
+			( "auth"  .to_string(), "token 🔑".to_string() ),
+
+		].into_iter().collect();
+
+		wf.set_deadline( Some( deadline ) );
+		wf.write_all( b"hello" ).unwrap();
+		assert_eq!( wf.meta(), None );
+
+		wf.set_route   ( Some( &[ 3 ] ) );
+		wf.set_trace_id( Some( 42 )     );
+		wf.set_meta    ( Some( &meta )  );
+		wf.write_all( b" world" ).unwrap();
+
+		assert_eq!( wf.meta()    , Some( meta.clone() ) );
+		assert_eq!( wf.trace_id(), Some( 42 )           );
+		assert_eq!( wf.route()   , Some( vec![ 3 ] )    );
+		assert_eq!( wf.msg()     , b"hello world"       );
+		assert_eq!( wf.deadline(), Some( deadline )     );
+
+		assert_eq!( wf, ThesWF::try_from( wf.as_buf().to_vec() ).unwrap() );
+
+		wf.set_meta( Some( &Metadata::new() ) );
+		assert_eq!( wf.meta(), Some( Metadata::new() ) );
+
+		wf.set_meta( None );
+
+		assert_eq!( wf.meta()    , None                                              );
+		assert_eq!( wf.trace_id(), Some( 42 )                                        );
+		assert_eq!( wf.route()   , Some( vec![ 3 ] )                                 );
+		assert_eq!( wf.msg()     , b"hello world"                                    );
+		assert_eq!( wf.len()     , ( LEN_HEADER + 11 + LEN_TRACE + 2*LEN_HOP ) as u64 );
+	}
+
+
+	#[test]
+	//
+	fn set_channel()
+	{
+		let mut wf = ThesWF::default();
+		let deadline = UNIX_EPOCH + Duration::from_millis( 1_600_000_000_123 );
+
+		let meta: Metadata = vec![ ( "tenant".to_string(), "acme".to_string() ) ].into_iter().collect();
+
+		wf.set_deadline( Some( deadline ) );
+		wf.write_all( b"hello" ).unwrap();
+		assert_eq!( wf.channel(), None );
+
+		wf.set_route   ( Some( &[ 3 ] ) );
+		wf.set_trace_id( Some( 42 )     );
+		wf.set_channel ( Some( 7 )      );
+		wf.set_meta    ( Some( &meta )  );
+		wf.write_all( b" world" ).unwrap();
+
+		assert_eq!( wf.channel() , Some( 7 )            );
+		assert_eq!( wf.meta()    , Some( meta.clone() ) );
+		assert_eq!( wf.trace_id(), Some( 42 )           );
+		assert_eq!( wf.route()   , Some( vec![ 3 ] )    );
+		assert_eq!( wf.msg()     , b"hello world"       );
+		assert_eq!( wf.deadline(), Some( deadline )     );
+
+		assert_eq!( wf, ThesWF::try_from( wf.as_buf().to_vec() ).unwrap() );
+
+		wf.set_channel( Some( 8 ) );
+		assert_eq!( wf.channel(), Some( 8 ) );
+
+		wf.set_meta   ( None );
+		wf.set_channel( None );
+
+		assert_eq!( wf.channel() , None                                              );
+		assert_eq!( wf.trace_id(), Some( 42 )                                        );
+		assert_eq!( wf.route()   , Some( vec![ 3 ] )                                 );
+		assert_eq!( wf.msg()     , b"hello world"                                    );
+		assert_eq!( wf.deadline(), Some( deadline )                                  );
+		assert_eq!( wf.len()     , ( LEN_HEADER + 11 + LEN_TRACE + 2*LEN_HOP ) as u64 );
+	}
+
+
+	#[test]
+	//
+	fn meta_malformed()
+	{
+		let mut wf = ThesWF::default();
+		wf.set_meta( Some( &vec![ ( "key".to_string(), "value".to_string() ) ].into_iter().collect() ) );
+
+		let mut buf = wf.as_buf().to_vec();
+		let     len = buf.len();
+
+		// Claim the key is longer than the headers.
+		//
+		buf[ LEN_HEADER ] = 200;
+
+		assert!( ThesWF::try_from( buf.clone() ).is_err() );
+
+		// Claim the headers are longer than the frame.
+		//
+		buf[ len-LEN_META ] = 200;
+
+		assert!( ThesWF::try_from( buf ).is_err() );
+	}
+
+
+	// Other implementations rely on this, so if it has to change, that's a breaking change of the
+	// wire format.
+	//
+	#[ cfg(not( feature = "sid128" )) ]
+	#[test]
+	//
+	fn byte_layout()
+	{
+		let mut wf = ThesWF::default();
+
+		wf.set_sid( ServiceID::from( 1_u64 ) );
+		wf.set_cid( ConnID::from( 2_u64 ) );
+		wf.set_deadline( Some( UNIX_EPOCH + Duration::from_millis( 3 ) ) );
+		wf.write_all( b"ab" ).unwrap();
+
+		let expect: &[u8] =
+		&[
+			34, 0, 0, 0, 0, 0, 0, 0, // length
+			 1, 0, 0, 0, 0, 0, 0, 0, // sid
+			 2, 0, 0, 0, 0, 0, 0, 0, // cid
+			 3, 0, 0, 0, 0, 0, 0, 0, // deadline
+			 b'a', b'b'            , // message
+		];
+
+		assert_eq!( 8 , IDX_SID );
+		assert_eq!( 16, IDX_CID );
+		assert_eq!( 24, IDX_DDL );
+		assert_eq!( 32, IDX_MSG );
+
+		assert_eq!( expect, wf.as_buf() );
+		assert_eq!( wf, ThesWF::try_from( expect.to_vec() ).unwrap() );
+	}
+
+
+	#[test]
+	//
+	fn route_too_long()
+	{
+		let mut wf = ThesWF::default();
+
+		wf.set_route( Some( &[ 3 ] ) );
+
+		let mut data = wf.as_buf().to_vec();
+		let end      = data.len();
+
+		data[ end-LEN_HOP.. ].as_mut().write_u64::<LittleEndian>( 2 ).unwrap();
+
+		assert!( ThesWF::try_from( data ).is_err() );
+	}
+
+
+	#[test]
+	//
+	fn key()
+	{
+		let sid = ServiceID::from_seed( &[ 1, 2, 3 ] );
+		let cid = ConnID::random();
+
+		let mut a = ThesWF::default();
+		let mut b = ThesWF::default();
+
+		a.set_sid( sid ).set_cid( cid ).write_all( b"first"  ).unwrap();
+		b.set_sid( sid ).set_cid( cid ).write_all( b"second" ).unwrap();
+
+		assert_ne!( a, b );
+		assert_eq!( a.key(), b.key() );
+		assert_eq!( RequestKey{ sid, cid }, a.key() );
+
+		b.set_cid( ConnID::random() );
+
+		assert_ne!( a.key(), b.key() );
+	}
+
+
 	fn frame( socket: Box<dyn MockConnection>, max_size: usize ) -> (Encoder<WriteHalf<Box<dyn MockConnection>>>, Decoder<ReadHalf<Box<dyn MockConnection>>>)
 	{
 		let (reader, writer) = socket.split();
@@ -379,4 +1235,149 @@ mod tests
 
 		test_suite.run().await;
 	}
+
+
+	type DummyFrame<St> = (Encoder<WriteHalf<Box<dyn MockConnection>>, DummyWF>, St);
+
+
+	fn frame_dummy( socket: Box<dyn MockConnection>, max_size: usize ) -> DummyFrame< Decoder<ReadHalf<Box<dyn MockConnection>>, DummyWF> >
+	{
+		let (reader, writer) = socket.split();
+
+		let stream = Decoder::with_format( reader, max_size );
+		let sink   = Encoder::with_format( writer, max_size );
+
+		(sink, stream)
+	}
+
+
+	// The codecs work for other wire formats than ThesWF.
+	//
+	#[async_std::test]
+	//
+	async fn decoder_encoder_other_format()
+	{
+		let test_suite = TestSuite::new( frame_dummy );
+
+		test_suite.run().await;
+	}
+
+
+	fn frame_dummy_noheap( socket: Box<dyn MockConnection>, max_size: usize ) -> DummyFrame< DecoderNoHeap<ReadHalf<Box<dyn MockConnection>>, DummyWF> >
+	{
+		let (reader, writer) = socket.split();
+
+		let stream = DecoderNoHeap::with_format( reader, max_size );
+		let sink   = Encoder::with_format( writer, max_size );
+
+		(sink, stream)
+	}
+
+
+	#[async_std::test]
+	//
+	async fn decoder_encoder_noheap_other_format()
+	{
+		let test_suite = TestSuite::new( frame_dummy_noheap );
+
+		test_suite.run().await;
+	}
+
+
+	#[async_std::test]
+	//
+	async fn decoder_progress()
+	{
+		let mut wf = ThesWF::default();
+		wf.write_all( &[ 7u8; 10_000 ] ).unwrap();
+
+		let total  = wf.len() as usize;
+		let pieces = wf.as_buf().chunks( 1000 ).map( |c| Ok( c.to_vec() ) ).collect::< Vec<io::Result<Vec<u8>>> >();
+		let reader = futures::stream::iter( pieces ).into_async_read();
+
+		let seen  = Arc::new( Mutex::new( Vec::new() ) );
+		let seen2 = seen.clone();
+
+		let mut stream = DecoderNoHeap::new( reader, total );
+
+		stream.on_progress( move |read, len|
+		{
+			seen2.lock().push( (read, len) );
+			Ok(())
+		});
+
+		let received = stream.next().await.expect( "a frame" ).expect( "decode frame" );
+
+		assert_eq!( wf.as_buf(), received.as_buf() );
+
+		let seen = seen.lock();
+
+		assert!( seen.len() > 1 );
+		assert!( seen.windows( 2 ).all( |w| w[0].0 < w[1].0 ) );
+		assert!( seen.iter().all( |(_, len)| *len == total ) );
+		assert_eq!( Some( &(total, total) ), seen.last() );
+	}
+
+
+
+	#[async_std::test]
+	//
+	async fn decoder_progress_heap()
+	{
+		let mut wf = ThesWF::default();
+		wf.write_all( &[ 7u8; 10_000 ] ).unwrap();
+
+		let total  = wf.len() as usize;
+		let pieces = wf.as_buf().chunks( 1000 ).map( |c| Ok( c.to_vec() ) ).collect::< Vec<io::Result<Vec<u8>>> >();
+		let reader = futures::stream::iter( pieces ).into_async_read();
+
+		let seen  = Arc::new( Mutex::new( Vec::new() ) );
+		let seen2 = seen.clone();
+
+		let mut stream = Decoder::new( reader, total );
+
+		stream.on_progress( move |read, len|
+		{
+			seen2.lock().push( (read, len) );
+			Ok(())
+		});
+
+		let received = stream.next().await.expect( "a frame" ).expect( "decode frame" );
+
+		assert_eq!( wf.as_buf(), received.as_buf() );
+
+		let seen = seen.lock();
+
+		assert!( seen.len() > 1 );
+		assert!( seen.windows( 2 ).all( |w| w[0].0 < w[1].0 ) );
+		assert!( seen.iter().all( |(_, len)| *len == total ) );
+		assert_eq!( Some( &(total, total) ), seen.last() );
+	}
+
+
+	#[async_std::test]
+	//
+	async fn decoder_progress_abort()
+	{
+		let mut wf = ThesWF::default();
+		wf.write_all( &[ 7u8; 10_000 ] ).unwrap();
+
+		let total  = wf.len() as usize;
+		let pieces = wf.as_buf().chunks( 1000 ).map( |c| Ok( c.to_vec() ) ).collect::< Vec<io::Result<Vec<u8>>> >();
+		let reader = futures::stream::iter( pieces ).into_async_read();
+
+		let mut stream = Decoder::new( reader, total );
+
+		stream.on_progress( |read, _len|
+		{
+			match read > 5000
+			{
+				true  => Err( WireErr::OutOfMemory{ context: "test".to_string(), size: read } ),
+				false => Ok(()),
+			}
+		});
+
+		assert!( matches!( stream.next().await, Some( Err( WireErr::OutOfMemory{..} ) ) ) );
+		assert!( stream.next().await.is_none() );
+	}
 }
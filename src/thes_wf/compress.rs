@@ -0,0 +1,333 @@
+use
+{
+	crate     :: { import::*, ThesWF, WireErr, WireFormat    } ,
+	std       :: { io::Write as IoWrite                      } ,
+	byteorder :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
+};
+
+
+// With compression, the payload of every frame starts with a flag byte:
+//
+// flag u8 | payload                      when the flag is FLAG_PLAIN
+// flag u8 | plain len u32 LE | lz4 block when the flag is FLAG_LZ4
+//
+const FLAG_PLAIN: u8 = 0;
+const FLAG_LZ4  : u8 = 1;
+
+const LEN_FLAG : usize = 1;
+const LEN_PLAIN: usize = 4;
+
+
+/// The number of bytes compression adds to frames that are not compressed. A frame is only sent compressed
+/// when that makes it smaller. Take this into account when choosing the `max_size` of the codec.
+//
+pub const COMPRESS_OVERHEAD: usize = LEN_FLAG;
+
+
+
+/// Which outgoing frames [`Compress`] compresses. Incoming frames are always decompressed when they are
+/// flagged as compressed, so both ends of a connection can use a different setting.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum Compression
+{
+	/// Compress all frames.
+	//
+	All,
+
+	/// Only compress responses to calls (frames with a full sid). Useful when requests are small
+	/// but responses are big.
+	//
+	Responses,
+
+	/// Compress everything but responses to calls.
+	//
+	Requests,
+}
+
+
+impl Compression
+{
+	fn applies( self, frame: &ThesWF ) -> bool
+	{
+		match self
+		{
+			Compression::All       =>  true                  ,
+			Compression::Responses =>  frame.sid().is_full() ,
+			Compression::Requests  => !frame.sid().is_full() ,
+		}
+	}
+}
+
+
+
+/// Whether a frame coming from an [`Encoder`](crate::thes_wf::Encoder) wrapped in [`Compress`] carries
+/// the flag of a compressed payload.
+//
+pub fn is_compressed( frame: &ThesWF ) -> bool
+{
+	frame.msg().first() == Some( &FLAG_LZ4 )
+}
+
+
+
+// Write the flag and payload to a new frame with the same header.
+//
+fn reframe( frame: &ThesWF, flag: u8, payload: &[u8] ) -> ThesWF
+{
+	let mut wf = ThesWF::with_capacity( LEN_FLAG + LEN_PLAIN + payload.len() );
+
+	wf.set_sid     ( frame.sid()              );
+	wf.set_cid     ( frame.cid()              );
+	wf.set_deadline( frame.deadline()         );
+	wf.set_meta    ( frame.meta().as_ref()    );
+	wf.set_channel ( frame.channel()          );
+	wf.set_trace_id( frame.trace_id()         );
+	wf.set_route   ( frame.route().as_deref() );
+
+	// unwrap: writing to a Vec can't fail.
+	//
+	wf.write_u8( flag ).unwrap();
+
+	if flag == FLAG_LZ4
+	{
+		// unwrap: frames are limited to max_size, which would have to be over 4GiB for this to fail.
+		//
+		let plain = u32::try_from( frame.msg().len() ).expect( "frame smaller than 4GiB" );
+
+		wf.write_u32::<LittleEndian>( plain ).unwrap();
+	}
+
+	wf.write_all( payload ).unwrap();
+
+	wf
+}
+
+
+
+/// The size in bytes under which [`Compress`] leaves payloads uncompressed. It can be changed while
+/// the connection is in use, eg. with [`SetCompressionThreshold`](crate::SetCompressionThreshold).
+/// Clones share the value. Defaults to 0, so all frames selected by [`Compression`] are compressed.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+pub struct CompressThreshold( Arc<AtomicUsize> );
+
+
+impl CompressThreshold
+{
+	/// The current threshold in bytes.
+	//
+	pub fn get( &self ) -> usize
+	{
+		self.0.load( Relaxed )
+	}
+
+
+	/// Only compress payloads of at least `threshold` bytes from the next frame on.
+	//
+	pub fn set( &self, threshold: usize )
+	{
+		self.0.store( threshold, Relaxed );
+	}
+}
+
+
+
+fn compress( frame: ThesWF, compression: Compression, threshold: usize ) -> ThesWF
+{
+	if compression.applies( &frame ) && frame.msg().len() >= threshold
+	{
+		let packed = lz4_flex::block::compress( frame.msg() );
+
+		if LEN_PLAIN + packed.len() < frame.msg().len()
+		{
+			return reframe( &frame, FLAG_LZ4, &packed );
+		}
+	}
+
+	reframe( &frame, FLAG_PLAIN, frame.msg() )
+}
+
+
+
+fn decompress( frame: ThesWF, max_size: usize ) -> Result<ThesWF, WireErr>
+{
+	let error = |context: String| WireErr::Deserialize{ context, source: None };
+
+	let mut msg = frame.msg();
+
+	let flag = msg.read_u8().map_err( |_| error( "frame is too short to carry the compression flag".to_string() ) )?;
+
+	let plain = match flag
+	{
+		FLAG_PLAIN => return Ok( reframe_plain( &frame, msg ) ),
+
+		FLAG_LZ4 =>
+		{
+			let len = msg.read_u32::<LittleEndian>()
+
+				.map_err( |_| error( "compressed frame is too short to carry its length".to_string() ) )?
+			;
+
+			let len = usize::try_from( len ).unwrap_or( usize::MAX );
+
+			// Don't let the remote make us allocate more than we would accept for an uncompressed frame.
+			//
+			if len > max_size
+			{
+				return Err( WireErr::MessageSizeExceeded{ context: "decompressing frame".to_string(), size: len, max_size } );
+			}
+
+			lz4_flex::block::decompress( msg, len )
+
+				.map_err( |e| error( format!( "failed to decompress frame with sid: {}, cid: {}: {}", frame.sid(), frame.cid(), e ) ) )?
+		}
+
+		_ => return Err( error( format!( "unknown compression flag: {}", flag ) ) ),
+	};
+
+	Ok( reframe_plain( &frame, &plain ) )
+}
+
+
+
+// A frame with the same header and the given payload, without flag.
+//
+fn reframe_plain( frame: &ThesWF, payload: &[u8] ) -> ThesWF
+{
+	let mut wf = ThesWF::with_capacity( payload.len() );
+
+	wf.set_sid     ( frame.sid()              );
+	wf.set_cid     ( frame.cid()              );
+	wf.set_deadline( frame.deadline()         );
+	wf.set_meta    ( frame.meta().as_ref()    );
+	wf.set_channel ( frame.channel()          );
+	wf.set_trace_id( frame.trace_id()         );
+	wf.set_route   ( frame.route().as_deref() );
+
+	// unwrap: writing to a Vec can't fail.
+	//
+	wf.write_all( payload ).unwrap();
+
+	wf
+}
+
+
+
+/// Sink adapter that compresses the payload of outgoing frames with LZ4 before passing them to the
+/// underlying sink, normally an [`Encoder`](crate::thes_wf::Encoder). Which frames get compressed is
+/// decided per frame based on [`Compression`] and the [`CompressThreshold`]. Frames that don't get smaller
+/// are sent uncompressed.
+///
+/// The remote must wrap its decoder in [`Decompress`].
+//
+#[ derive( Debug ) ]
+//
+pub struct Compress<T>
+{
+	inner      : T                 ,
+	compression: Compression       ,
+	threshold  : CompressThreshold ,
+}
+
+
+impl<T> Compress<T>
+{
+	/// Compress the frames selected by `compression` before sending them to `inner`.
+	//
+	pub fn new( inner: T, compression: Compression ) -> Self
+	{
+		Self { inner, compression, threshold: CompressThreshold::default() }
+	}
+
+
+	/// The threshold under which payloads are not compressed. Hand it to [`Peer::set_compress_threshold`](crate::Peer::set_compress_threshold)
+	/// to be able to change it through the peer.
+	//
+	pub fn threshold( &self ) -> CompressThreshold
+	{
+		self.threshold.clone()
+	}
+}
+
+
+impl<T> Sink<ThesWF> for Compress<T>
+
+	where T: Sink<ThesWF, Error=WireErr> + Unpin
+
+{
+	type Error = WireErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		Pin::new( &mut self.inner ).poll_ready( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: ThesWF ) -> Result<(), Self::Error>
+	{
+		let packed = compress( msg, self.compression, self.threshold.get() );
+
+		Pin::new( &mut self.inner ).start_send( packed )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.inner ).poll_flush( cx )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.inner ).poll_close( cx )
+	}
+}
+
+
+
+/// Stream adapter that decompresses incoming frames from the underlying stream, normally
+/// a [`Decoder`](crate::thes_wf::Decoder). Frames that can't be decompressed are returned
+/// as [`WireErr::Deserialize`], frames that would decompress to more than `max_size` bytes
+/// as [`WireErr::MessageSizeExceeded`].
+//
+#[ derive( Debug ) ]
+//
+pub struct Decompress<T>
+{
+	inner   : T     ,
+	max_size: usize ,
+}
+
+
+impl<T> Decompress<T>
+{
+	/// Decompress all frames coming from `inner`.
+	//
+	pub fn new( inner: T, max_size: usize ) -> Self
+	{
+		Self { inner, max_size }
+	}
+}
+
+
+impl<T> Stream for Decompress<T>
+
+	where T: Stream< Item = Result<ThesWF, WireErr> > + Unpin
+
+{
+	type Item = Result<ThesWF, WireErr>;
+
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		match futures::ready!( Pin::new( &mut self.inner ).poll_next( cx ) )
+		{
+			Some( Ok( frame ) ) => Some( decompress( frame, self.max_size ) ).into(),
+			other               => other.into(),
+		}
+	}
+}
@@ -1,6 +1,6 @@
 use
 {
-	crate     :: { ThesWF                      } ,
+	crate     :: { ThesWF, WireFormat          } ,
 	super     :: { *                           } ,
 	byteorder :: { ReadBytesExt, LittleEndian  } ,
 	std       :: { future::Future              } ,
@@ -9,33 +9,79 @@ use
 
 
 
-pub struct Decoder<T>
+/// Reads frames of a [WireFormat] from an [`AsyncRead`](FutAsyncRead). Each frame must start with its
+/// total length as a little endian u64, see [Encoder](super::Encoder).
+//
+pub struct Decoder<T, W = ThesWF>
 {
 	byte_stream: Option<T>                                                                     ,
 	get_len    : Option< Pin<Box< dyn Future<Output=(T, io::Result<[u8;LEN_LEN]>)> + Send >> > ,
-	get_msg    : Option< Pin<Box< dyn Future<Output=(T, io::Result<Vec<u8>     >)> + Send >> > ,
+	get_msg    : Option< Pin<Box< dyn Future<Output=ReadMsg<T>> + Send >> >                   ,
 	closed     : bool                                                                          ,
 	max_size   : usize                                                                         ,
+	progress   : Option< DecodeProgress >                                                      ,
+	buffers    : Arc< dyn BufferProvider >                                                     ,
+	_format    : PhantomData< fn() -> W >                                                      ,
 }
 
 
+// The future reading the payload of a frame gives back the transport and the progress callback.
+//
+type ReadMsg<T> = ( T, Option<DecodeProgress>, Result<Vec<u8>, WireErr> );
+
+
 impl<T> Decoder<T>
 {
+	/// Create a decoder for [ThesWF].
+	//
 	pub fn new( byte_stream: T, max_size: usize ) -> Self
+	{
+		Self::with_format( byte_stream, max_size )
+	}
+}
+
+
+impl<T, W> Decoder<T, W>
+{
+	/// Create a decoder for another wire format than [ThesWF].
+	//
+	pub fn with_format( byte_stream: T, max_size: usize ) -> Self
 	{
 		Self
 		{
-			byte_stream: Some( byte_stream ) ,
-			get_len    : None                ,
-			get_msg    : None                ,
-			closed     : false               ,
-			max_size                         ,
+			byte_stream: Some( byte_stream )     ,
+			get_len    : None                    ,
+			get_msg    : None                    ,
+			closed     : false                   ,
+			max_size                             ,
+			progress   : None                    ,
+			buffers    : Arc::new( HeapBuffers ) ,
+			_format    : PhantomData             ,
 		}
 	}
+
+
+	/// Read frames into buffers from `buffers` instead of the global allocator.
+	//
+	pub fn with_buffers( mut self, buffers: impl BufferProvider + 'static ) -> Self
+	{
+		self.buffers = Arc::new( buffers );
+		self
+	}
+
+
+	/// Get notified once the length of a frame is known and every time part of its payload has been read,
+	/// see [`DecodeProgress`]. Returning an error aborts decoding and closes the stream. Only the latest
+	/// callback is kept.
+	//
+	pub fn on_progress( &mut self, progress: impl FnMut( usize, usize ) -> Result<(), WireErr> + Send + 'static )
+	{
+		self.progress = Some( Box::new( progress ) );
+	}
 }
 
 
-impl<T: fmt::Debug> fmt::Debug for Decoder<T>
+impl<T: fmt::Debug, W> fmt::Debug for Decoder<T, W>
 {
 	fn fmt( &self, fmt: &mut fmt::Formatter<'_> ) -> fmt::Result
 	{
@@ -46,17 +92,19 @@ impl<T: fmt::Debug> fmt::Debug for Decoder<T>
 			.field( "get_msg"    , &self.get_len.as_ref().map( |_| "future getting the message"      ) )
 			.field( "closed"     , &self.closed                                                        )
 			.field( "max_size"   , &self.max_size                                                      )
+			.field( "progress"   , &self.progress.as_ref().map( |_| "progress callback"              ) )
 
 		.finish()
 	}
 }
 
 
-impl<T> Stream for Decoder<T>
+impl<T, W> Stream for Decoder<T, W>
 
-	where T: FutAsyncRead + Unpin + Send + 'static
+	where T: FutAsyncRead + Unpin + Send + 'static            ,
+	      W: WireFormat + TryFrom< Vec<u8>, Error = WireErr > ,
 {
-	type Item = Result<ThesWF, WireErr>;
+	type Item = Result<W, WireErr>;
 
 
 	#[log_derive::logfn(Trace)]
@@ -83,24 +131,29 @@ impl<T> Stream for Decoder<T>
 						return Poll::Pending;
 					}
 
-					Poll::Ready( (transport, Err(e)) ) =>
+					Poll::Ready( (transport, progress, Err(e)) ) =>
 					{
-						self.closed = true;
+						self.closed      = true;
 						self.byte_stream = Some(transport);
+						self.progress    = progress;
 
-						match e.kind()
+						match e
 						{
-							io::ErrorKind::UnexpectedEof => return Poll::Ready( None ),
-							_                            => return Some(Err( WireErr::from(e) )).into(),
+							WireErr::Io{ kind: io::ErrorKind::UnexpectedEof, .. } => return Poll::Ready( None ),
+							_                                                     => return Some(Err( e )).into(),
 						}
 					}
 
-					Poll::Ready( (transport, Ok(all)) ) =>
+					Poll::Ready( (transport, progress, Ok(all)) ) =>
 					{
 						self.byte_stream = Some(transport);
-						let thes_wf = ThesWF::try_from( all )?;
+						self.progress    = progress;
 
-						return Poll::Ready( Some(Ok( thes_wf )) );
+						let mut frame = W::try_from( all )?;
+
+						frame.return_to( self.buffers.clone() );
+
+						return Poll::Ready( Some(Ok( frame )) );
 					}
 				}
 			}
@@ -132,9 +185,16 @@ impl<T> Stream for Decoder<T>
 
 					Poll::Ready( (mut transport, Ok(buf)) ) =>
 					{
-						let len: usize = buf[ 0..LEN_LEN ].as_ref().read_u64::<LittleEndian>().unwrap().try_into().unwrap();
+						let field      = buf[ 0..LEN_LEN ].as_ref().read_u64::<LittleEndian>().unwrap();
+						let (len, sid) = read_len_field( field );
+						let len        = usize::try_from( len ).unwrap_or( usize::MAX );
+						let elided     = sid.is_some();
 
-						debug_assert!( len >= LEN_HEADER );
+						debug_assert!( len >= LEN_LEN );
+
+						// The sid and connID were left out, see Encoder::elide_null_header.
+						//
+						let skip = if elided { LEN_ELIDED } else { 0 };
 
 						if len > self.max_size
 						{
@@ -142,26 +202,81 @@ impl<T> Stream for Decoder<T>
 							{
 								size    : len                          ,
 								max_size: self.max_size                ,
-								context : "ThesWF Decoder".to_string() ,
+								context : "Decoder".to_string() ,
 							};
 
 							return Poll::Ready( Some(Err( err )) );
 						}
 
-						// Create a zeroed buffer of the size of the entire message.
+						// We can't know where the next frame starts.
+						//
+						if elided && len < LEN_HEADER
+						{
+							self.closed = true;
+
+							let err = WireErr::Deserialize{ context: "Decoder: elided frame shorter than the header".to_string(), source: None };
+
+							return Poll::Ready( Some(Err( err )) );
+						}
+
+						// Get a zeroed buffer of the size of the entire message.
 						// TODO: check the perf difference with an unzeroed buffer.
 						//
-						let mut all = vec![0u8;len];
+						let mut all = match self.buffers.alloc( len )
+						{
+							Ok ( buf ) => buf,
 
-						// put the length in the new buffer.
+							// We can't read the rest of this frame, so we can't find the next one either.
+							//
+							Err( err ) =>
+							{
+								self.closed = true;
+								return Poll::Ready( Some(Err( err )) );
+							}
+						};
+
+						// put the length in the new buffer and the elided sid, the connID is null.
+						//
+						all[ 0..LEN_LEN ].copy_from_slice( &( len as u64 ).to_le_bytes() );
+						all[ LEN_LEN..LEN_LEN + skip ].iter_mut().for_each( |b| *b = 0 );
+
+						if let Some( sid ) = sid
+						{
+							// unwrap: the slice has room for the sid.
+							//
+							sid.write_le( &mut &mut all[ IDX_SID..IDX_SID + LEN_SID ] ).unwrap();
+						}
+
+						// The callback goes along with the future and comes back with the transport.
 						//
-						all[ 0..LEN_LEN ].copy_from_slice( &buf );
+						let mut progress = self.progress.take();
 
 						self.get_msg = Some( async move
 						{
-							let res = transport.read_exact( &mut all[LEN_LEN..] ).await.map( |_| all );
+							let mut pos = LEN_LEN + skip;
+
+							// Report once the buffer is allocated and after every read.
+							//
+							let res = loop
+							{
+								if let Some( progress ) = &mut progress
+								{
+									if let Err( e ) = progress( pos, len ) { break Err( e ) }
+								}
+
+								if pos == len { break Ok( all ) }
+
+								match transport.read( &mut all[ pos.. ] ).await
+								{
+									Ok ( 0    ) => break Err( WireErr::from( io::Error::from( io::ErrorKind::UnexpectedEof ) ) ),
+									Ok ( read ) => pos += read,
+
+									Err( e ) if e.kind() == io::ErrorKind::Interrupted => {}
+									Err( e )                                           => break Err( WireErr::from(e) ),
+								}
+							};
 
-							(transport, res)
+							(transport, progress, res)
 
 						}.boxed() );
 					}
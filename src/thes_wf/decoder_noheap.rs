@@ -1,6 +1,6 @@
 use
 {
-	crate     :: { ThesWF                      } ,
+	crate     :: { ThesWF, WireFormat          } ,
 	super     :: { *                           } ,
 	byteorder :: { ReadBytesExt, LittleEndian  } ,
 	std       :: { io::{ Write, Cursor }       } ,
@@ -8,38 +8,120 @@ use
 
 
 
-#[ derive(Debug) ]
+/// Callback for [`DecoderNoHeap::on_progress`] and [`Decoder::on_progress`](super::Decoder::on_progress).
+/// Receives the number of bytes read so far and the total length of the frame being decoded (both
+/// including the header). Returning an error aborts decoding and closes the stream, which allows
+/// enforcing custom limits mid-transfer.
 //
-pub struct DecoderNoHeap<T>
+pub type DecodeProgress = Box< dyn FnMut( usize, usize ) -> Result<(), WireErr> + Send >;
+
+
+/// Like [Decoder](super::Decoder), but reads straight into the buffer of the frame instead of boxing
+/// futures.
+//
+pub struct DecoderNoHeap<T, W = ThesWF>
 {
 	byte_stream : T                         ,
 	in_progress : Option< Cursor<Vec<u8>> > ,
 	closed      : bool                      ,
 	max_size    : usize                     ,
+	progress    : Option< DecodeProgress >  ,
+	buffers     : Arc< dyn BufferProvider > ,
+	_format     : PhantomData< fn() -> W >  ,
 }
 
 
 impl<T> DecoderNoHeap<T>
 {
+	/// Create a decoder for [ThesWF].
+	//
 	pub fn new( byte_stream: T, max_size: usize ) -> Self
+	{
+		Self::with_format( byte_stream, max_size )
+	}
+}
+
+
+impl<T, W> DecoderNoHeap<T, W>
+{
+	/// Create a decoder for another wire format than [ThesWF].
+	//
+	pub fn with_format( byte_stream: T, max_size: usize ) -> Self
 	{
 		Self
 		{
-			byte_stream         ,
-			max_size            ,
-			in_progress : None  ,
-			closed      : false ,
+			byte_stream                           ,
+			max_size                              ,
+			in_progress : None                    ,
+			closed      : false                   ,
+			progress    : None                    ,
+			buffers     : Arc::new( HeapBuffers ) ,
+			_format     : PhantomData             ,
+		}
+	}
+
+
+	/// Read frames into buffers from `buffers` instead of the global allocator.
+	//
+	pub fn with_buffers( mut self, buffers: impl BufferProvider + 'static ) -> Self
+	{
+		self.buffers = Arc::new( buffers );
+		self
+	}
+
+
+	/// Get notified once the length of a frame is known and every time part of its payload has been read.
+	/// Useful to show progress when receiving very large frames. Only the latest callback is kept.
+	//
+	pub fn on_progress( &mut self, progress: impl FnMut( usize, usize ) -> Result<(), WireErr> + Send + 'static )
+	{
+		self.progress = Some( Box::new( progress ) );
+	}
+
+
+	// Report progress on the current frame. On error the stream is closed.
+	//
+	fn report( &mut self, read: usize, total: usize ) -> Result<(), WireErr>
+	{
+		if let Some( progress ) = &mut self.progress
+		{
+			if let Err( e ) = progress( read, total )
+			{
+				self.closed = true;
+				return Err( e );
+			}
 		}
+
+		Ok(())
+	}
+}
+
+
+
+impl<T: fmt::Debug, W> fmt::Debug for DecoderNoHeap<T, W>
+{
+	fn fmt( &self, fmt: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		fmt.debug_struct( "thes_wf::DecoderNoHeap" )
+
+			.field( "byte_stream", &self.byte_stream                                   )
+			.field( "in_progress", &self.in_progress                                   )
+			.field( "closed"     , &self.closed                                        )
+			.field( "max_size"   , &self.max_size                                      )
+			.field( "progress"   , &self.progress.as_ref().map( |_| "progress callback" ) )
+
+		.finish()
 	}
 }
 
 
 
-impl<T> Stream for DecoderNoHeap<T>
+impl<T, W> Stream for DecoderNoHeap<T, W>
 
-	where T: FutAsyncRead + Unpin
+	where T: FutAsyncRead + Unpin                             ,
+	      W: WireFormat + TryFrom< Vec<u8>, Error = WireErr > ,
 {
-	type Item = Result<ThesWF, WireErr>;
+	type Item = Result<W, WireErr>;
 
 
 	// #[log_derive::logfn(Debug)]
@@ -100,9 +182,10 @@ impl<T> Stream for DecoderNoHeap<T>
 				//
 				pos if  pos == LEN_LEN  &&  in_progress.get_ref().len() == LEN_LEN  =>
 				{
-					// TODO: this can truncate.
-					//
-					let len: usize = in_progress.get_ref()[ 0..LEN_LEN ].as_ref().read_u64::<LittleEndian>().unwrap().try_into().unwrap();
+					let field      = in_progress.get_ref()[ 0..LEN_LEN ].as_ref().read_u64::<LittleEndian>().unwrap();
+					let (len, sid) = read_len_field( field );
+					let len        = usize::try_from( len ).unwrap_or( usize::MAX );
+					let elided     = sid.is_some();
 
 					if len > self.max_size
 					{
@@ -110,7 +193,7 @@ impl<T> Stream for DecoderNoHeap<T>
 						{
 							size    : len                          ,
 							max_size: self.max_size                ,
-							context : "ThesWF Decoder".to_string() ,
+							context : "DecoderNoHeap".to_string() ,
 						};
 
 						return Poll::Ready( Some(Err( err )) );
@@ -120,16 +203,52 @@ impl<T> Stream for DecoderNoHeap<T>
 					//
 					// TODO: do we get perf wins if we use debug_assert! here and get_unchecked_mut below?
 					//
-					assert!( len >= LEN_HEADER );
+					assert!( len > LEN_LEN );
+
+					// We can't know where the next frame starts.
+					//
+					if elided && len < LEN_HEADER
+					{
+						self.closed = true;
+
+						let err = WireErr::Deserialize{ context: "DecoderNoHeap: elided frame shorter than the header".to_string(), source: None };
+
+						return Poll::Ready( Some(Err( err )) );
+					}
 
-					// Create a zeroed buffer of the size of the entire message.
+					// Get a zeroed buffer of the size of the entire message.
 					// TODO: check the perf difference with an unzeroed buffer.
 					//
-					let mut tmp = io::Cursor::new( vec![0u8;len] );
+					let mut tmp = match self.buffers.alloc( len )
+					{
+						Ok ( buf ) => io::Cursor::new( buf ),
+
+						// We can't read the rest of this frame, so we can't find the next one either.
+						//
+						Err( err ) =>
+						{
+							self.closed = true;
+							return Poll::Ready( Some(Err( err )) );
+						}
+					};
 
-					// put the length in the new buffer.
+					// put the length in the new buffer. The sid and connID might have been left out, see
+					// Encoder::elide_null_header, in which case we put back the sid, the connID is null and
+					// we continue after them.
 					//
-					tmp.write( &in_progress.get_ref()[0..LEN_LEN] )?;
+					tmp.write( &( len as u64 ).to_le_bytes() )?;
+
+					if let Some( sid ) = sid
+					{
+						tmp.get_mut()[ LEN_LEN..LEN_LEN + LEN_ELIDED ].iter_mut().for_each( |b| *b = 0 );
+						sid.write_le( &mut tmp )?;
+						tmp.set_position( ( LEN_LEN + LEN_ELIDED ) as u64 );
+					}
+
+					if let Err( e ) = self.report( tmp.position() as usize, len )
+					{
+						return Some(Err( e )).into();
+					}
 
 					in_progress = tmp;
 
@@ -155,6 +274,12 @@ impl<T> Stream for DecoderNoHeap<T>
 						Poll::Ready(Ok( read )) if read < to_read =>
 						{
 							in_progress.set_position( (pos + read) as u64 );
+
+							if let Err( e ) = self.report( pos + read, len )
+							{
+								return Some(Err( e )).into();
+							}
+
 							continue;
 						}
 
@@ -165,9 +290,16 @@ impl<T> Stream for DecoderNoHeap<T>
 							in_progress.set_position( in_progress.position() + read as u64 );
 							debug_assert_eq!( len as u64, in_progress.position() );
 
-							let thes_wf = ThesWF::try_from( in_progress.into_inner() )?;
+							if let Err( e ) = self.report( len, len )
+							{
+								return Some(Err( e )).into();
+							}
+
+							let mut frame = W::try_from( in_progress.into_inner() )?;
+
+							frame.return_to( self.buffers.clone() );
 
-							return Poll::Ready( Some(Ok( thes_wf )) );
+							return Poll::Ready( Some(Ok( frame )) );
 						}
 
 						_ => unreachable!( "read more bytes than buffer size" ),
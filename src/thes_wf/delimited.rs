@@ -0,0 +1,259 @@
+use
+{
+	crate   :: { import::*, ThesWF, WireFormat, WireErr, ServiceID, ConnID } ,
+	futures :: { io::{ AsyncBufReadExt, BufReader }, stream                } ,
+	std     :: { io::Write as IoWrite                                      } ,
+};
+
+
+/// Writes a [WireFormat] as a line of text ended by a delimiter byte instead of a length prefix, to
+/// bridge to a peer that speaks a text protocol, eg. JSON over newlines. [DelimitedDecoder] reads
+/// these lines back. A line holds the sid in hex as shown by `{:x}`, the cid in decimal and the
+/// payload, separated by a space:
+///
+/// ```text
+/// 3d3f2e16e9a2c3b1 7 {"a":5}
+/// ```
+///
+/// A cid of 0 is a send. The payload must not contain the delimiter, so JSON needs to be on a single
+/// line. Deadlines, headers, trace ids and route traces are not carried over.
+//
+#[ derive(Debug) ]
+//
+pub struct DelimitedEncoder<T, W = ThesWF>
+{
+	out_bytes: T                          ,
+	delimiter: u8                         ,
+	buffer   : Option< (Vec<u8>, usize) > ,
+	_format  : PhantomData< fn( W ) >     ,
+}
+
+
+impl<T, W> DelimitedEncoder<T, W>
+{
+	/// Write frames to `out_bytes`, ending each one with `delimiter`, usually `b'\n'`.
+	//
+	pub fn new( out_bytes: T, delimiter: u8 ) -> Self
+	{
+		Self
+		{
+			out_bytes            ,
+			delimiter            ,
+			buffer : None        ,
+			_format: PhantomData ,
+		}
+	}
+}
+
+
+impl<T, W> Sink<W> for DelimitedEncoder<T, W>
+
+	where T: FutAsyncWrite + Unpin,
+	      W: WireFormat           ,
+
+{
+	type Error = WireErr;
+
+
+	fn poll_ready( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		self.poll_flush( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: W ) -> Result<(), Self::Error>
+	{
+		if self.buffer.is_some()
+		{
+			panic!( "call `poll_ready` before start_send" )
+		}
+
+		if msg.msg().contains( &self.delimiter )
+		{
+			let err = io::Error::new( io::ErrorKind::InvalidInput, "DelimitedEncoder: the payload contains the delimiter" );
+
+			return Err( WireErr::from( err ) );
+		}
+
+		let cid: u64 = msg.cid().into();
+
+		let mut line = format!( "{:x} {} ", msg.sid(), cid ).into_bytes();
+
+		line.extend_from_slice( msg.msg() );
+		line.push( self.delimiter );
+
+		self.buffer = Some( (line, 0) );
+
+		Ok(())
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		loop { match self.buffer.take()
+		{
+			None => return Poll::Ready( Ok(()) ),
+
+			Some( (line, mut pos) ) =>
+			{
+				match Pin::new( &mut self.out_bytes ).poll_write( cx, &line[pos..] )
+				{
+					Poll::Pending =>
+					{
+						self.buffer = Some( (line, pos) );
+						return Poll::Pending;
+					}
+
+					Poll::Ready( Ok(0) ) =>
+					{
+						return Err( WireErr::from( io::Error::from( io::ErrorKind::ConnectionAborted ) )).into();
+					}
+
+					Poll::Ready( Ok(x) ) =>
+					{
+						pos += x;
+
+						if pos == line.len()
+						{
+							return Ok(()).into()
+						}
+
+						self.buffer = Some( (line, pos) );
+					}
+
+					Poll::Ready( Err(e) ) => return Err( WireErr::from(e) ).into(),
+				}
+			}
+		}}
+	}
+
+
+	fn poll_close( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.poll_flush( cx )
+	}
+}
+
+
+
+/// Reads the lines written by [DelimitedEncoder] as frames of a [WireFormat]. A line that can't be
+/// parsed gives an error, but since the delimiter marks where the next frame starts, the stream
+/// continues. The stream ends after a line longer than `max_size`.
+//
+pub struct DelimitedDecoder<W = ThesWF>
+{
+	inner: Pin<Box< dyn Stream< Item = Result<W, WireErr> > + Send >>,
+}
+
+
+impl<W: WireFormat> DelimitedDecoder<W>
+{
+	/// Read frames from `byte_stream`, each one ended by `delimiter`, usually `b'\n'`.
+	//
+	pub fn new( byte_stream: impl FutAsyncRead + Unpin + Send + 'static, delimiter: u8, max_size: usize ) -> Self
+	{
+		let reader = Some( BufReader::new( byte_stream ) );
+
+		let inner = stream::unfold( reader, move |reader| async move
+		{
+			let mut reader = reader?;
+
+			match next_line( &mut reader, delimiter, max_size ).await
+			{
+				Ok ( Some( line ) ) => Some(( parse::<W>( &line ), Some( reader ) )),
+				Ok ( None         ) => None,
+
+				// An io error or a line we can't find the end of.
+				//
+				Err( err ) => Some(( Err( err ), None )),
+			}
+		});
+
+		Self { inner: inner.boxed() }
+	}
+}
+
+
+impl<W> fmt::Debug for DelimitedDecoder<W>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "thes_wf::DelimitedDecoder" )
+	}
+}
+
+
+impl<W> Stream for DelimitedDecoder<W>
+{
+	type Item = Result<W, WireErr>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		self.inner.as_mut().poll_next( cx )
+	}
+}
+
+
+
+// The next line without the delimiter. None at the end of the stream. An incomplete last line is dropped.
+//
+async fn next_line<T>( reader: &mut BufReader<T>, delimiter: u8, max_size: usize ) -> Result< Option<Vec<u8>>, WireErr >
+
+	where T: FutAsyncRead + Unpin
+{
+	let mut line = Vec::new();
+
+	loop
+	{
+		let buf = reader.fill_buf().await?;
+
+		if buf.is_empty() { return Ok( None ) }
+
+		let (used, done) = match buf.iter().position( |b| *b == delimiter )
+		{
+			Some( end ) => { line.extend_from_slice( &buf[ ..end ] ); ( end + 1  , true  ) }
+			None        => { line.extend_from_slice( buf           ); ( buf.len(), false ) }
+		};
+
+		reader.consume_unpin( used );
+
+		if line.len() > max_size
+		{
+			return Err( WireErr::MessageSizeExceeded{ context: "DelimitedDecoder".to_string(), size: line.len(), max_size } );
+		}
+
+		if done { return Ok( Some( line ) ) }
+	}
+}
+
+
+// Parse `<sid in hex> <cid> <payload>`.
+//
+fn parse<W: WireFormat>( line: &[u8] ) -> Result<W, WireErr>
+{
+	let mut parts = line.splitn( 3, |b| *b == b' ' );
+
+	let sid = parts.next().and_then( |s| std::str::from_utf8( s ).ok() ).and_then( ServiceID::from_hex );
+	let cid = parts.next().and_then( |c| std::str::from_utf8( c ).ok() ).and_then( |c| c.parse::<u64>().ok() );
+
+	let (sid, cid) = match (sid, cid)
+	{
+		(Some( sid ), Some( cid )) => (sid, cid),
+
+		_ => return Err( WireErr::Deserialize
+		{
+			context: "DelimitedDecoder: expected a line like `<sid in hex> <cid> <payload>`".to_string(),
+			source : None,
+		}),
+	};
+
+	let payload = parts.next().unwrap_or_default();
+
+	let mut wf = W::with_capacity( payload.len() );
+
+	wf.set_sid( sid                 );
+	wf.set_cid( ConnID::from( cid ) );
+	wf.write_all( payload )?;
+
+	Ok( wf )
+}
@@ -1,33 +1,140 @@
-use crate::{ import::*, ThesWF, WireErr };
+use
+{
+	crate :: { import::*, ThesWF, WireFormat, WireErr, BufferProvider, HeapBuffers, ServiceID } ,
+	super :: { elided_len_field, LEN_ELIDED, LEN_LEN, IDX_SID                               } ,
+};
+
+
+/// Frames a [WireFormat] onto an [`AsyncWrite`](FutAsyncWrite). It writes out the bytes of each frame
+/// as they are, so the wire format must start with its total length as a little endian u64, like
+/// [ThesWF] does. [Decoder](super::Decoder) and [DecoderNoHeap](super::DecoderNoHeap) rely on that
+/// to read the frames back.
+//
+pub struct Encoder<T, W = ThesWF>
+{
+	out_bytes: T                             ,
+	buffer   : Option< (Pending<W>, usize) > ,
+	max_size : usize                         ,
+	elide    : Arc< AtomicBool >             ,
+	buffers  : Arc< dyn BufferProvider >     ,
+}
 
 
+// The frame being written, either as is or with the null sid and connID left out.
+//
 #[ derive(Debug) ]
 //
-pub struct Encoder<T>
+enum Pending<W>
+{
+	Whole ( W       ),
+	Elided( Vec<u8> ),
+}
+
+
+impl<W: AsRef<[u8]>> AsRef<[u8]> for Pending<W>
 {
-	out_bytes: T                         ,
-	buffer   : Option< (ThesWF, usize) > ,
-	max_size : usize                     ,
+	fn as_ref( &self ) -> &[u8]
+	{
+		match self
+		{
+			Pending::Whole ( msg   ) => msg.as_ref(),
+			Pending::Elided( bytes ) => bytes,
+		}
+	}
 }
 
 
 impl<T> Encoder<T>
 {
+	/// Create an encoder for [ThesWF].
+	//
 	pub fn new( out_bytes: T, max_size: usize ) -> Self
+	{
+		Self::with_format( out_bytes, max_size )
+	}
+}
+
+
+impl<T, W> Encoder<T, W>
+{
+	/// Create an encoder for another wire format than [ThesWF].
+	//
+	pub fn with_format( out_bytes: T, max_size: usize ) -> Self
 	{
 		Self
 		{
-			out_bytes    ,
-			max_size     ,
-			buffer: None ,
+			out_bytes                        ,
+			max_size                         ,
+			buffer : None                    ,
+			elide  : Arc::default()          ,
+			buffers: Arc::new( HeapBuffers ) ,
 		}
 	}
+
+
+	/// Take the buffers of frames with a null header left out from `buffers` instead of the global
+	/// allocator, see [`Encoder::elide_null_header`]. They are given back once written.
+	//
+	pub fn with_buffers( mut self, buffers: impl BufferProvider + 'static ) -> Self
+	{
+		self.buffers = Arc::new( buffers );
+		self
+	}
+
+
+	/// Leave out the sid and the connID of frames in which both are null, eg. errors about sends, which
+	/// saves 16 bytes per frame (24 with the `sid128` feature). The decoders put them back, but only
+	/// decoders that know about this do, so the remote must run a version that does.
+	///
+	/// The same goes for control frames, like the pings of [`Peer::set_ping`](crate::Peer::set_ping),
+	/// which have a null connID. Their control code goes in the length field instead of the sid.
+	///
+	/// This only works for wire formats laid out like [ThesWF]. Off by default.
+	//
+	pub fn elide_null_header( self, elide: bool ) -> Self
+	{
+		self.elide.store( elide, Relaxed );
+		self
+	}
+
+
+	// Lets the peer switch elide_null_header on an encoder it owns, see Peer::set_elide_null_header.
+	//
+	pub(crate) fn elide_switch( &self ) -> Arc< AtomicBool >
+	{
+		self.elide.clone()
+	}
+
+
+	/// The underlying writer.
+	//
+	pub fn get_ref( &self ) -> &T
+	{
+		&self.out_bytes
+	}
 }
 
 
-impl<T> Sink<ThesWF> for Encoder<T>
+impl<T: fmt::Debug, W: fmt::Debug> fmt::Debug for Encoder<T, W>
+{
+	fn fmt( &self, fmt: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		fmt.debug_struct( "thes_wf::Encoder" )
 
-	where T: FutAsyncWrite + Unpin
+			.field( "out_bytes", &self.out_bytes )
+			.field( "buffer"   , &self.buffer    )
+			.field( "max_size" , &self.max_size  )
+			.field( "elide"    , &self.elide     )
+
+		.finish()
+	}
+}
+
+
+impl<T, W> Sink<W> for Encoder<T, W>
+
+	where T: FutAsyncWrite + Unpin            ,
+	      W: WireFormat + AsRef<[u8]> + Unpin ,
 
 {
 	type Error = WireErr;
@@ -39,14 +146,26 @@ impl<T> Sink<ThesWF> for Encoder<T>
 	}
 
 
-	fn start_send( mut self: Pin<&mut Self>, msg: ThesWF ) -> Result<(), Self::Error>
+	fn start_send( mut self: Pin<&mut Self>, msg: W ) -> Result<(), Self::Error>
 	{
 		if self.buffer.is_some()
 		{
 			panic!( "call `poll_ready` before start_send" )
 		}
 
-		self.buffer = Some( (msg, 0) );
+		let sid = msg.sid();
+
+		let pending = if self.elide.load( Relaxed ) && msg.cid().is_null() && ( sid.is_null() || sid.is_control() )
+		{
+			Pending::Elided( elide( msg.as_ref(), sid, &*self.buffers )? )
+		}
+
+		else
+		{
+			Pending::Whole( msg )
+		};
+
+		self.buffer = Some( (pending, 0) );
 
 		Ok(())
 	}
@@ -60,7 +179,7 @@ impl<T> Sink<ThesWF> for Encoder<T>
 
 			Some( (msg, mut pos) ) =>
 			{
-				match Pin::new( &mut self.out_bytes ).poll_write( cx, &msg.as_buf()[pos..] )
+				match Pin::new( &mut self.out_bytes ).poll_write( cx, &msg.as_ref()[pos..] )
 				{
 					Poll::Pending =>
 					{
@@ -81,8 +200,13 @@ impl<T> Sink<ThesWF> for Encoder<T>
 
 						// we wrote all
 						//
-						if pos == msg.as_buf().len()
+						if pos == msg.as_ref().len()
 						{
+							if let Pending::Elided( bytes ) = msg
+							{
+								self.buffers.release( bytes );
+							}
+
 							return Ok(()).into()
 						}
 
@@ -109,3 +233,20 @@ impl<T> Sink<ThesWF> for Encoder<T>
 		self.poll_flush( cx )
 	}
 }
+
+
+
+// The bytes of the frame without the sid and connID. The length field keeps the length of the whole frame
+// and the code of a control sid.
+//
+fn elide( frame: &[u8], sid: ServiceID, buffers: &dyn BufferProvider ) -> Result< Vec<u8>, WireErr >
+{
+	let mut bytes = buffers.alloc( frame.len() - LEN_ELIDED )?;
+
+	let len = u64::from_le_bytes( frame[ ..LEN_LEN ].try_into().expect( "length field" ) );
+
+	bytes[ ..LEN_LEN ].copy_from_slice( &elided_len_field( len, sid ).to_le_bytes() );
+	bytes[ LEN_LEN.. ].copy_from_slice( &frame[ IDX_SID + LEN_ELIDED.. ]     );
+
+	Ok( bytes )
+}
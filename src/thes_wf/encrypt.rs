@@ -0,0 +1,332 @@
+use
+{
+	crate            :: { import::*, ThesWF, WireErr, WireFormat      } ,
+	std              :: { io::Write as IoWrite                        } ,
+	std              :: { time::{ SystemTime, UNIX_EPOCH }            } ,
+	byteorder        :: { ByteOrder, LittleEndian                     } ,
+	chacha20poly1305 :: { ChaCha20Poly1305, Key, Nonce                } ,
+	chacha20poly1305 :: { aead::{ Aead, NewAead, Payload }            } ,
+};
+
+
+const LEN_NONCE: usize = 12;
+const LEN_TAG  : usize = 16;
+const LEN_TIME : usize =  8;
+
+
+/// The number of bytes encryption adds to each frame (nonce + authentication tag). Take this into
+/// account when choosing the `max_size` of the codec.
+//
+pub const ENCRYPT_OVERHEAD: usize = LEN_NONCE + LEN_TAG;
+
+/// The number of bytes the timestamp adds to each frame on top of [`ENCRYPT_OVERHEAD`] when
+/// [`FrameCipher::max_age`] is set.
+//
+pub const TIMESTAMP_OVERHEAD: usize = LEN_TIME;
+
+
+
+/// ChaCha20-Poly1305 with a pre-shared key, used by [`Encrypt`] and [`Decrypt`].
+///
+/// The payload of every frame is encrypted with a random nonce, which is prepended to the
+/// ciphertext. The header and the trailers after the payload, like the trace id, stay in the clear so
+/// frames can still be routed, but they are authenticated as associated data, so they cannot be
+/// changed without the frame failing to decrypt.
+///
+/// Both sides of a connection must use the same key.
+//
+#[ derive( Clone ) ]
+//
+pub struct FrameCipher
+{
+	cipher : ChaCha20Poly1305 ,
+	max_age: Option<Duration> ,
+}
+
+
+impl FrameCipher
+{
+	/// Create a cipher from a 256 bit pre-shared key.
+	//
+	pub fn new( key: &[u8; 32] ) -> Self
+	{
+		Self { cipher: ChaCha20Poly1305::new( Key::from_slice( key ) ), max_age: None }
+	}
+
+
+	/// Protect against replays of old frames. Each frame gets an encrypted timestamp and incoming frames
+	/// whose timestamp is more than `max_age` away from our clock are refused with [`WireErr::Replay`].
+	/// Frames can still be replayed within the window, so keep it short, but leave room for the clock
+	/// difference between both processes and the transit time.
+	///
+	/// This changes the frame format, so both sides of a connection must set it. It adds
+	/// [`TIMESTAMP_OVERHEAD`] bytes to each frame.
+	//
+	pub fn max_age( mut self, max_age: Duration ) -> Self
+	{
+		self.max_age = Some( max_age );
+		self
+	}
+
+
+	// The header and the trailers, which are authenticated but not encrypted. Both sides compute
+	// it over the frame as it goes over the wire.
+	//
+	fn aad( frame: &ThesWF ) -> Vec<u8>
+	{
+		let buf     = frame.as_buf();
+		let trailer = buf.len() - frame.trailer_len();
+
+		let mut aad = buf[ super::IDX_SID..super::IDX_MSG ].to_vec();
+		aad.extend_from_slice( &buf[ trailer.. ] );
+
+		aad
+	}
+
+
+	// A frame with the same header as `frame` and room for `capacity` bytes of payload.
+	//
+	fn reframe( frame: &ThesWF, capacity: usize ) -> ThesWF
+	{
+		let mut wf = ThesWF::with_capacity( capacity );
+
+		wf.set_sid     ( frame.sid()              );
+		wf.set_cid     ( frame.cid()              );
+		wf.set_deadline( frame.deadline()         );
+		wf.set_meta    ( frame.meta().as_ref()    );
+		wf.set_channel ( frame.channel()          );
+		wf.set_trace_id( frame.trace_id()         );
+		wf.set_route   ( frame.route().as_deref() );
+
+		wf
+	}
+
+
+	fn encrypt( &self, frame: ThesWF ) -> ThesWF
+	{
+		let mut nonce = [0u8; LEN_NONCE];
+		rand::thread_rng().fill( &mut nonce );
+
+		// The timestamp goes in front of the payload, so it's encrypted with it.
+		//
+		let stamped;
+
+		let msg = match self.max_age
+		{
+			None    => frame.msg(),
+			Some(_) =>
+			{
+				let mut buf = vec![ 0u8; LEN_TIME ];
+				LittleEndian::write_u64( &mut buf, now_millis() );
+				buf.extend_from_slice( frame.msg() );
+
+				stamped = buf;
+				&stamped
+			}
+		};
+
+		let mut wf = Self::reframe( &frame, ENCRYPT_OVERHEAD + msg.len() );
+		let     aad = Self::aad( &wf );
+
+		// expect: ChaCha20Poly1305 can only fail for payloads over 256GiB.
+		//
+		let sealed = self.cipher.encrypt( Nonce::from_slice( &nonce ), Payload{ msg, aad: &aad } )
+
+			.expect( "encrypt frame" );
+
+		// unwrap: writing to a Vec can't fail.
+		//
+		wf.write_all( &nonce  ).unwrap();
+		wf.write_all( &sealed ).unwrap();
+
+		wf
+	}
+
+
+	fn decrypt( &self, frame: ThesWF ) -> Result<ThesWF, WireErr>
+	{
+		let msg = frame.msg();
+
+		if msg.len() < ENCRYPT_OVERHEAD
+		{
+			return Err( WireErr::Decrypt{ context: "frame is too short to be encrypted".to_string() } );
+		}
+
+		let aad = Self::aad( &frame );
+
+		let opened = self.cipher.decrypt( Nonce::from_slice( &msg[..LEN_NONCE] ), Payload{ msg: &msg[LEN_NONCE..], aad: &aad } )
+
+			.map_err( |_| WireErr::Decrypt
+			{
+				context: format!( "authentication failed for frame with sid: {}, cid: {}", frame.sid(), frame.cid() )
+			})?
+		;
+
+		let payload = match self.max_age
+		{
+			None            => &opened[..],
+			Some( max_age ) => self.check_age( &frame, &opened, max_age )?,
+		};
+
+		let mut wf = Self::reframe( &frame, payload.len() );
+
+		// unwrap: writing to a Vec can't fail.
+		//
+		wf.write_all( payload ).unwrap();
+
+		Ok( wf )
+	}
+
+
+	// Refuse frames whose timestamp is outside of the window and return the payload after the timestamp.
+	//
+	fn check_age<'a>( &self, frame: &ThesWF, opened: &'a [u8], max_age: Duration ) -> Result<&'a [u8], WireErr>
+	{
+		if opened.len() < LEN_TIME
+		{
+			return Err( WireErr::Decrypt{ context: "frame is too short to hold a timestamp".to_string() } );
+		}
+
+		let sent = LittleEndian::read_u64( &opened[..LEN_TIME] );
+		let now  = now_millis();
+		let max  = u64::try_from( max_age.as_millis() ).unwrap_or( u64::MAX );
+
+		// Also refuse frames from the future, they might be recorded now to be replayed later.
+		//
+		if now.saturating_sub( sent ) > max || sent.saturating_sub( now ) > max
+		{
+			return Err( WireErr::Replay
+			{
+				context: format!
+				(
+					"frame with sid: {}, cid: {} was sent at {} ms, now is {} ms, the maximum age is {} ms",
+					frame.sid(), frame.cid(), sent, now, max,
+				)
+			});
+		}
+
+		Ok( &opened[LEN_TIME..] )
+	}
+}
+
+
+// Milliseconds since the unix epoch.
+//
+fn now_millis() -> u64
+{
+	let since = SystemTime::now().duration_since( UNIX_EPOCH ).unwrap_or_default();
+
+	u64::try_from( since.as_millis() ).unwrap_or( u64::MAX )
+}
+
+
+impl fmt::Debug for FrameCipher
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "FrameCipher" )
+	}
+}
+
+
+
+/// Sink adapter that encrypts outgoing frames before passing them to the underlying sink, normally
+/// an [`Encoder`](crate::thes_wf::Encoder).
+//
+#[ derive( Debug ) ]
+//
+pub struct Encrypt<T>
+{
+	inner : T           ,
+	cipher: FrameCipher ,
+}
+
+
+impl<T> Encrypt<T>
+{
+	/// Encrypt all frames before sending them to `inner`.
+	//
+	pub fn new( inner: T, cipher: FrameCipher ) -> Self
+	{
+		Self { inner, cipher }
+	}
+}
+
+
+impl<T> Sink<ThesWF> for Encrypt<T>
+
+	where T: Sink<ThesWF, Error=WireErr> + Unpin
+
+{
+	type Error = WireErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		Pin::new( &mut self.inner ).poll_ready( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: ThesWF ) -> Result<(), Self::Error>
+	{
+		let sealed = self.cipher.encrypt( msg );
+
+		Pin::new( &mut self.inner ).start_send( sealed )
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.inner ).poll_flush( cx )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.inner ).poll_close( cx )
+	}
+}
+
+
+
+/// Stream adapter that decrypts incoming frames from the underlying stream, normally
+/// a [`Decoder`](crate::thes_wf::Decoder). Frames that fail to decrypt are returned
+/// as [`WireErr::Decrypt`].
+//
+#[ derive( Debug ) ]
+//
+pub struct Decrypt<T>
+{
+	inner : T           ,
+	cipher: FrameCipher ,
+}
+
+
+impl<T> Decrypt<T>
+{
+	/// Decrypt all frames coming from `inner`.
+	//
+	pub fn new( inner: T, cipher: FrameCipher ) -> Self
+	{
+		Self { inner, cipher }
+	}
+}
+
+
+impl<T> Stream for Decrypt<T>
+
+	where T: Stream< Item = Result<ThesWF, WireErr> > + Unpin
+
+{
+	type Item = Result<ThesWF, WireErr>;
+
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		match futures::ready!( Pin::new( &mut self.inner ).poll_next( cx ) )
+		{
+			Some( Ok( frame ) ) => Some( self.cipher.decrypt( frame ) ).into(),
+			other               => other.into(),
+		}
+	}
+}
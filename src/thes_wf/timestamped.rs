@@ -0,0 +1,75 @@
+use
+{
+	crate :: { ThesWF, WireFormat  } ,
+	super :: { *                   } ,
+	std   :: { time::Instant       } ,
+};
+
+
+/// A frame together with the moment it was read off the transport, see [`Decoder::timestamped`].
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct Received<W = ThesWF>
+{
+	/// The frame.
+	//
+	pub frame: W,
+
+	/// When the last byte of the frame was read.
+	//
+	pub at: Instant,
+}
+
+
+
+/// Stream adapter that yields the frames of a [Decoder] together with the moment they were received,
+/// eg. to measure how long they wait before being processed. Created with [`Decoder::timestamped`].
+//
+#[ derive( Debug ) ]
+//
+pub struct Timestamped<T, W = ThesWF>
+{
+	inner: Decoder<T, W>,
+}
+
+
+impl<T, W> Decoder<T, W>
+{
+	/// Yield each frame in a [Received] with the [Instant] it was read off the transport. This is meant
+	/// for diagnostics, the peer itself takes frames without timestamp.
+	//
+	pub fn timestamped( self ) -> Timestamped<T, W>
+	{
+		Timestamped { inner: self }
+	}
+}
+
+
+impl<T, W> Timestamped<T, W>
+{
+	/// Get back the decoder.
+	//
+	pub fn into_inner( self ) -> Decoder<T, W>
+	{
+		self.inner
+	}
+}
+
+
+impl<T, W> Stream for Timestamped<T, W>
+
+	where T: FutAsyncRead + Unpin + Send + 'static            ,
+	      W: WireFormat + TryFrom< Vec<u8>, Error = WireErr > ,
+{
+	type Item = Result<Received<W>, WireErr>;
+
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		Pin::new( &mut self.inner ).poll_next( cx ).map( |item|
+		{
+			item.map( |frame| frame.map( |frame| Received{ frame, at: Instant::now() } ) )
+		})
+	}
+}
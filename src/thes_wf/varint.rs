@@ -0,0 +1,317 @@
+use
+{
+	super   :: { *                                       } ,
+	futures :: { io::{ AsyncReadExt, BufReader }, stream } ,
+};
+
+
+// The longest LEB128 encoding of a u64.
+//
+const MAX_VARINT: usize = 10;
+
+
+/// Writes [ThesWF] frames with a compact header, for connections that carry many tiny messages. The
+/// length, the cid and the deadline field are LEB128 varints instead of little endian u64s, so a send
+/// without deadline has 3 bytes of overhead besides the sid rather than 24. [VarintDecoder] reads them
+/// back into regular frames:
+///
+/// ```text
+/// length of the rest | sid                  | cid    | deadline field | message and metadata |
+/// varint             | u64 LE, u128 sid128  | varint | varint         | as in ThesWF         |
+/// ```
+///
+/// The sid stays fixed size since it's a hash, which doesn't get shorter as a varint. Both ends of a
+/// connection must use this framing, see [`Peer::from_async_read_varint`](crate::Peer::from_async_read_varint).
+//
+#[ derive(Debug) ]
+//
+pub struct VarintEncoder<T>
+{
+	out_bytes: T                          ,
+	buffer   : Option< (Vec<u8>, usize) > ,
+}
+
+
+impl<T> VarintEncoder<T>
+{
+	/// Write frames to `out_bytes`.
+	//
+	pub fn new( out_bytes: T ) -> Self
+	{
+		Self { out_bytes, buffer: None }
+	}
+
+
+	/// The underlying writer.
+	//
+	pub fn get_ref( &self ) -> &T
+	{
+		&self.out_bytes
+	}
+}
+
+
+impl<T> Sink<ThesWF> for VarintEncoder<T>
+
+	where T: FutAsyncWrite + Unpin,
+
+{
+	type Error = WireErr;
+
+
+	fn poll_ready( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Result<(), Self::Error> >
+	{
+		self.poll_flush( cx )
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, msg: ThesWF ) -> Result<(), Self::Error>
+	{
+		if self.buffer.is_some()
+		{
+			panic!( "call `poll_ready` before start_send" )
+		}
+
+		let buf  = msg.as_buf();
+		let rest = &buf[ IDX_MSG.. ];
+
+		let mut body = Vec::with_capacity( LEN_SID + 2 * MAX_VARINT + rest.len() );
+
+		body.extend_from_slice( &buf[ IDX_SID..IDX_SID+LEN_SID ] );
+		write_varint( &mut body, msg.cid().into()    );
+		write_varint( &mut body, msg.deadline_field() );
+		body.extend_from_slice( rest );
+
+		let mut frame = Vec::with_capacity( MAX_VARINT + body.len() );
+
+		write_varint( &mut frame, body.len() as u64 );
+		frame.extend_from_slice( &body );
+
+		self.buffer = Some( (frame, 0) );
+
+		Ok(())
+	}
+
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		loop { match self.buffer.take()
+		{
+			None => return Poll::Ready( Ok(()) ),
+
+			Some( (frame, mut pos) ) =>
+			{
+				match Pin::new( &mut self.out_bytes ).poll_write( cx, &frame[pos..] )
+				{
+					Poll::Pending =>
+					{
+						self.buffer = Some( (frame, pos) );
+						return Poll::Pending;
+					}
+
+					Poll::Ready( Ok(0) ) =>
+					{
+						return Err( WireErr::from( io::Error::from( io::ErrorKind::ConnectionAborted ) )).into();
+					}
+
+					Poll::Ready( Ok(x) ) =>
+					{
+						pos += x;
+
+						if pos == frame.len()
+						{
+							return Ok(()).into()
+						}
+
+						self.buffer = Some( (frame, pos) );
+					}
+
+					Poll::Ready( Err(e) ) => return Err( WireErr::from(e) ).into(),
+				}
+			}
+		}}
+	}
+
+
+	fn poll_close( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.poll_flush( cx )
+	}
+}
+
+
+
+/// Reads the frames written by [VarintEncoder] as regular [ThesWF] frames. `max_size` applies to the
+/// compact frames on the wire. The stream ends after an error, since the start of the next frame
+/// can't be found anymore.
+//
+pub struct VarintDecoder
+{
+	inner: Pin<Box< dyn Stream< Item = Result<ThesWF, WireErr> > + Send >>,
+}
+
+
+impl VarintDecoder
+{
+	/// Read frames from `byte_stream`.
+	//
+	pub fn new( byte_stream: impl FutAsyncRead + Unpin + Send + 'static, max_size: usize ) -> Self
+	{
+		let reader = Some( BufReader::new( byte_stream ) );
+
+		let inner = stream::unfold( reader, move |reader| async move
+		{
+			let mut reader = reader?;
+
+			match next_frame( &mut reader, max_size ).await
+			{
+				Ok ( Some( frame ) ) => Some(( Ok( frame ), Some( reader ) )),
+				Ok ( None          ) => None,
+				Err( err           ) => Some(( Err( err ), None )),
+			}
+		});
+
+		Self { inner: inner.boxed() }
+	}
+}
+
+
+impl fmt::Debug for VarintDecoder
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "thes_wf::VarintDecoder" )
+	}
+}
+
+
+impl Stream for VarintDecoder
+{
+	type Item = Result<ThesWF, WireErr>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		self.inner.as_mut().poll_next( cx )
+	}
+}
+
+
+
+// The next frame, with its regular header. None at the end of the stream, also when it ends in the
+// middle of a frame.
+//
+async fn next_frame<T>( reader: &mut BufReader<T>, max_size: usize ) -> Result< Option<ThesWF>, WireErr >
+
+	where T: FutAsyncRead + Unpin
+{
+	let mut len   = 0u64;
+	let mut shift = 0;
+
+	loop
+	{
+		let mut byte = [0u8];
+
+		match reader.read_exact( &mut byte ).await
+		{
+			Ok (_)                                             => {}
+			Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok( None ),
+			Err(e)                                             => return Err( e.into() ),
+		}
+
+		if shift >= 64
+		{
+			return Err( malformed( "the length doesn't fit in a u64" ) );
+		}
+
+		len   |= u64::from( byte[0] & 0x7f ) << shift;
+		shift += 7;
+
+		if byte[0] & 0x80 == 0 { break }
+	}
+
+
+	let len = usize::try_from( len ).unwrap_or( usize::MAX );
+
+	if len > max_size
+	{
+		return Err( WireErr::MessageSizeExceeded{ context: "VarintDecoder".to_string(), size: len, max_size } );
+	}
+
+	let mut body = vec![ 0u8; len ];
+
+	match reader.read_exact( &mut body ).await
+	{
+		Ok (_)                                             => {}
+		Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok( None ),
+		Err(e)                                             => return Err( e.into() ),
+	}
+
+
+	if body.len() < LEN_SID
+	{
+		return Err( malformed( "not enough bytes for the sid" ) );
+	}
+
+	let (sid, mut rest) = body.split_at( LEN_SID );
+
+	let cid = read_varint( &mut rest ).ok_or_else( || malformed( "invalid cid"            ) )?;
+	let ddl = read_varint( &mut rest ).ok_or_else( || malformed( "invalid deadline field" ) )?;
+
+	let mut buf = Vec::with_capacity( LEN_HEADER + rest.len() );
+
+	// unwrap: writing to a Vec can't fail.
+	//
+	buf.write_u64::<LittleEndian>( ( LEN_HEADER + rest.len() ) as u64 ).unwrap();
+	buf.extend_from_slice( sid );
+	buf.write_u64::<LittleEndian>( cid ).unwrap();
+	buf.write_u64::<LittleEndian>( ddl ).unwrap();
+	buf.extend_from_slice( rest );
+
+	ThesWF::try_from( buf ).map( Some )
+}
+
+
+fn malformed( context: &str ) -> WireErr
+{
+	WireErr::Deserialize{ context: format!( "VarintDecoder: {}", context ), source: None }
+}
+
+
+fn write_varint( buf: &mut Vec<u8>, mut n: u64 )
+{
+	while n >= 0x80
+	{
+		buf.push( n as u8 | 0x80 );
+		n >>= 7;
+	}
+
+	buf.push( n as u8 );
+}
+
+
+// None if the bytes run out or the number doesn't fit in a u64.
+//
+fn read_varint( bytes: &mut &[u8] ) -> Option<u64>
+{
+	let     all = *bytes;
+	let mut n   = 0u64;
+
+	for (i, byte) in all.iter().enumerate().take( MAX_VARINT )
+	{
+		let bits = u64::from( byte & 0x7f );
+
+		// The 10th byte can only hold the highest bit.
+		//
+		if i == MAX_VARINT - 1 && bits > 1 { return None }
+
+		n |= bits << ( 7 * i );
+
+		if byte & 0x80 == 0
+		{
+			*bytes = &all[ i+1.. ];
+			return Some( n );
+		}
+	}
+
+	None
+}
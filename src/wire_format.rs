@@ -1,22 +1,35 @@
 use crate::{ import::*, PeerErr } ;
+use std::time::SystemTime         ;
 
-mod unique_id  ;
-mod conn_id    ;
-mod service_id ;
-mod wire_err   ;
-mod wire_type  ;
+mod unique_id       ;
+mod buffer_provider ;
+mod conn_id         ;
+mod error_source    ;
+mod frame_kind      ;
+mod request_key     ;
+mod service_id      ;
+mod wire_err        ;
 
 #[ cfg(test) ] mod tests;
 #[ cfg(test) ] pub use tests::*;
 
 pub use
 {
-	service_id :: * ,
-	conn_id    :: * ,
-	wire_err   :: * ,
+	buffer_provider :: * ,
+	service_id      :: * ,
+	conn_id         :: * ,
+	error_source    :: * ,
+	request_key     :: * ,
+	wire_err        :: * ,
 };
 
-pub(crate) use wire_type::WireType;
+pub use frame_kind::FrameKind;
+
+
+/// Headers carried next to the payload of a frame, see [`WireFormat::meta`].
+//
+pub type Metadata = HashMap<String, String>;
+
 
 /// Trait holding the required functionality to function as a WireFormat for thespis_remote.
 //
@@ -43,6 +56,119 @@ pub trait WireFormat : Message< Return = Result<(), PeerErr> > + Default + Clone
 	//
 	fn set_cid( &mut self, cid: ConnID ) -> &mut Self;
 
+	/// The identity of this request, made of the sid and the cid. Two frames with the same sid and
+	/// cid have the same key, whatever their payload.
+	//
+	fn key( &self ) -> RequestKey
+	{
+		RequestKey { sid: self.sid(), cid: self.cid() }
+	}
+
+	/// The point in time after which the caller no longer cares about the response to this call.
+	/// [Peer](crate::Peer) rejects incoming calls past their deadline and relays forward it unchanged,
+	/// so it holds across all hops to the provider. Since it's an absolute time, the clocks of the
+	/// processes involved need to be reasonably in sync.
+	///
+	/// The default implementation is for wire formats that can't carry a deadline and returns `None`.
+	//
+	fn deadline( &self ) -> Option<SystemTime>
+	{
+		None
+	}
+
+	/// Set the deadline. The default implementation ignores it.
+	//
+	fn set_deadline( &mut self, _deadline: Option<SystemTime> ) -> &mut Self
+	{
+		self
+	}
+
+	/// The ids of the relaying peers this frame went through, in order. This is diagnostic metadata
+	/// and only present when the caller asked for it by setting an empty route on the request, see
+	/// [`WireFormat::set_route`]. [RelayMap](crate::RelayMap) appends the id of its peer to a route
+	/// that is present and copies it to the response, so the caller gets the whole path back.
+	///
+	/// The default implementation is for wire formats that can't carry a route and returns `None`.
+	//
+	fn route( &self ) -> Option< Vec<usize> >
+	{
+		None
+	}
+
+	/// Set the route trace, `None` to remove it. The default implementation ignores it.
+	//
+	fn set_route( &mut self, _route: Option<&[usize]> ) -> &mut Self
+	{
+		self
+	}
+
+	/// An opaque id chosen by the caller to correlate logs across processes, independent of the
+	/// [`cid`](WireFormat::cid). Handlers registered with `Services::register_traced` get it with the
+	/// message and the response to a call carries the trace id of the request, see `RemoteAddr::call_traced`.
+	/// Relays forward it unchanged.
+	///
+	/// The default implementation is for wire formats that can't carry a trace id and returns `None`.
+	//
+	fn trace_id( &self ) -> Option<u64>
+	{
+		None
+	}
+
+	/// Set the trace id, `None` to remove it. The default implementation ignores it.
+	//
+	fn set_trace_id( &mut self, _trace_id: Option<u64> ) -> &mut Self
+	{
+		self
+	}
+
+	/// Small string headers the caller attaches to a request, separate from the serialized message, eg.
+	/// an auth token or a tenant id. Handlers registered with `Services::register_with_meta` get them with
+	/// the message, see `RemoteAddr::call_with_meta`. Relays forward them unchanged.
+	///
+	/// The default implementation is for wire formats that can't carry headers and returns `None`.
+	//
+	fn meta( &self ) -> Option<Metadata>
+	{
+		None
+	}
+
+	/// Set the headers, `None` to remove them. The default implementation ignores them.
+	//
+	fn set_meta( &mut self, _meta: Option<&Metadata> ) -> &mut Self
+	{
+		self
+	}
+
+	/// The channel this frame is sent on, so several independent sets of services can share one
+	/// connection, eg. one per tenant. An incoming request is dispatched to the service maps registered
+	/// for its channel with [`Peer::register_channel`](crate::Peer::register_channel), so the same sid can
+	/// mean something different on each channel. `None` is the default channel, served by the service
+	/// maps from `register_services`. Set it on outgoing requests with `RemoteAddr::set_channel`.
+	///
+	/// The default implementation is for wire formats that can't carry a channel and returns `None`.
+	//
+	fn channel( &self ) -> Option<u64>
+	{
+		None
+	}
+
+	/// Set the channel, `None` for the default channel. The default implementation ignores it.
+	//
+	fn set_channel( &mut self, _channel: Option<u64> ) -> &mut Self
+	{
+		self
+	}
+
+	/// Give the buffer of this frame back to `buffers` when the frame is dropped, so it can be handed
+	/// out again, see [`BufferProvider::release`]. The decoders call this on the frames they read into
+	/// a buffer from their provider. The default implementation is for wire formats that don't own a
+	/// plain buffer and ignores it.
+	//
+	fn return_to( &mut self, _buffers: Arc<dyn BufferProvider> ) -> &mut Self
+	{
+		self
+	}
+
 	/// The serialized payload message. This is the actual actor message to be deserialized and
 	/// delivered to the actor.
 	//
@@ -58,22 +184,26 @@ pub trait WireFormat : Message< Return = Result<(), PeerErr> > + Default + Clone
 	//
 	fn with_capacity( size: usize ) -> Self;
 
-	/// Deciphers from the sid and cid values what kind of message this is. It distinguishes between
-	/// the variants in [`WireType`].
+	/// Deciphers from the sid and cid values what kind of message this is, eg. for custom routing. It
+	/// distinguishes between the variants in [`FrameKind`].
 	//
-	fn kind( &self ) -> WireType
+	fn kind( &self ) -> FrameKind
 	{
 		match self.sid()
 		{
-			x if x.is_null() => WireType::ConnectionError ,
-			x if x.is_full() => WireType::CallResponse    ,
+			x if x.is_null     () => FrameKind::Error     ,
+			x if x.is_full     () => FrameKind::Response  ,
+			x if x.is_chunk    () => FrameKind::Chunk     ,
+			x if x.is_stream   () => FrameKind::Stream    ,
+			x if x.is_handshake() => FrameKind::Handshake ,
+			x if x.is_control  () => FrameKind::Control   ,
 
 			_ =>
 			{
 				match self.cid()
 				{
-					x if x.is_null() => WireType::IncomingSend ,
-					_                => WireType::IncomingCall ,
+					x if x.is_null() => FrameKind::Send ,
+					_                => FrameKind::Call ,
 				}
 			}
 		}
@@ -0,0 +1,128 @@
+use crate::{ import::*, * };
+
+
+/// Hands out the buffers that frames are read into or built in. The decoders in [thes_wf](crate::thes_wf)
+/// ask for one buffer per frame, once they know its length, as do [`ThesWF::try_with_capacity`](crate::thes_wf::ThesWF::try_with_capacity)
+/// and the [Encoder](crate::thes_wf::Encoder) when it leaves out a null header. The default, [HeapBuffers],
+/// uses the global allocator and [BufferPool] reuses buffers. Implement this to serve frames from memory
+/// you control, eg. a fixed arena on an embedded target.
+///
+/// When no buffer is available, return [`WireErr::OutOfMemory`]. The decoder then returns the error
+/// and closes the stream, since the rest of the frame can no longer be read.
+///
+/// Buffers come back through [`BufferProvider::release`] when the frame using them is dropped, see
+/// [`WireFormat::return_to`].
+//
+pub trait BufferProvider: Send + Sync
+{
+	/// A zeroed buffer of exactly `len` bytes, or [`WireErr::OutOfMemory`].
+	//
+	fn alloc( &self, len: usize ) -> Result< Vec<u8>, WireErr >;
+
+	/// Take back a buffer handed out by [`BufferProvider::alloc`] once it's no longer used, eg. to hand
+	/// it out again. Its length and content are unspecified. The default implementation drops it.
+	//
+	fn release( &self, _buf: Vec<u8> ) {}
+}
+
+
+
+/// The default [BufferProvider]. It allocates from the global allocator, but reports a failed
+/// allocation as [`WireErr::OutOfMemory`] instead of aborting the process.
+//
+#[ derive( Debug, Clone, Copy, Default ) ]
+//
+pub struct HeapBuffers;
+
+
+impl BufferProvider for HeapBuffers
+{
+	fn alloc( &self, len: usize ) -> Result< Vec<u8>, WireErr >
+	{
+		let mut buf = Vec::new();
+
+		buf.try_reserve_exact( len ).map_err( |_|
+		{
+			WireErr::OutOfMemory{ context: "HeapBuffers".to_string(), size: len }
+
+		})?;
+
+		buf.resize( len, 0 );
+
+		Ok( buf )
+	}
+}
+
+
+
+/// A [BufferProvider] that keeps the buffers given back to it and hands them out again, so a
+/// connection that receives frames of similar sizes stops allocating once it's warmed up. Clones
+/// share the pool.
+///
+/// At most `max_buffers` are kept, the others are dropped. A buffer is reused for any frame that
+/// fits in its capacity, otherwise a new one is allocated like [HeapBuffers] does.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct BufferPool
+{
+	free       : Arc<Mutex< Vec<Vec<u8>> >> ,
+	max_buffers: usize                      ,
+}
+
+
+impl BufferPool
+{
+	/// Create a pool that keeps at most `max_buffers` free buffers.
+	//
+	pub fn new( max_buffers: usize ) -> Self
+	{
+		Self { free: Arc::new( Mutex::new( Vec::new() ) ), max_buffers }
+	}
+
+
+	/// The number of free buffers in the pool.
+	//
+	pub fn available( &self ) -> usize
+	{
+		self.free.lock().len()
+	}
+}
+
+
+impl BufferProvider for BufferPool
+{
+	fn alloc( &self, len: usize ) -> Result< Vec<u8>, WireErr >
+	{
+		let reused =
+		{
+			let mut free = self.free.lock();
+
+			free.iter().position( |buf| buf.capacity() >= len ).map( |i| free.swap_remove( i ) )
+		};
+
+		match reused
+		{
+			Some( mut buf ) =>
+			{
+				buf.clear();
+				buf.resize( len, 0 );
+
+				Ok( buf )
+			}
+
+			None => HeapBuffers.alloc( len ),
+		}
+	}
+
+
+	fn release( &self, buf: Vec<u8> )
+	{
+		let mut free = self.free.lock();
+
+		if free.len() < self.max_buffers
+		{
+			free.push( buf );
+		}
+	}
+}
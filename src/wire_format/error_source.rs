@@ -0,0 +1,80 @@
+use crate::{ import::* };
+
+
+/// The underlying error of a [`WireErr`](crate::WireErr) or [`PeerErr`](crate::PeerErr), eg. the
+/// `serde_cbor::Error` when a message fails to deserialize or the `io::Error` of the transport.
+///
+/// The error types of this crate are `Clone` and `PartialEq`, which most underlying errors are not,
+/// so they are wrapped in an `Arc`. Two `ErrorSource` compare equal when their `Display` output is
+/// the same.
+///
+/// The wrapped error is returned as `source()` by the error that holds it, so you can
+/// downcast it:
+///
+/// ```ignore
+/// let cbor_err = err.source().and_then( |e| e.downcast_ref::<serde_cbor::Error>() );
+/// ```
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct ErrorSource
+{
+	inner: Arc< dyn std::error::Error + Send + Sync >,
+}
+
+
+impl ErrorSource
+{
+	/// Wrap an error.
+	//
+	pub fn new( error: impl std::error::Error + Send + Sync + 'static ) -> Self
+	{
+		Self { inner: Arc::new( error ) }
+	}
+
+
+	/// Access the wrapped error.
+	//
+	pub fn inner( &self ) -> &( dyn std::error::Error + 'static )
+	{
+		&*self.inner
+	}
+}
+
+
+impl PartialEq for ErrorSource
+{
+	fn eq( &self, other: &Self ) -> bool
+	{
+		self.inner.to_string() == other.inner.to_string()
+	}
+}
+
+impl Eq for ErrorSource {}
+
+
+impl fmt::Display for ErrorSource
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		self.inner.fmt( f )
+	}
+}
+
+
+impl From< serde_cbor::Error > for ErrorSource
+{
+	fn from( inner: serde_cbor::Error ) -> Self
+	{
+		Self::new( inner )
+	}
+}
+
+
+impl From< io::Error > for ErrorSource
+{
+	fn from( inner: io::Error ) -> Self
+	{
+		Self::new( inner )
+	}
+}
@@ -0,0 +1,45 @@
+/// What a frame is for, as decided by [`WireFormat::kind`](crate::WireFormat::kind) from its sid and cid.
+///
+/// `Call`, `Send`, `Response` and `Error` are the frames that make up the request/response protocol.
+/// The other variants are frames the [Peer](crate::Peer) handles itself. More of those might be added,
+/// so matches on this need a wildcard arm.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+#[ non_exhaustive ]
+//
+pub enum FrameKind
+{
+	/// A request that wants a response. The sid is the service, the cid is chosen by the caller.
+	//
+	Call,
+
+	/// A request that doesn't want a response. The sid is the service, the cid is null.
+	//
+	Send,
+
+	/// The response to a call. The sid is full (all bits set), the cid is the one of the call.
+	//
+	Response,
+
+	/// The remote failed to process one of our requests or our connection. The sid is null, the cid
+	/// is the one of the call that failed, or null when it's not about a call.
+	//
+	Error,
+
+	/// A piece of a bigger frame, see [`Chunked`](crate::Chunked).
+	//
+	Chunk,
+
+	/// A frame of a bidirectional stream, see [`OpenStream`](crate::OpenStream).
+	//
+	Stream,
+
+	/// Announces the maximum frame size the remote accepts.
+	//
+	Handshake,
+
+	/// Meant for the [Peer](crate::Peer) itself, eg. to advertise capacity or to ping.
+	//
+	Control,
+}
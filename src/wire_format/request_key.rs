@@ -0,0 +1,30 @@
+use crate::{ import::*, * };
+
+
+/// Identifies a request by its service id and connection id, without the payload. Obtain it with
+/// [`WireFormat::key`]. Useful as the key of a cache that deduplicates requests.
+///
+/// Note that the cid is only unique among the calls in flight on one connection, and is null for
+/// sends.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Hash ) ]
+//
+pub struct RequestKey
+{
+	/// The service id of the request.
+	//
+	pub sid: ServiceID,
+
+	/// The connection id of the request.
+	//
+	pub cid: ConnID,
+}
+
+
+impl fmt::Display for RequestKey
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "sid: {}, cid: {}", self.sid, self.cid )
+	}
+}
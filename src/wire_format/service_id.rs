@@ -1,10 +1,16 @@
 use
 {
-	crate :: { import::*           } ,
-	super :: { unique_id::UniqueID } ,
+	crate     :: { import::*                                 } ,
+	super     :: { unique_id::UniqueID                       } ,
+	byteorder :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
 };
 
 
+// The sid of the control frame with code 0, the codes follow.
+//
+const CONTROL_BASE: u64 = u64::MAX - 0x1ff;
+
+
 static SERVICES: SyncLazy<Mutex< HashMap<ServiceID, &'static str> >> = SyncLazy::new( ||
 
 	Mutex::new( HashMap::new() )
@@ -14,19 +20,30 @@ static SERVICES: SyncLazy<Mutex< HashMap<ServiceID, &'static str> >> = SyncLazy:
 /// identifying the type to which the payload needs to be deserialized and the actor to which
 /// this message is to be delivered.
 ///
-/// Ideally we want to use 128 bits here to have globally unique identifiers with little chance
-/// of collision, but we use xxhash which for the moment only supports 64 bit, so we hash the
-/// namespace and typename separately both to 64 bits.
+/// By default a ServiceID is 64 bits. For less chance of collision between services, enable the
+/// `sid128` feature to make it 128 bits. Since xxhash only hashes to 64 bits, the seed is then hashed
+/// twice, with a different hash seed for each half. The low half is the same as the 64 bit ServiceID.
+/// The width changes the wire format, so all processes that talk to each other must agree on it.
 ///
-/// 2 values are reserved, all zero's and all one's are used as special values by Peer to
-/// detect error conditions. If ever your namespace + typename would hash to one of these,
-/// please change them.
+/// Some values are reserved. All zero's and all one's are used as special values by Peer to
+/// detect error conditions, `u64::MAX - 1` marks chunks of a message that was split up with
+/// [`Chunked`](crate::peer::Chunked), `u64::MAX - 2` marks the frames of a bidirectional stream,
+/// see [`OpenStream`](crate::peer::OpenStream) and `u64::MAX - 3` marks the handshake in which peers
+/// agree on the maximum frame size, see [`Peer::announce_max_frame_size`](crate::Peer::announce_max_frame_size).
+/// The 256 values from `u64::MAX - 0x1ff` mark control frames, see [`ServiceID::control`].
+/// If ever your namespace + typename would hash to one of these, please change them.
 //
 #[ derive( Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize ) ]
 //
 pub struct ServiceID
 {
 	inner: UniqueID,
+
+	// The most significant 64 bits.
+	//
+	#[ cfg( feature = "sid128" ) ]
+	//
+	high: u64,
 }
 
 
@@ -34,14 +51,88 @@ impl ServiceID
 {
 	/// Seed the ServiceID. It might be data that will be hashed to generate the id.
 	/// An identical input here should always give an identical ServiceID.
+	///
+	/// This is a const fn, so sids can be computed at compile time, eg. to use them as patterns:
+	/// `const ADD: ServiceID = ServiceID::from_seed( b"myns::Add" );`
+	//
+	pub const fn from_seed( data: &[u8] ) -> Self
+	{
+		let sid = Self
+		{
+			inner: UniqueID::from_seed( data ),
+
+			#[ cfg( feature = "sid128" ) ]
+			//
+			high: super::unique_id::xxh64( data, 1 ),
+		};
+
+		debug_assert!( !sid.is_null(), "Hashing your namespace + typename generated a hash that is all zero's, which is a reserved value. Please slightly change either one." );
+		debug_assert!( !sid.is_control(), "Hashing your namespace + typename generated a hash reserved for control frames. Please slightly change either one." );
+
+		sid
+	}
+
+
+	/// The size of a ServiceID on the wire in bytes. 8, or 16 with the `sid128` feature.
+	//
+	pub const SIZE: usize = if cfg!( feature = "sid128" ) { 16 } else { 8 };
+
+
+	/// Write the ServiceID as it goes on the wire, a little endian integer of [`ServiceID::SIZE`] bytes.
+	//
+	pub fn write_le( &self, out: &mut impl io::Write ) -> io::Result<()>
+	{
+		out.write_u64::<LittleEndian>( self.inner.into() )?;
+
+		#[ cfg( feature = "sid128" ) ]
+		//
+		out.write_u64::<LittleEndian>( self.high )?;
+
+		Ok(())
+	}
+
+
+	/// Read a ServiceID written by [`ServiceID::write_le`].
+	//
+	pub fn read_le( input: &mut impl io::Read ) -> io::Result<Self>
+	{
+		Ok( Self
+		{
+			inner: UniqueID::from( input.read_u64::<LittleEndian>()? ),
+
+			#[ cfg( feature = "sid128" ) ]
+			//
+			high: input.read_u64::<LittleEndian>()?,
+		})
+	}
+
+
+	/// Parse a ServiceID as shown by `{:x}`, with or without `0x` in front. `None` if `hex` isn't a
+	/// hexadecimal number that fits.
 	//
-	pub fn from_seed( data: &[u8] ) -> Self
+	pub fn from_hex( hex: &str ) -> Option<Self>
 	{
-		let inner = UniqueID::from_seed( data );
+		let hex = hex.strip_prefix( "0x" ).unwrap_or( hex );
 
-		debug_assert!( !inner.is_null(), "Hashing your namespace + typename generated a hash that is all zero's, which is a reserved value. Please slightly change either one." );
+		#[ cfg(     feature = "sid128"  ) ] let raw = u128::from_str_radix( hex, 16 ).ok()?;
+		#[ cfg(not( feature = "sid128" )) ] let raw = u64 ::from_str_radix( hex, 16 ).ok()?;
 
-		Self{ inner }
+		Some( Self::from( raw ) )
+	}
+
+
+	// The reserved values other than null all have the high half set.
+	//
+	fn reserved( low: u64 ) -> Self
+	{
+		Self
+		{
+			inner: UniqueID::from( low ),
+
+			#[ cfg( feature = "sid128" ) ]
+			//
+			high: u64::MAX,
+		}
 	}
 
 
@@ -50,14 +141,18 @@ impl ServiceID
 	//
 	pub fn null() -> Self
 	{
-		Self{ inner: UniqueID::null() }
+		Self::from( 0 )
 	}
 
 
 	/// Predicate for null values (all bytes are 0).
 	//
-	pub fn is_null( &self ) -> bool
+	pub const fn is_null( &self ) -> bool
 	{
+		#[ cfg( feature = "sid128" ) ]
+		//
+		if self.high != 0 { return false }
+
 		self.inner.is_null()
 	}
 
@@ -66,7 +161,7 @@ impl ServiceID
 	//
 	pub fn full() -> Self
 	{
-		Self{ inner: UniqueID::full() }
+		Self::reserved( UniqueID::full().into() )
 	}
 
 
@@ -74,10 +169,101 @@ impl ServiceID
 	//
 	pub fn is_full( &self ) -> bool
 	{
+		#[ cfg( feature = "sid128" ) ]
+		//
+		if self.high != u64::MAX { return false }
+
 		self.inner.is_full()
 	}
 
 
+	/// The ServiceID used in the header of chunks of a bigger message. Value reserved by thespis.
+	//
+	pub fn chunk() -> Self
+	{
+		Self::reserved( u64::MAX - 1 )
+	}
+
+
+	/// Predicate for the chunk marker.
+	//
+	pub fn is_chunk( &self ) -> bool
+	{
+		*self == Self::chunk()
+	}
+
+
+	/// The ServiceID used in the header of the frames of a bidirectional stream. Value reserved by thespis.
+	//
+	pub fn stream() -> Self
+	{
+		Self::reserved( u64::MAX - 2 )
+	}
+
+
+	/// Predicate for the stream marker.
+	//
+	pub fn is_stream( &self ) -> bool
+	{
+		*self == Self::stream()
+	}
+
+
+	/// The ServiceID used in the header of the handshake frame that announces the maximum frame size a peer
+	/// accepts. Value reserved by thespis.
+	//
+	pub fn handshake() -> Self
+	{
+		Self::reserved( u64::MAX - 3 )
+	}
+
+
+	/// Predicate for the handshake marker.
+	//
+	pub fn is_handshake( &self ) -> bool
+	{
+		*self == Self::handshake()
+	}
+
+
+	/// The ServiceID used in the header of a control frame with the given code. Control frames are meant
+	/// for the [Peer](crate::Peer) itself, eg. keepalives or cancellation, so they are never delivered to
+	/// a [ServiceMap](crate::ServiceMap). Values reserved by thespis.
+	//
+	pub fn control( code: u8 ) -> Self
+	{
+		Self::reserved( CONTROL_BASE + u64::from( code ) )
+	}
+
+
+	/// Predicate for the control frame markers.
+	//
+	pub const fn is_control( &self ) -> bool
+	{
+		#[ cfg( feature = "sid128" ) ]
+		//
+		if self.high != u64::MAX { return false }
+
+		let low = self.inner.get();
+
+		low >= CONTROL_BASE && low - CONTROL_BASE <= u8::MAX as u64
+	}
+
+
+	/// The code of a control frame, `None` if this isn't a control marker.
+	//
+	pub fn control_code( &self ) -> Option<u8>
+	{
+		#[ cfg( feature = "sid128" ) ]
+		//
+		if self.high != u64::MAX { return None }
+
+		let low: u64 = self.inner.into();
+
+		low.checked_sub( CONTROL_BASE ).and_then( |code| u8::try_from( code ).ok() )
+	}
+
+
 	/// Register the typename a ServiceID refers to so it can be used later for log output.
 	/// the `service_map!` macro does this automatically for you.
 	//
@@ -97,12 +283,26 @@ impl ServiceID
 
 		s.get( &sid ).copied()
 	}
+
+
+	/// A copy of all the registered typenames by ServiceID, eg. to export them for a tool that needs to
+	/// decode captured frames. A service map made with `service_map!` registers its services when
+	/// the first `Services` is created.
+	//
+	pub fn registered_services() -> HashMap<ServiceID, String>
+	{
+		let s = SERVICES.lock();
+
+		s.iter().map( |(sid, name)| ( *sid, name.to_string() ) ).collect()
+	}
 }
 
 
 
 /// Internally is also represented as u64, so you just get a copy.
 //
+#[ cfg(not( feature = "sid128" )) ]
+//
 impl Into< u64 > for ServiceID
 {
 	fn into( self ) -> u64
@@ -112,13 +312,44 @@ impl Into< u64 > for ServiceID
 }
 
 
-/// The object will just keep the bytes as internal representation, no copies will be made
+/// With the `sid128` feature, the high half is zero.
 //
 impl From< u64 > for ServiceID
 {
-	fn from( bytes: u64 ) -> Self
+	fn from( low: u64 ) -> Self
 	{
-		Self { inner: UniqueID::from( bytes ) }
+		Self
+		{
+			inner: UniqueID::from( low ),
+
+			#[ cfg( feature = "sid128" ) ]
+			//
+			high: 0,
+		}
+	}
+}
+
+
+#[ cfg( feature = "sid128" ) ]
+//
+impl Into< u128 > for ServiceID
+{
+	fn into( self ) -> u128
+	{
+		( u128::from( self.high ) << 64 ) | u128::from( Into::<u64>::into( self.inner ) )
+	}
+}
+
+
+#[ cfg( feature = "sid128" ) ]
+//
+impl From< u128 > for ServiceID
+{
+	fn from( id: u128 ) -> Self
+	{
+		// The casts truncate on purpose, to get each half.
+		//
+		Self { inner: UniqueID::from( id as u64 ), high: ( id >> 64 ) as u64 }
 	}
 }
 
@@ -130,7 +361,7 @@ impl fmt::Display for ServiceID
 		match Self::service_name( *self )
 		{
 			Some(name) => write!( f, "{}", name ),
-			None       => self.inner.fmt( f ),
+			None       => write!( f, "{:#x}", self ),
 		}
 	}
 }
@@ -143,17 +374,25 @@ impl fmt::Debug for ServiceID
 	{
 		match Self::service_name( *self )
 		{
-			Some(name) => write!( f, "ServiceID: {} ({:?})", name, self.inner ),
-			None       => self.inner.fmt( f ),
+			Some(name) => write!( f, "ServiceID: {} ({:#x})", name, self ),
+			None       => write!( f, "{:#x}", self ),
 		}
 	}
 }
 
 
+/// Always shows all digits, so the width of the ServiceID is visible.
+//
 impl fmt::LowerHex for ServiceID
 {
 	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
 	{
-		fmt::LowerHex::fmt( &self.inner, f )
+		if f.alternate() { write!( f, "0x" )?; }
+
+		#[ cfg( feature = "sid128" ) ]
+		//
+		write!( f, "{:016x}", self.high )?;
+
+		write!( f, "{:016x}", Into::<u64>::into( self.inner ) )
 	}
 }
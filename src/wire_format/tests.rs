@@ -12,12 +12,15 @@
 //!   - try to exceed the allowed length.
 //!   - fuzz test
 //!
+//! The suite is generic over the wire format, [DummyWF] is a second format to run it against.
+//!
 use
 {
-	super           :: { *, assert_eq          } ,
-	futures_ringbuf :: { Endpoint, Sketchy, Dictator } ,
-	async_executors :: { AsyncStd              } ,
-	futures         :: { task::LocalSpawnExt, join   } ,
+	super           :: { *, assert_eq                              } ,
+	futures_ringbuf :: { Endpoint, Sketchy, Dictator               } ,
+	async_executors :: { AsyncStd                                  } ,
+	futures         :: { task::LocalSpawnExt, join                 } ,
+	byteorder       :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
 };
 
 
@@ -186,3 +189,146 @@ impl<Wf, Si, St> TestSuite<Wf, Si, St>
 		}
 	}
 }
+
+
+
+/// A minimal wire format to verify that the codecs don't depend on ThesWF. It has no deadline and the
+/// cid comes before the sid:
+///
+/// ```text
+/// u64 length | u64 cid | sid | serialized message
+/// ```
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct DummyWF
+{
+	data: Vec<u8>,
+}
+
+
+impl DummyWF
+{
+	const IDX_CID   : usize = 8;
+	const IDX_SID   : usize = 16;
+	const LEN_HEADER: usize = Self::IDX_SID + ServiceID::SIZE;
+
+
+	fn set_len( &mut self )
+	{
+		let len = self.data.len() as u64;
+
+		self.data[ 0..Self::IDX_CID ].as_mut().write_u64::<LittleEndian>( len ).unwrap();
+	}
+}
+
+
+impl Message for DummyWF
+{
+	type Return = Result<(), PeerErr>;
+}
+
+
+impl WireFormat for DummyWF
+{
+	fn sid( &self ) -> ServiceID
+	{
+		ServiceID::read_le( &mut self.data[ Self::IDX_SID..Self::LEN_HEADER ].as_ref() ).unwrap()
+	}
+
+
+	fn set_sid( &mut self, sid: ServiceID ) -> &mut Self
+	{
+		sid.write_le( &mut self.data[ Self::IDX_SID..Self::LEN_HEADER ].as_mut() ).unwrap();
+		self
+	}
+
+
+	fn cid( &self ) -> ConnID
+	{
+		self.data[ Self::IDX_CID..Self::IDX_SID ].as_ref().read_u64::<LittleEndian>().unwrap().into()
+	}
+
+
+	fn set_cid( &mut self, cid: ConnID ) -> &mut Self
+	{
+		self.data[ Self::IDX_CID..Self::IDX_SID ].as_mut().write_u64::<LittleEndian>( cid.into() ).unwrap();
+		self
+	}
+
+
+	fn msg( &self ) -> &[u8]
+	{
+		&self.data[ Self::LEN_HEADER.. ]
+	}
+
+
+	fn len( &self ) -> u64
+	{
+		self.data.len() as u64
+	}
+
+
+	fn with_capacity( size: usize ) -> Self
+	{
+		let mut data = Vec::with_capacity( size + Self::LEN_HEADER );
+		data.resize( Self::LEN_HEADER, 0 );
+
+		let mut wf = Self { data };
+		wf.set_len();
+
+		wf
+	}
+}
+
+
+impl Default for DummyWF
+{
+	fn default() -> Self
+	{
+		Self::with_capacity( 0 )
+	}
+}
+
+
+impl io::Write for DummyWF
+{
+	fn write( &mut self, buf: &[u8] ) -> io::Result<usize>
+	{
+		self.data.extend_from_slice( buf );
+		self.set_len();
+
+		Ok( buf.len() )
+	}
+
+
+	fn flush( &mut self ) -> io::Result<()>
+	{
+		Ok(())
+	}
+}
+
+
+impl AsRef<[u8]> for DummyWF
+{
+	fn as_ref( &self ) -> &[u8]
+	{
+		&self.data
+	}
+}
+
+
+impl TryFrom< Vec<u8> > for DummyWF
+{
+	type Error = WireErr;
+
+	fn try_from( data: Vec<u8> ) -> Result< Self, WireErr >
+	{
+		if data.len() < Self::LEN_HEADER
+		{
+			return Err( WireErr::Deserialize{ context: "DummyWF: not enough bytes even for the header.".to_string(), source: None } );
+		}
+
+		Ok( Self { data } )
+	}
+}
@@ -33,20 +33,16 @@ impl UniqueID
 	///
 	/// An identical input here should always give an identical UniqueID.
 	//
-	pub(crate) fn from_seed( data: &[u8] ) -> Self
+	pub(crate) const fn from_seed( data: &[u8] ) -> Self
 	{
-		let mut h = XxHash64::default();
-
-		h.write( data );
-
-		Self { id: h.finish() }
+		Self { id: xxh64( data, 0 ) }
 	}
 
 
 	/// And empty UniqueID. Can be used to signify the abscence of an id, would usually be all
 	/// zero bytes.
 	//
-	pub(crate) fn null() -> Self
+	pub(crate) const fn null() -> Self
 	{
 		Self { id: 0 }
 	}
@@ -56,7 +52,7 @@ impl UniqueID
 	/// one bytes.
 	/// u128 still isn't well supported everywhere (WASM), so use 2 u64;
 	//
-	pub(crate) fn full() -> Self
+	pub(crate) const fn full() -> Self
 	{
 		Self { id: u64::MAX }
 	}
@@ -64,7 +60,7 @@ impl UniqueID
 
 	/// Predicate for null values (all bytes are 0).
 	//
-	pub(crate) fn is_null( &self ) -> bool
+	pub(crate) const fn is_null( &self ) -> bool
 	{
 		self.id == 0
 	}
@@ -72,10 +68,139 @@ impl UniqueID
 
 	/// Predicate for null values (all bytes are 0).
 	//
-	pub(crate) fn is_full( &self ) -> bool
+	pub(crate) const fn is_full( &self ) -> bool
 	{
 		self.id == u64::MAX
 	}
+
+
+	/// The raw value, usable in const context unlike `Into<u64>`.
+	//
+	pub(crate) const fn get( &self ) -> u64
+	{
+		self.id
+	}
+}
+
+
+
+// XXH64, the same hash as `twox_hash::XxHash64`, but as a const fn so service ids can be computed
+// at compile time. Hashers from crates can't be used in const context.
+//
+const PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+
+pub(crate) const fn xxh64( data: &[u8], seed: u64 ) -> u64
+{
+	let len = data.len();
+	let mut i = 0;
+
+	let mut h = if len >= 32
+	{
+		let mut v1 = seed.wrapping_add( PRIME_1 ).wrapping_add( PRIME_2 );
+		let mut v2 = seed.wrapping_add( PRIME_2 );
+		let mut v3 = seed;
+		let mut v4 = seed.wrapping_sub( PRIME_1 );
+
+		while i + 32 <= len
+		{
+			v1 = round( v1, read_u64( data, i      ) );
+			v2 = round( v2, read_u64( data, i +  8 ) );
+			v3 = round( v3, read_u64( data, i + 16 ) );
+			v4 = round( v4, read_u64( data, i + 24 ) );
+
+			i += 32;
+		}
+
+		let mut h = v1.rotate_left( 1 )
+			.wrapping_add( v2.rotate_left(  7 ) )
+			.wrapping_add( v3.rotate_left( 12 ) )
+			.wrapping_add( v4.rotate_left( 18 ) )
+		;
+
+		h = merge( h, v1 );
+		h = merge( h, v2 );
+		h = merge( h, v3 );
+		merge( h, v4 )
+	}
+
+	else { seed.wrapping_add( PRIME_5 ) };
+
+
+	h = h.wrapping_add( len as u64 );
+
+	while i + 8 <= len
+	{
+		h ^= round( 0, read_u64( data, i ) );
+		h  = h.rotate_left( 27 ).wrapping_mul( PRIME_1 ).wrapping_add( PRIME_4 );
+		i += 8;
+	}
+
+	if i + 4 <= len
+	{
+		h ^= ( read_u32( data, i ) as u64 ).wrapping_mul( PRIME_1 );
+		h  = h.rotate_left( 23 ).wrapping_mul( PRIME_2 ).wrapping_add( PRIME_3 );
+		i += 4;
+	}
+
+	while i < len
+	{
+		h ^= ( data[i] as u64 ).wrapping_mul( PRIME_5 );
+		h  = h.rotate_left( 11 ).wrapping_mul( PRIME_1 );
+		i += 1;
+	}
+
+	h ^= h >> 33;
+	h  = h.wrapping_mul( PRIME_2 );
+	h ^= h >> 29;
+	h  = h.wrapping_mul( PRIME_3 );
+	h ^  h >> 32
+}
+
+
+const fn round( acc: u64, input: u64 ) -> u64
+{
+	acc.wrapping_add( input.wrapping_mul( PRIME_2 ) ).rotate_left( 31 ).wrapping_mul( PRIME_1 )
+}
+
+
+const fn merge( acc: u64, val: u64 ) -> u64
+{
+	( acc ^ round( 0, val ) ).wrapping_mul( PRIME_1 ).wrapping_add( PRIME_4 )
+}
+
+
+const fn read_u64( data: &[u8], at: usize ) -> u64
+{
+	let mut out = 0;
+	let mut i   = 8;
+
+	while i > 0
+	{
+		i   -= 1;
+		out  = out << 8 | data[ at + i ] as u64;
+	}
+
+	out
+}
+
+
+const fn read_u32( data: &[u8], at: usize ) -> u32
+{
+	let mut out = 0;
+	let mut i   = 4;
+
+	while i > 0
+	{
+		i   -= 1;
+		out  = out << 8 | data[ at + i ] as u32;
+	}
+
+	out
 }
 
 
@@ -158,6 +283,27 @@ mod tests
 	}
 
 
+	// The const hash must give the same ids as the hasher used before, and before that on the wire.
+	//
+	#[test]
+	//
+	fn xxh64_matches_twox_hash()
+	{
+		let data: Vec<u8> = (0..100).map( |i| i as u8 ).collect();
+
+		for len in 0..data.len()
+		{
+			for seed in &[ 0, 1, u64::MAX ]
+			{
+				let mut h = XxHash64::with_seed( *seed );
+				h.write( &data[ ..len ] );
+
+				assert_eq!( h.finish(), xxh64( &data[ ..len ], *seed ), "len: {}, seed: {}", len, seed );
+			}
+		}
+	}
+
+
 	#[test]
 	//
 	fn full()
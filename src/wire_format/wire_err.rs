@@ -1,4 +1,4 @@
-use crate::{ import::* };
+use crate::{ import::*, ErrorSource };
 
 
 /// Errors that can happen in thespis_impl.
@@ -34,25 +34,78 @@ pub enum WireErr
 		/// The contex in which the error happened.
 		//
 		context: String,
+
+		/// The underlying error from the deserializer if there is one.
+		//
+		source: Option<ErrorSource>,
+	},
+
+
+	/// Failed to decrypt or authenticate an incoming frame. Either the remote uses a different key or
+	/// the data was tampered with. The connection will be closed.
+	//
+	Decrypt
+	{
+		/// The contex in which the error happened.
+		//
+		context: String,
+	},
+
+
+	/// An incoming frame is older than allowed, see [`FrameCipher::max_age`](crate::thes_wf::FrameCipher::max_age).
+	/// It might be replayed by an attacker. The connection will be closed.
+	//
+	Replay
+	{
+		/// The contex in which the error happened.
+		//
+		context: String,
+	},
+
+
+	/// No buffer could be obtained for an incoming frame, see [BufferProvider](crate::BufferProvider).
+	/// The stream is closed, since the rest of the frame can no longer be read.
+	//
+	OutOfMemory
+	{
+		/// The contex in which the error happened.
+		//
+		context: String,
+
+		/// The size in bytes of the buffer that was asked for.
+		//
+		size: usize,
 	},
 
 
 	/// An io::Error happenend in the underlying network connection.
-	///
-	/// We don't want to use source here because io::Error is not clone, but
-	/// for some people having backtraces might be important?
 	//
 	Io
 	{
 		/// The ErrorKind
 		//
-		kind: std::io::ErrorKind
+		kind: std::io::ErrorKind,
+
+		/// The original io::Error.
+		//
+		source: ErrorSource,
 	},
 }
 
 
 
-impl std::error::Error for WireErr {}
+impl std::error::Error for WireErr
+{
+	fn source( &self ) -> Option< &(dyn std::error::Error + 'static) >
+	{
+		match &self
+		{
+			WireErr::Deserialize{ source, .. } => source.as_ref().map( ErrorSource::inner ),
+			WireErr::Io         { source, .. } => Some( source.inner() ),
+			_                                  => None,
+		}
+	}
+}
 
 
 impl fmt::Display for WireErr
@@ -65,11 +118,23 @@ impl fmt::Display for WireErr
 
 				write!( f, "Maximum message size exceeded: context: {}, actual: {} bytes, allowed: {} bytes.", context, size, max_size ),
 
-			WireErr::Deserialize{ context } =>
+			WireErr::Deserialize{ context, .. } =>
 
 				write!( f, "Failed to deserialize incoming data. The connection will be closed because the stream integrity can no longer be assumed{}", context ),
 
-			WireErr::Io{ kind } =>
+			WireErr::Decrypt{ context } =>
+
+				write!( f, "Failed to decrypt incoming frame. The connection will be closed: {}", context ),
+
+			WireErr::Replay{ context } =>
+
+				write!( f, "Refused an incoming frame that is too old, it might be replayed. The connection will be closed: {}", context ),
+
+			WireErr::OutOfMemory{ context, size } =>
+
+				write!( f, "Out of memory: no buffer for an incoming frame of {} bytes. The connection will be closed: {}", size, context ),
+
+			WireErr::Io{ kind, .. } =>
 
 				write!( f, "Io: {:?}", kind ),
 		}
@@ -82,7 +147,7 @@ impl From< std::io::Error > for WireErr
 {
 	fn from( inner: std::io::Error ) -> WireErr
 	{
-		WireErr::Io{ kind: inner.kind() }
+		WireErr::Io{ kind: inner.kind(), source: inner.into() }
 	}
 }
 
@@ -1,11 +0,0 @@
-/// Type of message.
-//
-#[ derive(Debug) ]
-//
-pub enum WireType
-{
-	ConnectionError,
-	IncomingSend,
-	IncomingCall,
-	CallResponse,
-}
@@ -0,0 +1,47 @@
+// Tests:
+//
+// ✔ Sends and calls to the sids of an old version of the services reach the handlers registered for
+//   the new sids through AliasMap, and the new sids keep working.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+// The same services under the namespace of a previous release, so with other sids.
+//
+service_map!
+(
+	namespace  : remotes_v1 ;
+	wire_format: ThesWF     ;
+	services   : Add, Show  ;
+);
+
+
+
+#[async_std::test]
+//
+async fn old_sids()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let sm = AliasMap::new( Arc::new( add_show_sum() ) )
+
+		.alias( <Add  as remotes_v1::Service>::sid(), <Add  as remotes::Service>::sid() )
+		.alias( <Show as remotes_v1::Service>::sid(), <Show as remotes::Service>::sid() )
+	;
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut old = remotes_v1::RemoteAddr::new( client_addr.clone() );
+	let mut new = remotes   ::RemoteAddr::new( client_addr.clone() );
+
+	old.send( Add( 5 ) ).await.expect( "send Add to old sid" );
+	new.send( Add( 2 ) ).await.expect( "send Add to new sid" );
+
+	assert_eq!( 7, old.call( Show ).await.expect( "call Show on old sid" ) );
+	assert_eq!( 7, new.call( Show ).await.expect( "call Show on new sid" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,45 @@
+// Tests:
+//
+// ✔ A peer with auto close on idle stops once its calls are done and all addresses are dropped.
+//
+mod common;
+
+use
+{
+	common   :: { *, import::{ *, assert_eq } } ,
+	async_std:: { future::timeout             } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn stops_when_idle()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+
+	let (client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut peer = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.set_auto_close_on_idle( true );
+
+	let client_handle = AsyncStd.spawn_handle( client_mb.start( peer ) ).expect( "start mailbox of Peer" );
+
+
+	let mut addr = remotes::RemoteAddr::new( client_addr );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+	addr.send( Add(5) ).await.expect( "send Add" );
+
+	assert_eq!( 10, addr.call( Show ).await.expect( "call Show" ) );
+
+	drop( addr );
+
+	// No CloseConnection was sent, yet the mailbox stops.
+	//
+	timeout( Duration::from_secs( 1 ), client_handle ).await.expect( "mailbox of peer to stop" );
+}
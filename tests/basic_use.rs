@@ -197,7 +197,7 @@ async fn call_after_close_connection()
 		//
 		let mut addr = remotes::RemoteAddr::new( peera.clone() );
 
-		assert_eq!( PeerEvent::ClosedByRemote, peera_evts.next().await.unwrap() );
+		assert_eq!( PeerEvent::ClosedByRemote( CloseReason::Eof ), peera_evts.next().await.unwrap() );
 
 
 		match addr.call( Add(5) ).await
@@ -0,0 +1,27 @@
+#![ cfg( feature = "blocking" ) ]
+
+// Tests:
+//
+// ✔ Call a remote service from synchronous code.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+#[test]
+//
+fn blocking_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = block_on( peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ) );
+
+	let mut addr = BlockingRemoteAddr::connect( client, 1024, remotes::RemoteAddr::new ).expect( "connect" );
+
+	addr.call( Add(5) ).expect( "call Add" );
+	addr.send( Add(3) ).expect( "send Add" );
+
+	assert_eq!( 8, addr.call( Show ).expect( "call Show" ) );
+}
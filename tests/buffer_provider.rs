@@ -0,0 +1,201 @@
+// Tests:
+//
+// ✔ With a tiny arena, both decoders read the frames that fit and return an out of memory error for
+//   a frame that doesn't, after which the stream ends.
+// ✔ ThesWF::try_with_capacity fails gracefully when the arena is exhausted.
+// ✔ With a BufferPool, the buffer of a decoded frame goes back to the pool when the frame is dropped
+//   and the decoder reads the next frame into it.
+// ✔ The buffers of ThesWF::try_with_capacity and of the encoder for elided frames go back to the pool.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq, assert_ne, assert_matches } } ,
+	futures :: { AsyncReadExt, Sink, SinkExt                           } ,
+	thes_wf :: { Decoder, DecoderNoHeap, Encoder                       } ,
+};
+
+
+// Hands out at most `left` bytes in total.
+//
+struct Arena
+{
+	left: AtomicUsize,
+}
+
+
+impl BufferProvider for Arena
+{
+	fn alloc( &self, len: usize ) -> Result< Vec<u8>, WireErr >
+	{
+		self.left.fetch_update( Relaxed, Relaxed, |left| left.checked_sub( len ) ).map_err( |_|
+		{
+			WireErr::OutOfMemory{ context: "Arena".to_string(), size: len }
+
+		})?;
+
+		Ok( vec![ 0; len ] )
+	}
+}
+
+
+fn arena() -> Arena
+{
+	Arena{ left: AtomicUsize::new( 100 ) }
+}
+
+
+fn frame( payload: &[u8] ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( ServiceID::from_seed( b"buffer_provider" ) );
+	wf.write_all( payload ).expect( "write payload" );
+
+	wf
+}
+
+
+// Send a frame that fits in the arena, one that doesn't and another one that would fit. The encoder
+// is returned to keep the connection open. The endpoints have room for all three frames.
+//
+async fn send_frames( socket: Endpoint ) -> impl Sink<ThesWF>
+{
+	let (_, writer) = socket.split();
+	let mut encoder = Encoder::new( writer, 1024 );
+
+	encoder.send( frame( &[ 1; 10  ] ) ).await.expect( "send small frame" );
+	encoder.send( frame( &[ 2; 100 ] ) ).await.expect( "send big frame"   );
+	encoder.send( frame( &[ 3; 10  ] ) ).await.expect( "send small frame" );
+
+	encoder
+}
+
+
+
+#[async_std::test]
+//
+async fn heap_decoder()
+{
+	let (server, client) = Endpoint::pair( 1024, 1024 );
+
+	let _encoder    = send_frames( client ).await;
+	let (reader, _) = server.split();
+	let decoder     = Decoder::new( reader, 1024 ).with_buffers( arena() );
+	let frames      = decoder.collect::<Vec<_>>().await;
+
+	assert_eq!( 2, frames.len() );
+	assert_eq!( &[ 1u8; 10 ], frames[0].as_ref().expect( "small frame" ).msg() );
+
+	assert_matches!( &frames[1], Err( WireErr::OutOfMemory{ size, .. } ) if *size == 100 + ThesWF::default().len() as usize );
+}
+
+
+
+#[async_std::test]
+//
+async fn noheap_decoder()
+{
+	let (server, client) = Endpoint::pair( 1024, 1024 );
+
+	let _encoder    = send_frames( client ).await;
+	let (reader, _) = server.split();
+	let decoder     = DecoderNoHeap::new( reader, 1024 ).with_buffers( arena() );
+	let frames      = decoder.collect::<Vec<_>>().await;
+
+	assert_eq!( 2, frames.len() );
+	assert_eq!( &[ 1u8; 10 ], frames[0].as_ref().expect( "small frame" ).msg() );
+
+	assert_matches!( &frames[1], Err( WireErr::OutOfMemory{ size, .. } ) if *size == 100 + ThesWF::default().len() as usize );
+}
+
+
+
+#[test]
+//
+fn try_with_capacity()
+{
+	let arena: Arc<dyn BufferProvider> = Arc::new( arena() );
+
+	assert!( ThesWF::try_with_capacity( 10, &arena ).is_ok() );
+
+	assert_matches!
+	(
+		ThesWF::try_with_capacity( 100, &arena ),
+		Err( WireErr::OutOfMemory{ .. } )
+	);
+}
+
+
+
+
+#[async_std::test]
+//
+async fn pool_reuse()
+{
+	let (server, client) = Endpoint::pair( 1024, 1024 );
+
+	let _encoder    = send_frames( client ).await;
+	let (reader, _) = server.split();
+	let pool        = BufferPool::new( 4 );
+	let mut decoder = Decoder::new( reader, 1024 ).with_buffers( pool.clone() );
+
+	let first = decoder.next().await.expect( "first frame" ).expect( "decode frame" );
+	let ptr   = first.as_ref().as_ptr();
+
+	assert_eq!( 0, pool.available() );
+
+	drop( first );
+
+	assert_eq!( 1, pool.available() );
+
+	// The second frame doesn't fit in the first buffer, so it gets a new one.
+	//
+	let second = decoder.next().await.expect( "second frame" ).expect( "decode frame" );
+
+	assert_ne!( ptr, second.as_ref().as_ptr() );
+	assert_eq!( 1, pool.available() );
+
+	drop( second );
+
+	let third = decoder.next().await.expect( "third frame" ).expect( "decode frame" );
+
+	assert_eq!( &[ 3u8; 10 ], third.msg()           );
+	assert_eq!( ptr         , third.as_ref().as_ptr() );
+	assert_eq!( 1           , pool.available()        );
+}
+
+
+
+#[async_std::test]
+//
+async fn pool_return()
+{
+	let pool                             = BufferPool::new( 4 );
+	let buffers: Arc<dyn BufferProvider> = Arc::new( pool.clone() );
+
+	drop( ThesWF::try_with_capacity( 10, &buffers ).expect( "alloc frame" ) );
+
+	assert_eq!( 1, pool.available() );
+
+
+	let (server, client) = Endpoint::pair( 1024, 1024 );
+
+	let (_, writer) = client.split();
+	let mut encoder = Encoder::new( writer, 1024 ).elide_null_header( true ).with_buffers( pool.clone() );
+
+	// A null sid and cid, so the header is left out.
+	//
+	let mut wf = ThesWF::default();
+	wf.write_all( &[ 4; 10 ] ).expect( "write payload" );
+
+	encoder.send( wf ).await.expect( "send elided frame" );
+
+	assert_eq!( 1, pool.available() );
+
+	let (reader, _) = server.split();
+	let frame       = Decoder::new( reader, 1024 ).next().await.expect( "a frame" ).expect( "decode frame" );
+
+	assert_eq!( &[ 4u8; 10 ], frame.msg() );
+}
@@ -0,0 +1,116 @@
+// Tests:
+//
+// ✔ A detached call resolves once sent, without keeping a slot for the response. The remote processes
+//   it as a call, so it waits for the backpressure.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { lock::Mutex as FutMutex     } ,
+	futures_timer :: { Delay                       } ,
+	crate         :: { peer::BackPressure          } ,
+};
+
+
+// Each Add waits for the gate to open.
+//
+#[ derive( Actor ) ] struct Gated
+{
+	gate: Arc<FutMutex<()>> ,
+	sum : i64               ,
+}
+
+
+impl Handler<Add> for Gated
+{
+	fn handle( &mut self, msg: Add ) -> Return<'_, ()> { async move
+	{
+		let _open = self.gate.lock().await;
+
+		self.sum += msg.0;
+
+	}.boxed() }
+}
+
+
+impl Handler<Show> for Gated
+{
+	fn handle( &mut self, _msg: Show ) -> Return<'_, i64> { async move
+	{
+		self.sum
+
+	}.boxed() }
+}
+
+
+service_map!
+(
+	namespace  : gated      ;
+	wire_format: ThesWF     ;
+	services   : Add, Show  ;
+);
+
+
+
+#[async_std::test]
+//
+async fn backpressure()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let gate   = Arc::new( FutMutex::new(()) );
+	let closed = gate.lock().await;
+
+	let gated = Addr::builder().start( Gated{ gate: gate.clone(), sum: 0 }, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = gated::Services::new();
+
+	sm.register_handler::<Add >( gated.clone_box() );
+	sm.register_handler::<Show>( gated.clone_box() );
+
+
+	let (mut server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read
+	(
+		server_addr.clone()                      ,
+		server                                   ,
+		1024                                     ,
+		AsyncStd                                 ,
+		Some( Arc::new( BackPressure::new(1) ) ) ,
+		None                                     ,
+
+	).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( sm ) );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = gated::RemoteAddr::new( client_addr.clone() );
+
+	addr.call_detached( Add(1) ).await.expect( "detached call Add" );
+	addr.call_detached( Add(2) ).await.expect( "detached call Add" );
+
+	assert_eq!( 0, client_addr.call( GetStatus ).await.expect( "get status" ).open_calls );
+
+
+	// The first call holds the only slot, the second one waits. Sends would not count as calls.
+	//
+	Delay::new( Duration::from_millis( 50 ) ).await;
+
+	let status = server_addr.call( GetStatus ).await.expect( "get status" );
+
+	assert_eq!( 1, status.inbound_calls );
+	assert!   ( status.backpressure      );
+
+	drop( closed );
+
+	assert_eq!( 3, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,75 @@
+// Tests:
+//
+// ✔ A handler registered with register_cancellable observes cancellation when the connection closes.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }                 } ,
+	futures       :: { channel::mpsc::{ unbounded, UnboundedSender } } ,
+	futures_timer :: { Delay                                       } ,
+	serde         :: { Serialize, Deserialize                      } ,
+};
+
+
+#[ derive( Actor ) ] struct Worker( UnboundedSender<&'static str> );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Work;
+
+impl Message for Work { type Return = (); }
+
+
+impl Handler< Cancellable<Work> > for Worker
+{
+	#[async_fn] fn handle( &mut self, msg: Cancellable<Work> )
+	{
+		self.0.unbounded_send( "started" ).expect( "send event" );
+
+		while !msg.token.is_cancelled()
+		{
+			Delay::new( Duration::from_millis(10) ).await;
+		}
+
+		self.0.unbounded_send( "cancelled" ).expect( "send event" );
+	}
+}
+
+
+service_map!
+(
+	namespace  : cancel ;
+	wire_format: ThesWF ;
+	services   : Work   ;
+);
+
+
+
+#[async_std::test]
+//
+async fn cancel_on_close()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+	let (tx, mut rx)     = unbounded();
+
+	let worker = Addr::builder().start( Worker( tx ), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = cancel::Services::new();
+	sm.register_cancellable::<Work>( worker.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = cancel::RemoteAddr::new( client_addr.clone() );
+
+	let call = AsyncStd.spawn_handle( async move { addr.call( Work ).await } ).expect( "spawn call" );
+
+	assert_eq!( Some( "started" ), rx.next().await );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	let cancelled = async_std::future::timeout( Duration::from_secs(1), rx.next() ).await;
+
+	assert_eq!( Ok( Some( "cancelled" ) ), cancelled.map_err( |_| "timeout" ) );
+	assert!( call.await.is_err() );
+}
@@ -0,0 +1,92 @@
+// Tests:
+//
+// ✔ CancelAll makes all outgoing calls waiting for a response fail with PeerErr::Cancelled right
+//   away and the peer keeps working for new calls.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+	serde         :: { Serialize, Deserialize      } ,
+};
+
+
+#[ derive( Actor ) ] struct Sloth;
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Nap;
+
+impl Message for Nap { type Return = (); }
+
+
+impl Handler< Nap > for Sloth
+{
+	#[async_fn] fn handle( &mut self, _msg: Nap )
+	{
+		Delay::new( Duration::from_secs( 60 ) ).await;
+	}
+}
+
+
+service_map!
+(
+	namespace  : sloth     ;
+	wire_format: ThesWF    ;
+	services   : Nap, Show ;
+);
+
+
+
+#[async_std::test]
+//
+async fn cancel_all()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let sloth  = Addr::builder().start( Sloth, &AsyncStd ).expect( "spawn actor mailbox" );
+	let sum    = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sm = sloth::Services::new();
+
+	sm.register_handler::<Nap >( sloth.clone_box() );
+	sm.register_handler::<Show>( sum  .clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let addr = sloth::RemoteAddr::new( client_addr.clone() );
+
+	let calls: Vec<_> = (0..3).map( |_|
+	{
+		let mut addr = addr.clone();
+
+		AsyncStd.spawn_handle( async move { addr.call( Nap ).await } ).expect( "spawn call" )
+
+	}).collect();
+
+
+	while client_addr.call( GetStatus ).await.expect( "get status" ).open_calls < 3
+	{
+		Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+	assert_eq!( 3, client_addr.call( CancelAll ).await.expect( "cancel all" ) );
+
+	for call in calls
+	{
+		let result = async_std::future::timeout( Duration::from_secs( 1 ), call ).await.expect( "cancelled right away" );
+
+		assert_matches!( result, Err( PeerErr::Cancelled{..} ) );
+	}
+
+	assert_eq!( 0, client_addr.call( GetStatus ).await.expect( "get status" ).open_calls );
+
+
+	// The connection is still usable.
+	//
+	let mut addr = addr;
+
+	assert_eq!( 0, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
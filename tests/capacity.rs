@@ -0,0 +1,118 @@
+// Tests:
+//
+// ✔ A provider whose backpressure slots are all taken advertises no free capacity, an idle one
+//   advertises all of its slots. The client sees both as events and in its status.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { lock::Mutex as FutMutex     } ,
+	futures_timer :: { Delay                       } ,
+	peer          :: { BackPressure                } ,
+};
+
+
+// Each Add waits for the gate to open.
+//
+#[ derive( Actor ) ] struct Gated
+{
+	gate: Arc<FutMutex<()>>,
+}
+
+
+impl Handler<Add> for Gated
+{
+	fn handle( &mut self, _msg: Add ) -> Return<'_, ()> { async move
+	{
+		let _open = self.gate.lock().await;
+
+	}.boxed() }
+}
+
+
+service_map!
+(
+	namespace  : gated  ;
+	wire_format: ThesWF ;
+	services   : Add    ;
+);
+
+
+
+// A provider with 2 backpressure slots.
+//
+fn provider( socket: Endpoint, gate: Arc<FutMutex<()>>, name: &str ) -> Addr<Peer>
+{
+	let gated = Addr::builder().start( Gated{ gate }, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = gated::Services::new();
+	sm.register_handler::<Add>( gated.clone_box() );
+
+	let (peer_addr, peer_mb) = Addr::builder().name( name.into() ).build();
+
+	let bp       = Some( Arc::new( BackPressure::new( 2 ) ) );
+	let mut peer = Peer::from_async_read( peer_addr.clone(), socket, 1024, AsyncStd, bp, None ).expect( "spawn peer" );
+
+	assert_eq!( Some( 2 ), peer.capacity() );
+
+	peer.register_services( Arc::new( sm ) );
+
+	AsyncStd.spawn( async{ peer_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	peer_addr
+}
+
+
+
+#[async_std::test]
+//
+async fn advertise()
+{
+	let (busy_server, busy_client) = Endpoint::pair( 64, 64 );
+	let (idle_server, idle_client) = Endpoint::pair( 64, 64 );
+
+	let gate   = Arc::new( FutMutex::new(()) );
+	let closed = gate.lock().await;
+
+	let mut busy = provider( busy_server, gate.clone()                  , "busy" );
+	let mut idle = provider( idle_server, Arc::new( FutMutex::new(()) ) , "idle" );
+
+	let (mut busy_client, mut busy_evts) = peer_connect( busy_client, AsyncStd, "busy_client" ).await;
+	let (mut idle_client, mut idle_evts) = peer_connect( idle_client, AsyncStd, "idle_client" ).await;
+
+
+	// Take both slots of the busy provider.
+	//
+	let mut addr  = gated::RemoteAddr::new( busy_client.clone() );
+	let mut addr2 = addr.clone();
+
+	let calls = join( addr.call( Add(1) ), addr2.call( Add(1) ) );
+
+	let check = async
+	{
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		busy.call( AdvertiseCapacity ).await.expect( "call AdvertiseCapacity" ).expect( "advertise capacity" );
+		idle.call( AdvertiseCapacity ).await.expect( "call AdvertiseCapacity" ).expect( "advertise capacity" );
+
+		assert_eq!( PeerEvent::RemoteCapacity( 0 ), busy_evts.next().await.unwrap() );
+		assert_eq!( PeerEvent::RemoteCapacity( 2 ), idle_evts.next().await.unwrap() );
+
+		assert_eq!( Some( 0 ), busy_client.call( GetStatus ).await.expect( "get status" ).remote_capacity );
+		assert_eq!( Some( 2 ), idle_client.call( GetStatus ).await.expect( "get status" ).remote_capacity );
+
+		drop( closed );
+	};
+
+	let ((a, b), _) = join( calls, check ).await;
+
+	a.expect( "call Add" );
+	b.expect( "call Add" );
+
+	assert_eq!( Some( 2 ), busy.call( GetStatus ).await.expect( "get status" ).capacity );
+
+	busy_client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	idle_client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,106 @@
+// Tests:
+//
+// ✔ Two service maps registered on two channels of one peer each get the calls and sends for the
+//   same sid on their own channel.
+// ✔ A request on a channel without service maps fails with UnknownService, also when the default
+//   channel has a handler for the sid.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }                 } ,
+	futures_timer :: { Delay                                       } ,
+	std           :: { sync::atomic::{ AtomicI64, Ordering::SeqCst } } ,
+};
+
+
+// Show answers `id`, Add adds to `total`.
+//
+fn services( id: i64, total: Arc<AtomicI64> ) -> remotes::Services
+{
+	let mut sm = remotes::Services::new();
+
+	sm.register_fn::<Show, _, _>( move |_| async move { id } );
+
+	sm.register_fn::<Add, _, _>( move |msg|
+	{
+		let total = total.clone();
+		async move { total.fetch_add( msg.0, SeqCst ); }
+	});
+
+	sm
+}
+
+
+
+#[async_std::test]
+//
+async fn two_channels()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let total1 = Arc::new( AtomicI64::new( 0 ) );
+	let total2 = Arc::new( AtomicI64::new( 0 ) );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.register_channel( 1, Arc::new( services( 1, total1.clone() ) ) );
+	peer.register_channel( 2, Arc::new( services( 2, total2.clone() ) ) );
+
+	let _server_handle = AsyncStd.spawn_handle( server_mb.start( peer ) ).expect( "start mailbox of Peer" );
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr1 = remotes::RemoteAddr::new( client_addr.clone() );
+	let mut addr2 = addr1.clone();
+
+	addr1.set_channel( Some( 1 ) );
+	addr2.set_channel( Some( 2 ) );
+
+	assert_eq!( 1, addr1.call( Show ).await.expect( "call Show on channel 1" ) );
+	assert_eq!( 2, addr2.call( Show ).await.expect( "call Show on channel 2" ) );
+
+	addr1.send( Add( 5 ) ).await.expect( "send Add on channel 1" );
+	addr2.send( Add( 7 ) ).await.expect( "send Add on channel 2" );
+
+	while total1.load( SeqCst ) < 5 || total2.load( SeqCst ) < 7
+	{
+		Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+	assert_eq!( 5, total1.load( SeqCst ) );
+	assert_eq!( 7, total2.load( SeqCst ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn unknown_channel()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.set_channel( Some( 3 ) );
+
+	match addr.call( Show ).await
+	{
+		Err( PeerErr::Remote{ err: ConnectionError::UnknownService{..}, .. } ) => {}
+		x => panic!( "unexpected result: {:?}", x ),
+	}
+
+	addr.set_channel( None );
+
+	assert_eq!( 0, addr.call( Show ).await.expect( "call Show on the default channel" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,331 @@
+// Tests:
+//
+// ✔ A call with a payload bigger than the max_size of the codec arrives intact when sent in chunks.
+// ✔ Same for a send.
+// ✔ The biggest frame written to the transport stays near the chunk size, and the receiver holds no
+//   memory for the transfer once the message is processed. Note that during the transfer the
+//   receiver does hold the whole message, chunks are not streamed to the handler.
+// ✔ Transfers that would exceed the number or the total size of the messages being reassembled are
+//   refused, the memory held stays within the limit and the connection keeps working.
+//
+// The peers in common use a codec with a max_size of 1024 bytes. The decoder refuses any frame
+// bigger than that, so the payloads below can only arrive by being chunked.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }     } ,
+	futures       :: { AsyncRead, AsyncWrite           } ,
+	futures_timer :: { Delay                           } ,
+	serde         :: { Serialize, Deserialize          } ,
+	std           :: { num::NonZeroUsize               } ,
+	std           :: { io::{ self, Write }             } ,
+	std           :: { task::{ Context, Poll }         } ,
+};
+
+
+#[ derive( Actor, Default ) ] struct Store( usize, u64 );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Upload( Vec<u8> );
+#[ derive( Serialize, Deserialize, Debug ) ] struct Last;
+
+impl Message for Upload { type Return = (usize, u64); }
+impl Message for Last   { type Return = (usize, u64); }
+
+
+fn checksum( data: &[u8] ) -> (usize, u64)
+{
+	( data.len(), data.iter().map( |b| *b as u64 ).sum() )
+}
+
+
+impl Handler< Upload > for Store
+{
+	#[async_fn] fn handle( &mut self, msg: Upload ) -> (usize, u64)
+	{
+		let (len, sum) = checksum( &msg.0 );
+
+		self.0 = len;
+		self.1 = sum;
+
+		(len, sum)
+	}
+}
+
+
+impl Handler< Last > for Store
+{
+	#[async_fn] fn handle( &mut self, _msg: Last ) -> (usize, u64)
+	{
+		(self.0, self.1)
+	}
+}
+
+
+service_map!
+(
+	namespace  : chunked      ;
+	wire_format: ThesWF       ;
+	services   : Upload, Last ;
+);
+
+
+
+fn sm() -> chunked::Services
+{
+	let store = Addr::builder().start( Store::default(), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = chunked::Services::new();
+
+	sm.register_handler::<Upload>( store.clone_box() );
+	sm.register_handler::<Last  >( store.clone_box() );
+
+	sm
+}
+
+
+fn upload_wf( payload: &[u8] ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( <Upload as chunked::Service>::sid() );
+	serde_cbor::to_writer( &mut wf, &Upload( payload.to_vec() ) ).expect( "serialize Upload" );
+
+	wf
+}
+
+
+fn payload() -> Vec<u8>
+{
+	(0..10_000).map( |i| (i % 251) as u8 ).collect()
+}
+
+
+
+#[async_std::test]
+//
+async fn chunked_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let payload = payload();
+	let wf      = upload_wf( &payload );
+
+	assert!( wf.len() > 1024 );
+
+	let rx = client_addr.call( Call::chunked( wf, NonZeroUsize::new( 512 ).unwrap() ) ).await
+
+		.expect( "call peer"  )
+		.expect( "send chunks" )
+	;
+
+	let resp = rx.await.expect( "receive response" ).expect( "no connection error" );
+
+	let result: (usize, u64) = serde_cbor::from_slice( resp.msg() ).expect( "deserialize response" );
+
+	assert_eq!( checksum( &payload ), result );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn chunked_send()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let payload = payload();
+
+	client_addr.call( Chunked::new( upload_wf( &payload ), NonZeroUsize::new( 700 ).unwrap() ) ).await
+
+		.expect( "call peer"  )
+		.expect( "send chunks" )
+	;
+
+	// The send is processed in a spawned task, so wait for it to reach the actor.
+	//
+	let mut addr = chunked::RemoteAddr::new( client_addr.clone() );
+
+	loop
+	{
+		let last = addr.call( Last ).await.expect( "call Last" );
+
+		if last.0 != 0
+		{
+			assert_eq!( checksum( &payload ), last );
+			break;
+		}
+
+		Delay::new( Duration::from_millis(10) ).await;
+	}
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// The first of two chunks of a send, so the transfer stays in progress.
+//
+fn first_chunk( transfer: u64, data: &[u8] ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( ServiceID::chunk()       );
+	wf.set_cid( ConnID::from( transfer ) );
+
+	<Upload as chunked::Service>::sid().write_le( &mut wf ).expect( "write sid" );
+
+	wf.write_all( &0u64.to_le_bytes() ).expect( "write cid"   );
+	wf.write_all( &0u32.to_le_bytes() ).expect( "write seq"   );
+	wf.write_all( &2u32.to_le_bytes() ).expect( "write total" );
+	wf.write_all( data                ).expect( "write data"  );
+
+	wf
+}
+
+
+
+#[async_std::test]
+//
+async fn bounded_reassembly()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr.clone(), server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+	let mut evts = peer.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	peer.register_services( Arc::new( sm() ) );
+	peer.set_max_reassemblies( 2, 1000 );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let refused = |e: &PeerEvent| matches!( e, PeerEvent::Error( PeerErr::WireFormat{ source: WireErr::OutOfMemory{..}, .. } ) );
+
+	// Too big for what's left of the total size.
+	//
+	client_addr.send( first_chunk( 1, &[1; 600] ) ).await.expect( "send chunk" );
+	client_addr.send( first_chunk( 2, &[2; 600] ) ).await.expect( "send chunk" );
+
+	evts.wait_for( refused ).await.expect( "refused transfer" );
+
+	// One transfer too many.
+	//
+	client_addr.send( first_chunk( 3, &[3; 100] ) ).await.expect( "send chunk" );
+	client_addr.send( first_chunk( 4, &[4; 100] ) ).await.expect( "send chunk" );
+
+	evts.wait_for( refused ).await.expect( "refused transfer" );
+
+	let status = server_addr.call( GetStatus ).await.expect( "get status" );
+
+	assert!( status.connected );
+	assert_eq!( 700, status.reassembly_bytes );
+
+	let mut addr = chunked::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( (0, 0), addr.call( Last ).await.expect( "call Last" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Records the biggest buffer written to the socket. The encoder writes one frame at a time, so this
+// is the size of the biggest frame.
+//
+struct PeakWrite
+{
+	inner: Endpoint        ,
+	peak : Arc<AtomicUsize> ,
+}
+
+
+impl AsyncRead for PeakWrite
+{
+	fn poll_read( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8] ) -> Poll< io::Result<usize> >
+	{
+		Pin::new( &mut self.inner ).poll_read( cx, buf )
+	}
+}
+
+
+impl AsyncWrite for PeakWrite
+{
+	fn poll_write( mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8] ) -> Poll< io::Result<usize> >
+	{
+		self.peak.fetch_max( buf.len(), Relaxed );
+
+		Pin::new( &mut self.inner ).poll_write( cx, buf )
+	}
+
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		Pin::new( &mut self.inner ).poll_flush( cx )
+	}
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< io::Result<()> >
+	{
+		Pin::new( &mut self.inner ).poll_close( cx )
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn peak_buffer()
+{
+	const CHUNK: usize = 256;
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "server" ).await;
+
+	let peak   = Arc::new( AtomicUsize::new( 0 ) );
+	let client = PeakWrite{ inner: client, peak: peak.clone() };
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+	let peer = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	AsyncStd.spawn( async{ client_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	let payload = payload();
+
+	let rx = client_addr.call( Call::chunked( upload_wf( &payload ), NonZeroUsize::new( CHUNK ).unwrap() ) ).await
+
+		.expect( "call peer"  )
+		.expect( "send chunks" )
+	;
+
+	let resp = rx.await.expect( "receive response" ).expect( "no connection error" );
+
+	let result: (usize, u64) = serde_cbor::from_slice( resp.msg() ).expect( "deserialize response" );
+
+	assert_eq!( checksum( &payload ), result );
+
+	// The frame header and the chunk header come on top of the data.
+	//
+	let peak = peak.load( Relaxed );
+
+	assert!( peak > CHUNK            , "peak: {}", peak );
+	assert!( peak <= CHUNK + 2 * 64  , "peak: {}", peak );
+	assert!( peak <  payload.len() / 10 );
+
+	let status = server_addr.call( GetStatus ).await.expect( "get status" );
+
+	assert_eq!( 0, status.reassembly_bytes );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,167 @@
+// Tests:
+//
+// ✔ After `threshold` failures in a row the circuit opens and calls fail fast without reaching the
+//   wrapped address. After the cooldown a probe goes through and closes the circuit when it succeeds.
+// ✔ A failed probe opens the circuit for another cooldown.
+// ✔ Errors that don't count as failures leave the circuit closed.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }                } ,
+	futures       :: { Sink                                       } ,
+	futures_timer :: { Delay                                      } ,
+	std           :: { num::NonZeroUsize, task::{ Context, Poll } } ,
+	std           :: { sync::atomic::AtomicBool                   } ,
+};
+
+
+#[ derive( Debug, Clone ) ] struct Ping;
+
+impl Message for Ping { type Return = usize; }
+
+
+// Fails with ConnectionClosed while down, otherwise returns the number of calls it got.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+struct Flaky
+{
+	calls: Arc<AtomicUsize> ,
+	down : Arc<AtomicBool>  ,
+}
+
+
+impl Address<Ping> for Flaky
+{
+	fn call( &mut self, _msg: Ping ) -> Return<'_, Result<usize, PeerErr>>
+	{
+		let calls = self.calls.fetch_add( 1, Relaxed ) + 1;
+		let down  = self.down.load( Relaxed );
+
+		async move
+		{
+			match down
+			{
+				true  => Err( PeerErr::ConnectionClosed{ ctx: PeerErrCtx::default() } ),
+				false => Ok( calls ),
+			}
+
+		}.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<Ping, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+impl Sink<Ping> for Flaky
+{
+	type Error = PeerErr;
+
+	fn poll_ready( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+	fn start_send( self: Pin<&mut Self>, _msg: Ping           ) -> Result<(), PeerErr>        { Ok(())                }
+	fn poll_flush( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+	fn poll_close( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+}
+
+
+impl Identify for Flaky
+{
+	fn id  ( &self ) -> usize            { 0    }
+	fn name( &self ) -> Option<Arc<str>> { None }
+}
+
+
+fn breaker( flaky: &Flaky ) -> CircuitBreakerAddr<Ping>
+{
+	let threshold = NonZeroUsize::new( 3 ).unwrap();
+
+	CircuitBreakerAddr::new( Box::new( flaky.clone() ), threshold, Duration::from_millis( 100 ) )
+}
+
+
+
+#[async_std::test]
+//
+async fn open_and_recover()
+{
+	let flaky    = Flaky::default();
+	let mut addr = breaker( &flaky );
+
+	flaky.down.store( true, Relaxed );
+
+	for _ in 0..3
+	{
+		assert_matches!( addr.call( Ping ).await, Err( PeerErr::ConnectionClosed{..} ) );
+	}
+
+	assert_eq!( CircuitState::Open, addr.state() );
+
+	// Fail fast, the wrapped address isn't called.
+	//
+	for _ in 0..5
+	{
+		assert_matches!( addr.call( Ping ).await, Err( PeerErr::CircuitOpen{..} ) );
+	}
+
+	assert_eq!( 3, flaky.calls.load( Relaxed ) );
+
+
+	flaky.down.store( false, Relaxed );
+	Delay::new( Duration::from_millis( 150 ) ).await;
+
+	assert_eq!( CircuitState::HalfOpen, addr.state() );
+
+	assert_eq!( 4, addr.call( Ping ).await.expect( "probe" ) );
+	assert_eq!( CircuitState::Closed, addr.state() );
+	assert_eq!( 5, addr.call( Ping ).await.expect( "call Ping" ) );
+}
+
+
+
+#[async_std::test]
+//
+async fn failed_probe()
+{
+	let flaky    = Flaky::default();
+	let mut addr = breaker( &flaky );
+
+	flaky.down.store( true, Relaxed );
+
+	for _ in 0..3
+	{
+		let _ = addr.call( Ping ).await;
+	}
+
+	Delay::new( Duration::from_millis( 150 ) ).await;
+
+	assert_matches!( addr.call( Ping ).await, Err( PeerErr::ConnectionClosed{..} ) );
+	assert_eq!( CircuitState::Open, addr.state() );
+	assert_matches!( addr.call( Ping ).await, Err( PeerErr::CircuitOpen{..} ) );
+	assert_eq!( 4, flaky.calls.load( Relaxed ) );
+}
+
+
+
+#[async_std::test]
+//
+async fn fail_if()
+{
+	let flaky    = Flaky::default();
+	let mut addr = breaker( &flaky ).fail_if( |e| matches!( e, PeerErr::Timeout{..} ) );
+
+	flaky.down.store( true, Relaxed );
+
+	for _ in 0..5
+	{
+		assert_matches!( addr.call( Ping ).await, Err( PeerErr::ConnectionClosed{..} ) );
+	}
+
+	assert_eq!( CircuitState::Closed, addr.state() );
+	assert_eq!( 5, flaky.calls.load( Relaxed ) );
+}
@@ -0,0 +1,46 @@
+// Tests:
+//
+// ✔ A service map generated with client_only can call the services of a full one with the same namespace.
+//
+mod common;
+
+use common::*                       ;
+use common::import::{ *, assert_eq };
+
+
+// Same namespace as the service map of the provider, but without the handler map.
+//
+mod client
+{
+	use super::*;
+
+	service_map!
+	(
+		namespace  : remotes        ;
+		wire_format: ThesWF         ;
+		client_only: true           ;
+		services   : Add, Sub, Show ;
+	);
+}
+
+
+
+#[async_std::test]
+//
+async fn client_only()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = client::remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( <Add as client::remotes::Service>::sid(), <Add as remotes::Service>::sid() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,96 @@
+// Tests:
+//
+// ✔ A connection without traffic closes with CloseReason::Idle after the idle timeout, the remote
+//   sees the incoming stream end.
+// ✔ A frame that is too big closes the connection with CloseReason::Protocol.
+// ✔ Traffic keeps the idle timeout from closing the connection.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { AsyncWriteExt                } ,
+	futures_timer :: { Delay                        } ,
+};
+
+
+async fn idle_server( socket: Endpoint, timeout: Duration ) -> (Addr<Peer>, Events<PeerEvent>)
+{
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr.clone(), socket, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let evts = peer.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	peer.register_services( Arc::new( add_show_sum() ) );
+	peer.set_idle_timeout( timeout ).expect( "set idle timeout" );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	(server_addr, evts)
+}
+
+
+
+#[async_std::test]
+//
+async fn idle()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, mut server_evts) = idle_server( server, Duration::from_millis( 100 ) ).await;
+	let (_client_addr, mut client_evts) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let evt = server_evts.wait_for( |e| matches!( e, PeerEvent::Closed(_) ) ).await.expect( "server closed" );
+
+	assert_eq!( PeerEvent::Closed( CloseReason::Idle ), evt );
+
+	let evt = client_evts.wait_for( |e| matches!( e, PeerEvent::ClosedByRemote(_) ) ).await.expect( "client closed" );
+
+	assert_eq!( PeerEvent::ClosedByRemote( CloseReason::Eof ), evt );
+}
+
+
+
+#[async_std::test]
+//
+async fn protocol()
+{
+	let (server, mut client) = Endpoint::pair( 1024, 1024 );
+
+	let (_server_addr, mut server_evts, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+	// The length of a frame way over the maximum size of 1024 bytes the server accepts.
+	//
+	client.write_all( &1_000_000u64.to_le_bytes() ).await.expect( "write frame length" );
+
+	let evt = server_evts.wait_for( |e| matches!( e, PeerEvent::Closed(_) ) ).await.expect( "server closed" );
+
+	assert_eq!( PeerEvent::Closed( CloseReason::Protocol ), evt );
+}
+
+
+
+#[async_std::test]
+//
+async fn busy()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, _) = idle_server( server, Duration::from_millis( 200 ) ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	for _ in 0..10
+	{
+		addr.call( Add(1) ).await.expect( "call Add" );
+
+		Delay::new( Duration::from_millis( 50 ) ).await;
+	}
+
+	assert!( server_addr.call( GetStatus ).await.expect( "get status" ).connected );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -6,7 +6,7 @@ use serde:: { Serialize, Deserialize };
 
 #[ derive( Actor ) ] pub struct Sum( pub i64 );
 
-#[ derive( Serialize, Deserialize, Debug ) ] pub struct Add( pub i64 );
+#[ derive( Serialize, Deserialize, Debug, Clone ) ] pub struct Add( pub i64 );
 #[ derive( Serialize, Deserialize, Debug ) ] pub struct Sub( pub i64 );
 #[ derive( Serialize, Deserialize, Debug ) ] pub struct Show;
 
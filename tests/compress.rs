@@ -0,0 +1,146 @@
+#![ cfg( feature = "compress" ) ]
+
+// Tests:
+//
+// ✔ With Compression::Responses, a call with a small request and a big response only has the
+//   response compressed on the wire, and the caller gets the full response.
+// ✔ SetCompressionThreshold changes at runtime which payloads are big enough to be compressed.
+//
+mod common;
+
+use
+{
+	common      :: { *, import::{ *, assert_eq }                       } ,
+	serde       :: { Serialize, Deserialize                            } ,
+	futures     :: { AsyncReadExt                                      } ,
+	parking_lot :: { Mutex                                             } ,
+	thes_wf     :: { Compress, Decompress, Compression, is_compressed  } ,
+	thes_wf     :: { Decoder, Encoder                                  } ,
+};
+
+
+const MAX_SIZE: usize = 16 * 1024;
+
+
+#[ derive( Actor ) ] struct Db;
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Query;
+
+impl Message for Query { type Return = Vec<u8>; }
+
+
+impl Handler< Query > for Db
+{
+	#[async_fn] fn handle( &mut self, _msg: Query ) -> Vec<u8>
+	{
+		vec![ 7; 8 * 1024 ]
+	}
+}
+
+
+service_map!
+(
+	namespace  : compress ;
+	wire_format: ThesWF   ;
+	services   : Query    ;
+);
+
+
+// The sid of incoming frames and whether they were compressed on the wire.
+//
+type Seen = Arc<Mutex< Vec<(ServiceID, bool)> >>;
+
+
+// Create a peer that compresses responses and records the incoming frames as they are on the wire.
+//
+fn peer( socket: Endpoint, sm: Option< compress::Services >, name: &str ) -> (Addr<Peer>, Seen)
+{
+	let (addr, mb) = Addr::builder().name( name.into() ).build();
+
+	let seen   = Seen::default();
+	let record = seen.clone();
+
+	let (reader, writer) = socket.split();
+
+	let stream = Decoder::new( reader, MAX_SIZE ).inspect( move |frame|
+	{
+		if let Ok( frame ) = frame
+		{
+			record.lock().push( (frame.sid(), is_compressed( frame )) );
+		}
+	});
+
+	let stream = Decompress::new( stream, MAX_SIZE );
+	let sink   = Compress::new( Encoder::new( writer, MAX_SIZE ), Compression::Responses );
+
+	let threshold = sink.threshold();
+	let mut peer  = Peer::new( addr.clone(), stream, sink, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.set_compress_threshold( threshold );
+
+	if let Some( sm ) = sm
+	{
+		peer.register_services( Arc::new( sm ) );
+	}
+
+	AsyncStd.spawn( async{ mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	(addr, seen)
+}
+
+
+
+#[async_std::test]
+//
+async fn responses_only()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let db     = Addr::builder().start( Db, &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sm = compress::Services::new();
+
+	sm.register_handler::<Query>( db.clone_box() );
+
+	let (_server_addr   , server_seen) = peer( server, Some( sm ), "server" );
+	let (mut client_addr, client_seen) = peer( client, None      , "client" );
+
+	let mut addr = compress::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( vec![ 7; 8 * 1024 ], addr.call( Query ).await.expect( "call Query" ) );
+
+	use compress::Service;
+
+	assert_eq!( vec![ ( Query::sid()     , false ) ], *server_seen.lock() );
+	assert_eq!( vec![ ( ServiceID::full(), true  ) ], *client_seen.lock() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn threshold()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let db     = Addr::builder().start( Db, &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sm = compress::Services::new();
+
+	sm.register_handler::<Query>( db.clone_box() );
+
+	let (mut server_addr, _          ) = peer( server, Some( sm ), "server" );
+	let (mut client_addr, client_seen) = peer( client, None      , "client" );
+
+	let mut addr = compress::RemoteAddr::new( client_addr.clone() );
+
+	server_addr.call( SetCompressionThreshold( 1024 * 1024 ) ).await.expect( "set threshold" );
+	assert_eq!( vec![ 7; 8 * 1024 ], addr.call( Query ).await.expect( "call Query" ) );
+
+	server_addr.call( SetCompressionThreshold( 0 ) ).await.expect( "set threshold" );
+	assert_eq!( vec![ 7; 8 * 1024 ], addr.call( Query ).await.expect( "call Query" ) );
+
+	assert_eq!( vec![ ( ServiceID::full(), false ), ( ServiceID::full(), true ) ], *client_seen.lock() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,101 @@
+// Tests:
+//
+// ✔ RemoteAddr::call_concrete gives the same results as Address::call.
+// ✔ RemoteAddr::call_concrete returns ConnectionClosed after the connection is closed.
+//
+mod common;
+
+use common::*                       ;
+use common::import::{ *, assert_eq };
+
+
+service_map!
+(
+	namespace       : concrete  ;
+	wire_format     : ThesWF    ;
+	concrete_futures: true      ;
+	services        : Add, Show ;
+);
+
+
+
+fn sm() -> concrete::Services
+{
+	let addr_handler = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = concrete::Services::new();
+
+	sm.register_handler::<Add >( addr_handler.clone_box() );
+	sm.register_handler::<Show>( addr_handler.clone_box() );
+
+	sm
+}
+
+
+
+// Compare the output of the concrete future with the boxed one.
+//
+#[async_std::test]
+//
+async fn same_as_boxed()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let peera = async move
+	{
+		let (_, _, handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "peera" ).await;
+
+		handle.await;
+	};
+
+
+	let peerb = async move
+	{
+		let (mut peera, _) = peer_connect( client, AsyncStd, "peer_b_to_peera" ).await;
+
+		let mut addr = concrete::RemoteAddr::new( peera.clone() );
+
+		assert_eq!( Ok(()), addr.call         ( Add(5) ).await );
+		assert_eq!( Ok(()), addr.call_concrete( Add(5) ).await );
+
+		let boxed    = addr.call         ( Show ).await;
+		let concrete = addr.call_concrete( Show ).await;
+
+		assert_eq!( Ok(10), boxed    );
+		assert_eq!( boxed , concrete );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection to peera" );
+	};
+
+	join( peera, peerb ).await;
+}
+
+
+
+// Errors should be the same as for the boxed version.
+//
+#[async_std::test]
+//
+async fn after_close()
+{
+	let (mut server, client) = Endpoint::pair( 64, 64 );
+
+	let nodea = async move
+	{
+		server.close().await.expect( "close connection" );
+	};
+
+
+	let nodeb = async move
+	{
+		let (peera, mut peera_evts) = peer_connect( client, AsyncStd, "nodeb_to_node_a" ).await;
+
+		let mut addr = concrete::RemoteAddr::new( peera.clone() );
+
+		assert_eq!( PeerEvent::ClosedByRemote( CloseReason::Eof ), peera_evts.next().await.unwrap() );
+
+		assert_matches!( addr.call_concrete( Add(5) ).await, Err( PeerErr::ConnectionClosed{..} ) );
+	};
+
+	join( nodea, nodeb ).await;
+}
@@ -0,0 +1,117 @@
+// Tests:
+//
+// ✔ Against a remote that never answers, connect_with_timeout fails with a timeout instead of hanging
+//   and closes the connection.
+// ✔ A transport that can't be established times out as well.
+// ✔ Against a responsive remote, the connection is returned and can be used.
+// ✔ When the mailbox of the peer is full, closing the connection doesn't wait past the deadline.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { future                      } ,
+	futures_timer :: { Delay                       } ,
+	std           :: { time::Instant               } ,
+};
+
+
+// The remote end of the connection is never read, so the ping is never answered.
+//
+#[async_std::test]
+//
+async fn black_hole()
+{
+	let (_black_hole, client) = Endpoint::pair( 64, 64 );
+
+	let mut evts  = None;
+	let     start = Instant::now();
+
+	let connect = async
+	{
+		let (addr, client_evts) = peer_connect( client, AsyncStd, "client" ).await;
+
+		evts = Some( client_evts );
+
+		Ok( addr )
+	};
+
+	let ready = |mut addr: Addr<Peer>| async move { Peer::ping( &mut addr ).await.map( |_| () ) };
+
+	let result = Peer::connect_with_timeout( Duration::from_millis( 200 ), connect, ready ).await;
+
+	assert!( matches!( result, Err( PeerErr::Timeout{..} ) ) );
+	assert!( start.elapsed() < Duration::from_secs( 10 ) );
+
+	let mut evts = evts.expect( "connect ran" );
+
+	evts.wait_for( |e| *e == PeerEvent::Closed( CloseReason::Local ) ).await.expect( "connection closed" );
+}
+
+
+
+#[async_std::test]
+//
+async fn transport_hangs()
+{
+	let connect = future::pending::< Result<Addr<Peer>, PeerErr> >();
+	let ready   = |_: Addr<Peer>| async { Ok(()) };
+
+	let err = Peer::connect_with_timeout( Duration::from_millis( 50 ), connect, ready ).await.expect_err( "time out" );
+
+	match err
+	{
+		PeerErr::Timeout{ ctx } => assert!( ctx.context.expect( "context" ).contains( "establishing the connection" ) ),
+		_                       => panic!( "unexpected error: {}", err ),
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn responsive()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+	let connect = async { Ok( peer_connect( client, AsyncStd, "client" ).await.0 ) };
+	let ready   = |mut addr: Addr<Peer>| async move { Peer::ping( &mut addr ).await.map( |_| () ) };
+
+	let mut client_addr = Peer::connect_with_timeout( Duration::from_secs( 5 ), connect, ready ).await.expect( "connect" );
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// The mailbox never runs, so once it's full, CloseConnection can't be delivered.
+//
+#[async_std::test]
+//
+async fn mailbox_full()
+{
+	let (mut addr, _mb) = Addr::<Peer>::builder().build();
+
+	while addr.send( CloseConnection{ remote: false, reason: "Fill the mailbox.".to_string() } ).now_or_never().is_some() {}
+
+	let connect = async { Ok( addr ) };
+	let ready   = |_: Addr<Peer>| async { Err( PeerErr::ConnectionClosed{ ctx: PeerErrCtx::default() } ) };
+
+	let fut = Peer::connect_with_timeout( Duration::from_millis( 50 ), connect, ready );
+
+	match future::select( Box::pin( fut ), Delay::new( Duration::from_secs( 10 ) ) ).await
+	{
+		future::Either::Left ( (Err( PeerErr::ConnectionClosed{..} ), _) ) => {}
+		future::Either::Left ( (other, _)                              ) => panic!( "unexpected result: {:?}", other.map( |_| () ) ),
+		future::Either::Right( _                                       ) => panic!( "closing the connection blocked" ),
+	}
+}
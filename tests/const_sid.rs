@@ -0,0 +1,27 @@
+// Tests:
+//
+// ✔ A sid computed in const context equals the one of the Service impl and of a call to from_seed at runtime.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+const ADD: ServiceID = ServiceID::from_seed( b"remotes::Add" );
+
+
+#[test]
+//
+fn const_sid()
+{
+	let seed = String::from( "remotes::" ) + "Add";
+
+	assert_eq!( ADD, <Add as remotes::Service>::sid() );
+	assert_eq!( ADD, ServiceID::from_seed( seed.as_bytes() ) );
+
+	match <Add as remotes::Service>::sid()
+	{
+		ADD => {}
+		sid => panic!( "unexpected sid: {}", sid ),
+	}
+}
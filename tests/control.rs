@@ -0,0 +1,76 @@
+// Tests:
+//
+// ✔ A control frame is handled by the peer, even when a service map claims the sid. It shows up
+//   as an event and never reaches the service map.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	peer   :: { Response                    } ,
+};
+
+
+
+// A service map that claims the sid of a control frame and counts what reaches it.
+//
+#[ derive( Debug, Default ) ]
+//
+struct Spy
+{
+	sids   : Vec<ServiceID>,
+	reached: AtomicUsize   ,
+}
+
+
+impl ServiceMap for Spy
+{
+	fn send_service( &self, _msg: ThesWF, ctx: PeerErrCtx )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<ThesWF>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.reached.fetch_add( 1, Relaxed );
+
+		Err( PeerErr::NoHandler{ ctx } )
+	}
+
+
+	fn call_service( &self, _msg: ThesWF, ctx: PeerErrCtx, _cancel: CancelToken )
+
+		-> Result< Pin<Box< dyn Future< Output=Result<Response<ThesWF>, PeerErr> > + Send >>, PeerErr >
+	{
+		self.reached.fetch_add( 1, Relaxed );
+
+		Err( PeerErr::NoHandler{ ctx } )
+	}
+
+
+	fn services( &self ) -> Box<dyn Iterator<Item = &ServiceID> + '_ >
+	{
+		Box::new( self.sids.iter() )
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn control_frame()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let spy = Arc::new( Spy{ sids: vec![ ServiceID::control( 200 ) ], ..Default::default() } );
+
+	let (_server_addr   , mut server_evts, _) = peer_listen ( server, spy.clone(), AsyncStd, "server" ).await;
+	let (mut client_addr, _                 ) = peer_connect( client,              AsyncStd, "client" ).await;
+
+	let control = Control{ code: 200, payload: b"hello".to_vec() };
+
+	client_addr.call( control.clone() ).await.expect( "call" ).expect( "send control frame" );
+
+	assert_eq!( Some( PeerEvent::Control( control ) ), server_evts.next().await );
+	assert_eq!( 0, spy.reached.load( Relaxed ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,52 @@
+// Tests:
+//
+// ✔ A send to a handler whose mailbox has stopped ends up in the dead letter channel.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { channel::mpsc::channel      } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn dead_handler()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	// A handler that is no longer running.
+	//
+	let (dead, mb) = Addr::<Sum>::builder().name( "dead".into() ).build();
+	drop( mb );
+
+	let mut sm = remotes::Services::new();
+	sm.register_handler::<Add>( dead.clone_box() );
+
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+	let (tx, mut dead_letters)   = channel( 4 );
+
+	let mut peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( sm ) );
+	peer.set_dead_letters( tx );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.send( Add(5) ).await.expect( "send Add" );
+
+	let (sid, frame) = dead_letters.next().await.expect( "dead letter" );
+
+	assert_eq!( <Add as remotes::Service>::sid(), sid );
+	assert_eq!( 5, serde_cbor::from_slice::<Add>( frame.msg() ).expect( "deserialize Add" ).0 );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
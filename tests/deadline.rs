@@ -0,0 +1,81 @@
+// Tests:
+//
+// ✔ A call whose deadline passes while the relay forwards it is rejected by the provider.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::*                } ,
+	futures       :: { io::AsyncReadExt, SinkExt   } ,
+	futures_timer :: { Delay                       } ,
+};
+
+
+
+// The link from the relay to the provider takes 100ms to deliver each frame.
+//
+fn slow_peer( addr: Addr<Peer>, socket: Endpoint ) -> Peer
+{
+	let (reader, writer) = socket.split();
+
+	let stream = thes_wf::Decoder::new( reader, 1024 );
+	let sink   = thes_wf::Encoder::new( writer, 1024 ).with( |wf: ThesWF| Box::pin( async move
+	{
+		Delay::new( Duration::from_millis( 100 ) ).await;
+
+		Ok::<_, WireErr>( wf )
+	}));
+
+	Peer::new( addr, stream, sink, AsyncStd, None, None ).expect( "spawn peer" )
+}
+
+
+
+#[async_std::test]
+//
+async fn expired_at_provider()
+{
+	let (ab, ba) = Endpoint::pair( 64, 64 );
+	let (bc, cb) = Endpoint::pair( 64, 64 );
+
+	// Provider
+	//
+	let (_, mut provider_evts, _provider_handle) = peer_listen( ab, Arc::new( add_show_sum() ), AsyncStd, "provider" ).await;
+
+
+	// Relay
+	//
+	let (mut to_provider, to_provider_mb) = Addr::builder().name( "relay_to_provider".into() ).build();
+	let to_provider_peer                  = slow_peer( to_provider.clone(), ba );
+	let _to_provider_handle               = AsyncStd.spawn_handle( to_provider_mb.start( to_provider_peer ) ).expect( "start mailbox of Peer" );
+
+	let handler: Box<dyn Relay> = Box::new( to_provider.clone() );
+	let relayed                 = vec![ <Add as remotes::Service>::sid() ];
+
+	let (_, _, _relay_handle) = peer_listen( bc, Arc::new( RelayMap::new( handler.into(), relayed ) ), AsyncStd, "relay" ).await;
+
+
+	// Consumer, gives up after 50ms.
+	//
+	let (mut to_relay, to_relay_mb) = Addr::builder().name( "consumer_to_relay".into() ).build();
+
+	let mut consumer = Peer::from_async_read( to_relay.clone(), cb, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	consumer.set_timeout( Duration::from_millis( 50 ) );
+	consumer.set_propagate_deadline( true );
+
+	AsyncStd.spawn( async{ to_relay_mb.start( consumer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut addr = remotes::RemoteAddr::new( to_relay.clone() );
+
+	assert_matches!( addr.call( Add(5) ).await, Err( PeerErr::Timeout{..} ) );
+
+	// The relay forwarded the call within the deadline, but it only reached the provider after it.
+	//
+	assert_matches!( provider_evts.next().await.unwrap(), PeerEvent::Error( PeerErr::Timeout{..} ) );
+
+	to_relay   .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_provider.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
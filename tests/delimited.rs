@@ -0,0 +1,100 @@
+// Tests:
+//
+// ✔ Newline delimited JSON written by DelimitedEncoder comes out of DelimitedDecoder unchanged.
+// ✔ Lines written by hand, like a text protocol peer would, decode. A malformed line gives an
+//   error and the next line still decodes.
+// ✔ A payload containing the delimiter is refused by the encoder.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { SinkExt                     } ,
+};
+
+
+fn json_wf( sid: u64, cid: u64, json: &str ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( ServiceID::from( sid ) );
+	wf.set_cid( ConnID::from( cid )    );
+	wf.write_all( json.as_bytes() ).expect( "write payload" );
+
+	wf
+}
+
+
+
+#[async_std::test]
+//
+async fn round_trip()
+{
+	let (a, b) = Endpoint::pair( 64, 64 );
+
+	let mut encoder = DelimitedEncoder::<_, ThesWF>::new( a, b'\n' );
+	let mut decoder = DelimitedDecoder::<ThesWF>::new( b, b'\n', 1024 );
+
+	let frames = vec!
+	[
+		json_wf( 0xbeef, 0, r#"{"sum":5}"#                       ),
+		json_wf( 0xcafe, 7, r#"{"name":"add","args":[1, 2, 3]}"# ),
+		json_wf( 0x1   , 8, ""                                   ),
+	];
+
+	for wf in &frames
+	{
+		encoder.send( wf.clone() ).await.expect( "send frame" );
+	}
+
+	for wf in frames
+	{
+		let got = decoder.next().await.expect( "a frame" ).expect( "decode frame" );
+
+		assert_eq!( wf.sid(), got.sid() );
+		assert_eq!( wf.cid(), got.cid() );
+		assert_eq!( wf.msg(), got.msg() );
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn text_peer()
+{
+	let (mut a, b) = Endpoint::pair( 256, 256 );
+
+	let mut decoder = DelimitedDecoder::<ThesWF>::new( b, b'\n', 1024 );
+
+	a.write_all( b"beef 3 {\"sum\":5}\nnot a frame\n0xcafe 0 []\n" ).await.expect( "write lines" );
+
+	let first = decoder.next().await.expect( "a frame" ).expect( "decode frame" );
+
+	assert_eq!( ServiceID::from( 0xbeef ), first.sid() );
+	assert_eq!( ConnID::from( 3 )        , first.cid() );
+	assert_eq!( br#"{"sum":5}"#          , first.msg() );
+
+	assert_matches!( decoder.next().await, Some( Err( WireErr::Deserialize{..} ) ) );
+
+	let third = decoder.next().await.expect( "a frame" ).expect( "decode frame" );
+
+	assert_eq!( ServiceID::from( 0xcafe ), third.sid() );
+	assert_eq!( b"[]"                    , third.msg() );
+}
+
+
+
+#[async_std::test]
+//
+async fn delimiter_in_payload()
+{
+	let (a, _b) = Endpoint::pair( 64, 64 );
+
+	let mut encoder = DelimitedEncoder::<_, ThesWF>::new( a, b'\n' );
+
+	let res = encoder.send( json_wf( 0xbeef, 0, "{\n}" ) ).await;
+
+	assert_matches!( res, Err( WireErr::Io{..} ) );
+}
@@ -0,0 +1,112 @@
+// Tests:
+//
+// ✔ When the acknowledgement of a delivered message gets lost, AtLeastOnce sends it again, so the
+//   remote handles it twice.
+// ✔ AtMostOnce returns the error without sending again, so the remote handles it once.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq }                } ,
+	futures :: { Sink                                       } ,
+	std     :: { num::NonZeroUsize, task::{ Context, Poll } } ,
+};
+
+
+// Delivers to the remote, but pretends the acknowledgement of the first call got lost.
+//
+#[ derive( Debug, Clone ) ]
+//
+struct LossyAck
+{
+	addr : remotes::RemoteAddr ,
+	calls: Arc<AtomicUsize>    ,
+}
+
+
+impl Address<Add> for LossyAck
+{
+	fn call( &mut self, msg: Add ) -> Return<'_, Result<(), PeerErr>>
+	{
+		let attempt = self.calls.fetch_add( 1, Relaxed ) + 1;
+
+		async move
+		{
+			self.addr.call( msg ).await?;
+
+			match attempt
+			{
+				1 => Err( PeerErr::Timeout{ ctx: PeerErrCtx::default() } ),
+				_ => Ok(()),
+			}
+
+		}.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<Add, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+impl Sink<Add> for LossyAck
+{
+	type Error = PeerErr;
+
+	fn poll_ready( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+	fn start_send( self: Pin<&mut Self>, _msg: Add            ) -> Result<(), PeerErr>        { Ok(())                }
+	fn poll_flush( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+	fn poll_close( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+}
+
+
+impl Identify for LossyAck
+{
+	fn id  ( &self ) -> usize            { 0    }
+	fn name( &self ) -> Option<Arc<str>> { None }
+}
+
+
+
+#[async_std::test]
+//
+async fn at_least_once()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (client_addr , _                ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr  = remotes::RemoteAddr::new( client_addr );
+	let mut lossy = LossyAck{ addr: addr.clone(), calls: Arc::new( AtomicUsize::new( 0 ) ) };
+
+	let delivery = Delivery::AtLeastOnce{ max_attempts: NonZeroUsize::new( 3 ).unwrap(), backoff: Duration::from_millis( 1 ) };
+
+	lossy.deliver( Add(5), delivery ).await.expect( "deliver Add" );
+
+	assert_eq!( 2 , lossy.calls.load( Relaxed )                  );
+	assert_eq!( 10, addr.call( Show ).await.expect( "call Show" ) );
+}
+
+
+
+#[async_std::test]
+//
+async fn at_most_once()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (client_addr , _                ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr  = remotes::RemoteAddr::new( client_addr );
+	let mut lossy = LossyAck{ addr: addr.clone(), calls: Arc::new( AtomicUsize::new( 0 ) ) };
+
+	assert_matches!( lossy.deliver( Add(5), Delivery::AtMostOnce ).await, Err( PeerErr::Timeout{..} ) );
+
+	assert_eq!( 1, lossy.calls.load( Relaxed )                  );
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+}
@@ -0,0 +1,176 @@
+// Tests:
+//
+// ✔ An outgoing call fails with DuplicateCid when the cid source only produces cids that are in use.
+// ✔ An outgoing call whose cid is in use gets a new one from the cid source and succeeds.
+// ✔ An incoming call reusing the cid of a call in flight is rejected.
+// ✔ An incoming call that fails no longer counts as inbound once the error is sent.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+	serde         :: { Serialize, Deserialize      } ,
+};
+
+
+#[ derive( Actor ) ] struct Worker;
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Slow;
+
+impl Message for Slow { type Return = (); }
+
+
+impl Handler< Slow > for Worker
+{
+	#[async_fn] fn handle( &mut self, _msg: Slow )
+	{
+		Delay::new( Duration::from_millis( 100 ) ).await;
+	}
+}
+
+
+service_map!
+(
+	namespace  : dup    ;
+	wire_format: ThesWF ;
+	services   : Slow   ;
+);
+
+
+
+fn sm() -> dup::Services
+{
+	let worker = Addr::builder().start( Worker, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = dup::Services::new();
+	sm.register_handler::<Slow>( worker.clone_box() );
+
+	sm
+}
+
+
+
+#[async_std::test]
+//
+async fn outgoing_exhausted()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "server" ).await;
+
+	// A client that always produces the same cid.
+	//
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut client = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	client.set_cid_source( || ConnID::from( 5 ) );
+
+	AsyncStd.spawn( async{ client_mb.start( client ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut first  = dup::RemoteAddr::new( client_addr.clone() );
+	let mut second = dup::RemoteAddr::new( client_addr.clone() );
+
+	let (a, b) = join( first.call( Slow ), second.call( Slow ) ).await;
+
+	assert_eq!( Ok(()), a );
+	assert_matches!( b, Err( PeerErr::DuplicateCid{..} ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// The second call first gets the cid of the first one, which is still in flight, then a free one.
+//
+#[async_std::test]
+//
+async fn outgoing_regenerate()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "server" ).await;
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut client = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let mut cids = vec![ 5, 5, 6 ].into_iter();
+
+	client.set_cid_source( move || ConnID::from( cids.next().expect( "no more cids" ) ) );
+
+	AsyncStd.spawn( async{ client_mb.start( client ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut first  = dup::RemoteAddr::new( client_addr.clone() );
+	let mut second = dup::RemoteAddr::new( client_addr.clone() );
+
+	let (a, b) = join( first.call( Slow ), second.call( Slow ) ).await;
+
+	assert_eq!( Ok(()), a );
+	assert_eq!( Ok(()), b );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn incoming_reject()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, mut server_evts, _server_handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	// Send raw frames, so the client doesn't get a chance to pick another cid.
+	//
+	let frame = ||
+	{
+		let mut wf = ThesWF::default();
+
+		wf.set_sid( <Slow as dup::Service>::sid() );
+		wf.set_cid( ConnID::from( 5 )              );
+
+		serde_cbor::to_writer( &mut wf, &Slow ).expect( "serialize Slow" );
+
+		wf
+	};
+
+	client_addr.send( frame() ).await.expect( "send first frame"  );
+	client_addr.send( frame() ).await.expect( "send second frame" );
+
+	assert_matches!
+	(
+		server_evts.next().await.unwrap(),
+		PeerEvent::Error( PeerErr::DuplicateCid{ ctx } ) if ctx.cid == Some( ConnID::from( 5 ) )
+	);
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Add is not a service of the server, so the call fails with UnknownService.
+//
+#[async_std::test]
+//
+async fn inbound_freed()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert_matches!( addr.call( Add(5) ).await, Err( PeerErr::Remote{ err: ConnectionError::UnknownService{..}, .. } ) );
+
+	assert_eq!( 0, server_addr.call( GetStatus ).await.expect( "get status" ).inbound_calls );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
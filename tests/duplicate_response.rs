@@ -0,0 +1,82 @@
+// Tests:
+//
+// ✔ When the remote answers a call twice, the caller gets the first response, the second one is dropped,
+//   published as DuplicateResponse and counted in the status. Later calls are not affected.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { SinkExt                     } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn duplicate_response()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let mut frames = peer.manual_dispatch();
+	let mut sink   = peer.wire_sink().expect( "get wire sink" );
+
+	let _server_handle = AsyncStd.spawn_handle( server_mb.start( peer ) ).expect( "start mailbox of Peer" );
+
+
+	// Answer each call to Show twice, with a different value, so we can tell which one the caller got.
+	//
+	let dispatch = async move
+	{
+		let mut count: i64 = 0;
+
+		while let Some( frame ) = frames.next().await
+		{
+			let frame = frame.expect( "incoming frame" );
+
+			for _ in 0..2
+			{
+				count += 1;
+
+				let mut resp = ThesWF::default();
+
+				resp.set_sid( ServiceID::full() );
+				resp.set_cid( frame.cid()       );
+
+				serde_cbor::to_writer( &mut resp, &count ).expect( "serialize response" );
+
+				sink.send( resp ).await.expect( "send response" );
+			}
+		}
+	};
+
+	AsyncStd.spawn( dispatch ).expect( "spawn dispatcher" );
+
+
+	let (mut client_addr, mut client_evts) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( 1, addr.call( Show ).await.expect( "call Show" ) );
+
+	let evt = client_evts.wait_for( |e| matches!( e, PeerEvent::DuplicateResponse{..} ) ).await.expect( "duplicate event" );
+
+	match evt
+	{
+		PeerEvent::DuplicateResponse{ sid, .. } => assert_eq!( <Show as remotes::Service>::sid(), sid ),
+		x                                       => panic!( "unexpected event: {:?}", x ),
+	}
+
+	assert_eq!( 1, client_addr.call( GetStatus ).await.expect( "get status" ).duplicate_responses );
+
+	// The next call gets its own first response.
+	//
+	assert_eq!( 3, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,147 @@
+// Tests:
+//
+// ✔ With Encoder::elide_null_header, a frame with a null sid and a null cid takes ServiceID::SIZE + 8
+//   bytes less on the wire and comes out of Decoder and DecoderNoHeap unchanged. Other frames are
+//   written as is.
+// ✔ A ping, which is a control frame with a null cid, is elided as well and comes out of both decoders
+//   with its control sid.
+// ✔ Peers with Peer::set_elide_null_header on both ends can ping each other and make calls.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { SinkExt, io::Cursor         } ,
+	std     :: { time::UNIX_EPOCH            } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn round_trip()
+{
+	let mut null = ThesWF::default();
+
+	null.set_deadline( Some( UNIX_EPOCH + Duration::from_millis( 1_600_000_000_123 ) ) );
+	null.write_all( &[ 1, 2, 3 ] ).expect( "write payload" );
+
+	assert!( null.sid().is_null() );
+	assert!( null.cid().is_null() );
+
+	let mut send = ThesWF::default();
+
+	send.set_sid( <Add as remotes::Service>::sid() );
+	send.write_all( &[ 5 ] ).expect( "write payload" );
+
+
+	let mut encoder = Encoder::new( Cursor::new( Vec::new() ), 1024 ).elide_null_header( true );
+
+	encoder.send( null.clone() ).await.expect( "send frame" );
+
+	assert_eq!( null.len() as usize - ServiceID::SIZE - 8, encoder.get_ref().get_ref().len() );
+
+	encoder.send( send.clone() ).await.expect( "send frame" );
+
+	let bytes = encoder.get_ref().get_ref().clone();
+
+	assert_eq!( null.len() + send.len() - ServiceID::SIZE as u64 - 8, bytes.len() as u64 );
+
+
+	let mut decoder = Decoder::new( Cursor::new( bytes.clone() ), 1024 );
+
+	assert_eq!( null, decoder.next().await.expect( "a frame" ).expect( "decode frame" ) );
+	assert_eq!( send, decoder.next().await.expect( "a frame" ).expect( "decode frame" ) );
+	assert!( decoder.next().await.is_none() );
+
+
+	let mut decoder = DecoderNoHeap::new( Cursor::new( bytes ), 1024 );
+
+	assert_eq!( null, decoder.next().await.expect( "a frame" ).expect( "decode frame" ) );
+	assert_eq!( send, decoder.next().await.expect( "a frame" ).expect( "decode frame" ) );
+}
+
+
+
+
+#[async_std::test]
+//
+async fn ping_frame()
+{
+	// A ping like the peer sends it: control code 2 and the nonce as payload.
+	//
+	let mut ping = ThesWF::default();
+
+	ping.set_sid( ServiceID::control( 2 ) );
+	ping.write_all( &7u64.to_le_bytes() ).expect( "write nonce" );
+
+	assert!( ping.cid().is_null() );
+
+
+	let mut encoder = Encoder::new( Cursor::new( Vec::new() ), 1024 ).elide_null_header( true );
+
+	encoder.send( ping.clone() ).await.expect( "send frame" );
+
+	let bytes = encoder.get_ref().get_ref().clone();
+
+	assert_eq!( ping.len() as usize - ServiceID::SIZE - 8, bytes.len() );
+
+
+	let mut decoder = Decoder::new( Cursor::new( bytes.clone() ), 1024 );
+	let decoded     = decoder.next().await.expect( "a frame" ).expect( "decode frame" );
+
+	assert_eq!( Some( 2 ), decoded.sid().control_code() );
+	assert_eq!( ping     , decoded                      );
+
+
+	let mut decoder = DecoderNoHeap::new( Cursor::new( bytes ), 1024 );
+
+	assert_eq!( ping, decoder.next().await.expect( "a frame" ).expect( "decode frame" ) );
+}
+
+
+
+// Create a peer with elided headers and start its mailbox.
+//
+fn elided_peer( socket: Endpoint, sm: Option<remotes::Services>, name: &str ) -> Addr<Peer>
+{
+	let (addr, mb) = Addr::builder().name( name.into() ).build();
+
+	let mut peer = Peer::from_async_read( addr.clone(), socket, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.set_elide_null_header( true );
+
+	if let Some( sm ) = sm
+	{
+		peer.register_services( Arc::new( sm ) );
+	}
+
+	AsyncStd.spawn( async{ mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	addr
+}
+
+
+
+#[async_std::test]
+//
+async fn ping_peers()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let _server_addr    = elided_peer( server, Some( add_show_sum() ), "server" );
+	let mut client_addr = elided_peer( client, None                  , "client" );
+
+	let rtt = Peer::ping( &mut client_addr ).await.expect( "ping" );
+
+	assert!( rtt < Duration::from_secs( 1 ) );
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,254 @@
+#![ cfg( feature = "encrypt" ) ]
+
+// Tests:
+//
+// ✔ Two peers with the same key can call each other.
+// ✔ With different keys, the first frame fails to authenticate and the connection gets closed.
+// ✔ The deadline of a call reaches the remote.
+// ✔ The trace id of a call reaches the remote and comes back with the response.
+// ✔ The headers of a call reach the remote.
+// ✔ A call on a channel reaches the service map of that channel.
+// ✔ A call with a route trace through an encrypted relay comes back with the id of the relay.
+//
+mod common;
+
+use common::*                       ;
+use common::import::{ *, assert_eq };
+
+use parking_lot::Mutex              ;
+
+
+const KEY_A: [u8; 32] = [ 7; 32 ];
+const KEY_B: [u8; 32] = [ 8; 32 ];
+
+
+
+async fn encrypted_peer
+(
+	socket: Endpoint                       ,
+	key   : &[u8; 32]                      ,
+	sm    : Option< Arc<dyn ServiceMap> >  ,
+	name  : &str                           ,
+)
+	-> (Addr<Peer>, Events<PeerEvent>)
+{
+	encrypted_peer_with( socket, key, sm, name, |_| {} ).await
+}
+
+
+
+// Like encrypted_peer, `setup` can configure the peer before it starts.
+//
+async fn encrypted_peer_with
+(
+	socket: Endpoint                       ,
+	key   : &[u8; 32]                      ,
+	sm    : Option< Arc<dyn ServiceMap> >  ,
+	name  : &str                           ,
+	setup : impl FnOnce( &mut Peer )       ,
+)
+	-> (Addr<Peer>, Events<PeerEvent>)
+{
+	let (peer_addr, peer_mb) = Addr::builder().name( name.into() ).build();
+
+	let delay    = Some( Duration::from_millis(10) );
+	let mut peer = Peer::from_async_read_encrypted( peer_addr.clone(), socket, 1024, key, AsyncStd, None, delay ).expect( "spawn peer" );
+
+	let evts = peer.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	if let Some( sm ) = sm
+	{
+		peer.register_services( sm );
+	}
+
+	setup( &mut peer );
+
+	AsyncStd.spawn( async{ peer_mb.start(peer).await; } ).expect( "start mailbox of Peer" );
+
+	(peer_addr, evts)
+}
+
+
+
+#[async_std::test]
+//
+async fn same_key()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _          ) = encrypted_peer( server, &KEY_A, Some( Arc::new( add_show_sum() ) ), "server" ).await;
+	let (mut client_addr, _       ) = encrypted_peer( client, &KEY_A, None                              , "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn different_key()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, mut server_evts) = encrypted_peer( server, &KEY_A, Some( Arc::new( add_show_sum() ) ), "server" ).await;
+	let (client_addr , mut client_evts) = encrypted_peer( client, &KEY_B, None                              , "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert!( addr.call( Add(5) ).await.is_err() );
+
+	assert_matches!
+	(
+		server_evts.next().await.unwrap(),
+		PeerEvent::Error( PeerErr::WireFormat{ source: WireErr::Decrypt{..}, .. } )
+	);
+
+	assert_eq!( PeerEvent::Closed        ( CloseReason::AuthFailed ), server_evts.next().await.unwrap() );
+	assert_eq!( PeerEvent::ClosedByRemote( CloseReason::Eof        ), client_evts.next().await.unwrap() );
+}
+
+
+
+// The server keeps the frames that pass its guard, which sees them decrypted.
+//
+fn recorder( frames: &Arc< Mutex< Vec<ThesWF> > > ) -> impl FnOnce( &mut Peer )
+{
+	let frames = frames.clone();
+
+	move |peer: &mut Peer| peer.set_guard( move |frame, _| { frames.lock().push( frame.clone() ); true } )
+}
+
+
+
+#[async_std::test]
+//
+async fn deadline()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let frames = Arc::new( Mutex::new( Vec::new() ) );
+
+	let (_server_addr, _   ) = encrypted_peer_with( server, &KEY_A, Some( Arc::new( add_show_sum() ) ), "server", recorder( &frames ) ).await;
+	let (mut client_addr, _) = encrypted_peer_with( client, &KEY_A, None, "client", |peer| peer.set_propagate_deadline( true ) ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	assert!( frames.lock().iter().all( |f| f.deadline().is_some() ) );
+	assert_eq!( 2, frames.lock().len() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn trace_id()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let frames = Arc::new( Mutex::new( Vec::new() ) );
+
+	let (_server_addr, _   ) = encrypted_peer_with( server, &KEY_A, Some( Arc::new( add_show_sum() ) ), "server", recorder( &frames ) ).await;
+	let (mut client_addr, _) = encrypted_peer( client, &KEY_A, None, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	let (sum, trace) = addr.call_traced( Show, 42 ).await.expect( "call Show" );
+
+	assert_eq!( 0         , sum                         );
+	assert_eq!( Some( 42 ), trace                       );
+	assert_eq!( Some( 42 ), frames.lock()[0].trace_id() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn meta()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let frames = Arc::new( Mutex::new( Vec::new() ) );
+
+	let (_server_addr, _   ) = encrypted_peer_with( server, &KEY_A, Some( Arc::new( add_show_sum() ) ), "server", recorder( &frames ) ).await;
+	let (mut client_addr, _) = encrypted_peer( client, &KEY_A, None, "client" ).await;
+
+	let mut addr    = remotes::RemoteAddr::new( client_addr.clone() );
+	let mut headers = Metadata::new();
+
+	headers.insert( "tenant".to_string(), "acme".to_string() );
+
+	assert_eq!( Ok(()), addr.call_with_meta( Add(5), headers.clone() ).await );
+	assert_eq!( Ok(5) , addr.call( Show ).await                              );
+
+	assert_eq!( Some( headers ), frames.lock()[0].meta() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn channel()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let register = |peer: &mut Peer| peer.register_channel( 3, Arc::new( add_show_sum() ) );
+
+	let (_server_addr, _   ) = encrypted_peer_with( server, &KEY_A, None, "server", register ).await;
+	let (mut client_addr, _) = encrypted_peer( client, &KEY_A, None, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.set_channel( Some( 3 ) );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(5) , addr.call( Show   ).await );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn route()
+{
+	let (provider, to_provider) = Endpoint::pair( 64, 64 );
+	let (relay   , to_relay   ) = Endpoint::pair( 64, 64 );
+
+	let (_provider_addr, _, _provider_handle) = peer_listen( provider, Arc::new( add_show_sum() ), AsyncStd, "provider" ).await;
+	let (relay_to_provider, _)                = peer_connect( to_provider, AsyncStd, "relay_to_provider" ).await;
+
+	let handler: Box<dyn Relay> = Box::new( relay_to_provider );
+	let relayed                 = vec![ <Show as remotes::Service>::sid() ];
+	let relay_map               = Arc::new( RelayMap::new( handler.into(), relayed ) );
+
+	let (relay_addr  , _) = encrypted_peer( relay   , &KEY_A, Some( relay_map ), "relay"    ).await;
+	let (mut consumer, _) = encrypted_peer( to_relay, &KEY_A, None             , "consumer" ).await;
+
+	let mut traced = ThesWF::default();
+
+	traced.set_sid( <Show as remotes::Service>::sid() );
+	serde_cbor::to_writer( &mut traced, &Show ).expect( "serialize Show" );
+	traced.set_route( Some( &[] ) );
+
+	let resp = RawPeerSink::new( consumer.clone() ).call( traced ).await.expect( "call Show" );
+
+	assert_eq!( Some( vec![ relay_addr.id() ] ), resp.route() );
+	assert_eq!( 0, serde_cbor::from_slice::<i64>( resp.msg() ).expect( "deserialize response" ) );
+
+	consumer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,126 @@
+// Tests:
+//
+// ✔ With a custom ErrorCodec on both ends, a call to an unknown service puts the custom encoding
+//   on the wire and the caller gets the decoded error.
+//
+mod common;
+
+use
+{
+	common      :: { *, import::{ *, assert_eq }               } ,
+	futures     :: { AsyncReadExt                              } ,
+	parking_lot :: { Mutex                                     } ,
+	byteorder   :: { ReadBytesExt, WriteBytesExt, LittleEndian } ,
+	thes_wf     :: { Decoder, Encoder                          } ,
+};
+
+
+const UNKNOWN: &[u8] = b"unknown_service:";
+const OTHER  : &[u8] = b"other";
+
+
+// Encodes unknown service errors as the tag followed by the sid and the cid in little endian, everything
+// else as "other", which decodes to an internal server error.
+//
+struct Tagged;
+
+impl ErrorCodec for Tagged
+{
+	fn encode( &self, err: &ConnectionError ) -> Vec<u8>
+	{
+		match err
+		{
+			ConnectionError::UnknownService{ sid: Some(sid), cid: Some(cid) } =>
+			{
+				let mut out = UNKNOWN.to_vec();
+
+				sid.write_le( &mut out ).expect( "write sid" );
+				out.write_u64::<LittleEndian>( (*cid).into() ).expect( "write cid" );
+
+				out
+			}
+
+			_ => OTHER.to_vec(),
+		}
+	}
+
+
+	fn decode( &self, payload: &[u8] ) -> Result<ConnectionError, ErrorSource>
+	{
+		if let Some( mut rest ) = payload.strip_prefix( UNKNOWN )
+		{
+			let sid = ServiceID::read_le( &mut rest ).map_err( ErrorSource::new )?;
+			let cid = rest.read_u64::<LittleEndian>().map_err( ErrorSource::new )?;
+
+			return Ok( ConnectionError::UnknownService{ sid: Some( sid ), cid: Some( cid.into() ) } );
+		}
+
+		Ok( ConnectionError::InternalServerError{ sid: None, cid: None } )
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn custom_error_codec()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+
+	// The server exposes Add and Show, but not Sub.
+	//
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut server = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	server.register_services( Arc::new( add_show_sum() ) );
+	server.set_error_codec( Tagged );
+
+	AsyncStd.spawn( async{ server_mb.start( server ).await; } ).expect( "start mailbox of Peer" );
+
+
+	// The client records the payload of the error frames it receives.
+	//
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let seen   = Arc::new( Mutex::new( Vec::new() ) );
+	let record = seen.clone();
+
+	let (reader, writer) = client.split();
+
+	let stream = Decoder::new( reader, 1024 ).inspect( move |frame|
+	{
+		if let Ok( frame ) = frame
+		{
+			if frame.sid().is_null() { record.lock().push( frame.msg().to_vec() ) }
+		}
+	});
+
+	let mut client = Peer::new( client_addr.clone(), stream, Encoder::new( writer, 1024 ), AsyncStd, None, None ).expect( "spawn peer" );
+
+	client.set_error_codec( Tagged );
+
+	AsyncStd.spawn( async{ client_mb.start( client ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	let (sid, cid) = match addr.call( Sub(1) ).await
+	{
+		Err( PeerErr::Remote{ err: ConnectionError::UnknownService{ sid: Some(sid), cid: Some(cid) }, .. } ) => (sid, cid),
+		other => panic!( "expected an unknown service error, got: {:?}", other ),
+	};
+
+	assert_eq!( <Sub as remotes::Service>::sid(), sid );
+
+
+	let mut expect = UNKNOWN.to_vec();
+
+	sid.write_le( &mut expect ).expect( "write sid" );
+	expect.write_u64::<LittleEndian>( cid.into() ).expect( "write cid" );
+
+	assert_eq!( vec![ expect ], *seen.lock() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
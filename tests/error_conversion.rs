@@ -0,0 +1,64 @@
+// Tests:
+//
+// ✔ A PeerErr converts into the ConnectionError a remote sees, keeping sid and cid, and back into a
+//   PeerErr with sid and cid in the context.
+// ✔ Timeouts convert to PeerErr::Timeout rather than a remote error.
+// ✔ Errors that are none of the business of the remote become an internal server error.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+#[test]
+//
+fn round_trip()
+{
+	let sid = <Add as remotes::Service>::sid();
+	let cid = ConnID::from( 7 );
+
+	let ctx = PeerErrCtx::default().peer_id( 3 ).sid( sid ).cid( cid );
+	let err = PeerErr::UnknownService{ ctx };
+
+	let conn_err = ConnectionError::from( &err );
+
+	assert_eq!( ConnectionError::UnknownService{ sid: Some( sid ), cid: Some( cid ) }, conn_err );
+
+	let back = PeerErr::from( conn_err.clone() );
+
+	assert_matches!( &back, PeerErr::Remote{ err, .. } if *err == conn_err );
+	assert_eq!( Some( sid ), back.ctx().sid );
+	assert_eq!( Some( cid ), back.ctx().cid );
+}
+
+
+
+#[test]
+//
+fn timeout()
+{
+	let sid = <Add as remotes::Service>::sid();
+	let err = PeerErr::from( ConnectionError::Timeout{ sid } );
+
+	assert_matches!( err, PeerErr::Timeout{..} );
+	assert_eq!( Some( sid ), err.ctx().sid );
+}
+
+
+
+#[test]
+//
+fn internal()
+{
+	let sid = <Add as remotes::Service>::sid();
+	let cid = ConnID::from( 7 );
+
+	let ctx = PeerErrCtx::default().sid( sid ).cid( cid );
+
+	assert_eq!
+	(
+		ConnectionError::InternalServerError{ sid: Some( sid ), cid: Some( cid ) },
+		ConnectionError::from( &PeerErr::HandlerDead{ ctx } ),
+	);
+}
@@ -0,0 +1,34 @@
+// Tests:
+//
+// ✔ RemoteAddr::estimate_size returns the exact number of bytes a send puts on the wire.
+//
+mod common;
+
+use common::*                       ;
+use common::import::{ *, assert_eq };
+
+
+
+#[async_std::test]
+//
+async fn estimate_send()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _             ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	let estimate = remotes::RemoteAddr::estimate_size( &Add(5) ).expect( "estimate size" );
+
+	addr.send( Add(5) ).await.expect( "send Add" );
+
+	// The peer processes its mailbox in order, so the send is out by now.
+	//
+	let status = client_addr.call( GetStatus ).await.expect( "get status" );
+
+	assert_eq!( estimate as u64, status.bytes_out );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -9,6 +9,10 @@
 // - ✔ Invalid Codec
 // - ✔ Header Unknown Service (Remote)Error
 // - ✔ Service map Deserialization (Remote)Error
+// - ✔ The serde error is reachable through Error::source
+// - ✔ Deserialize errors carry the sid and the cause
+// - ✔ wait_for skips events until the one we want and fails when the events end first
+// - ✔ With set_report_send_errors, a malformed send is reported back to the sender as a RemoteError
 // - ✔ handling remote errors on call (let the caller know there were connection errors) -> tested in relay.rs
 //
 // - TODO: fuzz, SEND A WHOLE BUNCH OF BINARY DATA OVER THE NETWORK AND VERIFY THE CORRECT ERROR FOR EACH TYPE OF INPUT.
@@ -47,7 +51,7 @@ async fn close_connection()
 		//
 		peera.send( peer::CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "Send CloseConnection" );
 
-		assert_eq!( PeerEvent::Closed,  peera_evts.next().await.unwrap() );
+		assert_eq!( PeerEvent::Closed( CloseReason::Local ), peera_evts.next().await.unwrap() );
 	};
 
 
@@ -88,7 +92,7 @@ async fn close_connection_call()
 		//
 		peera.call( peer::CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "Send CloseConnection" );
 
-		assert_eq!( PeerEvent::Closed, peera_evts.next().await.unwrap() );
+		assert_eq!( PeerEvent::Closed( CloseReason::Local ), peera_evts.next().await.unwrap() );
 	};
 
 
@@ -193,9 +197,14 @@ async fn call_deserialize()
 
 		match evts.next().await.unwrap()
 		{
-			PeerEvent::Error( PeerErr::Deserialize{ ctx } ) =>
+			PeerEvent::Error( err @ PeerErr::Deserialize{..} ) =>
 			{
-				assert_eq!( ctx.context.unwrap(), "Services::call_service" );
+				// The original serde error should be reachable through the source chain.
+				//
+				let source = std::error::Error::source( &err ).expect( "PeerErr::Deserialize has a source" );
+
+				assert!( source.downcast_ref::<serde_cbor::Error>().is_some() );
+				assert_eq!( err.ctx().context.as_deref(), Some( "Services::call_service" ) );
 			}
 
 			_ => unreachable!( "Should be PeerEvent::Error( PeerErr::Deserialize" )
@@ -271,7 +280,7 @@ async fn sm_deserialize_error()
 
 		match evts.next().await.unwrap()
 		{
-			PeerEvent::Error( PeerErr::Deserialize{ ctx } ) =>
+			PeerEvent::Error( PeerErr::Deserialize{ ctx, .. } ) =>
 			{
 				assert_eq!( ctx.context.unwrap(), "Services::call_service" );
 			}
@@ -323,3 +332,129 @@ async fn sm_deserialize_error()
 
 	join( a_handle, b_handle ).await;
 }
+
+
+
+// The Deserialize error for a malformed send should tell which service it was for and why it failed.
+//
+#[async_std::test]
+//
+async fn deserialize_error_context()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+	let sid              = <Add as remotes::Service>::sid();
+
+	let nodea = async move
+	{
+		let (_, mut evts, handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "nodea" ).await;
+
+		match evts.next().await.unwrap()
+		{
+			PeerEvent::Error( err @ PeerErr::Deserialize{..} ) =>
+			{
+				assert_eq!( err.ctx().sid, Some( sid ) );
+
+				let cause = match &err
+				{
+					PeerErr::Deserialize{ source: Some( source ), .. } => source.to_string(),
+					_                                                   => unreachable!( "Should have a source" ),
+				};
+
+				assert!( !cause.is_empty() );
+
+				let display = err.to_string();
+
+				assert!( display.contains( &sid.to_string() ) );
+				assert!( display.contains( &cause           ) );
+			}
+
+			_ => unreachable!( "Should be PeerEvent::Error( PeerErr::Deserialize" )
+		}
+
+		handle.await;
+	};
+
+
+	let nodeb = async move
+	{
+		let (mut peera, _) = peer_connect( client, AsyncStd, "nodeb_to_nodea" ).await;
+
+		// A send for a known service, but the payload is not a valid Add.
+		//
+		let mut wf = ThesWF::with_capacity( 2 );
+		wf.set_sid( sid );
+		wf.set_cid( ConnID::null() );
+		wf.write( &[3,3] ).unwrap();
+
+		peera.send( wf ).await.expect( "send to peera" );
+
+		peera.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	};
+
+
+	join( nodea, nodeb ).await;
+}
+
+
+
+// wait_for resolves on the first matching event and errors once the stream ends.
+//
+#[async_std::test]
+//
+async fn wait_for()
+{
+	let mut evts = futures::stream::iter( vec!
+	[
+		PeerEvent::RemoteCapacity  ( 3                ) ,
+		PeerEvent::RelayDisappeared( 7                ) ,
+		PeerEvent::ClosedByRemote  ( CloseReason::Eof ) ,
+		PeerEvent::RemoteCapacity  ( 5                ) ,
+	]);
+
+	let evt = evts.wait_for( |e| matches!( e, PeerEvent::ClosedByRemote(_) ) ).await;
+	assert_eq!( Ok( PeerEvent::ClosedByRemote( CloseReason::Eof ) ), evt );
+
+	// The skipped events are consumed, the ones after the match are not.
+	//
+	let evt = evts.wait_for( |e| matches!( e, PeerEvent::RemoteCapacity(_) ) ).await;
+	assert_eq!( Ok( PeerEvent::RemoteCapacity( 5 ) ), evt );
+
+	assert_matches!( evts.wait_for( |_| true ).await, Err( PeerErr::PeerGone{..} ) );
+}
+
+
+
+#[async_std::test]
+//
+async fn report_send_errors()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( add_show_sum() ) );
+	peer.set_report_send_errors( true );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, mut client_evts) = peer_connect( client, AsyncStd, "client" ).await;
+
+	// A send of Add with a payload that doesn't deserialize.
+	//
+	let sid = <Add as remotes::Service>::sid();
+
+	let mut wf = ThesWF::with_capacity( 2 );
+	wf.set_sid( sid );
+	wf.write( &[3,3] ).unwrap();
+
+	client_addr.send( wf ).await.expect( "send malformed Add" );
+
+	let evt = client_evts.wait_for( |e| matches!( e, PeerEvent::RemoteError(_) ) ).await.expect( "remote error" );
+
+	assert_eq!( PeerEvent::RemoteError( ConnectionError::Deserialize{ sid: Some( sid ), cid: None } ), evt );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,121 @@
+// Tests:
+//
+// ✔ With fair queuing, a call to a service that trickles in gets a slot before the backlog of another
+//   service that floods the peer.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::*                } ,
+	crate         :: { peer::BackPressure          } ,
+	futures       :: { future::join_all            } ,
+	futures_timer :: { Delay                       } ,
+	serde         :: { Serialize, Deserialize      } ,
+	std           :: { num::NonZeroUsize           } ,
+};
+
+
+#[ derive( Actor ) ] struct Counter( usize );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Flood;
+#[ derive( Serialize, Deserialize, Debug ) ] struct Trickle;
+
+impl Message for Flood   { type Return = ();    }
+impl Message for Trickle { type Return = usize; }
+
+
+impl Handler<Flood> for Counter
+{
+	#[async_fn] fn handle( &mut self, _msg: Flood )
+	{
+		Delay::new( Duration::from_millis( 10 ) ).await;
+
+		self.0 += 1;
+	}
+}
+
+
+// Returns the number of Flood calls processed before it.
+//
+impl Handler<Trickle> for Counter
+{
+	#[async_fn] fn handle( &mut self, _msg: Trickle ) -> usize
+	{
+		self.0
+	}
+}
+
+
+service_map!
+(
+	namespace  : fair           ;
+	wire_format: ThesWF         ;
+	services   : Flood, Trickle ;
+);
+
+
+
+#[async_std::test]
+//
+async fn no_starvation()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let counter = Addr::builder().start( Counter(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = fair::Services::new();
+
+	sm.register_handler::<Flood  >( counter.clone_box() );
+	sm.register_handler::<Trickle>( counter.clone_box() );
+
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read
+	(
+		server_addr                              ,
+		server                                   ,
+		1024                                     ,
+		AsyncStd                                 ,
+		Some( Arc::new( BackPressure::new(1) ) ) ,
+		None                                     ,
+
+	).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( sm ) );
+	peer.set_fair_queuing( Vec::new(), NonZeroUsize::new( 64 ).unwrap() );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let addr = fair::RemoteAddr::new( client_addr.clone() );
+
+
+	let flood = join_all( (0..20).map( |_|
+	{
+		let mut addr = addr.clone();
+
+		async move { addr.call( Flood ).await.expect( "call Flood" ) }
+	}));
+
+	let trickle = async
+	{
+		// Let the flood come in first.
+		//
+		Delay::new( Duration::from_millis( 5 ) ).await;
+
+		addr.clone().call( Trickle ).await.expect( "call Trickle" )
+	};
+
+
+	let (_, before) = join( flood, trickle ).await;
+
+	// In the order of arrival it would wait for all 20.
+	//
+	assert!( before <= 3, "Trickle waited for {} Flood calls", before );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
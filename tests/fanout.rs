@@ -0,0 +1,205 @@
+// Tests:
+//
+// ✔ A call returns the first success, even if it is slower than the errors from other providers.
+// ✔ When all providers fail, the errors of all of them are returned.
+// ✔ QuorumAddr returns the value 2 out of 3 replicas agree on.
+// ✔ QuorumAddr errors when no 2 replicas agree.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+	serde         :: { Serialize, Deserialize      } ,
+	std           :: { num::NonZeroUsize           } ,
+};
+
+
+#[ derive( Actor ) ] struct Provider;
+#[ derive( Actor ) ] struct Replica( usize );
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ] struct Ping;
+#[ derive( Serialize, Deserialize, Debug, Clone ) ] struct Read;
+
+impl Message for Ping { type Return = usize; }
+impl Message for Read { type Return = usize; }
+
+
+impl Handler< Ping > for Provider
+{
+	#[async_fn] fn handle( &mut self, _msg: Ping ) -> usize
+	{
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		42
+	}
+}
+
+
+impl Handler< Read > for Replica
+{
+	#[async_fn] fn handle( &mut self, _msg: Read ) -> usize
+	{
+		self.0
+	}
+}
+
+
+service_map!
+(
+	namespace  : fanout     ;
+	wire_format: ThesWF     ;
+	services   : Ping, Read ;
+);
+
+
+
+// Without a handler, the provider will answer with an error right away.
+//
+async fn provider( working: bool, name: &str ) -> (Addr<Peer>, JoinHandle< MailboxEnd<Peer> >)
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let mut sm = fanout::Services::new();
+
+	if working
+	{
+		let provider = Addr::builder().start( Provider, &AsyncStd ).expect( "spawn actor mailbox" );
+
+		sm.register_handler::<Ping>( provider.clone_box() );
+	}
+
+	let (_, _, handle) = peer_listen ( server, Arc::new( sm ), AsyncStd, name ).await;
+	let (client, _   ) = peer_connect( client, AsyncStd, &format!( "client_{}", name ) ).await;
+
+	(client, handle)
+}
+
+
+
+async fn replica( value: usize ) -> (Addr<Peer>, JoinHandle< MailboxEnd<Peer> >)
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let replica = Addr::builder().start( Replica( value ), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = fanout::Services::new();
+	sm.register_handler::<Read>( replica.clone_box() );
+
+	let (_, _, handle) = peer_listen ( server, Arc::new( sm ), AsyncStd, "replica" ).await;
+	let (client, _   ) = peer_connect( client, AsyncStd, "client_replica" ).await;
+
+	(client, handle)
+}
+
+
+
+fn remote_addrs<S>( peers: &[Addr<Peer>] ) -> Vec< BoxAddress<S, PeerErr> >
+
+	where fanout::RemoteAddr: Address<S, Error=PeerErr>,
+	      S                 : Message,
+{
+	peers.iter().map( |p| Box::new( fanout::RemoteAddr::new( p.clone() ) ) as BoxAddress<S, PeerErr> ).collect()
+}
+
+
+
+fn fanout_addr( peers: &[Addr<Peer>] ) -> FanoutAddr<Ping>
+{
+	FanoutAddr::new( remote_addrs( peers ) )
+}
+
+
+
+async fn quorum_read( values: &[usize] ) -> Result<usize, PeerErr>
+{
+	let mut peers   = Vec::new();
+	let mut handles = Vec::new();
+
+	for value in values
+	{
+		let (peer, handle) = replica( *value ).await;
+
+		peers  .push( peer   );
+		handles.push( handle );
+	}
+
+	let mut addr = QuorumAddr::new( remote_addrs( &peers ), NonZeroUsize::new( 2 ).unwrap() );
+	let result   = addr.call( Read ).await;
+
+	for mut peer in peers
+	{
+		peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	}
+
+	result
+}
+
+
+
+#[async_std::test]
+//
+async fn first_success()
+{
+	let (slow , _slow_handle ) = provider( true , "slow"  ).await;
+	let (err_a, _err_a_handle) = provider( false, "err_a" ).await;
+	let (err_b, _err_b_handle) = provider( false, "err_b" ).await;
+
+	let peers    = vec![ slow, err_a, err_b ];
+	let mut addr = fanout_addr( &peers );
+
+	assert_eq!( Ok(42), addr.call( Ping ).await );
+
+	for mut peer in peers
+	{
+		peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn all_fail()
+{
+	let (err_a, _err_a_handle) = provider( false, "err_a" ).await;
+	let (err_b, _err_b_handle) = provider( false, "err_b" ).await;
+
+	let peers    = vec![ err_a, err_b ];
+	let mut addr = fanout_addr( &peers );
+
+	match addr.call( Ping ).await
+	{
+		Err( PeerErr::Fanout{ errors, .. } ) =>
+		{
+			assert_eq!( 2, errors.len() );
+			assert!( errors.iter().all( |e| matches!( e, PeerErr::Remote{..} ) ) );
+		}
+
+		other => panic!( "expected PeerErr::Fanout, got: {:?}", other ),
+	}
+
+	for mut peer in peers
+	{
+		peer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn quorum_agree()
+{
+	assert_eq!( Ok(7), quorum_read( &[ 7, 7, 9 ] ).await );
+}
+
+
+
+#[async_std::test]
+//
+async fn quorum_disagree()
+{
+	assert_matches!( quorum_read( &[ 7, 8, 9 ] ).await, Err( PeerErr::NoQuorum{ quorum: 2, agreed: 1, .. } ) );
+}
@@ -0,0 +1,36 @@
+// Tests:
+//
+// ✔ Call::frame and CallResponse::frame give back the wrapped frame.
+//
+mod common;
+
+use common::*                       ;
+use common::import::{ *, assert_eq };
+
+
+
+#[test]
+//
+fn frame_accessors()
+{
+	let sid = <Add as remotes::Service>::sid();
+	let cid = ConnID::random();
+
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( sid );
+	wf.set_cid( cid );
+	serde_cbor::to_writer( &mut wf, &Add(5) ).expect( "serialize Add" );
+
+	let call = Call::new( wf.clone() );
+
+	assert_eq!( sid      , call.frame().sid() );
+	assert_eq!( cid      , call.frame().cid() );
+	assert_eq!( wf.msg() , call.frame().msg() );
+
+	let resp = CallResponse::new( wf.clone() );
+
+	assert_eq!( sid      , resp.frame().sid() );
+	assert_eq!( cid      , resp.frame().cid() );
+	assert_eq!( wf.msg() , resp.frame().msg() );
+}
@@ -0,0 +1,50 @@
+// Tests:
+//
+// ✔ WireFormat::kind classifies sends, calls, responses and errors from the sid and cid.
+// ✔ Frames meant for the peer itself get their own kind.
+//
+mod common;
+
+use common::*                       ;
+use common::import::{ *, assert_eq };
+
+
+fn frame( sid: ServiceID, cid: ConnID ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( sid );
+	wf.set_cid( cid );
+
+	wf
+}
+
+
+
+#[test]
+//
+fn kind()
+{
+	let add = <Add as remotes::Service>::sid();
+	let cid = ConnID::random();
+
+	assert_eq!( FrameKind::Send    , frame( add              , ConnID::null() ).kind() );
+	assert_eq!( FrameKind::Call    , frame( add              , cid            ).kind() );
+	assert_eq!( FrameKind::Response, frame( ServiceID::full(), cid            ).kind() );
+	assert_eq!( FrameKind::Error   , frame( ServiceID::null(), cid            ).kind() );
+	assert_eq!( FrameKind::Error   , frame( ServiceID::null(), ConnID::null() ).kind() );
+}
+
+
+
+#[test]
+//
+fn kind_internal()
+{
+	let transfer = ConnID::random();
+
+	assert_eq!( FrameKind::Chunk    , frame( ServiceID::chunk    (   ), transfer       ).kind() );
+	assert_eq!( FrameKind::Stream   , frame( ServiceID::stream   (   ), transfer       ).kind() );
+	assert_eq!( FrameKind::Handshake, frame( ServiceID::handshake(   ), ConnID::null() ).kind() );
+	assert_eq!( FrameKind::Control  , frame( ServiceID::control  ( 1 ), ConnID::null() ).kind() );
+}
@@ -0,0 +1,94 @@
+// Tests:
+//
+// ✔ Frames sent concurrently from many tasks over clones of the same peer address never interleave on
+//   the connection. Every frame decodes intact and the frames of each task arrive in the order it sent them.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { AsyncReadExt                } ,
+};
+
+
+const TASKS : u64   = 16  ;
+const FRAMES: u64   = 20  ;
+const FILL  : usize = 500 ;
+
+
+// The id of the frame followed by a fill byte unique to the task, much bigger than the buffers of the
+// connection, so every frame takes several writes.
+//
+fn frame( task: u64, seq: u64 ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( <Add as remotes::Service>::sid() );
+	wf.write_all( &( task << 32 | seq ).to_le_bytes() ).expect( "write id"   );
+	wf.write_all( &[ task as u8; FILL ]               ).expect( "write fill" );
+
+	wf
+}
+
+
+
+#[async_std::test]
+//
+async fn concurrent_senders()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	// Read the raw frames on the other end until the connection closes.
+	//
+	let (reader, _writer) = server.split();
+
+	let frames = AsyncStd.spawn_handle( thes_wf::Decoder::new( reader, 1024 ).collect::<Vec<_>>() ).expect( "spawn reader" );
+
+	let senders: Vec<_> = (0..TASKS).map( |task|
+	{
+		let mut addr = client_addr.clone();
+
+		AsyncStd.spawn_handle( async move
+		{
+			for seq in 0..FRAMES
+			{
+				addr.send( frame( task, seq ) ).await.expect( "send frame" );
+			}
+
+		}).expect( "spawn sender" )
+
+	}).collect();
+
+	futures::future::join_all( senders ).await;
+
+	client_addr.call( Shutdown ).await.expect( "shutdown" );
+
+
+	let frames = frames.await;
+	let mut next = vec![ 0; TASKS as usize ];
+
+	assert_eq!( ( TASKS * FRAMES ) as usize, frames.len() );
+
+	for frame in frames
+	{
+		let frame = frame.expect( "valid frame" );
+		let msg   = frame.msg();
+
+		assert_eq!( <Add as remotes::Service>::sid(), frame.sid() );
+		assert_eq!( 8 + FILL, msg.len() );
+
+		let mut id = [ 0u8; 8 ];
+		id.copy_from_slice( &msg[ ..8 ] );
+
+		let id   = u64::from_le_bytes( id );
+		let task = id >> 32;
+
+		assert!( msg[ 8.. ].iter().all( |b| *b == task as u8 ) );
+		assert_eq!( next[ task as usize ], id & 0xffff_ffff );
+
+		next[ task as usize ] += 1;
+	}
+}
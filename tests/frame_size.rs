@@ -0,0 +1,69 @@
+// Tests:
+//
+// ✔ Peers with different limits agree on the smallest and refuse bigger frames before sending.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { SinkExt                     } ,
+	futures_timer :: { Delay                       } ,
+};
+
+
+
+// Create a peer that announces the given limit and start it's mailbox.
+//
+fn peer( socket: Endpoint, max_size: usize, name: &str ) -> Addr<Peer>
+{
+	let (addr, mb) = Addr::builder().name( name.into() ).build();
+
+	let mut peer = Peer::from_async_read( addr.clone(), socket, max_size, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.announce_max_frame_size( max_size ).expect( "announce max frame size" );
+
+	AsyncStd.spawn( async{ mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	addr
+}
+
+
+
+#[async_std::test]
+//
+async fn agree_on_smallest()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let mut big   = peer( server, 4096, "big"   );
+	let mut small = peer( client, 1024, "small" );
+
+	// Wait for the handshake of the small peer to arrive.
+	//
+	for _ in 0..100
+	{
+		if big.call( QueryMaxFrameSize ).await.expect( "query max frame size" ) == Some( 1024 ) { break }
+
+		Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+	assert_eq!( Some( 1024 ), big  .call( QueryMaxFrameSize ).await.expect( "query max frame size" ) );
+	assert_eq!( Some( 1024 ), small.call( QueryMaxFrameSize ).await.expect( "query max frame size" ) );
+
+
+	// A frame of 2000 bytes is refused by the big peer without sending it.
+	//
+	let mut wf = ThesWF::with_capacity( 2000 );
+
+	wf.set_sid( <Add as remotes::Service>::sid() );
+	wf.write_all( &[ 0u8; 2000 ] ).expect( "write payload" );
+
+	assert_matches!
+	(
+		big.call( wf ).await.expect( "call peer" ),
+		Err( PeerErr::WireFormat{ source: WireErr::MessageSizeExceeded{ size, max_size: 1024, .. }, .. } ) if size > 2000
+	);
+
+	big.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,53 @@
+// Tests:
+//
+// ✔ A guard denying Add for an anonymous connection rejects the call before the handler runs,
+//   other services still work.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+#[async_std::test]
+//
+async fn deny_add()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	// Nobody authenticated on this connection.
+	//
+	let identity: Option<String> = None;
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( add_show_sum() ) );
+
+	peer.set_guard( move |frame, _ctx|
+	{
+		identity.is_some() || frame.sid() != <Add as remotes::Service>::sid()
+	});
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+	let mut addr             = remotes::RemoteAddr::new( client_addr.clone() );
+
+	match addr.call( Add(5) ).await
+	{
+		Err( PeerErr::Remote{ err: ConnectionError::Unauthorized{ sid, .. }, .. } ) =>
+
+			assert_eq!( Some( <Add as remotes::Service>::sid() ), sid ),
+
+		other => panic!( "expected an unauthorized error, got: {:?}", other ),
+	}
+
+	// The handler never saw the Add.
+	//
+	assert_eq!( 0, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
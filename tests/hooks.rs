@@ -0,0 +1,106 @@
+// Tests:
+//
+// ✔ The outgoing and incoming hooks of the calling peer see the serialized request and response of a call.
+// ✔ The outgoing hook sees the error a peer sends back for a call it can't handle.
+//
+mod common;
+
+use
+{
+	common      :: { *, import::{ *, assert_eq } } ,
+	parking_lot :: { Mutex                       } ,
+};
+
+
+type Frames = Arc< Mutex< Vec<(ServiceID, ConnID, Vec<u8>)> > >;
+
+
+#[async_std::test]
+//
+async fn record_payloads()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut peer = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let outgoing: Frames = Default::default();
+	let incoming: Frames = Default::default();
+
+	let out = outgoing.clone();
+	let inc = incoming.clone();
+
+	peer.set_outgoing_hook( move |sid, cid, bytes| out.lock().push( (sid, cid, bytes.to_vec()) ) );
+	peer.set_incoming_hook( move |sid, cid, bytes| inc.lock().push( (sid, cid, bytes.to_vec()) ) );
+
+	AsyncStd.spawn( async{ client_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+
+	// Only keep the request and the response, in case the peers exchange control frames.
+	//
+	let outgoing: Vec<_> = outgoing.lock().iter().filter( |f| f.0 == <Add as remotes::Service>::sid() ).cloned().collect();
+	let incoming: Vec<_> = incoming.lock().iter().filter( |f| f.0.is_full()                           ).cloned().collect();
+
+	assert_eq!( 1, outgoing.len() );
+	assert_eq!( 1, incoming.len() );
+
+	let (sid, cid, request) = &outgoing[0];
+
+	assert_eq!( <Add as remotes::Service>::sid(), *sid );
+	assert_eq!( serde_cbor::to_vec( &Add(5) ).expect( "serialize Add" ), *request );
+
+	let (sid, resp_cid, response) = &incoming[0];
+
+	assert!( sid.is_full() );
+	assert_eq!( cid, resp_cid );
+	assert_eq!( serde_cbor::to_vec( &() ).expect( "serialize ()" ), *response );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// The server has no services, so it answers the call with an error frame.
+//
+#[async_std::test]
+//
+async fn record_error()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let outgoing: Frames = Default::default();
+	let out              = outgoing.clone();
+
+	peer.set_outgoing_hook( move |sid, cid, bytes| out.lock().push( (sid, cid, bytes.to_vec()) ) );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+	let mut addr             = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect_err( "call Add without a handler" );
+
+	let errors: Vec<_> = outgoing.lock().iter().filter( |f| f.0.is_null() ).cloned().collect();
+
+	assert_eq!( 1, errors.len() );
+
+	let (_, cid, payload) = &errors[0];
+
+	assert!( !cid.is_null()      );
+	assert!( !payload.is_empty() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,90 @@
+#![ cfg( feature = "http" ) ]
+
+// Tests:
+//
+// ✔ POST an Add and a Show to the HttpAdapter and get the serialized responses back.
+// ✔ A POST to a service that isn't exposed gives 404.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { AsyncReadExt                } ,
+};
+
+
+// Post `body` to the service and return the status and the body of the response.
+//
+async fn post( socket: &mut Endpoint, sid: ServiceID, body: &[u8] ) -> (u16, Vec<u8>)
+{
+	let head = format!( "POST /{:x} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n", sid, body.len() );
+
+	socket.write_all( head.as_bytes() ).await.expect( "write head" );
+	socket.write_all( body            ).await.expect( "write body" );
+
+
+	// Read the head of the response byte by byte, so we don't read into the body.
+	//
+	let mut head = Vec::new();
+	let mut byte = [0u8; 1];
+
+	while !head.ends_with( b"\r\n\r\n" )
+	{
+		socket.read_exact( &mut byte ).await.expect( "read head" );
+		head.push( byte[0] );
+	}
+
+	let head   = String::from_utf8( head ).expect( "utf8 head" );
+	let status = head[ 9..12 ].parse().expect( "parse status" );
+
+	let len = head.lines()
+
+		.find_map( |l| l.strip_prefix( "Content-Length: " ) )
+		.expect( "content length" )
+		.parse()
+		.expect( "parse content length" )
+	;
+
+	let mut body = vec![ 0; len ];
+	socket.read_exact( &mut body ).await.expect( "read body" );
+
+	(status, body)
+}
+
+
+
+#[async_std::test]
+//
+async fn post_calls()
+{
+	let (server, mut client) = Endpoint::pair( 64, 64 );
+
+	let adapter: HttpAdapter = HttpAdapter::new( Arc::new( add_show_sum() ), 1024 );
+
+	AsyncStd.spawn( async move
+	{
+		adapter.serve( server ).await.expect( "serve http" );
+
+	}).expect( "spawn http adapter" );
+
+
+	let add = serde_cbor::to_vec( &Add(5) ).expect( "serialize Add" );
+	let (status, body) = post( &mut client, <Add as remotes::Service>::sid(), &add ).await;
+
+	assert_eq!( 200, status );
+	assert_eq!( serde_cbor::to_vec( &() ).expect( "serialize ()" ), body );
+
+
+	let show = serde_cbor::to_vec( &Show ).expect( "serialize Show" );
+	let (status, body) = post( &mut client, <Show as remotes::Service>::sid(), &show ).await;
+
+	assert_eq!( 200, status );
+	assert_eq!( 5  , serde_cbor::from_slice::<i64>( &body ).expect( "deserialize response" ) );
+
+
+	let sub = serde_cbor::to_vec( &Sub(1) ).expect( "serialize Sub" );
+	let (status, _) = post( &mut client, <Sub as remotes::Service>::sid(), &sub ).await;
+
+	assert_eq!( 404, status );
+}
@@ -0,0 +1,28 @@
+// Tests:
+//
+// ✔ Call a service over an in process pair of peers.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+#[async_std::test]
+//
+async fn in_process_call()
+{
+	let services: Arc<dyn ServiceMap> = Arc::new( add_show_sum() );
+
+	let (mut client, _server, _handles) = Peer::in_process_pair( Vec::new(), vec![ services ], 1024, AsyncStd )
+
+		.expect( "create in process pair" );
+
+	let mut addr = remotes::RemoteAddr::new( client.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	client.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
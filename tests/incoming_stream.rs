@@ -0,0 +1,62 @@
+// Tests:
+//
+// ✔ Sends to a service registered with Services::incoming come out of the stream in order.
+// ✔ Calls to such a service fail, the server has no handler for them.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn incoming_in_order()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let mut sm     = remotes::Services::new();
+	let     stream = sm.incoming::<Add>();
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _             ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	for i in 1..=3
+	{
+		addr.send( Add(i) ).await.expect( "send Add" );
+	}
+
+	let received: Vec<i64> = stream.take( 3 ).map( |msg| msg.expect( "deserialize Add" ).0 ).collect().await;
+
+	assert_eq!( vec![ 1, 2, 3 ], received );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn incoming_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let mut sm      = remotes::Services::new();
+	let     _stream = sm.incoming::<Add>();
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _             ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	let res = addr.call( Add(1) ).await;
+
+	assert!( matches!( res, Err( PeerErr::Remote{ err: ConnectionError::InternalServerError{..}, .. } ) ), "{:?}", res );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
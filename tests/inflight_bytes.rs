@@ -0,0 +1,115 @@
+// Tests:
+//
+// ✔ With a budget smaller than a frame, the peer doesn't read the next frame while the call of the
+//   previous one hasn't finished. Once it's done, the remaining frames get processed.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { lock::Mutex as FutMutex     } ,
+	futures_timer :: { Delay                       } ,
+	std           :: { num::NonZeroUsize           } ,
+};
+
+
+// Each Add waits for the gate to open.
+//
+#[ derive( Actor ) ] struct Gated
+{
+	gate: Arc<FutMutex<()>> ,
+	sum : i64               ,
+}
+
+
+impl Handler<Add> for Gated
+{
+	fn handle( &mut self, msg: Add ) -> Return<'_, ()> { async move
+	{
+		let _open = self.gate.lock().await;
+
+		self.sum += msg.0;
+
+	}.boxed() }
+}
+
+
+impl Handler<Show> for Gated
+{
+	fn handle( &mut self, _msg: Show ) -> Return<'_, i64> { async move
+	{
+		self.sum
+
+	}.boxed() }
+}
+
+
+service_map!
+(
+	namespace  : gated      ;
+	wire_format: ThesWF     ;
+	services   : Add, Show  ;
+);
+
+
+
+#[async_std::test]
+//
+async fn stop_reading()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let gate   = Arc::new( FutMutex::new(()) );
+	let closed = gate.lock().await;
+
+	let gated = Addr::builder().start( Gated{ gate: gate.clone(), sum: 0 }, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = gated::Services::new();
+
+	sm.register_handler::<Add >( gated.clone_box() );
+	sm.register_handler::<Show>( gated.clone_box() );
+
+
+	let (mut server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr.clone(), server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( sm ) );
+	peer.set_max_inflight_bytes( NonZeroUsize::new( 1 ).unwrap() );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr  = gated::RemoteAddr::new( client_addr.clone() );
+	let mut addr2 = addr.clone();
+	let mut addr3 = addr.clone();
+
+	let calls = join3( addr.call( Add(1) ), addr2.call( Add(2) ), addr3.call( Add(3) ) );
+
+	let mut one = 0;
+
+	let check = async
+	{
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		// Only the first frame has been read, the others wait in the connection.
+		//
+		one = server_addr.call( GetStatus ).await.expect( "get status" ).bytes_in;
+
+		drop( closed );
+	};
+
+	let ((a, b, c), _) = join( calls, check ).await;
+
+	a.expect( "call Add" );
+	b.expect( "call Add" );
+	c.expect( "call Add" );
+
+	assert_eq!( 3 * one, server_addr.call( GetStatus ).await.expect( "get status" ).bytes_in );
+	assert_eq!( 6      , addr.call( Show ).await.expect( "call Show" )                        );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
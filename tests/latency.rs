@@ -0,0 +1,75 @@
+// Tests:
+//
+// ✔ A handler with a known delay shows up with the right count and latencies in the range of the delay.
+//   Services that weren't called have no statistics.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+};
+
+
+#[ derive( Actor ) ] struct Slow;
+
+
+impl Handler<Add> for Slow
+{
+	fn handle( &mut self, _msg: Add ) -> Return<'_, ()> { async move
+	{
+		Delay::new( Duration::from_millis( 20 ) ).await;
+
+	}.boxed() }
+}
+
+
+impl Handler<Show> for Slow
+{
+	fn handle( &mut self, _msg: Show ) -> Return<'_, i64> { async move
+	{
+		0
+
+	}.boxed() }
+}
+
+
+
+#[async_std::test]
+//
+async fn latency()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let slow = Addr::builder().start( Slow, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = remotes::Services::new();
+
+	sm.register_handler::<Add >( slow.clone_box() );
+	sm.register_handler::<Show>( slow.clone_box() );
+
+	let latency = LatencyMap::new( Arc::new( sm ) );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( latency.clone() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	for _ in 0..5
+	{
+		addr.call( Add(1) ).await.expect( "call Add" );
+	}
+
+	let snapshot = latency.snapshot();
+	let stats    = snapshot[ &<Add as remotes::Service>::sid() ];
+
+	assert_eq!( 1, snapshot.len() );
+	assert_eq!( 5, stats.count    );
+
+	assert!( stats.p50 >= Duration::from_millis( 20  ) );
+	assert!( stats.p99 >= stats.p50                    );
+	assert!( stats.max <  Duration::from_millis( 500 ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,55 @@
+// Tests:
+//
+// ✔ A LazyRemoteAddr created before the provider is up connects on the first call. Concurrent first
+//   calls share the connection.
+//
+mod common;
+
+use
+{
+	common      :: { *, import::{ *, assert_eq } } ,
+	parking_lot :: { Mutex                       } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn connect_on_first_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let socket   = Arc::new( Mutex::new( Some( client ) ) );
+	let connects = Arc::new( AtomicUsize::new( 0 ) );
+	let count    = connects.clone();
+
+	let lazy = remotes::LazyRemoteAddr::new( move ||
+	{
+		let socket = socket.lock().take();
+
+		count.fetch_add( 1, Relaxed );
+
+		async move
+		{
+			let socket = socket.expect( "only connect once" );
+
+			Ok( peer_connect( socket, AsyncStd, "client" ).await.0 )
+		}
+	});
+
+	assert_eq!( 0, connects.load( Relaxed ) );
+
+
+	// Now the provider comes up.
+	//
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+
+	let (a, b) = join( lazy.call( Add(5) ), lazy.call( Add(5) ) ).await;
+
+	a.expect( "call Add" );
+	b.expect( "call Add" );
+
+	assert_eq!( 10, lazy.clone().call( Show ).await.expect( "call Show" ) );
+	assert_eq!( 1 , connects.load( Relaxed ) );
+}
@@ -0,0 +1,53 @@
+// Tests:
+//
+// ✔ on_connect runs once when the peer starts, on_disconnect runs once with the CloseConnection that
+//   closed the connection, even when the connection is closed twice.
+//
+mod common;
+
+use
+{
+	common      :: { *, import::{ *, assert_eq } } ,
+	parking_lot :: { Mutex                       } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn callbacks()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut peer = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let connects    = Arc::new( AtomicUsize::new( 0 ) );
+	let disconnects = Arc::new( Mutex::new( Vec::new() ) );
+
+	let c = connects   .clone();
+	let d = disconnects.clone();
+
+	peer.on_connect   ( move ||              { c.fetch_add( 1, Relaxed );                                  } );
+	peer.on_disconnect( move |close, reason| { d.lock().push( (close.remote, close.reason.clone(), reason) ); } );
+
+	AsyncStd.spawn( async{ client_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	// The response is processed after the peer started.
+	//
+	remotes::RemoteAddr::new( client_addr.clone() ).call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 1, connects.load( Relaxed ) );
+	assert!( disconnects.lock().is_empty() );
+
+
+	client_addr.call( CloseConnection{ remote: false, reason: "bye".to_string()   } ).await.expect( "close connection" );
+	client_addr.call( CloseConnection{ remote: false, reason: "again".to_string() } ).await.expect( "close connection" );
+
+	assert_eq!( 1, connects.load( Relaxed ) );
+	assert_eq!( vec![ (false, "bye".to_string(), CloseReason::Local) ], *disconnects.lock() );
+}
@@ -0,0 +1,77 @@
+// Tests:
+//
+// ✔ Calls and sends round trip between two LocalPeers on a LocalPool, with the state of the handlers in an Rc.
+// ✔ A call to a LocalPeer without services fails with UnknownService.
+// ✔ After close, calls fail with ConnectionClosed.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+	std           :: { cell::RefCell, rc::Rc       } ,
+};
+
+
+
+#[test]
+//
+fn round_trip()
+{
+	let mut pool = LocalPool::new();
+	let exec     = pool.spawner();
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	// Not Send, so this can't be used with Peer.
+	//
+	let sum = Rc::new( RefCell::new( 0 ) );
+
+	let mut sm = remotes::LocalServices::new();
+
+	let add  = sum.clone();
+	let show = sum.clone();
+
+	sm.register_fn::<Add, _, _>( move |msg| { let sum = add .clone(); async move { *sum.borrow_mut() += msg.0; } } );
+	sm.register_fn::<Show, _, _>( move |_ | { let sum = show.clone(); async move { *sum.borrow()             } } );
+
+	let server = LocalPeer::from_async_read( server, 1024, Some( Rc::new( sm ) ), exec.clone() ).expect( "create server" );
+	let client = LocalPeer::from_async_read( client, 1024, None                 , exec         ).expect( "create client" );
+
+	let addr = remotes::LocalRemoteAddr::new( client.clone() );
+
+	pool.run_until( async
+	{
+		addr.call( Add(5) ).await.expect( "call Add" );
+		addr.send( Add(5) )      .expect( "send Add" );
+
+		// The send is processed in a task of the nursery, so wait for it.
+		//
+		while *sum.borrow() != 10
+		{
+			Delay::new( Duration::from_millis(10) ).await;
+		}
+
+		assert_eq!( 10, addr.call( Show ).await.expect( "call Show" ) );
+
+
+		// The client has no services.
+		//
+		let to_client = remotes::LocalRemoteAddr::new( server.clone() );
+
+		assert_matches!
+		(
+			to_client.call( Add(1) ).await,
+			Err( PeerErr::Remote{ err: ConnectionError::UnknownService{..}, .. } )
+		);
+	});
+
+
+	client.close();
+
+	pool.run_until( async
+	{
+		assert_matches!( addr.call( Show ).await, Err( PeerErr::ConnectionClosed{..} ) );
+	});
+}
@@ -0,0 +1,81 @@
+// Tests:
+//
+// ✔ With manual dispatch, incoming sends and calls come out of the raw stream. Matching them by sid and
+//   answering calls through the wire sink of the peer works like a service map would.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { SinkExt                     } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn manual_dispatch()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let mut frames = peer.manual_dispatch();
+	let mut sink   = peer.wire_sink().expect( "get wire sink" );
+
+	let _server_handle = AsyncStd.spawn_handle( server_mb.start( peer ) ).expect( "start mailbox of Peer" );
+
+
+	// Our own dispatcher, a Sum without a service map.
+	//
+	let dispatch = async move
+	{
+		let mut total = 0;
+
+		while let Some( frame ) = frames.next().await
+		{
+			let frame = frame.expect( "incoming frame" );
+
+			if frame.sid() == <Add as remotes::Service>::sid()
+			{
+				let add: Add = serde_cbor::from_slice( frame.msg() ).expect( "deserialize Add" );
+
+				total += add.0;
+			}
+
+			else if frame.sid() == <Show as remotes::Service>::sid()
+			{
+				let mut resp = ThesWF::default();
+
+				resp.set_sid( ServiceID::full() );
+				resp.set_cid( frame.cid()       );
+
+				serde_cbor::to_writer( &mut resp, &total ).expect( "serialize response" );
+
+				sink.send( resp ).await.expect( "send response" );
+			}
+
+			else
+			{
+				panic!( "unexpected sid: {}", frame.sid() );
+			}
+		}
+	};
+
+	AsyncStd.spawn( dispatch ).expect( "spawn dispatcher" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.send( Add(5) ).await.expect( "send Add" );
+	addr.send( Add(2) ).await.expect( "send Add" );
+
+	assert_eq!( 7, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
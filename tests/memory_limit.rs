@@ -0,0 +1,256 @@
+// Tests:
+//
+// ✔ With a memory limit smaller than a frame, a flood of calls triggers MemoryLimitExceeded and the peer
+//   doesn't read further frames until the call being processed is done. Then the others get processed.
+// ✔ With close, exceeding the limit closes the connection.
+// ✔ With many outstanding calls under a small limit, the calls that don't fit in the response table are
+//   refused. Reading isn't blocked, so the others get their response.
+// ✔ A frame the decoder is still reading is charged to the limit for its full size.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { lock::Mutex as FutMutex     } ,
+	futures       :: { future::join_all            } ,
+	futures_timer :: { Delay                       } ,
+	std           :: { num::NonZeroUsize           } ,
+};
+
+
+// Each Add waits for the gate to open.
+//
+#[ derive( Actor ) ] struct Gated
+{
+	gate: Arc<FutMutex<()>> ,
+	sum : i64               ,
+}
+
+
+impl Handler<Add> for Gated
+{
+	fn handle( &mut self, msg: Add ) -> Return<'_, ()> { async move
+	{
+		let _open = self.gate.lock().await;
+
+		self.sum += msg.0;
+
+	}.boxed() }
+}
+
+
+impl Handler<Show> for Gated
+{
+	fn handle( &mut self, _msg: Show ) -> Return<'_, i64> { async move
+	{
+		self.sum
+
+	}.boxed() }
+}
+
+
+service_map!
+(
+	namespace  : gated     ;
+	wire_format: ThesWF    ;
+	services   : Add, Show ;
+);
+
+
+// A server with a memory limit of a single byte. Returns its address, events and the gate of the handler.
+//
+async fn server( socket: Endpoint, close: bool ) -> (Addr<Peer>, Events<PeerEvent>, Arc<FutMutex<()>>)
+{
+	let gate  = Arc::new( FutMutex::new(()) );
+	let gated = Addr::builder().start( Gated{ gate: gate.clone(), sum: 0 }, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = gated::Services::new();
+
+	sm.register_handler::<Add >( gated.clone_box() );
+	sm.register_handler::<Show>( gated.clone_box() );
+
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr.clone(), socket, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+	let evts     = peer.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	peer.register_services( Arc::new( sm ) );
+	peer.set_memory_limit( NonZeroUsize::new( 1 ).unwrap(), close );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	(server_addr, evts, gate)
+}
+
+
+
+#[async_std::test]
+//
+async fn stop_reading()
+{
+	let (server_end, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, mut evts, gate) = server( server_end, false ).await;
+	let closed = gate.lock().await;
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr  = gated::RemoteAddr::new( client_addr.clone() );
+	let mut addr2 = addr.clone();
+	let mut addr3 = addr.clone();
+
+	let calls = join3( addr.call( Add(1) ), addr2.call( Add(2) ), addr3.call( Add(3) ) );
+
+	let mut one = 0;
+
+	let check = async
+	{
+		let evt = evts.wait_for( |e| matches!( e, PeerEvent::MemoryLimitExceeded{..} ) ).await.expect( "limit event" );
+
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		// Only the first frame has been read, the others wait in the connection.
+		//
+		one = server_addr.call( GetStatus ).await.expect( "get status" ).bytes_in;
+
+		assert_eq!( PeerEvent::MemoryLimitExceeded{ used: one as usize, limit: 1 }, evt );
+
+		drop( closed );
+	};
+
+	let ((a, b, c), _) = join( calls, check ).await;
+
+	a.expect( "call Add" );
+	b.expect( "call Add" );
+	c.expect( "call Add" );
+
+	assert_eq!( 3 * one, server_addr.call( GetStatus ).await.expect( "get status" ).bytes_in );
+	assert_eq!( 6      , addr.call( Show ).await.expect( "call Show" )                        );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn close()
+{
+	let (server_end, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, mut evts, gate) = server( server_end, true ).await;
+	let _closed = gate.lock().await;
+
+	let (client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = gated::RemoteAddr::new( client_addr );
+
+	addr.send( Add(1) ).await.expect( "send Add" );
+
+	evts.wait_for( |e| matches!( e, PeerEvent::MemoryLimitExceeded{..} ) ).await.expect( "limit event" );
+
+	let evt = evts.wait_for( |e| matches!( e, PeerEvent::Closed(_) ) ).await.expect( "closed event" );
+
+	assert_eq!( PeerEvent::Closed( CloseReason::MemoryLimit ), evt );
+}
+
+
+
+#[async_std::test]
+//
+async fn outgoing_calls()
+{
+	let (server_end, client_end) = Endpoint::pair( 64, 64 );
+
+	let gate   = Arc::new( FutMutex::new(()) );
+	let closed = gate.lock().await;
+	let gated  = Addr::builder().start( Gated{ gate: gate.clone(), sum: 0 }, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = gated::Services::new();
+
+	sm.register_handler::<Add >( gated.clone_box() );
+	sm.register_handler::<Show>( gated.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server_end, Arc::new( sm ), AsyncStd, "server" ).await;
+
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut peer = Peer::from_async_read( client_addr.clone(), client_end, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.set_memory_limit( NonZeroUsize::new( 1024 ).unwrap(), false );
+
+	AsyncStd.spawn( async{ client_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut addr   = gated::RemoteAddr::new( client_addr.clone() );
+	let mut status = client_addr.clone();
+
+	let calls = join_all( (0..50).map( |_|
+	{
+		let mut addr = addr.clone();
+
+		async move { addr.call( Add(1) ).await }
+	}));
+
+	let mut open = 0;
+
+	let check = async
+	{
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		open = status.call( GetStatus ).await.expect( "get status" ).open_calls;
+
+		assert!( open > 0  );
+		assert!( open < 50 );
+
+		drop( closed );
+	};
+
+	let (results, _) = join( calls, check ).await;
+
+	let mut done = 0;
+
+	for result in results
+	{
+		match result
+		{
+			Ok(()) => done += 1,
+			Err( PeerErr::WireFormat{ source: WireErr::OutOfMemory{..}, .. } ) => {}
+			Err( e ) => panic!( "expected OutOfMemory, got: {:?}", e ),
+		}
+	}
+
+	assert_eq!( open, done );
+
+	// The table is empty again, so calls go through.
+	//
+	assert_eq!( done as i64, addr.call( Show ).await.expect( "call Show" ) );
+	assert_eq!( 0, client_addr.call( GetStatus ).await.expect( "get status" ).open_calls );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn partial_frame()
+{
+	let (server_end, mut client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, _evts, _gate) = server( server_end, false ).await;
+
+	assert_eq!( Some( 0 ), server_addr.call( GetStatus ).await.expect( "get status" ).memory_used );
+
+	// The length of a frame of 500 bytes, but only the first 100 bytes after it.
+	//
+	client.write_all( &500u64.to_le_bytes() ).await.expect( "write length"  );
+	client.write_all( &[ 0u8; 100 ]         ).await.expect( "write payload" );
+
+	Delay::new( Duration::from_millis( 50 ) ).await;
+
+	assert_eq!( Some( 500 ), server_addr.call( GetStatus ).await.expect( "get status" ).memory_used );
+}
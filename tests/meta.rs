@@ -0,0 +1,72 @@
+// Tests:
+//
+// ✔ A handler registered with register_with_meta gets the headers of a call made with call_with_meta.
+// ✔ A call without headers gives the handler none.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	serde  :: { Serialize, Deserialize      } ,
+};
+
+
+#[ derive( Actor ) ] struct Echo;
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Ask;
+
+// Returns the headers the handler saw.
+//
+impl Message for Ask { type Return = Metadata; }
+
+
+impl Handler< WithMeta<Ask> > for Echo
+{
+	#[async_fn] fn handle( &mut self, msg: WithMeta<Ask> ) -> Metadata
+	{
+		msg.meta
+	}
+}
+
+
+service_map!
+(
+	namespace  : meta   ;
+	wire_format: ThesWF ;
+	services   : Ask    ;
+);
+
+
+
+#[async_std::test]
+//
+async fn headers()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let echo   = Addr::builder().start( Echo, &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sm = meta::Services::new();
+
+	sm.register_with_meta::<Ask>( echo.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = meta::RemoteAddr::new( client_addr.clone() );
+
+	let mut headers = Metadata::new();
+
+	headers.insert( "authorization".to_string(), "Bearer abc".to_string() );
+	headers.insert( "tenant"       .to_string(), "acme"      .to_string() );
+
+	let seen = addr.call_with_meta( Ask, headers ).await.expect( "call Ask" );
+
+	assert_eq!( 2, seen.len() );
+	assert_eq!( Some( "Bearer abc" ), seen.get( "authorization" ).map( String::as_str ) );
+	assert_eq!( Some( "acme"       ), seen.get( "tenant"        ).map( String::as_str ) );
+
+	assert!( addr.call( Ask ).await.expect( "call Ask" ).is_empty() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,120 @@
+// Tests:
+//
+// ✔ GetOpenCalls filtered by sid only returns the cids of the pending calls to that service.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { lock::Mutex as FutMutex     } ,
+	futures_timer :: { Delay                       } ,
+};
+
+
+// Each Add waits for the gate to open. Show waits behind it in the mailbox.
+//
+#[ derive( Actor ) ] struct Gated
+{
+	gate: Arc<FutMutex<()>> ,
+	sum : i64               ,
+}
+
+
+impl Handler<Add> for Gated
+{
+	fn handle( &mut self, msg: Add ) -> Return<'_, ()> { async move
+	{
+		let _open = self.gate.lock().await;
+
+		self.sum += msg.0;
+
+	}.boxed() }
+}
+
+
+impl Handler<Show> for Gated
+{
+	fn handle( &mut self, _msg: Show ) -> Return<'_, i64> { async move
+	{
+		self.sum
+
+	}.boxed() }
+}
+
+
+service_map!
+(
+	namespace  : gated      ;
+	wire_format: ThesWF     ;
+	services   : Add, Show  ;
+);
+
+
+
+#[async_std::test]
+//
+async fn filter_by_sid()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let gate   = Arc::new( FutMutex::new(()) );
+	let closed = gate.lock().await;
+
+	let gated = Addr::builder().start( Gated{ gate: gate.clone(), sum: 0 }, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = gated::Services::new();
+
+	sm.register_handler::<Add >( gated.clone_box() );
+	sm.register_handler::<Show>( gated.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _             ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let addr = gated::RemoteAddr::new( client_addr.clone() );
+
+
+	let calls = async
+	{
+		let mut a = addr.clone();
+		let mut b = addr.clone();
+		let mut c = addr.clone();
+
+		join3( a.call( Add(1) ), b.call( Add(2) ), c.call( Show ) ).await
+	};
+
+	let query = async
+	{
+		// Let the calls go out.
+		//
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		let add_sid  = Some( <Add  as gated::Service>::sid() );
+		let show_sid = Some( <Show as gated::Service>::sid() );
+
+		let all  = client_addr.call( GetOpenCalls{ sid: None     } ).await.expect( "get open calls" );
+		let add  = client_addr.call( GetOpenCalls{ sid: add_sid  } ).await.expect( "get open calls" );
+		let show = client_addr.call( GetOpenCalls{ sid: show_sid } ).await.expect( "get open calls" );
+
+		drop( closed );
+
+		(all, add, show)
+	};
+
+
+	let ( (a, b, c), (all, add, show) ) = join( calls, query ).await;
+
+	a.expect( "call Add" );
+	b.expect( "call Add" );
+
+	assert_eq!( 3, c.expect( "call Show" ) );
+
+	assert_eq!( 3, all .len() );
+	assert_eq!( 2, add .len() );
+	assert_eq!( 1, show.len() );
+
+	assert!( !add.contains( &show[0] ) );
+	assert!( add.iter().chain( &show ).all( |cid| all.contains( cid ) ) );
+
+	assert!( client_addr.call( GetOpenCalls::default() ).await.expect( "get open calls" ).is_empty() );
+}
@@ -0,0 +1,98 @@
+// Tests:
+//
+// ✔ A PassThroughMap handles Add with the local service map and passes a send for a service it doesn't
+//   know through to the downstream handler, with the payload untouched.
+//
+mod common;
+
+use
+{
+	common      :: { *, import::{ *, assert_eq } } ,
+	parking_lot :: { Mutex                       } ,
+	serde       :: { Serialize, Deserialize      } ,
+};
+
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Ping( String );
+
+impl Message for Ping { type Return = (); }
+
+
+service_map!
+(
+	namespace  : proxied ;
+	wire_format: ThesWF  ;
+	services   : Ping    ;
+);
+
+
+// Keeps the frames passed through to it.
+//
+#[ derive( Actor, Default ) ] struct Capture
+{
+	frames: Arc<Mutex< Vec<ThesWF> >>,
+}
+
+
+impl Handler<ThesWF> for Capture
+{
+	#[async_fn] fn handle( &mut self, frame: ThesWF )
+	{
+		self.frames.lock().push( frame );
+	}
+}
+
+
+impl Handler< Call<ThesWF> > for Capture
+{
+	#[async_fn] fn handle( &mut self, _call: Call<ThesWF> ) -> <Call<ThesWF> as Message>::Return
+	{
+		Err( PeerErr::NoHandler{ ctx: PeerErrCtx::default() } )
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn local_and_pass_through()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let capture = Capture::default();
+	let frames  = capture.frames.clone();
+
+	let downstream              = Addr::builder().start( capture, &AsyncStd ).expect( "spawn capture" );
+	let handler: Box<dyn Relay> = Box::new( downstream );
+	let ping_sid                = <Ping as proxied::Service>::sid();
+
+	let sm = PassThroughMap::new( Arc::new( add_show_sum() ), handler.into(), vec![ ping_sid ] );
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _             ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut local = remotes::RemoteAddr::new( client_addr.clone() );
+	let mut ping  = proxied::RemoteAddr::new( client_addr.clone() );
+
+
+	local.call( Add(5) ).await.expect( "call Add" );
+	ping.send( Ping( "opaque".to_string() ) ).await.expect( "send Ping" );
+
+	assert_eq!( 5, local.call( Show ).await.expect( "call Show" ) );
+
+
+	// The send is processed concurrently with the call to Show.
+	//
+	while frames.lock().is_empty()
+	{
+		futures_timer::Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+	let frame = frames.lock()[0].clone();
+	let ping: Ping = serde_cbor::from_slice( frame.msg() ).expect( "deserialize Ping" );
+
+	assert_eq!( ping_sid, frame.sid() );
+	assert_eq!( "opaque", ping.0      );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
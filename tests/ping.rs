@@ -0,0 +1,41 @@
+// Tests:
+//
+// ✔ Pinging a connected peer returns a small round trip time.
+// ✔ Pinging over a closed connection fails.
+//
+mod common;
+
+use common::{ *, import::* };
+
+
+
+#[async_std::test]
+//
+async fn ping()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _             ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let rtt = Peer::ping( &mut client_addr ).await.expect( "ping" );
+
+	assert!( rtt > Duration::from_secs( 0 ) );
+	assert!( rtt < Duration::from_secs( 1 ) );
+}
+
+
+
+#[async_std::test]
+//
+async fn ping_closed()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen ( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _             ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	client_addr.call( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	assert_matches!( Peer::ping( &mut client_addr ).await, Err( PeerErr::ConnectionClosed{..} ) );
+}
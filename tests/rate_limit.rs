@@ -0,0 +1,77 @@
+// Tests:
+//
+// ✔ Sends on a RemoteAddr with a rate limit are paced, but all arrive in order.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq }                 } ,
+	futures :: { channel::mpsc::{ unbounded, UnboundedSender } } ,
+	serde   :: { Serialize, Deserialize                      } ,
+	std     :: { num::NonZeroU32, time::Instant              } ,
+};
+
+
+#[ derive( Actor ) ] struct Collector( UnboundedSender<usize> );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Frame( usize );
+
+impl Message for Frame { type Return = (); }
+
+
+impl Handler< Frame > for Collector
+{
+	#[async_fn] fn handle( &mut self, msg: Frame )
+	{
+		self.0.unbounded_send( msg.0 ).expect( "send frame" );
+	}
+}
+
+
+service_map!
+(
+	namespace  : rate   ;
+	wire_format: ThesWF ;
+	services   : Frame  ;
+);
+
+
+
+#[async_std::test]
+//
+async fn paced_sends()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+	let (tx, rx)         = unbounded();
+
+	let collector = Addr::builder().start( Collector( tx ), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = rate::Services::new();
+	sm.register_handler::<Frame>( collector.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = rate::RemoteAddr::new( client_addr.clone() );
+	addr.set_rate_limit( RateLimit::new( NonZeroU32::new( 10 ).unwrap() ) );
+
+	let start = Instant::now();
+
+	for i in 0..30
+	{
+		addr.send( Frame( i ) ).await.expect( "send Frame" );
+	}
+
+	let received: Vec<usize> = rx.take( 30 ).collect().await;
+	let elapsed              = start.elapsed();
+
+	assert_eq!( (0..30).collect::<Vec<_>>(), received );
+
+	// The first frame goes out right away, the other 29 wait 100ms each.
+	//
+	assert!( elapsed >= Duration::from_millis( 2800 ), "too fast: {:?}", elapsed );
+	assert!( elapsed <  Duration::from_millis( 3500 ), "too slow: {:?}", elapsed );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
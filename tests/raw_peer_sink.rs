@@ -0,0 +1,104 @@
+// Tests:
+//
+// ✔ A captured frame replayed through RawPeerSink is processed like the original, both as send and as call.
+// ✔ The wire sink of a peer, and its clones, deliver raw frames to the remote.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq }               } ,
+	std     :: { io                                        } ,
+	futures :: { channel::mpsc::unbounded, stream, SinkExt } ,
+};
+
+
+
+// Capture the frame a RemoteAddr produces for Add(5), by giving the peer a channel as its connection.
+//
+async fn capture_add() -> ThesWF
+{
+	let (tx, mut rx) = unbounded();
+	let (addr, mb)   = Addr::builder().name( "capture".into() ).build();
+
+	let incoming = stream::pending::<Result<ThesWF, WireErr>>();
+	let outgoing = tx.sink_map_err( |_| WireErr::from( io::Error::from( io::ErrorKind::BrokenPipe ) ) );
+
+	let peer = Peer::new( addr.clone(), incoming, outgoing, AsyncStd, None, None ).expect( "spawn peer" );
+
+	AsyncStd.spawn( async{ mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	remotes::RemoteAddr::new( addr ).send( Add(5) ).await.expect( "send Add" );
+
+	rx.next().await.expect( "captured frame" )
+}
+
+
+
+#[async_std::test]
+//
+async fn replay()
+{
+	let frame = capture_add().await;
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut raw  = RawPeerSink::new( client_addr.clone() );
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+
+	raw.send( frame.clone() ).await.expect( "replay send" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+
+	let resp = raw.call( frame.clone() ).await.expect( "replay call" );
+
+	serde_cbor::from_slice::<()>( resp.msg() ).expect( "deserialize response" );
+
+	assert_eq!( 10, addr.call( Show ).await.expect( "call Show" ) );
+
+
+	// The same as a normal call.
+	//
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 15, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn wire_sink()
+{
+	let frame = capture_add().await;
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let client = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let mut sink  = client.wire_sink().expect( "get wire sink" );
+	let mut clone = sink.clone();
+
+	AsyncStd.spawn( async{ client_mb.start( client ).await; } ).expect( "start mailbox of Peer" );
+
+
+	sink .send( frame.clone() ).await.expect( "send raw frame"          );
+	clone.send( frame         ).await.expect( "send raw frame on clone" );
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( 10, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,68 @@
+// Tests:
+//
+// ✔ A handler returning a RawResponse has its bytes delivered as is and the caller can decode them.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	serde  :: { Serialize, Deserialize      } ,
+};
+
+
+// Holds a blob in serialized form.
+//
+#[ derive( Actor ) ] struct Cache( RawResponse );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Get;
+
+impl Message for Get { type Return = RawResponse; }
+
+
+impl Handler< Get > for Cache
+{
+	#[async_fn] fn handle( &mut self, _msg: Get ) -> RawResponse
+	{
+		self.0.clone()
+	}
+}
+
+
+service_map!
+(
+	namespace  : raw    ;
+	wire_format: ThesWF ;
+	services   : Get    ;
+);
+
+
+
+#[async_std::test]
+//
+async fn raw_response()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let blob   = vec![ "cached".to_string(), "blob".to_string() ];
+	let cached = RawResponse::encode( &blob ).expect( "encode blob" );
+	let cache  = Addr::builder().start( Cache( cached.clone() ), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = raw::Services::new();
+	sm.register_handler::<Get>( cache.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = raw::RemoteAddr::new( client_addr.clone() );
+
+	let resp = addr.call( Get ).await.expect( "call Get" );
+
+	// If the server had serialized the RawResponse through serde, it would have been wrapped
+	// in a CBOR byte string and the bytes would differ.
+	//
+	assert_eq!( cached, resp );
+	assert_eq!( blob  , resp.decode::<Vec<String>>().expect( "decode blob" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,71 @@
+// Tests:
+//
+// ✔ A service registered with an async closure responds to calls.
+// ✔ Sends to a service registered with an async closure reach the closure.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }                 } ,
+	futures_timer :: { Delay                                       } ,
+	std           :: { sync::atomic::{ AtomicI64, Ordering::SeqCst } } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let mut sm = remotes::Services::new();
+
+	sm.register_fn::<Show, _, _>( |_| async { 42 } );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	assert_eq!( 42, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn send()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let total  = Arc::new( AtomicI64::new( 0 ) );
+	let total2 = total.clone();
+	let mut sm = remotes::Services::new();
+
+	sm.register_fn::<Add, _, _>( move |msg|
+	{
+		let total = total2.clone();
+		async move { total.fetch_add( msg.0, SeqCst ); }
+	});
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.send( Add( 5 ) ).await.expect( "send Add" );
+	addr.send( Add( 2 ) ).await.expect( "send Add" );
+
+	while total.load( SeqCst ) < 7
+	{
+		Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+	assert_eq!( 7, total.load( SeqCst ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
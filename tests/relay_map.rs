@@ -3,12 +3,63 @@
 // - ... a bunch of stuff to be written ...
 // ✔ use with addr -> already tested in relay.rs
 // ✔ test a load balancing scenario
+// ✔ route on the content of the payload with ServiceHandler::Route
+// ✔ ServiceHandler::Sticky sends all calls of a consumer connection to the same backend
+// ✔ RelayMapBuilder relays each group of services to its own backend
+// ✔ a service listed twice in a group is kept once, a service in two groups makes build fail
+// ✔ concurrent calls over a RelayPool get spread over the connections
+// ✔ live_services excludes the services of a dead backend, services still lists them
+// ✔ drain waits for a relayed call in flight, its response still reaches the consumer, later calls are refused
+//   and later sends are dropped without taking down the relay
+// ✔ drain_backend sends new calls of a RelayPool to the other connections, remove_backend refuses while
+//   a call to the backend is in flight and succeeds once it's done. Once all connections are draining,
+//   calls are refused and sends are dropped without taking down the relay
 
 
 mod common;
 
 use common::*                       ;
 use common::import::{ *, assert_eq };
+use futures_timer::Delay            ;
+use serde::{ Serialize, Deserialize };
+use std::collections::HashMap       ;
+
+
+#[ derive( Actor ) ] struct Counter( usize );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Slow;
+#[ derive( Serialize, Deserialize, Debug ) ] struct Count;
+
+impl Message for Slow  { type Return = ();    }
+impl Message for Count { type Return = usize; }
+
+
+impl Handler< Slow > for Counter
+{
+	#[async_fn] fn handle( &mut self, _msg: Slow )
+	{
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		self.0 += 1;
+	}
+}
+
+
+impl Handler< Count > for Counter
+{
+	#[async_fn] fn handle( &mut self, _msg: Count ) -> usize
+	{
+		self.0
+	}
+}
+
+
+service_map!
+(
+	namespace  : pool        ;
+	wire_format: ThesWF      ;
+	services   : Slow, Count ;
+);
 
 
 // Test relaying messages
@@ -79,6 +130,397 @@ async fn relay_once_load_balance()
 
 
 
+// Route Add messages to a backend based on the first byte of the payload. Add(1) and Add(2) have
+// the same sid but serialize to different bytes.
+//
+#[async_std::test]
+//
+async fn route_on_payload()
+{
+	let (ab, ba) = Endpoint::pair( 64, 64 );
+	let (ac, ca) = Endpoint::pair( 64, 64 );
+	let (rc, cr) = Endpoint::pair( 64, 64 );
+
+	let mut sum_b = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sum_c = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let sm = |sum: &Addr<Sum>|
+	{
+		let mut sm = remotes::Services::new();
+		sm.register_handler::<Add>( sum.clone_box() );
+		Arc::new( sm )
+	};
+
+	// Keep the join handles, dropping them would stop the peers.
+	//
+	let (_, _, _handle_b) = peer_listen( ba, sm( &sum_b ), AsyncStd, "backend_b" ).await;
+	let (_, _, _handle_c) = peer_listen( ca, sm( &sum_c ), AsyncStd, "backend_c" ).await;
+
+	let (mut to_b, _) = peer_connect( ab, AsyncStd, "relay_to_b" ).await;
+	let (mut to_c, _) = peer_connect( ac, AsyncStd, "relay_to_c" ).await;
+
+	let (b, c) = (to_b.clone(), to_c.clone());
+
+	let route: RouteClosure = Box::new( move |wf: &ThesWF| -> Box<dyn Relay>
+	{
+		match wf.msg().first()
+		{
+			Some( 1 ) => Box::new( b.clone() ),
+			_         => Box::new( c.clone() ),
+		}
+	});
+
+	let rm = RelayMap::new( ServiceHandler::Route( route ), vec![ <Add as remotes::Service>::sid() ] );
+
+	let (mut relay, _, _handle_r) = peer_listen( rc, Arc::new( rm ), AsyncStd, "relay" ).await;
+	let (mut consumer, _) = peer_connect( cr, AsyncStd, "consumer" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( consumer.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(1) ).await );
+	assert_eq!( Ok(()), addr.call( Add(2) ).await );
+	assert_eq!( Ok(()), addr.call( Add(1) ).await );
+
+	assert_eq!( 2, sum_b.call( Show ).await.expect( "call sum_b" ) );
+	assert_eq!( 2, sum_c.call( Show ).await.expect( "call sum_c" ) );
+
+	consumer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	relay   .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_b    .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_c    .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Two consumers connect to the same RelayMap. Each gets its own backend, assigned on its first call.
+//
+#[async_std::test]
+//
+async fn sticky_per_connection()
+{
+	let (ab, ba) = Endpoint::pair( 64, 64 );
+	let (ac, ca) = Endpoint::pair( 64, 64 );
+	let (r1, c1) = Endpoint::pair( 64, 64 );
+	let (r2, c2) = Endpoint::pair( 64, 64 );
+
+	let mut sum_b = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sum_c = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let sm = |sum: &Addr<Sum>|
+	{
+		let mut sm = remotes::Services::new();
+		sm.register_handler::<Add>( sum.clone_box() );
+		Arc::new( sm )
+	};
+
+	let (_, _, _handle_b) = peer_listen( ba, sm( &sum_b ), AsyncStd, "backend_b" ).await;
+	let (_, _, _handle_c) = peer_listen( ca, sm( &sum_c ), AsyncStd, "backend_c" ).await;
+
+	let (mut to_b, _) = peer_connect( ab, AsyncStd, "relay_to_b" ).await;
+	let (mut to_c, _) = peer_connect( ac, AsyncStd, "relay_to_c" ).await;
+
+	let backends = vec![ to_b.clone(), to_c.clone() ];
+	let assigned = Arc::new( parking_lot::Mutex::new( HashMap::<usize, usize>::new() ) );
+	let seen     = assigned.clone();
+
+	let sticky: StickyClosure = Box::new( move |_: &ServiceID, peer_id: Option<usize>| -> Box<dyn Relay>
+	{
+		let mut assigned = seen.lock();
+		let next         = assigned.len();
+		let idx          = *assigned.entry( peer_id.expect( "peer id" ) ).or_insert( next );
+
+		Box::new( backends[ idx ].clone() )
+	});
+
+	let rm = Arc::new( RelayMap::new( ServiceHandler::Sticky( sticky ), vec![ <Add as remotes::Service>::sid() ] ) );
+
+	let (mut relay1, _, _handle_r1) = peer_listen( r1, rm.clone(), AsyncStd, "relay1" ).await;
+	let (mut relay2, _, _handle_r2) = peer_listen( r2, rm        , AsyncStd, "relay2" ).await;
+
+	let (mut consumer1, _) = peer_connect( c1, AsyncStd, "consumer1" ).await;
+	let (mut consumer2, _) = peer_connect( c2, AsyncStd, "consumer2" ).await;
+
+	let mut addr1 = remotes::RemoteAddr::new( consumer1.clone() );
+	let mut addr2 = remotes::RemoteAddr::new( consumer2.clone() );
+
+	assert_eq!( Ok(()), addr1.call( Add( 1) ).await );
+	assert_eq!( Ok(()), addr2.call( Add(10) ).await );
+	assert_eq!( Ok(()), addr1.call( Add( 1) ).await );
+	assert_eq!( Ok(()), addr2.call( Add(10) ).await );
+
+	assert_eq!( 2, assigned.lock().len() );
+
+	assert_eq!(  2, sum_b.call( Show ).await.expect( "call sum_b" ) );
+	assert_eq!( 20, sum_c.call( Show ).await.expect( "call sum_c" ) );
+
+	consumer1.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	consumer2.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	relay1   .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	relay2   .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_b     .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_c     .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// Add goes to backend b, Sub to backend c, through a single map.
+//
+#[async_std::test]
+//
+async fn builder_groups()
+{
+	let (ab, ba) = Endpoint::pair( 64, 64 );
+	let (ac, ca) = Endpoint::pair( 64, 64 );
+	let (rc, cr) = Endpoint::pair( 64, 64 );
+
+	let mut sum_b = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sum_c = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let sm = |sum: &Addr<Sum>|
+	{
+		let mut sm = remotes::Services::new();
+		sm.register_handler::<Add>( sum.clone_box() );
+		sm.register_handler::<Sub>( sum.clone_box() );
+		Arc::new( sm )
+	};
+
+	let (_, _, _handle_b) = peer_listen( ba, sm( &sum_b ), AsyncStd, "backend_b" ).await;
+	let (_, _, _handle_c) = peer_listen( ca, sm( &sum_c ), AsyncStd, "backend_c" ).await;
+
+	let (mut to_b, _) = peer_connect( ab, AsyncStd, "relay_to_b" ).await;
+	let (mut to_c, _) = peer_connect( ac, AsyncStd, "relay_to_c" ).await;
+
+	let add = <Add as remotes::Service>::sid();
+	let sub = <Sub as remotes::Service>::sid();
+
+	let rm = RelayMapBuilder::new()
+
+		.group( ServiceHandler::Address( Box::new( to_b.clone() ) ), vec![ add ] )
+		.group( ServiceHandler::Address( Box::new( to_c.clone() ) ), vec![ sub ] )
+		.build()
+		.expect( "build relay map" )
+	;
+
+	assert_eq!( vec![ add, sub ], rm.services().copied().collect::<Vec<_>>() );
+
+	let (mut relay, _, _handle_r) = peer_listen( rc, Arc::new( rm ), AsyncStd, "relay" ).await;
+	let (mut consumer, _) = peer_connect( cr, AsyncStd, "consumer" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( consumer.clone() );
+
+	assert_eq!( Ok(()), addr.call( Add(5) ).await );
+	assert_eq!( Ok(()), addr.call( Sub(3) ).await );
+
+	assert_eq!(  5, sum_b.call( Show ).await.expect( "call sum_b" ) );
+	assert_eq!( -3, sum_c.call( Show ).await.expect( "call sum_c" ) );
+
+	consumer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	relay   .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_b    .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_c    .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A service listed twice in a group is only kept once, but a service in two groups is an error.
+//
+#[async_std::test]
+//
+async fn builder_overlap()
+{
+	let (ab, ba) = Endpoint::pair( 64, 64 );
+
+	let sum = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = remotes::Services::new();
+	sm.register_handler::<Add>( sum.clone_box() );
+
+	let (_, _, _handle) = peer_listen( ba, Arc::new( sm ), AsyncStd, "backend" ).await;
+
+	let (mut to_b, _) = peer_connect( ab, AsyncStd, "relay_to_b" ).await;
+
+	let add = <Add as remotes::Service>::sid();
+	let sub = <Sub as remotes::Service>::sid();
+
+	let rm = RelayMap::new( ServiceHandler::Address( Box::new( to_b.clone() ) ), vec![ add, add, sub ] );
+
+	assert_eq!( vec![ add, sub ], rm.services().copied().collect::<Vec<_>>() );
+
+	let overlap = RelayMapBuilder::new()
+
+		.group( ServiceHandler::Address( Box::new( to_b.clone() ) ), vec![ add, sub ] )
+		.group( ServiceHandler::Address( Box::new( to_b.clone() ) ), vec![ sub ] )
+		.build()
+	;
+
+	assert_eq!( Some( OverlappingGroups{ sid: sub } ), overlap.err() );
+
+	to_b.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+// With 3 connections and calls that take a while, LeastBusy should use every connection in turn.
+//
+#[async_std::test]
+//
+async fn pool_least_busy()
+{
+	let mut counters = Vec::new();
+	let mut conns    = Vec::new();
+	let mut handles  = Vec::new();
+
+	for i in 0..3
+	{
+		let (ab, ba) = Endpoint::pair( 64, 64 );
+
+		let counter = Addr::builder().start( Counter(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+		let mut sm = pool::Services::new();
+		sm.register_handler::<Slow>( counter.clone_box() );
+
+		let (_, _, handle) = peer_listen ( ba, Arc::new( sm ), AsyncStd, &format!( "backend_{}", i ) ).await;
+		let (conn, _     ) = peer_connect( ab, AsyncStd, &format!( "relay_to_backend_{}", i ) ).await;
+
+		counters.push( counter );
+		conns   .push( conn    );
+		handles .push( handle  );
+	}
+
+	let relays: Vec<Box<dyn Relay>> = conns.iter().map( |c| Box::new( c.clone() ) as Box<dyn Relay> ).collect();
+	let rm                          = RelayMap::new( RelayPool::new( relays, PoolPolicy::LeastBusy ).into(), vec![ <Slow as pool::Service>::sid() ] );
+
+	let (rc, cr) = Endpoint::pair( 64, 64 );
+
+	let (mut relay, _, _relay_handle) = peer_listen ( rc, Arc::new( rm ), AsyncStd, "relay" ).await;
+	let (mut consumer, _           ) = peer_connect( cr, AsyncStd, "consumer" ).await;
+
+	let addr = pool::RemoteAddr::new( consumer.clone() );
+
+	let calls = (0..6).map( |_|
+	{
+		let mut addr = addr.clone();
+
+		async move { addr.call( Slow ).await }
+	});
+
+	for res in futures::future::join_all( calls ).await
+	{
+		assert_eq!( Ok(()), res );
+	}
+
+	for counter in &mut counters
+	{
+		assert_eq!( 2, counter.call( Count ).await.expect( "call counter" ) );
+	}
+
+	consumer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	relay   .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	for mut conn in conns
+	{
+		conn.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn drain()
+{
+	let (ab, ba) = Endpoint::pair( 64, 64 );
+	let (rc, cr) = Endpoint::pair( 64, 64 );
+
+	let mut counter = Addr::builder().start( Counter(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = pool::Services::new();
+	sm.register_handler::<Slow>( counter.clone_box() );
+
+	let (_, _, _backend_handle) = peer_listen ( ba, Arc::new( sm ), AsyncStd, "backend" ).await;
+	let (mut to_backend, _    ) = peer_connect( ab, AsyncStd, "relay_to_backend" ).await;
+
+	let handler: Box<dyn Relay> = Box::new( to_backend.clone() );
+	let rm                      = Arc::new( RelayMap::new( handler.into(), vec![ <Slow as pool::Service>::sid() ] ) );
+
+	let (mut relay, mut relay_evts, _relay_handle) = peer_listen ( rc, rm.clone(), AsyncStd, "relay" ).await;
+	let (mut consumer, _                        ) = peer_connect( cr, AsyncStd, "consumer" ).await;
+
+	let mut addr  = pool::RemoteAddr::new( consumer.clone() );
+	let mut addr2 = addr.clone();
+
+	// Slow takes 50ms on the backend, so it's still in flight when we start draining.
+	//
+	let drain = async
+	{
+		Delay::new( Duration::from_millis( 20 ) ).await;
+
+		rm.drain().await;
+
+		// The call finished before drain resolved.
+		//
+		assert_eq!( 1, counter.call( Count ).await.expect( "call counter" ) );
+	};
+
+	let (res, _) = join( addr.call( Slow ), drain ).await;
+
+	assert_eq!( Ok(()), res );
+	assert!( rm.is_draining() );
+
+	assert_matches!( addr2.call( Slow ).await, Err( PeerErr::ShuttingDown{..} ) );
+
+	// A send is refused on the relay, which keeps running.
+	//
+	addr2.send( Slow ).await.expect( "send Slow" );
+
+	relay_evts.wait_for( |e| matches!( e, PeerEvent::Error( err @ PeerErr::ShuttingDown{..} ) if err.ctx().cid.is_none() ) )
+
+		.await.expect( "refused send" );
+
+	assert_matches!( addr2.call( Slow ).await, Err( PeerErr::ShuttingDown{..} ) );
+	assert_eq!( 1, counter.call( Count ).await.expect( "call counter" ) );
+
+	consumer  .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	relay     .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	to_backend.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn live_services()
+{
+	let (_ab, cx) = Endpoint::pair( 64, 64 );
+
+	let (alive, mb) = Addr::builder().name( "alive".into() ).build();
+	let peer        = Peer::from_async_read( alive.clone(), cx, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	AsyncStd.spawn( mb.start(peer).map(|_|()) ).expect( "Start mailbox of Peer" );
+
+
+	// A backend that is no longer running.
+	//
+	let (dead, mb) = Addr::<Peer>::builder().name( "dead".into() ).build();
+	drop( mb );
+
+
+	let add  = <Add  as remotes::Service>::sid();
+	let show = <Show as remotes::Service>::sid();
+
+	let route: RelayClosure = Box::new( move |sid: &ServiceID| -> Box<dyn Relay>
+	{
+		if *sid == add { Box::new( alive.clone() ) }
+		else           { Box::new( dead .clone() ) }
+	});
+
+	let rm = RelayMap::new( ServiceHandler::Closure( route ), vec![ add, show ] );
+
+	assert_eq!( vec![ add       ], rm.live_services()                          );
+	assert_eq!( vec![ add, show ], rm.services().copied().collect::<Vec<_>>() );
+}
+
+
+
 // Test debug implementation.
 // Fixes the output of the debug implementation. Mainly, this fixes the sid impl. If sid's change,
 // that would be a breaking change, because people might be counting on them, especially if there
@@ -122,3 +564,89 @@ async fn debug()
 
 	assert_eq!( txt, format!( "{:?}", rm ) );
 }
+
+
+
+#[async_std::test]
+//
+async fn drain_backend()
+{
+	let mut counters = Vec::new();
+	let mut conns    = Vec::new();
+	let mut handles  = Vec::new();
+
+	for i in 0..2
+	{
+		let (ab, ba) = Endpoint::pair( 64, 64 );
+
+		let counter = Addr::builder().start( Counter(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+		let mut sm = pool::Services::new();
+		sm.register_handler::<Slow>( counter.clone_box() );
+
+		let (_, _, handle) = peer_listen ( ba, Arc::new( sm ), AsyncStd, &format!( "backend_{}", i ) ).await;
+		let (conn, _     ) = peer_connect( ab, AsyncStd, &format!( "relay_to_backend_{}", i ) ).await;
+
+		counters.push( counter );
+		conns   .push( conn    );
+		handles .push( handle  );
+	}
+
+	let relays: Vec<Box<dyn Relay>> = conns.iter().map( |c| Box::new( c.clone() ) as Box<dyn Relay> ).collect();
+	let rm                          = Arc::new( RelayMap::new( RelayPool::new( relays, PoolPolicy::RoundRobin ).into(), vec![ <Slow as pool::Service>::sid() ] ) );
+
+	let (rc, cr) = Endpoint::pair( 64, 64 );
+
+	let (mut relay, mut relay_evts, _relay_handle) = peer_listen ( rc, rm.clone(), AsyncStd, "relay" ).await;
+	let (mut consumer, _                        ) = peer_connect( cr, AsyncStd, "consumer" ).await;
+
+	let mut addr  = pool::RemoteAddr::new( consumer.clone() );
+	let mut addr2 = addr.clone();
+	let drained   = conns[0].id();
+
+	// RoundRobin sends the first call to the first backend. Slow takes 50ms, so it's still in flight
+	// when we drain.
+	//
+	let maintenance = async
+	{
+		Delay::new( Duration::from_millis( 20 ) ).await;
+
+		assert!( rm.drain_backend( drained ) );
+		assert_eq!( Err( RemoveBackendErr::Busy{ id: drained, in_flight: 1 } ), rm.remove_backend( drained ) );
+
+		for _ in 0..2
+		{
+			assert_eq!( Ok(()), addr2.call( Slow ).await );
+		}
+	};
+
+	let (res, _) = join( addr.call( Slow ), maintenance ).await;
+
+	assert_eq!( Ok(()), res );
+	assert_eq!( Ok(()), rm.remove_backend( drained ) );
+	assert_eq!( Err( RemoveBackendErr::Unknown{ id: drained } ), rm.remove_backend( drained ) );
+
+	assert_eq!( 1, counters[0].call( Count ).await.expect( "call counter" ) );
+	assert_eq!( 2, counters[1].call( Count ).await.expect( "call counter" ) );
+
+	// With all connections of the pool draining, sends are refused on the relay, which keeps running.
+	//
+	assert!( rm.drain_backend( conns[1].id() ) );
+
+	addr.send( Slow ).await.expect( "send Slow" );
+
+	relay_evts.wait_for( |e| matches!( e, PeerEvent::Error( err @ PeerErr::ShuttingDown{..} ) if err.ctx().cid.is_none() ) )
+
+		.await.expect( "refused send" );
+
+	assert_matches!( addr.call( Slow ).await, Err( PeerErr::ShuttingDown{..} ) );
+	assert_eq!( 2, counters[1].call( Count ).await.expect( "call counter" ) );
+
+	consumer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	relay   .send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	for mut conn in conns
+	{
+		conn.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+	}
+}
@@ -0,0 +1,101 @@
+// Tests:
+//
+// ✔ Replace the service maps of a peer while calls are coming in. No call should fail.
+// ✔ ReloadServices after the connection was closed returns ConnectionClosed.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }              } ,
+	std           :: { sync::atomic::{ AtomicUsize, Ordering } } ,
+	futures_timer :: { Delay                                    } ,
+};
+
+
+fn sm( handler: &Addr<Sum> ) -> remotes::Services
+{
+	let mut sm = remotes::Services::new();
+
+	sm.register_handler::<Add >( handler.clone_box() );
+	sm.register_handler::<Show>( handler.clone_box() );
+
+	sm
+}
+
+
+
+#[async_std::test]
+//
+async fn reload_mid_traffic()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let mut sum1 = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sum2 = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let (mut server_addr, mut server_evts, _server_handle) = peer_listen( server, Arc::new( sm( &sum1 ) ), AsyncStd, "server" ).await;
+	let (mut client_addr, _                 ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr  : remotes::RemoteAddr = remotes::RemoteAddr::new( client_addr.clone() );
+	let     done  : Arc<AtomicUsize>    = Arc::new( AtomicUsize::new(0) );
+	let     done2 : Arc<AtomicUsize>    = done.clone();
+	let     sm2   : Arc<dyn ServiceMap> = Arc::new( sm( &sum2 ) );
+
+
+	let calls = async move
+	{
+		for _ in 0..100
+		{
+			assert_eq!( Ok(()), addr.call( Add(1) ).await );
+
+			done.fetch_add( 1, Ordering::SeqCst );
+		}
+
+		addr
+	};
+
+
+	let reload = async move
+	{
+		while done2.load( Ordering::SeqCst ) < 50
+		{
+			Delay::new( Duration::from_millis(1) ).await;
+		}
+
+		server_addr.call( ReloadServices{ services: vec![ sm2 ] } ).await
+
+			.expect( "call server peer" )
+			.expect( "reload services" )
+		;
+
+		server_addr
+	};
+
+
+	let (mut addr, mut server_addr) = join( calls, reload ).await;
+
+	// This one is guaranteed to arrive after the reload.
+	//
+	assert_eq!( Ok(()), addr.call( Add(1) ).await );
+
+	let total1 = sum1.call( Show ).await.expect( "call sum1" );
+	let total2 = sum2.call( Show ).await.expect( "call sum2" );
+
+	assert!( total1 >= 50 );
+	assert!( total2 >= 1  );
+	assert_eq!( 101, total1 + total2 );
+
+	// Show is now handled by the new service map.
+	//
+	assert_eq!( Ok(total2), addr.call( Show ).await );
+
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	assert_eq!( PeerEvent::ClosedByRemote( CloseReason::Eof ), server_evts.next().await.unwrap() );
+
+	let res = server_addr.call( ReloadServices{ services: Vec::new() } ).await.expect( "call server peer" );
+
+	assert_matches!( res, Err( PeerErr::ConnectionClosed{..} ) );
+}
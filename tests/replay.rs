@@ -0,0 +1,58 @@
+#![ cfg( feature = "encrypt" ) ]
+
+// Tests:
+//
+// ✔ With FrameCipher::max_age, a fresh frame decrypts and the same frame replayed after the window
+//   is refused with WireErr::Replay.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }              } ,
+	futures       :: { channel::mpsc::unbounded, stream, SinkExt } ,
+	futures_timer :: { Delay                                    } ,
+	thes_wf       :: { Encrypt, Decrypt, FrameCipher            } ,
+};
+
+
+const KEY: [u8; 32] = [ 7; 32 ];
+
+
+
+#[async_std::test]
+//
+async fn replay()
+{
+	let cipher = FrameCipher::new( &KEY ).max_age( Duration::from_millis( 100 ) );
+
+	let mut frame = ThesWF::default();
+	frame.set_sid( ServiceID::from_seed( b"replay" ) );
+	frame.write_all( b"hello" ).expect( "write payload" );
+
+
+	// Record the frame as it goes on the wire.
+	//
+	let (tx, mut rx) = unbounded();
+
+	let mut encrypt = Encrypt::new( tx.sink_map_err( |_| -> WireErr { unreachable!() } ), cipher.clone() );
+
+	encrypt.send( frame.clone() ).await.expect( "encrypt frame" );
+
+	let sealed: ThesWF = rx.next().await.expect( "sealed frame" );
+
+
+	let mut fresh = Decrypt::new( stream::iter( vec![ Ok( sealed.clone() ) ] ), cipher.clone() );
+
+	let opened = fresh.next().await.expect( "frame" ).expect( "decrypt fresh frame" );
+
+	assert_eq!( frame.sid(), opened.sid() );
+	assert_eq!( b"hello"   , opened.msg() );
+
+
+	Delay::new( Duration::from_millis( 200 ) ).await;
+
+	let mut replayed = Decrypt::new( stream::iter( vec![ Ok( sealed ) ] ), cipher );
+
+	assert_matches!( replayed.next().await, Some( Err( WireErr::Replay{..} ) ) );
+}
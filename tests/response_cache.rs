@@ -0,0 +1,102 @@
+// Tests:
+//
+// ✔ A second call with the same payload is served from the cache, the handler only runs once.
+//   Calls with another payload or that aren't cached still reach the handler.
+// ✔ An expired entry is not served.
+// ✔ A response from the cache carries the trace id of the call it answers.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures_timer :: { Delay                       } ,
+};
+
+
+// Cache Add by its payload, leave Show alone.
+//
+fn cache() -> ResponseCache< Vec<u8> >
+{
+	ResponseCache::new( Arc::new( add_show_sum() ), |frame: &ThesWF|
+	{
+		( frame.sid() == <Add as remotes::Service>::sid() ).then( || frame.msg().to_vec() )
+	})
+}
+
+
+
+#[async_std::test]
+//
+async fn cached()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( cache() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	addr.call( Add(1) ).await.expect( "call Add" );
+
+	assert_eq!( 6, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn expired()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let cache = cache().ttl( Duration::from_millis( 10 ) );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( cache ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	Delay::new( Duration::from_millis( 20 ) ).await;
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 10, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn trace_id()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( cache() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	let (_, first ) = addr.call_traced( Add(5), 1 ).await.expect( "call Add" );
+	let (_, second) = addr.call_traced( Add(5), 2 ).await.expect( "call Add" );
+
+	assert_eq!( Some( 1 ), first  );
+	assert_eq!( Some( 2 ), second );
+
+	// The second call came from the cache.
+	//
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,99 @@
+// Tests:
+//
+// ✔ A call that times out twice succeeds on the third attempt.
+// ✔ The last error is returned when all attempts fail.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq }                } ,
+	futures :: { Sink                                       } ,
+	std     :: { num::NonZeroUsize, task::{ Context, Poll } } ,
+};
+
+
+#[ derive( Debug, Clone ) ] struct Ping;
+
+impl Message for Ping { type Return = usize; }
+
+
+// Times out on the first two calls, then returns the number of the attempt.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+struct Flaky
+{
+	calls: Arc<AtomicUsize>,
+}
+
+
+impl Address<Ping> for Flaky
+{
+	fn call( &mut self, _msg: Ping ) -> Return<'_, Result<usize, PeerErr>>
+	{
+		let attempt = self.calls.fetch_add( 1, Relaxed ) + 1;
+
+		async move
+		{
+			match attempt
+			{
+				1 | 2 => Err( PeerErr::Timeout{ ctx: PeerErrCtx::default() } ),
+				_     => Ok( attempt ),
+			}
+
+		}.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<Ping, PeerErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+impl Sink<Ping> for Flaky
+{
+	type Error = PeerErr;
+
+	fn poll_ready( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+	fn start_send( self: Pin<&mut Self>, _msg: Ping           ) -> Result<(), PeerErr>        { Ok(())                }
+	fn poll_flush( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+	fn poll_close( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), PeerErr>> { Poll::Ready( Ok(()) ) }
+}
+
+
+impl Identify for Flaky
+{
+	fn id  ( &self ) -> usize            { 0    }
+	fn name( &self ) -> Option<Arc<str>> { None }
+}
+
+
+
+#[async_std::test]
+//
+async fn succeeds_after_retries()
+{
+	let flaky    = Flaky::default();
+	let attempts = NonZeroUsize::new( 5 ).unwrap();
+	let mut addr = RetryAddr::new( Box::new( flaky.clone() ), attempts, Duration::from_millis( 1 ) );
+
+	assert_eq!( 3, addr.call( Ping ).await.expect( "call Ping" ) );
+	assert_eq!( 3, flaky.calls.load( Relaxed ) );
+}
+
+
+
+#[async_std::test]
+//
+async fn gives_up()
+{
+	let flaky    = Flaky::default();
+	let attempts = NonZeroUsize::new( 2 ).unwrap();
+	let mut addr = RetryAddr::new( Box::new( flaky.clone() ), attempts, Duration::from_millis( 1 ) );
+
+	assert_matches!( addr.call( Ping ).await, Err( PeerErr::Timeout{..} ) );
+	assert_eq!( 2, flaky.calls.load( Relaxed ) );
+}
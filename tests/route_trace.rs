@@ -0,0 +1,81 @@
+// Tests:
+//
+// ✔ A call with a route trace over two relays comes back with the ids of both relays, in order.
+// ✔ Without a route trace, the response doesn't get one.
+//
+mod common;
+
+use common::{ *, import::{ *, assert_eq } };
+
+
+
+// Relay Add and Show from `listen` to `next`. Returns the id of the relaying peer.
+//
+fn relay( next: Addr<Peer>, listen: Endpoint, name: &str ) -> usize
+{
+	let (peer_addr, peer_mb) = Addr::builder().name( name.into() ).build();
+	let id                   = peer_addr.id();
+
+	let mut peer = Peer::from_async_read( peer_addr, listen, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let handler: Box<dyn Relay> = Box::new( next );
+	let relayed                 = vec![ <Add as remotes::Service>::sid(), <Show as remotes::Service>::sid() ];
+
+	peer.register_services( Arc::new( RelayMap::new( handler.into(), relayed ) ) );
+
+	AsyncStd.spawn( async{ peer_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	id
+}
+
+
+// A frame calling Show.
+//
+fn show() -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( <Show as remotes::Service>::sid() );
+	serde_cbor::to_writer( &mut wf, &Show ).expect( "serialize Show" );
+
+	wf
+}
+
+
+
+#[async_std::test]
+//
+async fn two_relays()
+{
+	let (provider, to_provider) = Endpoint::pair( 64, 64 );
+	let (relay2  , to_relay2  ) = Endpoint::pair( 64, 64 );
+	let (relay1  , to_relay1  ) = Endpoint::pair( 64, 64 );
+
+	let (_provider_addr, _, _provider_handle) = peer_listen( provider, Arc::new( add_show_sum() ), AsyncStd, "provider" ).await;
+
+	let (relay2_to_provider, _) = peer_connect( to_provider, AsyncStd, "relay2_to_provider" ).await;
+	let relay2_id               = relay( relay2_to_provider, relay2, "relay2" );
+
+	let (relay1_to_relay2, _) = peer_connect( to_relay2, AsyncStd, "relay1_to_relay2" ).await;
+	let relay1_id             = relay( relay1_to_relay2, relay1, "relay1" );
+
+	let (mut consumer, _) = peer_connect( to_relay1, AsyncStd, "consumer" ).await;
+	let mut raw           = RawPeerSink::new( consumer.clone() );
+
+
+	let mut traced = show();
+	traced.set_route( Some( &[] ) );
+
+	let resp = raw.call( traced ).await.expect( "call Show" );
+
+	assert_eq!( Some( vec![ relay1_id, relay2_id ] ), resp.route() );
+	assert_eq!( 0, serde_cbor::from_slice::<i64>( resp.msg() ).expect( "deserialize response" ) );
+
+
+	let resp = raw.call( show() ).await.expect( "call Show" );
+
+	assert_eq!( None, resp.route() );
+
+
+	consumer.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
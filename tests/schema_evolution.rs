@@ -0,0 +1,147 @@
+// Tests:
+//
+// ✔ A client with an older version of a response type decodes the fields it knows when the provider
+//   added a field.
+// ✔ A client with a newer version decodes the response of an older provider when the new field has
+//   `#[serde(default)]`.
+// ✔ Without `#[serde(default)]`, the error names the type and the missing field.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	serde  :: { Serialize, Deserialize      } ,
+};
+
+
+// Each module has its own version of the response type. The service names are the same, so the
+// sids match across versions.
+//
+macro_rules! version
+{
+	( $name: ident, $info: item, $make: expr ) =>
+	{
+		mod $name
+		{
+			use super::*;
+
+			#[ derive( Serialize, Deserialize, Debug ) ] pub struct GetInfo;
+
+			#[ derive( Serialize, Deserialize, Debug, PartialEq ) ] $info
+
+			impl Message for GetInfo { type Return = Info; }
+
+			service_map!
+			(
+				namespace  : info    ;
+				wire_format: ThesWF  ;
+				services   : GetInfo ;
+			);
+
+
+			#[ derive( Actor ) ] pub struct Provider;
+
+			impl Handler< GetInfo > for Provider
+			{
+				#[async_fn] fn handle( &mut self, _msg: GetInfo ) -> Info
+				{
+					$make
+				}
+			}
+
+
+			pub fn sm() -> info::Services
+			{
+				let provider = Addr::builder().start( Provider, &AsyncStd ).expect( "spawn actor mailbox" );
+
+				let mut sm = info::Services::new();
+				sm.register_handler::<GetInfo>( provider.clone_box() );
+
+				sm
+			}
+		}
+	}
+}
+
+
+version!( v1, pub struct Info { pub name: String }, Info{ name: "provider".to_string() } );
+
+version!
+(
+	v2,
+	pub struct Info { pub name: String, #[ serde( default ) ] pub build: u32 },
+	Info{ name: "provider".to_string(), build: 2 }
+);
+
+version!
+(
+	strict,
+	pub struct Info { pub name: String, pub build: u32 },
+	Info{ name: "provider".to_string(), build: 2 }
+);
+
+
+
+#[async_std::test]
+//
+async fn older_client()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( v2::sm() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = v1::info::RemoteAddr::new( client_addr.clone() );
+
+	let info = addr.call( v1::GetInfo ).await.expect( "call GetInfo" );
+
+	assert_eq!( v1::Info{ name: "provider".to_string() }, info );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn newer_client()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( v1::sm() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = v2::info::RemoteAddr::new( client_addr.clone() );
+
+	let info = addr.call( v2::GetInfo ).await.expect( "call GetInfo" );
+
+	assert_eq!( v2::Info{ name: "provider".to_string(), build: 0 }, info );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn missing_field()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( v1::sm() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _)              = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = strict::info::RemoteAddr::new( client_addr.clone() );
+
+	let err = addr.call( strict::GetInfo ).await.expect_err( "build is missing" );
+
+	assert_matches!( err, PeerErr::Deserialize{..} );
+
+	let msg = err.to_string();
+
+	assert!( msg.contains( "strict::Info" ), "{}", msg );
+	assert!( msg.contains( "build"        ), "{}", msg );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
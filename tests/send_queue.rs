@@ -0,0 +1,214 @@
+// Tests:
+//
+// ✔ With OverflowPolicy::DropOldest, when the handler doesn't accept sends and the queue is full, the
+//   oldest sends are dropped and counted and the newest ones are delivered once the handler is ready.
+// ✔ With OverflowPolicy::DropNewest and set_report_dropped_sends, the sender gets a SendRejected event
+//   for the dropped send.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq }                       } ,
+	futures       :: { Sink, SinkExt                                     } ,
+	futures_timer :: { Delay                                             } ,
+	parking_lot   :: { Mutex                                             } ,
+	std           :: { num::NonZeroUsize, task::{ Context, Poll, Waker } } ,
+};
+
+
+#[ derive( Debug, Default ) ]
+//
+struct Gate
+{
+	open    : bool       ,
+	wakers  : Vec<Waker> ,
+	received: Vec<i64>   ,
+}
+
+
+// A handler whose mailbox stays full until the gate opens.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+struct Jammed
+{
+	gate: Arc<Mutex<Gate>>,
+}
+
+
+impl Jammed
+{
+	fn open( &self )
+	{
+		let mut gate = self.gate.lock();
+
+		gate.open = true;
+		gate.wakers.drain(..).for_each( Waker::wake );
+	}
+}
+
+
+impl Address<Add> for Jammed
+{
+	fn call( &mut self, msg: Add ) -> Return<'_, Result<(), ThesErr>>
+	{
+		async move { self.send( msg ).await }.boxed()
+	}
+
+
+	fn clone_box( &self ) -> BoxAddress<Add, ThesErr>
+	{
+		Box::new( self.clone() )
+	}
+}
+
+
+impl Sink<Add> for Jammed
+{
+	type Error = ThesErr;
+
+	fn poll_ready( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), ThesErr>>
+	{
+		let mut gate = self.gate.lock();
+
+		if gate.open { return Poll::Ready( Ok(()) ) }
+
+		gate.wakers.push( cx.waker().clone() );
+		Poll::Pending
+	}
+
+	fn start_send( self: Pin<&mut Self>, msg: Add            ) -> Result<(), ThesErr>        { self.gate.lock().received.push( msg.0 ); Ok(()) }
+	fn poll_flush( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), ThesErr>> { Poll::Ready( Ok(()) )                          }
+	fn poll_close( self: Pin<&mut Self>, _cx: &mut Context<'_> ) -> Poll<Result<(), ThesErr>> { Poll::Ready( Ok(()) )                          }
+}
+
+
+impl Identify for Jammed
+{
+	fn id  ( &self ) -> usize            { 0    }
+	fn name( &self ) -> Option<Arc<str>> { None }
+}
+
+
+service_map!
+(
+	namespace  : jammed ;
+	wire_format: ThesWF ;
+	services   : Add    ;
+);
+
+
+
+#[async_std::test]
+//
+async fn drop_oldest()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let jammed = Jammed::default();
+	let mut sm = jammed::Services::new();
+
+	sm.register_handler::<Add>( jammed.clone_box() );
+
+
+	let (mut server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr.clone(), server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( sm ) );
+	peer.set_send_queue( NonZeroUsize::new( 2 ).unwrap(), OverflowPolicy::DropOldest );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = jammed::RemoteAddr::new( client_addr.clone() );
+
+	for i in 0..10
+	{
+		addr.send( Add(i) ).await.expect( "send Add" );
+	}
+
+
+	// Wait for the server to have processed all of them.
+	//
+	while server_addr.call( GetStatus ).await.expect( "get status" ).dropped_sends < 8
+	{
+		Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+	jammed.open();
+
+	while jammed.gate.lock().received.len() < 2
+	{
+		Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+
+	let mut received = jammed.gate.lock().received.clone();
+	received.sort_unstable();
+
+	assert_eq!( vec![ 8, 9 ], received );
+	assert_eq!( 8, server_addr.call( GetStatus ).await.expect( "get status" ).dropped_sends );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn drop_newest_rejected()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let jammed = Jammed::default();
+	let mut sm = jammed::Services::new();
+
+	sm.register_handler::<Add>( jammed.clone_box() );
+
+
+	let (mut server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer = Peer::from_async_read( server_addr.clone(), server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	peer.register_services( Arc::new( sm ) );
+	peer.set_send_queue( NonZeroUsize::new( 2 ).unwrap(), OverflowPolicy::DropNewest );
+	peer.set_report_dropped_sends( true );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, mut client_evts) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = jammed::RemoteAddr::new( client_addr.clone() );
+
+	for i in 0..3
+	{
+		addr.send( Add(i) ).await.expect( "send Add" );
+	}
+
+
+	use jammed::Service;
+
+	let evt = client_evts.wait_for( |e| matches!( e, PeerEvent::SendRejected{..} ) ).await.expect( "SendRejected event" );
+
+	assert_eq!( PeerEvent::SendRejected{ sid: Add::sid() }, evt );
+	assert_eq!( 1, server_addr.call( GetStatus ).await.expect( "get status" ).dropped_sends );
+
+
+	// The queued sends are still delivered.
+	//
+	jammed.open();
+
+	while jammed.gate.lock().received.len() < 2
+	{
+		Delay::new( Duration::from_millis( 10 ) ).await;
+	}
+
+	assert_eq!( vec![ 0, 1 ], jammed.gate.lock().received.clone() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,136 @@
+// Tests:
+//
+// ✔ A service with a custom codec is encoded with it in both directions, while the other services of
+//   the same service map keep using CBOR.
+//
+mod common;
+
+use
+{
+	common      :: { *, import::{ *, assert_eq } } ,
+	parking_lot :: { Mutex                       } ,
+	serde       :: { Serialize, Deserialize      } ,
+	std         :: { io                          } ,
+};
+
+
+#[ derive( Serialize, Deserialize, Debug, PartialEq ) ] struct Note( String );
+
+impl Message for Note { type Return = usize; }
+
+
+// CBOR as hex text.
+//
+fn hex_codec() -> ServiceCodec<Note>
+{
+	ServiceCodec
+	{
+		encode: |note, out|
+		{
+			let cbor = serde_cbor::to_vec( note )?;
+			let hex: String = cbor.iter().map( |b| format!( "{:02x}", b ) ).collect();
+
+			out.write_all( hex.as_bytes() ).map_err( Into::into )
+		},
+
+		decode: |bytes|
+		{
+			let invalid = || io::Error::from( io::ErrorKind::InvalidData );
+
+			let cbor = bytes.chunks( 2 ).map( |pair|
+			{
+				let pair = std::str::from_utf8( pair ).map_err( |_| invalid() )?;
+
+				u8::from_str_radix( pair, 16 ).map_err( |_| invalid() )
+
+			}).collect::< Result<Vec<u8>, io::Error> >()?;
+
+			serde_cbor::from_slice( &cbor ).map_err( Into::into )
+		},
+	}
+}
+
+
+#[ derive( Actor, Default ) ] struct Board
+{
+	sum  : i64   ,
+	notes: usize ,
+}
+
+
+impl Handler<Add> for Board
+{
+	#[async_fn] fn handle( &mut self, msg: Add )
+	{
+		self.sum += msg.0;
+	}
+}
+
+
+impl Handler<Note> for Board
+{
+	#[async_fn] fn handle( &mut self, msg: Note ) -> usize
+	{
+		assert_eq!( "hello", msg.0 );
+
+		self.notes += 1;
+		self.notes
+	}
+}
+
+
+service_map!
+(
+	namespace  : board                           ;
+	wire_format: ThesWF                          ;
+	services   : Add, Note => crate::hex_codec() ;
+);
+
+
+
+#[async_std::test]
+//
+async fn custom_codec()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let board  = Addr::builder().start( Board::default(), &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sm = board::Services::new();
+
+	sm.register_handler::<Add >( board.clone_box() );
+	sm.register_handler::<Note>( board.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut peer = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	let outgoing: Arc<Mutex< Vec<(ServiceID, Vec<u8>)> >> = Default::default();
+	let out = outgoing.clone();
+
+	peer.set_outgoing_hook( move |sid, _, bytes| out.lock().push( (sid, bytes.to_vec()) ) );
+
+	AsyncStd.spawn( async{ client_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut addr = board::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 1, addr.call( Note( "hello".to_string() ) ).await.expect( "call Note" ) );
+
+
+	let payload = |sid: ServiceID| outgoing.lock().iter().find( |f| f.0 == sid ).map( |f| f.1.clone() ).expect( "frame sent" );
+
+	let mut hex = Vec::new();
+	( hex_codec().encode )( &Note( "hello".to_string() ), &mut hex ).expect( "encode Note" );
+
+	assert_eq!( hex, payload( <Note as board::Service>::sid() ) );
+	assert_eq!( serde_cbor::to_vec( &Add(5) ).expect( "serialize Add" ), payload( <Add as board::Service>::sid() ) );
+
+	assert_eq!( Note( "hello".to_string() ), ( hex_codec().decode )( &hex ).expect( "decode Note" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -5,6 +5,11 @@
 // - ✔ Verify that the same      service, in the same      namespace but in different servicemap has identical sid
 // - ✔ Test clone.
 // - ✔ Test Debug.
+// - ✔ Test handler_info.
+// - ✔ Test register_handler_for with one actor for three services.
+// - ✔ Test try_register_handler refuses a second handler and keeps the first one.
+// - ✔ Test ServiceID::registered_services.
+// - ✔ Test restoring the services of a snapshot in a fresh service map.
 // - Test ServiceID::Debug
 // - Test adding services at runtime.
 //
@@ -135,6 +140,107 @@ async fn debug()
 
 
 
+// Test handler_info, it should list the registered handlers with their actors.
+//
+#[async_std::test]
+//
+async fn handler_info()
+{
+	let (sum_addr , _) = Addr::<Sum>::builder().name( "sum".into()  ).build();
+	let (show_addr, _) = Addr::<Sum>::builder().name( "show".into() ).build();
+
+	let mut sm = remotes::Services::new();
+
+	sm.register_handler::<Add >( sum_addr .clone_box() );
+	sm.register_handler::<Show>( show_addr.clone_box() );
+
+	use remotes::Service;
+
+	let expect = vec!
+	[
+		HandlerInfo{ sid: Add ::sid(), actor_id: sum_addr .id(), name: Some( "sum".into()  ) },
+		HandlerInfo{ sid: Show::sid(), actor_id: show_addr.id(), name: Some( "show".into() ) },
+	];
+
+	assert_eq!( expect, sm.handler_info() );
+}
+
+
+
+// Register one actor for three services at once, all of them should route to it.
+//
+#[async_std::test]
+//
+async fn register_handler_for()
+{
+	let sum_addr = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = remotes::Services::new();
+
+	sm.register_handler_for::<(Add, Sub, Show)>( &sum_addr );
+
+	let info = sm.handler_info();
+
+	assert_eq!( 3, info.len() );
+	assert!( info.iter().all( |h| h.actor_id == sum_addr.id() ) );
+
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+	addr.call( Sub(2) ).await.expect( "call Sub" );
+
+	assert_eq!( 3, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// The second handler is refused, calls still reach the first one.
+//
+#[async_std::test]
+//
+async fn try_register_handler()
+{
+	let first  = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+	let second = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = remotes::Services::new();
+
+	use remotes::Service;
+
+	assert_eq!( Ok(()), sm.try_register_handler::<Add >( first.clone_box() ) );
+	assert_eq!( Ok(()), sm.try_register_handler::<Show>( first.clone_box() ) );
+
+	assert_eq!
+	(
+		Err( AlreadyRegistered{ sid: Add::sid() } ),
+		sm.try_register_handler::<Add>( second.clone_box() ),
+	);
+
+
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
 // Test debug implementation of ServiceID
 //
 #[async_std::test]
@@ -151,3 +257,78 @@ async fn debug_service_id()
 }
 
 
+
+// Test exporting the registry of service names.
+//
+#[test]
+//
+fn registered_services()
+{
+	let _sm = remotes::Services::new();
+
+	use remotes::Service;
+
+	let registry = ServiceID::registered_services();
+
+	assert_eq!( Some( "remotes::Add"  ), registry.get( &Add ::sid() ).map( String::as_str ) );
+	assert_eq!( Some( "remotes::Sub"  ), registry.get( &Sub ::sid() ).map( String::as_str ) );
+	assert_eq!( Some( "remotes::Show" ), registry.get( &Show::sid() ).map( String::as_str ) );
+}
+
+
+
+// Export the services, serialized like a supervisor would store them, and restore them in a fresh
+// service map from one that has handlers for all services.
+//
+#[async_std::test]
+//
+async fn snapshot_restore()
+{
+	let sum_addr = Addr::builder().start( Sum(0), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut before = remotes::Services::new();
+
+	before.register_handler::<Add >( sum_addr.clone_box() );
+	before.register_handler::<Show>( sum_addr.clone_box() );
+
+	let bytes    = serde_cbor::to_vec( &before.snapshot() ).expect( "serialize snapshot" );
+	let snapshot = serde_cbor::from_slice::<ServicesSnapshot>( &bytes ).expect( "deserialize snapshot" );
+
+	assert_eq!( before.snapshot(), snapshot );
+
+
+	// After the restart, everything is available, but only what was exposed before should be restored.
+	//
+	let mut all = remotes::Services::new();
+
+	all.register_handler_for::<(Add, Sub, Show)>( &sum_addr );
+
+	let mut restored = remotes::Services::new();
+
+	restored.restore( &snapshot, &all ).expect( "restore services" );
+
+	assert!( restored.snapshot().same_services( &snapshot ) );
+	assert!( !all    .snapshot().same_services( &snapshot ) );
+
+	let mut sids: Vec<ServiceID> = restored.services().copied().collect();
+	sids.sort_by_key( |sid| format!( "{:?}", sid ) );
+
+	let mut expect: Vec<ServiceID> = snapshot.sids().collect();
+	expect.sort_by_key( |sid| format!( "{:?}", sid ) );
+
+	assert_eq!( expect, sids );
+
+
+	// A service map that lacks a handler restores nothing.
+	//
+	let mut partial = remotes::Services::new();
+
+	partial.register_handler::<Add>( sum_addr.clone_box() );
+
+	let mut fresh = remotes::Services::new();
+
+	use remotes::Service;
+
+	assert_eq!( Err( MissingServices{ sids: vec![ Show::sid() ] } ), fresh.restore( &snapshot, &partial ) );
+	assert_eq!( 0, fresh.services().count() );
+}
@@ -0,0 +1,74 @@
+// Tests:
+//
+// ✔ Sends queued before Shutdown all reach the remote before the connection closes.
+// ✔ A call still waiting for a response fails with PeerErr::ShuttingDown.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { AsyncReadExt                } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn flushes_sends()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	// Read the raw frames on the other end until the connection closes.
+	//
+	let (reader, _writer) = server.split();
+
+	let frames = AsyncStd.spawn_handle( thes_wf::Decoder::new( reader, 1024 ).collect::<Vec<_>>() ).expect( "spawn reader" );
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	for i in 0..10
+	{
+		addr.send( Add(i) ).await.expect( "send Add" );
+	}
+
+	client_addr.call( Shutdown ).await.expect( "shutdown" );
+
+	let frames = frames.await;
+
+	assert_eq!( 10, frames.len() );
+
+	for frame in frames
+	{
+		assert_eq!( <Add as remotes::Service>::sid(), frame.expect( "valid frame" ).sid() );
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn pending_call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	// Nobody answers on the other end.
+	//
+	let (reader, _writer) = server.split();
+	let mut frames        = thes_wf::Decoder::new( reader, 1024 );
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+	let call     = AsyncStd.spawn_handle( async move { addr.call( Show ).await } ).expect( "spawn call" );
+
+	// Make sure the call went out.
+	//
+	frames.next().await.expect( "call frame" ).expect( "valid frame" );
+
+	client_addr.call( Shutdown ).await.expect( "shutdown" );
+
+	assert_matches!( call.await, Err( PeerErr::ShuttingDown{..} ) );
+}
@@ -0,0 +1,77 @@
+// Tests:
+//
+// ✔ When the executor refuses to spawn the task for an incoming call, the caller gets SpawnFailed
+//   and the peer emits an error event.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::*                                   } ,
+	futures :: { task::{ FutureObj, SpawnError }                } ,
+	std     :: { sync::atomic::{ AtomicBool, Ordering::SeqCst } } ,
+};
+
+
+
+// An executor that stops spawning when told to, like one that is shutting down.
+//
+#[ derive( Clone ) ]
+//
+struct Refuse
+{
+	refuse: Arc<AtomicBool>,
+}
+
+
+impl<Out: 'static + Send> SpawnHandle<Out> for Refuse
+{
+	fn spawn_handle_obj( &self, future: FutureObj<'static, Out> ) -> Result<JoinHandle<Out>, SpawnError>
+	{
+		if self.refuse.load( SeqCst )
+		{
+			return Err( SpawnError::shutdown() );
+		}
+
+		AsyncStd.spawn_handle_obj( future )
+	}
+}
+
+
+impl Spawn for Refuse
+{
+	fn spawn_obj( &self, future: FutureObj<'static, ()> ) -> Result<(), SpawnError>
+	{
+		AsyncStd.spawn_obj( future )
+	}
+}
+
+
+
+#[async_std::test]
+//
+async fn spawn_failed()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let exec = Refuse{ refuse: Arc::new( AtomicBool::new( false ) ) };
+
+	let (_server_addr, mut server_evts, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), exec.clone(), "server" ).await;
+	let (client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr );
+
+	// Make sure everything is up and running before we pull the plug.
+	//
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	exec.refuse.store( true, SeqCst );
+
+	assert_matches!
+	(
+		addr.call( Show ).await,
+		Err( PeerErr::Remote{ err: ConnectionError::SpawnFailed{..}, .. } )
+	);
+
+	assert_matches!( server_evts.next().await.unwrap(), PeerEvent::Error( PeerErr::Spawn{..} ) );
+}
@@ -0,0 +1,54 @@
+// Tests:
+//
+// ✔ When the remote stops reading, SinkStalled is published once writing a frame takes longer than
+//   the threshold.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	std    :: { time::Instant               } ,
+};
+
+
+#[async_std::test]
+//
+async fn sink_stalled()
+{
+	// Nobody ever reads from the other end.
+	//
+	let (_server, client) = Endpoint::pair( 64, 64 );
+
+	let threshold = Duration::from_millis( 50 );
+
+	let (client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut client = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	client.set_stall_threshold( threshold );
+
+	let mut evts = client.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	AsyncStd.spawn( async{ client_mb.start( client ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let start    = Instant::now();
+	let mut addr = remotes::RemoteAddr::new( client_addr );
+
+	AsyncStd.spawn( async move
+	{
+		for i in 0..16
+		{
+			if addr.send( Add(i) ).await.is_err() { break }
+		}
+
+	}).expect( "spawn sender" );
+
+
+	let evt = evts.wait_for( |e| matches!( e, PeerEvent::SinkStalled{..} ) ).await.expect( "stall event" );
+
+	assert!( start.elapsed() >= threshold );
+
+	assert_eq!( PeerEvent::SinkStalled{ queued_frames: 1 }, evt );
+}
@@ -0,0 +1,93 @@
+// Tests:
+//
+// ✔ After some calls and sends, GetStatus reports the connection as connected and idle, with the bytes
+//   sent by one side equal to the bytes received by the other.
+// ✔ After CloseConnection, the peer reports it's no longer connected.
+// ✔ The remote address set before starting the peer can be read back, and is None when not set.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	std    :: { time::SystemTime            } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn status()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let before = client_addr.call( GetStatus ).await.expect( "get status" );
+
+	assert!( before.connected );
+	assert_eq!( None, before.last_activity );
+	assert_eq!( 0   , before.bytes_in      );
+	assert_eq!( 0   , before.bytes_out     );
+
+
+	let start    = SystemTime::now();
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+	addr.send( Add(5) ).await.expect( "send Add" );
+
+	assert_eq!( 10, addr.call( Show ).await.expect( "call Show" ) );
+
+
+	let client_status = client_addr.call( GetStatus ).await.expect( "get status" );
+	let server_status = server_addr.call( GetStatus ).await.expect( "get status" );
+
+	assert!( client_status.connected     );
+	assert!( !client_status.backpressure );
+
+	assert_eq!( 0, client_status.open_calls    );
+	assert_eq!( 0, server_status.inbound_calls );
+	assert_eq!( 0, client_status.open_streams  );
+
+	assert!( client_status.last_activity.expect( "activity" ) >= start );
+	assert!( client_status.bytes_out > 0 );
+	assert!( client_status.bytes_in  > 0 );
+
+	assert_eq!( client_status.bytes_out, server_status.bytes_in  );
+	assert_eq!( client_status.bytes_in , server_status.bytes_out );
+
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	// The peer no longer holds its own address, but ours keeps the mailbox alive.
+	//
+	assert!( !client_addr.call( GetStatus ).await.expect( "get status" ).connected );
+}
+
+
+
+#[async_std::test]
+//
+async fn remote_addr()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (mut server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut peer   = Peer::from_async_read( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+	let     remote = "127.0.0.1:4000".parse().expect( "parse address" );
+
+	peer.set_remote_addr( remote );
+
+	AsyncStd.spawn( async{ client_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+	assert_eq!( Some( remote ), Peer::remote_addr( &mut client_addr ).await.expect( "remote_addr" ) );
+	assert_eq!( Some( remote ), client_addr.call( GetStatus ).await.expect( "get status" ).remote_addr );
+	assert_eq!( None          , Peer::remote_addr( &mut server_addr ).await.expect( "remote_addr" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
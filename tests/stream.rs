@@ -0,0 +1,134 @@
+// Tests:
+//
+// ✔ Open a stream, exchange several items each way and close it from the client.
+// ✔ A guard denying the service closes the stream right away, the handler never runs and the
+//   server reports Unauthorized.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { SinkExt                     } ,
+	serde   :: { Serialize, Deserialize      } ,
+};
+
+
+#[ derive( Actor ) ] struct Echo;
+
+// Opens an echo stream, the items sent back are prefixed with the string.
+//
+#[ derive( Serialize, Deserialize, Debug ) ] struct Open( String );
+
+impl Message for Open { type Return = (); }
+
+
+impl Handler< Streaming<Open> > for Echo
+{
+	#[async_fn] fn handle( &mut self, msg: Streaming<Open> )
+	{
+		let prefix           = msg.msg.0;
+		let (mut tx, mut rx) = msg.channel.split::<String, String>();
+
+		tx.send( format!( "{}hello", prefix ) ).await.expect( "send greeting" );
+
+		while let Some( item ) = rx.next().await
+		{
+			let item = item.expect( "deserialize item" );
+
+			tx.send( format!( "{}{}", prefix, item ) ).await.expect( "send item" );
+		}
+
+		tx.close().await.expect( "close stream" );
+	}
+}
+
+
+service_map!
+(
+	namespace  : streams ;
+	wire_format: ThesWF  ;
+	services   : Open    ;
+);
+
+
+
+#[async_std::test]
+//
+async fn bidirectional()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let echo = Addr::builder().start( Echo, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = streams::Services::new();
+	sm.register_stream::<Open>( echo.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = streams::RemoteAddr::new( client_addr.clone() );
+
+	let (mut tx, mut rx) = addr.open_stream::<Open, String, String>( Open( "echo: ".to_string() ) ).await.expect( "open stream" );
+
+	assert_eq!( "echo: hello", rx.next().await.expect( "greeting" ).expect( "deserialize" ) );
+
+	for line in &[ "one", "two", "three" ]
+	{
+		tx.send( line.to_string() ).await.expect( "send line" );
+
+		assert_eq!( format!( "echo: {}", line ), rx.next().await.expect( "echo" ).expect( "deserialize" ) );
+	}
+
+	// The handler closes its end when we close ours.
+	//
+	tx.close().await.expect( "close stream" );
+
+	assert!( rx.next().await.is_none() );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn guard_refuses_open()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let echo = Addr::builder().start( Echo, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = streams::Services::new();
+	sm.register_stream::<Open>( echo.clone_box() );
+
+	let (server_addr, server_mb) = Addr::builder().name( "server".into() ).build();
+
+	let mut peer        = Peer::from_async_read( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+	let mut server_evts = peer.observe( ObserveConfig::default() ).await.expect( "pharos not closed" );
+
+	peer.register_services( Arc::new( sm ) );
+	peer.set_guard( |frame, _ctx| frame.sid() != <Open as streams::Service>::sid() );
+
+	AsyncStd.spawn( async{ server_mb.start( peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+	let mut addr             = streams::RemoteAddr::new( client_addr.clone() );
+
+	let (_tx, mut rx) = addr.open_stream::<Open, String, String>( Open( "echo: ".to_string() ) ).await.expect( "open stream" );
+
+	// No greeting, the stream is closed before the handler could send one.
+	//
+	assert!( rx.next().await.is_none() );
+
+	let evt = server_evts.wait_for( |e| matches!( e, PeerEvent::Error( PeerErr::Unauthorized{..} ) ) ).await.expect( "unauthorized event" );
+
+	match evt
+	{
+		PeerEvent::Error( PeerErr::Unauthorized{ ctx } ) => assert_eq!( Some( <Open as streams::Service>::sid() ), ctx.sid ),
+		other                                            => panic!( "expected an unauthorized error, got: {:?}", other ),
+	}
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
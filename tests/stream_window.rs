@@ -0,0 +1,222 @@
+// Tests:
+//
+// ✔ With two streams open on the same connection, the provider stops sending on the one we don't
+//   read once its window is used up, while the other one runs to completion. Reading the paused
+//   stream afterwards gets all of its items.
+// ✔ A remote that keeps sending once the window is used up gets its stream closed. The reader
+//   gets the items that came in within the window, then the end of the stream.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq }                          } ,
+	futures :: { SinkExt, channel::mpsc::{ unbounded, UnboundedSender } } ,
+	serde   :: { Serialize, Deserialize                               } ,
+};
+
+
+// The codec of the peers in common has a max_size of 1024 bytes.
+//
+const ITEM : usize = 500  ;
+const ITEMS: usize = 2000 ;
+
+
+// Counts the items sent on each stream.
+//
+#[ derive( Actor ) ] struct Flood( Vec<Arc<AtomicUsize>> );
+
+// Open a stream that sends ITEMS items. The number is the index of the counter.
+//
+#[ derive( Serialize, Deserialize, Debug ) ] struct Open( usize );
+
+impl Message for Open { type Return = (); }
+
+
+impl Handler< Streaming<Open> > for Flood
+{
+	#[async_fn] fn handle( &mut self, msg: Streaming<Open> )
+	{
+		let sent        = self.0[ msg.msg.0 ].clone();
+		let (mut tx, _) = msg.channel.split::<Vec<u8>, ()>();
+
+		AsyncStd.spawn( async move
+		{
+			for _ in 0..ITEMS
+			{
+				tx.send( vec![ 1; ITEM ] ).await.expect( "send item" );
+				sent.fetch_add( 1, Relaxed );
+			}
+
+			tx.close().await.expect( "close stream" );
+
+		}).expect( "spawn flood" );
+	}
+}
+
+
+// Hands the receiving end of the stream to the test without reading it.
+//
+#[ derive( Actor ) ] struct Hoard( UnboundedSender< StreamRx<Vec<u8>> > );
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Hold;
+
+impl Message for Hold { type Return = (); }
+
+
+impl Handler< Streaming<Hold> > for Hoard
+{
+	#[async_fn] fn handle( &mut self, msg: Streaming<Hold> )
+	{
+		let (_, rx) = msg.channel.split::<(), Vec<u8>>();
+
+		self.0.unbounded_send( rx ).expect( "hand out stream" );
+	}
+}
+
+
+service_map!
+(
+	namespace  : window     ;
+	wire_format: ThesWF     ;
+	services   : Open, Hold ;
+);
+
+
+
+#[async_std::test]
+//
+async fn paused_stream()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let counters = vec![ Arc::new( AtomicUsize::new(0) ), Arc::new( AtomicUsize::new(0) ) ];
+	let flood    = Addr::builder().start( Flood( counters.clone() ), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = window::Services::new();
+	sm.register_stream::<Open>( flood.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = window::RemoteAddr::new( client_addr.clone() );
+
+	let (_tx_a, mut rx_a) = addr.open_stream::<Open, (), Vec<u8>>( Open(0) ).await.expect( "open stream a" );
+	let (_tx_b, mut rx_b) = addr.open_stream::<Open, (), Vec<u8>>( Open(1) ).await.expect( "open stream b" );
+
+
+	// Only read b.
+	//
+	let mut read_b = 0;
+
+	while let Some( item ) = rx_b.next().await
+	{
+		assert_eq!( ITEM, item.expect( "deserialize item" ).len() );
+		read_b += 1;
+	}
+
+	assert_eq!( ITEMS, read_b );
+
+
+	// A frame holds a few bytes more than the item, so a is held back before it fills the window.
+	//
+	let sent_a = counters[0].load( Relaxed );
+
+	assert!( sent_a < ITEMS                    , "sent: {}", sent_a );
+	assert!( sent_a <= STREAM_WINDOW / ITEM + 1, "sent: {}", sent_a );
+
+
+	// Now read a.
+	//
+	let mut read_a = 0;
+
+	while let Some( item ) = rx_a.next().await
+	{
+		item.expect( "deserialize item" );
+		read_a += 1;
+	}
+
+	assert_eq!( ITEMS, read_a                      );
+	assert_eq!( ITEMS, counters[0].load( Relaxed ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+// A frame of the stream with cid 1 that the client opened, written by hand so it can ignore the window.
+//
+fn stream_frame( tag: u8, payload: &[u8] ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( ServiceID::stream() );
+	wf.set_cid( ConnID::from( 1 )   );
+
+	wf.write_all( &[ tag ] ).expect( "write tag"     );
+	wf.write_all( payload  ).expect( "write payload" );
+
+	wf
+}
+
+
+
+#[async_std::test]
+//
+async fn overflow()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (tx, mut streams) = unbounded();
+	let hoard             = Addr::builder().start( Hoard( tx ), &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = window::Services::new();
+	sm.register_stream::<Hold>( hoard.clone_box() );
+
+	let (mut server_addr, mut server_evts, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+
+	// Open: the sid of the service and the opening message.
+	//
+	let mut open = Vec::new();
+
+	<Hold as window::Service>::sid().write_le( &mut open ).expect( "write sid" );
+	serde_cbor::to_writer( &mut open, &Hold ).expect( "serialize Hold" );
+
+	client_addr.send( stream_frame( 0, &open ) ).await.expect( "send open" );
+
+	let mut rx = streams.next().await.expect( "stream opened" );
+
+
+	// Items of ITEM bytes, twice as many as the window holds.
+	//
+	let payload = serde_cbor::to_vec( &vec![ 1u8; ITEM ] ).expect( "serialize item" );
+	let items   = 2 * STREAM_WINDOW / ITEM;
+
+	for _ in 0..items
+	{
+		client_addr.send( stream_frame( 1, &payload ) ).await.expect( "send item" );
+	}
+
+	server_evts.wait_for( |e| matches!( e, PeerEvent::Error( PeerErr::WireFormat{ source: WireErr::Deserialize{..}, .. } ) ) )
+
+		.await.expect( "overflow event" );
+
+	assert_eq!( 0, server_addr.call( GetStatus ).await.expect( "get status" ).open_streams );
+
+
+	// The remote may overshoot the window with its last item.
+	//
+	let mut read = 0;
+
+	while let Some( item ) = rx.next().await
+	{
+		assert_eq!( ITEM, item.expect( "deserialize item" ).len() );
+		read += 1;
+	}
+
+	assert_eq!( STREAM_WINDOW / ( 1 + payload.len() ) + 1, read );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,127 @@
+// Tests:
+//
+// ✔ A call that is in flight while both ends swap to a new transport gets its response over the
+//   new transport.
+// ✔ Swapping the transport of a closed connection fails with ConnectionClosed.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { AsyncReadExt                } ,
+	futures       :: { lock::Mutex as FutMutex     } ,
+	futures_timer :: { Delay                       } ,
+	thes_wf       :: { Decoder, Encoder            } ,
+};
+
+
+// Each Show waits for the gate to open.
+//
+#[ derive( Actor ) ] struct Gated
+{
+	gate: Arc<FutMutex<()>> ,
+}
+
+
+impl Handler<Show> for Gated
+{
+	fn handle( &mut self, _msg: Show ) -> Return<'_, i64> { async move
+	{
+		let _open = self.gate.lock().await;
+
+		42
+
+	}.boxed() }
+}
+
+
+service_map!
+(
+	namespace  : gated  ;
+	wire_format: ThesWF ;
+	services   : Show   ;
+);
+
+
+// The transport of a new connection, counting the frames read from it.
+//
+fn transport( socket: Endpoint, frames: Arc<AtomicUsize> ) -> SwapTransport
+{
+	let (reader, writer) = socket.split();
+
+	let stream = Decoder::new( reader, 1024 ).inspect( move |_| { frames.fetch_add( 1, Relaxed ); } );
+	let sink   = Encoder::new( writer, 1024 );
+
+	SwapTransport::new( stream, sink )
+}
+
+
+
+#[async_std::test]
+//
+async fn swap_during_call()
+{
+	let (server , client ) = Endpoint::pair( 64, 64 );
+	let (server2, client2) = Endpoint::pair( 64, 64 );
+
+	let gate   = Arc::new( FutMutex::new(()) );
+	let closed = gate.lock().await;
+
+	let gated = Addr::builder().start( Gated{ gate: gate.clone() }, &AsyncStd ).expect( "spawn actor mailbox" );
+
+	let mut sm = gated::Services::new();
+	sm.register_handler::<Show>( gated.clone_box() );
+
+	let (mut server_addr, _, _server_handle) = peer_listen ( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _                ) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = gated::RemoteAddr::new( client_addr.clone() );
+
+	let client_frames = Arc::new( AtomicUsize::new( 0 ) );
+	let server_frames = Arc::new( AtomicUsize::new( 0 ) );
+
+	let swap = async
+	{
+		// Let the call reach the handler.
+		//
+		Delay::new( Duration::from_millis( 50 ) ).await;
+
+		client_addr.call( transport( client2, client_frames.clone() ) ).await.expect( "call client" ).expect( "swap client" );
+		server_addr.call( transport( server2, server_frames.clone() ) ).await.expect( "call server" ).expect( "swap server" );
+
+		drop( closed );
+	};
+
+	let (resp, _) = join( addr.call( Show ), swap ).await;
+
+	assert_eq!( 42, resp.expect( "call Show" ) );
+	assert_eq!( 1 , client_frames.load( Relaxed ) );
+
+
+	// New calls go over the new transport as well.
+	//
+	assert_eq!( 42, addr.call( Show ).await.expect( "call Show" ) );
+	assert_eq!( 2 , client_frames.load( Relaxed ) );
+	assert_eq!( 1 , server_frames.load( Relaxed ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn swap_closed()
+{
+	let (_server, client ) = Endpoint::pair( 64, 64 );
+	let (_, client2      ) = Endpoint::pair( 64, 64 );
+
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	client_addr.call( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+
+	let res = client_addr.call( transport( client2, Arc::default() ) ).await.expect( "call client" );
+
+	assert_matches!( res, Err( PeerErr::ConnectionClosed{..} ) );
+}
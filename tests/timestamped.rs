@@ -0,0 +1,57 @@
+// Tests:
+//
+// ✔ A timestamped decoder gives frames received with a gap timestamps that differ by about that gap.
+//
+mod common;
+
+use
+{
+	common        :: { *, import::{ *, assert_eq } } ,
+	futures       :: { AsyncReadExt, SinkExt       } ,
+	futures_timer :: { Delay                       } ,
+	thes_wf       :: { Decoder, Encoder            } ,
+};
+
+
+// A frame with the given payload.
+//
+fn frame( payload: &[u8] ) -> ThesWF
+{
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( ServiceID::from_seed( b"timestamped" ) );
+	wf.write_all( payload ).expect( "write payload" );
+
+	wf
+}
+
+
+
+#[async_std::test]
+//
+async fn gap()
+{
+	let (sender, receiver) = Endpoint::pair( 64, 64 );
+
+	let (_, writer) = sender  .split();
+	let (reader, _) = receiver.split();
+
+	let mut encoder = Encoder::new( writer, 1024 );
+	let mut decoder = Decoder::new( reader, 1024 ).timestamped();
+
+	encoder.send( frame( b"first" ) ).await.expect( "send first" );
+	let first = decoder.next().await.expect( "some" ).expect( "decode first" );
+
+	Delay::new( Duration::from_millis( 200 ) ).await;
+
+	encoder.send( frame( b"second" ) ).await.expect( "send second" );
+	let second = decoder.next().await.expect( "some" ).expect( "decode second" );
+
+	assert_eq!( b"first" , first .frame.msg() );
+	assert_eq!( b"second", second.frame.msg() );
+
+	let gap = second.at - first.at;
+
+	assert!( gap >= Duration::from_millis( 200 ), "gap: {:?}", gap );
+	assert!( gap <  Duration::from_millis( 400 ), "gap: {:?}", gap );
+}
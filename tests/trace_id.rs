@@ -0,0 +1,101 @@
+// Tests:
+//
+// ✔ A handler registered with register_traced gets the trace id of a call made with call_traced and
+//   the caller gets it back on the response.
+// ✔ A call without trace id gives the handler none.
+// ✔ A handler registered with register_handler doesn't see the trace id, but the response still carries
+//   it. Without trace id on the request, the response has none.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq } } ,
+	serde  :: { Serialize, Deserialize      } ,
+};
+
+
+#[ derive( Actor ) ] struct Echo;
+
+#[ derive( Serialize, Deserialize, Debug ) ] struct Ask;
+
+// Returns the trace id the handler saw.
+//
+impl Message for Ask { type Return = Option<u64>; }
+
+
+impl Handler< Traced<Ask> > for Echo
+{
+	#[async_fn] fn handle( &mut self, msg: Traced<Ask> ) -> Option<u64>
+	{
+		msg.trace_id
+	}
+}
+
+
+service_map!
+(
+	namespace  : trace  ;
+	wire_format: ThesWF ;
+	services   : Ask    ;
+);
+
+
+
+#[async_std::test]
+//
+async fn round_trip()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let echo   = Addr::builder().start( Echo, &AsyncStd ).expect( "spawn actor mailbox" );
+	let mut sm = trace::Services::new();
+
+	sm.register_traced::<Ask>( echo.clone_box() );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( sm ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = trace::RemoteAddr::new( client_addr.clone() );
+
+	let (seen, echoed) = addr.call_traced( Ask, 0xdead_beef ).await.expect( "call Ask" );
+
+	assert_eq!( Some( 0xdead_beef ), seen   );
+	assert_eq!( Some( 0xdead_beef ), echoed );
+
+	assert_eq!( None, addr.call( Ask ).await.expect( "call Ask" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn plain_handler()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( add_show_sum() ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut raw = RawPeerSink::new( client_addr.clone() );
+
+	let mut wf = ThesWF::default();
+
+	wf.set_sid( <Show as remotes::Service>::sid() );
+	serde_cbor::to_writer( &mut wf, &Show ).expect( "serialize Show" );
+
+	let resp = raw.call( wf.clone() ).await.expect( "call Show" );
+
+	assert_eq!( None, resp.trace_id() );
+
+	wf.set_trace_id( Some( 7 ) );
+
+	let resp = raw.call( wf ).await.expect( "call Show" );
+
+	assert_eq!( Some( 7 ), resp.trace_id() );
+	assert_eq!( 0, serde_cbor::from_slice::<i64>( resp.msg() ).expect( "deserialize response" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,100 @@
+// Tests:
+//
+// ✔ A call rejected by the validator fails with a validation error carrying the reason, and the handler
+//   doesn't run. Calls that pass go through.
+// ✔ A send rejected by the validator is dropped and reported in the events of the receiving peer.
+//
+mod common;
+
+use
+{
+	common :: { *, import::{ *, assert_eq }                 } ,
+	std    :: { sync::atomic::{ AtomicI64, Ordering::SeqCst } } ,
+};
+
+
+// Count the calls that reach the handler.
+//
+fn services( calls: Arc<AtomicI64> ) -> remotes::Services
+{
+	let mut sm = remotes::Services::new();
+
+	sm.register_fn::<Add, _, _>( move |_|
+	{
+		let calls = calls.clone();
+		async move { calls.fetch_add( 1, SeqCst ); }
+	});
+
+	sm.set_validator::<Add, _>( |msg| if msg.0 == 0 { Err( "cannot add zero".to_string() ) } else { Ok(()) } );
+
+	sm
+}
+
+
+
+#[async_std::test]
+//
+async fn call()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let calls = Arc::new( AtomicI64::new( 0 ) );
+
+	let (_server_addr, _, _server_handle) = peer_listen( server, Arc::new( services( calls.clone() ) ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	match addr.call( Add(0) ).await
+	{
+		Err( PeerErr::Remote{ err: ConnectionError::Validation{ sid, reason, .. }, .. } ) =>
+		{
+			assert_eq!( Some( <Add as remotes::Service>::sid() ), sid );
+			assert_eq!( "cannot add zero", reason );
+		}
+
+		x => panic!( "unexpected result: {:?}", x ),
+	}
+
+	assert_eq!( 0, calls.load( SeqCst ) );
+
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 1, calls.load( SeqCst ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
+
+
+
+#[async_std::test]
+//
+async fn send()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let calls = Arc::new( AtomicI64::new( 0 ) );
+
+	let (_server_addr, mut server_evts, _server_handle) = peer_listen( server, Arc::new( services( calls.clone() ) ), AsyncStd, "server" ).await;
+	let (mut client_addr, _) = peer_connect( client, AsyncStd, "client" ).await;
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.send( Add(0) ).await.expect( "send Add" );
+
+	let evt = server_evts.wait_for( |e| matches!( e, PeerEvent::Error(_) ) ).await.expect( "error event" );
+
+	match evt
+	{
+		PeerEvent::Error( PeerErr::Validation{ reason, .. } ) => assert_eq!( "cannot add zero", reason ),
+		x                                                     => panic!( "unexpected event: {:?}", x ),
+	}
+
+	// Calls are processed in order, so the send has been handled by now.
+	//
+	addr.call( Add(5) ).await.expect( "call Add" );
+
+	assert_eq!( 1, calls.load( SeqCst ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}
@@ -0,0 +1,80 @@
+// Tests:
+//
+// ✔ A tiny frame written by VarintEncoder takes fewer bytes than the same frame with the fixed header
+//   and comes out of VarintDecoder unchanged, with a deadline and a trace id as well.
+// ✔ Two peers created with from_async_read_varint can call each other.
+//
+mod common;
+
+use
+{
+	common  :: { *, import::{ *, assert_eq } } ,
+	futures :: { SinkExt, io::Cursor         } ,
+	std     :: { time::UNIX_EPOCH            } ,
+};
+
+
+
+#[async_std::test]
+//
+async fn round_trip()
+{
+	let mut send = ThesWF::default();
+
+	send.set_sid( <Add as remotes::Service>::sid() );
+	send.write_all( &[ 5 ] ).expect( "write payload" );
+
+	let mut call = send.clone();
+
+	call.set_cid     ( ConnID::from( 7 )                                                );
+	call.set_deadline( Some( UNIX_EPOCH + Duration::from_millis( 1_600_000_000_123 ) ) );
+	call.set_trace_id( Some( 42 )                                                       );
+
+	let mut encoder = VarintEncoder::new( Cursor::new( Vec::new() ) );
+
+	encoder.send( send.clone() ).await.expect( "send frame" );
+
+	let compact = encoder.get_ref().get_ref().len();
+
+	// The length, the cid and the deadline field take a byte each.
+	//
+	assert_eq!( send.len() as usize - 21, compact );
+
+	encoder.send( call.clone() ).await.expect( "send frame" );
+
+
+	let mut decoder = VarintDecoder::new( Cursor::new( encoder.get_ref().get_ref().clone() ), 1024 );
+
+	assert_eq!( send, decoder.next().await.expect( "a frame" ).expect( "decode frame" ) );
+	assert_eq!( call, decoder.next().await.expect( "a frame" ).expect( "decode frame" ) );
+	assert!( decoder.next().await.is_none() );
+}
+
+
+
+#[async_std::test]
+//
+async fn peers()
+{
+	let (server, client) = Endpoint::pair( 64, 64 );
+
+	let (server_addr    , server_mb) = Addr::builder().name( "server".into() ).build();
+	let (mut client_addr, client_mb) = Addr::builder().name( "client".into() ).build();
+
+	let mut server_peer = Peer::from_async_read_varint( server_addr, server, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+	let     client_peer = Peer::from_async_read_varint( client_addr.clone(), client, 1024, AsyncStd, None, None ).expect( "spawn peer" );
+
+	server_peer.register_services( Arc::new( add_show_sum() ) );
+
+	AsyncStd.spawn( async{ server_mb.start( server_peer ).await; } ).expect( "start mailbox of Peer" );
+	AsyncStd.spawn( async{ client_mb.start( client_peer ).await; } ).expect( "start mailbox of Peer" );
+
+
+	let mut addr = remotes::RemoteAddr::new( client_addr.clone() );
+
+	addr.send( Add( 5 ) ).await.expect( "send Add" );
+
+	assert_eq!( 5, addr.call( Show ).await.expect( "call Show" ) );
+
+	client_addr.send( CloseConnection{ remote: false, reason: "Program end.".to_string() } ).await.expect( "close connection" );
+}